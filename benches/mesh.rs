@@ -0,0 +1,147 @@
+//! Benchmark of vertex welding and mesh optimization.
+//!
+//! Run with `cargo bench --bench mesh`. Compares hash-based welding of `SimpleMesh` with
+//! reverse linear search of `SimpleMesh::insert_vertex`, and measures average cache miss
+//! ratio of a mesh with shuffled triangles before and after `SurfaceSharedData::optimize`.
+
+extern crate rg3d;
+
+use std::time::{Duration, Instant};
+use rg3d::{
+    core::{
+        math::{
+            vec2::Vec2,
+            vec3::Vec3,
+            vec4::Vec4,
+        },
+        color::Color,
+    },
+    renderer::surface::{SurfaceSharedData, Vertex},
+    utils::{
+        SimpleMesh,
+        weld::WeldOptions,
+        mesh_optimizer,
+    },
+};
+
+/// Triangle soup of a grid of quads with given count of quads per side, like polygon
+/// corners of FBX. Order of triangles is shuffled if `seed` is not zero.
+fn grid(size: usize, seed: u32) -> Vec<Vec3> {
+    let mut triangles = Vec::with_capacity(size * size * 2);
+    for y in 0..size {
+        for x in 0..size {
+            let p = |dx: usize, dy: usize| Vec3::new((x + dx) as f32, (y + dy) as f32, 0.0);
+            triangles.push([p(0, 0), p(1, 0), p(0, 1)]);
+            triangles.push([p(1, 0), p(1, 1), p(0, 1)]);
+        }
+    }
+    if seed != 0 {
+        // Xorshift, so shuffle is same on every run.
+        let mut state = seed;
+        for i in (1..triangles.len()).rev() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            triangles.swap(i, state as usize % (i + 1));
+        }
+    }
+    triangles.iter().flat_map(|t| t.iter().copied()).collect()
+}
+
+/// Welding by reverse linear search, it is used by `SimpleMesh::insert_vertex`.
+fn weld_linear(points: &[Vec3]) -> (Vec<Vec3>, Vec<u32>) {
+    let mut vertices: Vec<Vec3> = Vec::new();
+    let mut indices = Vec::with_capacity(points.len());
+    for point in points {
+        indices.push(match vertices.iter().rposition(|v| v.x == point.x && v.y == point.y && v.z == point.z) {
+            Some(existing_index) => existing_index as u32,
+            None => {
+                vertices.push(*point);
+                vertices.len() as u32 - 1
+            }
+        });
+    }
+    (vertices, indices)
+}
+
+fn weld_hashed(points: &[Vec3], options: WeldOptions) -> SimpleMesh<Vec3> {
+    let mut mesh = SimpleMesh::with_weld_options(options);
+    for point in points {
+        mesh.insert_welded_vertex(*point);
+    }
+    mesh
+}
+
+fn measure<T, F: FnMut() -> T>(mut func: F) -> (T, Duration) {
+    let start = Instant::now();
+    let result = func();
+    (result, start.elapsed())
+}
+
+fn bench_welding() {
+    println!("Welding of triangle soup (exact)");
+    println!("{:>10} {:>10} {:>14} {:>14} {:>10}", "triangles", "vertices", "linear, ms", "hashed, ms", "speedup");
+    for size in [32, 64, 128, 256, 317].iter() {
+        let points = grid(*size, 0);
+        let (mesh, hashed) = measure(|| weld_hashed(&points, WeldOptions::default()));
+        // Linear search is too slow for big meshes.
+        let linear = if *size <= 128 {
+            let ((vertices, indices), linear) = measure(|| weld_linear(&points));
+            assert_eq!(vertices.len(), mesh.vertices.len());
+            assert_eq!(indices, mesh.indices);
+            Some(linear)
+        } else {
+            None
+        };
+        println!("{:>10} {:>10} {:>14} {:>14.2} {:>10}",
+                 points.len() / 3,
+                 mesh.vertices.len(),
+                 linear.map_or("-".to_owned(), |linear| format!("{:.2}", linear.as_secs_f64() * 1000.0)),
+                 hashed.as_secs_f64() * 1000.0,
+                 linear.map_or("-".to_owned(), |linear| format!("{:.1}x", linear.as_secs_f64() / hashed.as_secs_f64())));
+    }
+
+    // Noisy positions are welded with tolerance only.
+    let points = grid(317, 0)
+        .iter()
+        .enumerate()
+        .map(|(i, p)| Vec3::new(p.x + (i % 7) as f32 * 1.0e-5, p.y, p.z))
+        .collect::<Vec<_>>();
+    let (exact, exact_time) = measure(|| weld_hashed(&points, WeldOptions::default()));
+    let (welded, welded_time) = measure(|| weld_hashed(&points, WeldOptions::new().with_position_epsilon(1.0e-3)));
+    println!("Noisy grid: {} vertices without tolerance ({:.2} ms), {} vertices with 1e-3 tolerance ({:.2} ms)",
+             exact.vertices.len(), exact_time.as_secs_f64() * 1000.0,
+             welded.vertices.len(), welded_time.as_secs_f64() * 1000.0);
+    println!();
+}
+
+fn bench_optimization() {
+    const CACHE_SIZE: usize = 16;
+
+    println!("Optimization of mesh with shuffled triangles (ACMR for {} entry FIFO cache)", CACHE_SIZE);
+    println!("{:>10} {:>14} {:>14} {:>10}", "triangles", "ACMR before", "ACMR after", "time, ms");
+    for size in [32, 128, 317].iter() {
+        let mut data = SurfaceSharedData::new();
+        for point in grid(*size, 0x2545_f491) {
+            data.insert_vertex(Vertex {
+                position: point,
+                tex_coord: Vec2::new(point.x, point.y),
+                normal: Vec3::new(0.0, 0.0, 1.0),
+                tangent: Vec4 { x: 1.0, y: 0.0, z: 0.0, w: 1.0 },
+                bone_weights: [0.0; 4],
+                bone_indices: [0; 4],
+                second_tex_coord: Vec2::new(0.0, 0.0),
+                color: Color::WHITE,
+            });
+        }
+        let before = mesh_optimizer::average_cache_miss_ratio(data.get_indices(), data.get_vertices().len(), CACHE_SIZE);
+        let (_, time) = measure(|| data.optimize());
+        let after = mesh_optimizer::average_cache_miss_ratio(data.get_indices(), data.get_vertices().len(), CACHE_SIZE);
+        println!("{:>10} {:>14.3} {:>14.3} {:>10.2}", data.get_indices().len() / 3, before, after, time.as_secs_f64() * 1000.0);
+    }
+}
+
+fn main() {
+    bench_welding();
+    bench_optimization();
+}
@@ -93,6 +93,8 @@ use rg3d::{
             State,
             Transition,
             Parameter,
+            Condition,
+            Event as MachineEvent,
         },
         AnimationSignal
     }
@@ -244,7 +246,7 @@ impl LocomotionMachine {
     const IDLE_TO_JUMP: &'static str = "IdleToJump";
     const JUMP_TO_IDLE: &'static str = "JumpToIdle";
 
-    const JUMP_SIGNAL: u64 = 1;
+    const JUMP_SIGNAL: &'static str = "Jump";
 
     fn new(scene: &mut Scene, model: Handle<Node>, resource_manager: &mut ResourceManager) -> Self {
         let mut machine = Machine::new();
@@ -259,8 +261,9 @@ impl LocomotionMachine {
             // Actual jump (applying force to physical body) must be synced with animation
             // so we have to be notified about this. This is where signals come into play
             // you can assign any signal in animation timeline and then in update loop you
-            // can iterate over them and react appropriately.
-            .add_signal(AnimationSignal::new(Self::JUMP_SIGNAL, 0.32))
+            // can iterate over them and react appropriately. Machine will dispatch signals
+            // of animations that contribute to final pose.
+            .add_signal(AnimationSignal::named(Self::JUMP_SIGNAL, 0.32))
             .set_loop(false);
 
         // Add transitions between states. This is the "heart" of animation blending state machine
@@ -270,7 +273,10 @@ impl LocomotionMachine {
             .add_transition(Transition::new("Walk->Jump", walk_state, jump_state, 0.20, Self::WALK_TO_JUMP))
             .add_transition(Transition::new("Idle->Walk", idle_state, walk_state, 0.30, Self::IDLE_TO_WALK))
             .add_transition(Transition::new("Idle->Jump", idle_state, jump_state, 0.25, Self::IDLE_TO_JUMP))
-            .add_transition(Transition::new("Jump->Idle", jump_state, idle_state, 0.30, Self::JUMP_TO_IDLE));
+            // Jump->Idle transition must wait until jump animation ends, so we combine rule
+            // with a check of source state.
+            .add_transition(Transition::with_condition("Jump->Idle", jump_state, idle_state, 0.30,
+                Condition::and(vec![Condition::rule(Self::JUMP_TO_IDLE), Condition::SourceFinished])));
 
         Self {
             machine,
@@ -285,7 +291,7 @@ impl LocomotionMachine {
             .set_parameter(Self::WALK_TO_IDLE, Parameter::Rule(!input.is_walking))
             .set_parameter(Self::WALK_TO_JUMP, Parameter::Rule(input.is_jumping))
             .set_parameter(Self::IDLE_TO_JUMP, Parameter::Rule(input.is_jumping))
-            .set_parameter(Self::JUMP_TO_IDLE, Parameter::Rule(!input.is_jumping && scene.animations.get(self.jump_animation).has_ended()))
+            .set_parameter(Self::JUMP_TO_IDLE, Parameter::Rule(!input.is_jumping))
             // Finally we can do update tick for machine that will evaluate current pose for character.
             .evaluate_pose(&scene.animations, dt)
             // Pose must be applied to graph - remember that animations operate on multiple nodes at once.
@@ -442,9 +448,11 @@ impl Player {
             }
         }
 
-        while let Some(event) = scene.animations.get_mut(self.locomotion_machine.jump_animation).pop_event() {
-            if event.signal_id == LocomotionMachine::JUMP_SIGNAL {
-                body.set_y_velocity(6.0 * dt);
+        while let Some(event) = self.locomotion_machine.machine.pop_event() {
+            if let MachineEvent::Signal { event, .. } = event {
+                if event.signal_name == LocomotionMachine::JUMP_SIGNAL {
+                    body.set_y_velocity(6.0 * dt);
+                }
             }
         }
 
@@ -2,15 +2,12 @@
 //!
 //! Difficulty: Medium.
 //!
-//! This example shows how to load scene in separate thread and how create standard
-//! loading screen which will show progress.
+//! This example shows how to load resources of scene on worker threads of resource manager
+//! and how create standard loading screen which will show progress.
 
 extern crate rg3d;
 
-use std::{
-    time::Instant,
-    sync::{Arc, Mutex},
-};
+use std::time::Instant;
 use rg3d::{
     scene::{
         base::{
@@ -22,7 +19,10 @@ use rg3d::{
         node::Node,
         Scene,
     },
-    engine::resource_manager::ResourceManager,
+    engine::resource_manager::{
+        ResourceManager,
+        ModelRequest,
+    },
     gui::{
         widget::WidgetBuilder,
         text::TextBuilder,
@@ -124,101 +124,98 @@ struct GameScene {
     walk_animation: Handle<Animation>,
 }
 
+/// Requests of every resource needed by scene. Resources are loaded by worker threads of
+/// resource manager, main thread only checks their state.
 struct SceneLoadContext {
-    data: Option<GameScene>,
-    message: String,
-    progress: f32,
+    model: ModelRequest,
+    walk_animation: ModelRequest,
 }
 
 impl SceneLoadContext {
-    pub fn report_progress(&mut self, progress: f32, message: &str) {
-        self.progress = progress;
-        self.message = message.to_owned();
-        println!("Loading progress: {}% - {}", progress * 100.0, message);
+    fn requests(&self) -> [&ModelRequest; 2] {
+        [&self.model, &self.walk_animation]
+    }
+
+    pub fn progress(&self) -> f32 {
+        let loaded = self.requests().iter().filter(|request| !request.is_loading()).count();
+        loaded as f32 / self.requests().len() as f32
+    }
+
+    pub fn message(&self) -> String {
+        match self.requests().iter().find(|request| request.is_loading()) {
+            Some(request) => format!("Loading {}...", request.path().display()),
+            None => "Done".to_owned(),
+        }
     }
 }
 
-fn create_scene_async(resource_manager: Arc<Mutex<ResourceManager>>) -> Arc<Mutex<SceneLoadContext>> {
-    // Create load context - it will be shared with caller and loader threads.
-    let context = Arc::new(Mutex::new(SceneLoadContext {
-        data: None,
-        message: "Starting..".to_string(),
-        progress: 0.0,
-    }));
-    let result = context.clone();
-
-    // Spawn separate thread which will create scene by loading various assets.
-    std::thread::spawn(move || {
-        let mut scene = Scene::new();
-
-        let mut resource_manager = resource_manager.lock().unwrap();
-
-        // It is important to lock context for short period of time so other thread can
-        // read data from it as soon as possible - not when everything was loaded.
-        context.lock().unwrap().report_progress(0.0, "Creating camera...");
-
-        // Camera is our eyes in the world - you won't see anything without it.
-        let camera = CameraBuilder::new(BaseBuilder::new()
-            .with_local_transform(TransformBuilder::new()
-                .with_local_position(Vec3::new(0.0, 6.0, -12.0))
-                .build()))
-            .build();
-
-        scene.graph.add_node(Node::Camera(camera));
-
-        context.lock().unwrap().report_progress(0.33, "Loading model...");
-
-        // Load model resource. Is does *not* adds anything to our scene - it just loads a
-        // resource then can be used later on to instantiate models from it on scene. Why
-        // loading of resource is separated from instantiation? Because there it is too
-        // inefficient to load a resource every time you trying to create instance of it -
-        // much more efficient is to load it one and then make copies of it. In case of
-        // models it is very efficient because single vertex and index buffer can be used
-        // for all models instances, so memory footprint on GPU will be lower.
-        let model_resource = resource_manager.request_model("examples/data/mutant.FBX").unwrap();
-
-        // Instantiate model on scene - but only geometry, without any animations.
-        // Instantiation is a process of embedding model resource data in desired scene.
-        let model_handle = model_resource.lock()
-            .unwrap()
-            .instantiate_geometry(&mut scene);
-
-        // Now we have whole sub-graph instantiated, we can start modifying model instance.
-        scene.graph
-            .get_mut(model_handle)
-            .base_mut()
-            .local_transform_mut()
-            // Our model is too big, fix it by scale.
-            .set_scale(Vec3::new(0.05, 0.05, 0.05));
-
-        context.lock().unwrap().report_progress(0.66, "Loading animation...");
-
-        // Add simple animation for our model. Animations are loaded from model resources -
-        // this is because animation is a set of skeleton bones with their own transforms.
-        let walk_animation_resource = resource_manager.request_model("examples/data/walk.fbx").unwrap();
-
-        // Once animation resource is loaded it must be re-targeted to our model instance.
-        // Why? Because animation in *resource* uses information about *resource* bones,
-        // not model instance bones, retarget_animations maps animations of each bone on
-        // model instance so animation will know about nodes it should operate on.
-        let walk_animation = *walk_animation_resource
-            .lock()
-            .unwrap()
-            .retarget_animations(model_handle, &mut scene)
-            .get(0)
-            .unwrap();
-
-        context.lock().unwrap().report_progress(1.0, "Done");
-
-        context.lock().unwrap().data = Some(GameScene {
-            scene,
-            model_handle,
-            walk_animation,
-        })
-    });
+fn request_scene_resources(resource_manager: &mut ResourceManager) -> SceneLoadContext {
+    // Requests return immediately, resource manager will load models on worker threads and
+    // change state of requests in engine update. Textures of models are loaded asynchronously
+    // too.
+    SceneLoadContext {
+        model: resource_manager.request_model_async("examples/data/mutant.FBX"),
+        walk_animation: resource_manager.request_model_async("examples/data/walk.fbx"),
+    }
+}
 
-    // Immediately return shared context.
-    result
+/// Creates scene once every resource is loaded. This is fast, because only copies of
+/// already loaded resources are made.
+fn create_scene(context: &SceneLoadContext) -> GameScene {
+    let mut scene = Scene::new();
+
+    // Camera is our eyes in the world - you won't see anything without it.
+    let camera = CameraBuilder::new(BaseBuilder::new()
+        .with_local_transform(TransformBuilder::new()
+            .with_local_position(Vec3::new(0.0, 6.0, -12.0))
+            .build()))
+        .build();
+
+    scene.graph.add_node(Node::Camera(camera));
+
+    // Model resource does *not* adds anything to our scene - it just a resource then can be
+    // used later on to instantiate models from it on scene. Why loading of resource is
+    // separated from instantiation? Because there it is too inefficient to load a resource
+    // every time you trying to create instance of it - much more efficient is to load it one
+    // and then make copies of it. In case of models it is very efficient because single
+    // vertex and index buffer can be used for all models instances, so memory footprint on
+    // GPU will be lower.
+    let model_resource = context.model.model().unwrap();
+
+    // Instantiate model on scene - but only geometry, without any animations.
+    // Instantiation is a process of embedding model resource data in desired scene.
+    let model_handle = model_resource.lock()
+        .unwrap()
+        .instantiate_geometry(&mut scene);
+
+    // Now we have whole sub-graph instantiated, we can start modifying model instance.
+    scene.graph
+        .get_mut(model_handle)
+        .base_mut()
+        .local_transform_mut()
+        // Our model is too big, fix it by scale.
+        .set_scale(Vec3::new(0.05, 0.05, 0.05));
+
+    // Add simple animation for our model. Animations are loaded from model resources -
+    // this is because animation is a set of skeleton bones with their own transforms.
+    let walk_animation_resource = context.walk_animation.model().unwrap();
+
+    // Once animation resource is loaded it must be re-targeted to our model instance.
+    // Why? Because animation in *resource* uses information about *resource* bones,
+    // not model instance bones, retarget_animations maps animations of each bone on
+    // model instance so animation will know about nodes it should operate on.
+    let walk_animation = *walk_animation_resource
+        .lock()
+        .unwrap()
+        .retarget_animations(model_handle, &mut scene)
+        .get(0)
+        .unwrap();
+
+    GameScene {
+        scene,
+        model_handle,
+        walk_animation,
+    }
 }
 
 struct InputController {
@@ -246,9 +243,10 @@ fn main() {
     let screen_size = window.inner_size().to_logical(window.scale_factor());
     let interface = create_ui(&mut engine.user_interface, Vec2::new(screen_size.width, screen_size.height));
 
-    // Create scene asynchronously - this method immediately returns empty load context
+    // Request resources of scene asynchronously - this method immediately returns requests
     // which will be filled with data over time.
-    let game_scene = create_scene_async(engine.resource_manager.clone());
+    let load_context = request_scene_resources(&mut engine.resource_manager.lock().unwrap());
+    let mut scene_loaded = false;
 
     // Initially these handles are None, once scene is loaded they'll be assigned.
     let mut scene_handle = Handle::NONE;
@@ -286,17 +284,24 @@ fn main() {
                     // Put your game logic here.
                     // ************************
 
-                    // Check each frame if our scene is created - here we just trying to lock context
-                    // without blocking, it is important for main thread to be functional while other
-                    // thread still loading data.
-                    if let Ok(mut load_context) = game_scene.try_lock() {
-                        if let Some(game_scene) = load_context.data.take() {
+                    // Check each frame if every resource of our scene is loaded - requests
+                    // are changed by resource manager in engine update, so main thread
+                    // is never blocked while resources are loading.
+                    if !scene_loaded {
+                        if let Some(request) = load_context.requests().iter().find(|request| request.error().is_some()) {
+                            panic!("Unable to load {}: {}", request.path().display(), request.error().unwrap());
+                        }
+
+                        if load_context.progress() >= 1.0 {
+                            let game_scene = create_scene(&load_context);
+
                             // Add scene to engine - engine will take ownership over scene and will return
                             // you a handle to scene which can be used later on to borrow it and do some
                             // actions you need.
                             scene_handle = engine.scenes.add(game_scene.scene);
                             model_handle = game_scene.model_handle;
                             walk_animation = game_scene.walk_animation;
+                            scene_loaded = true;
 
                             // Once scene is loaded, we should hide progress bar and text.
                             if let UiNode::ProgressBar(progress_bar) = engine.user_interface.node_mut(interface.progress_bar) {
@@ -310,11 +315,11 @@ fn main() {
 
                         // Report progress in UI.
                         if let UiNode::ProgressBar(progress_bar) = engine.user_interface.node_mut(interface.progress_bar) {
-                            progress_bar.set_progress(load_context.progress);
+                            progress_bar.set_progress(load_context.progress());
                         }
 
                         if let UiNode::Text(progress_text) = engine.user_interface.node_mut(interface.progress_text) {
-                            progress_text.set_text(format!("Loading scene: {}%\n{}", load_context.progress * 100.0, load_context.message));
+                            progress_text.set_text(format!("Loading scene: {}%\n{}", load_context.progress() * 100.0, load_context.message()));
                         }
                     }
 
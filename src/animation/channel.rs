@@ -0,0 +1,265 @@
+//! Channels of animation tracks and key frame reduction.
+//!
+//! Each track stores position, scale and rotation in separate channels, so every channel
+//! can have its own set of keys. This allows to remove redundant keys from each channel
+//! independently - for example most of bones in skeletal animation have constant scale
+//! and translation, such channels will be stored as a single key.
+
+use crate::core::{
+    math::{
+        vec3::Vec3,
+        quat::Quat,
+        clampf,
+    },
+    visitor::{Visit, VisitResult, Visitor},
+};
+
+/// Value of a channel that can be interpolated and compared with other value.
+pub trait ChannelValue: Copy {
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+
+    /// Returns "distance" between values, it is used to measure error of reduction.
+    fn distance(&self, other: &Self) -> f32;
+}
+
+impl ChannelValue for Vec3 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec3::distance(self, other)
+    }
+}
+
+impl ChannelValue for Quat {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+
+    /// Angle in radians between rotations. Calculated using chord length instead of acos of
+    /// dot product, because acos is very imprecise for small angles.
+    fn distance(&self, other: &Self) -> f32 {
+        let dot = self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w;
+        // q and -q represent same rotation.
+        let s = if dot < 0.0 { -1.0 } else { 1.0 };
+        let dx = self.x - s * other.x;
+        let dy = self.y - s * other.y;
+        let dz = self.z - s * other.z;
+        let dw = self.w - s * other.w;
+        let chord = (dx * dx + dy * dy + dz * dz + dw * dw).sqrt();
+        4.0 * clampf(0.5 * chord, 0.0, 1.0).asin()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ChannelKey<T> {
+    pub time: f32,
+    pub value: T,
+}
+
+/// Sorted set of keys of a single channel. Channel with single key is a constant channel,
+/// its value is the same at any time.
+#[derive(Clone, Debug)]
+pub struct TrackChannel<T> {
+    keys: Vec<ChannelKey<T>>,
+}
+
+impl<T> Default for TrackChannel<T> {
+    fn default() -> Self {
+        Self {
+            keys: Vec::new()
+        }
+    }
+}
+
+impl<T: ChannelValue> TrackChannel<T> {
+    pub fn add_key(&mut self, time: f32, value: T) {
+        let key = ChannelKey { time, value };
+        // Keys are usually added in order, so check last key first.
+        if self.keys.last().map_or(true, |k| k.time <= time) {
+            self.keys.push(key);
+        } else if let Some(index) = self.keys.iter().position(|k| time < k.time) {
+            self.keys.insert(index, key);
+        } else {
+            self.keys.push(key);
+        }
+    }
+
+    pub fn keys(&self) -> &[ChannelKey<T>] {
+        &self.keys
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.keys.len() == 1
+    }
+
+    /// Returns interpolated value at given time, None if channel has no keys.
+    pub fn value(&self, time: f32) -> Option<T> {
+        let first = self.keys.first()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+
+        let last = self.keys.last()?;
+        if time >= last.time {
+            return Some(last.value);
+        }
+
+        let right_index = self.keys.iter().position(|k| k.time >= time)?;
+        let left = &self.keys[right_index - 1];
+        let right = &self.keys[right_index];
+        let interpolator = (time - left.time) / (right.time - left.time);
+        Some(left.value.interpolate(&right.value, interpolator))
+    }
+
+    /// Removes every key that can be restored by interpolation of neighbour keys with error
+    /// less than given tolerance. Error is measured at removed keys and between them, because
+    /// interpolation of rotations is not linear. If every key is within tolerance of first key,
+    /// then only first key will be left.
+    pub fn reduce(&mut self, tolerance: f32) {
+        if self.keys.len() < 2 {
+            return;
+        }
+
+        let first = self.keys[0].value;
+        if self.keys.iter().all(|k| k.value.distance(&first) <= tolerance) {
+            self.keys.truncate(1);
+            return;
+        }
+
+        let mut result = vec![self.keys[0]];
+        let mut anchor = 0;
+        for end in 2..self.keys.len() {
+            let a = self.keys[anchor];
+            let b = self.keys[end];
+            let span = b.time - a.time;
+            let fits_at = |time: f32, value: &T| {
+                let t = if span > std::f32::EPSILON { (time - a.time) / span } else { 0.0 };
+                a.value.interpolate(&b.value, t).distance(value) <= tolerance
+            };
+            let fits = self.keys[(anchor + 1)..end].iter().all(|k| fits_at(k.time, &k.value)) &&
+                self.keys[anchor..=end].windows(2).all(|pair| {
+                    let middle = pair[0].value.interpolate(&pair[1].value, 0.5);
+                    fits_at(0.5 * (pair[0].time + pair[1].time), &middle)
+                });
+            if !fits {
+                anchor = end - 1;
+                result.push(self.keys[anchor]);
+            }
+        }
+        result.push(self.keys[self.keys.len() - 1]);
+
+        self.keys = result;
+    }
+}
+
+/// Maximum error allowed per channel when reducing key frames. Position and scale
+/// tolerances are in units of these values, rotation tolerance is in radians.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct KeyFrameReductionSettings {
+    pub position_tolerance: f32,
+    pub scale_tolerance: f32,
+    pub rotation_tolerance: f32,
+}
+
+impl Default for KeyFrameReductionSettings {
+    fn default() -> Self {
+        Self {
+            position_tolerance: 0.001,
+            scale_tolerance: 0.001,
+            rotation_tolerance: 0.001,
+        }
+    }
+}
+
+impl Visit for KeyFrameReductionSettings {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.position_tolerance.visit("PositionTolerance", visitor)?;
+        self.scale_tolerance.visit("ScaleTolerance", visitor)?;
+        self.rotation_tolerance.visit("RotationTolerance", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Amount of keys before and after reduction.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct KeyFrameReductionReport {
+    pub keys_before: usize,
+    pub keys_after: usize,
+}
+
+impl KeyFrameReductionReport {
+    /// Returns how much times amount of keys was reduced.
+    pub fn compression_ratio(&self) -> f32 {
+        if self.keys_after == 0 {
+            1.0
+        } else {
+            self.keys_before as f32 / self.keys_after as f32
+        }
+    }
+
+    pub(in crate) fn merge(&mut self, other: &KeyFrameReductionReport) {
+        self.keys_before += other.keys_before;
+        self.keys_after += other.keys_after;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{
+            Track,
+            KeyFrame,
+            channel::{ChannelValue, KeyFrameReductionSettings},
+        },
+        core::math::{
+            vec3::Vec3,
+            quat::Quat,
+        },
+    };
+
+    #[test]
+    fn key_frame_reduction_within_tolerance() {
+        let mut track = Track::new();
+        let frame_count = 300;
+        for i in 0..frame_count {
+            let time = i as f32 / 30.0;
+            // Linear translation, constant scale and smooth rotation.
+            track.add_key_frame(KeyFrame::new(
+                time,
+                Vec3::new(time, 2.0 * time, 0.0),
+                Vec3::new(1.0, 1.0, 1.0),
+                Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), time.sin())));
+        }
+        let original = track.clone();
+
+        let settings = KeyFrameReductionSettings::default();
+        let report = track.reduce_key_frames(&settings);
+
+        assert_eq!(report.keys_before, 3 * frame_count);
+        assert!(report.compression_ratio() > 2.0);
+        assert!(track.scale_channel().is_constant());
+        assert_eq!(track.position_channel().keys().len(), 2);
+
+        // Step is not a multiple of key interval, so error is checked between keys too.
+        // Only floating point error is allowed above tolerance.
+        let epsilon = 1.0e-5;
+        let mut time = 0.0;
+        while time < 11.0 {
+            let expected = original.get_local_pose(time).unwrap();
+            let actual = track.get_local_pose(time).unwrap();
+            assert!(expected.position.distance(&actual.position) <= settings.position_tolerance + epsilon);
+            assert!(expected.scale.distance(&actual.scale) <= settings.scale_tolerance + epsilon);
+            assert!(ChannelValue::distance(&expected.rotation, &actual.rotation) <= settings.rotation_tolerance + epsilon);
+            time += 0.007;
+        }
+    }
+}
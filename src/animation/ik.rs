@@ -0,0 +1,795 @@
+//! Inverse kinematics solvers.
+//!
+//! Solvers operate directly on local transforms of scene graph nodes, so they must be applied
+//! *after* animation pose was applied to graph (`AnimationPose::apply`) and *before* graph will
+//! calculate global transforms for rendering (which is done by engine on each update). Solvers
+//! do not rely on global transforms calculated by graph on previous frame, they calculate actual
+//! global transforms of affected nodes by themselves.
+//!
+//! There are three kinds of solvers:
+//! - Two bone - analytical solver for limbs like arm (shoulder, elbow, wrist) or leg (hip, knee,
+//!   ankle). Typical usage - foot placement on slopes.
+//! - Look at - rotates single node so its axis will point to a target. Typical usage - head or
+//!   weapon aiming.
+//! - FABRIK - iterative solver for chains of arbitrary length, like tails or tentacles.
+//!
+//! Each solver has weight which defines how much solved pose affects animated pose, weight can
+//! be constant or taken from Weight parameter of a machine, so solvers can be faded in and out
+//! by the machine.
+//!
+//! Example:
+//!
+//! ```no_run
+//! use rg3d::{
+//!     animation::{
+//!         ik::{IkSolverContainer, IkSolver, LookAt},
+//!         machine::{Machine, PoseWeight},
+//!         AnimationContainer,
+//!     },
+//!     core::{pool::Handle, math::vec3::Vec3},
+//!     scene::graph::Graph,
+//! };
+//!
+//! fn update(machine: &mut Machine, solvers: &mut IkSolverContainer, graph: &mut Graph, animations: &AnimationContainer, dt: f32) {
+//!     machine.evaluate_pose(animations, dt).apply(graph);
+//!     solvers.solve(graph, machine);
+//! }
+//!
+//! let head = Handle::default(); // Assume that this is correct handle.
+//! let mut solvers = IkSolverContainer::new();
+//! let look_at = solvers.add(IkSolver::LookAt(LookAt::new(head, Vec3::LOOK, PoseWeight::Parameter("Aim".to_owned()))));
+//! solvers.get_mut(look_at).set_target(Vec3::new(1.0, 2.0, 3.0));
+//! ```
+
+use crate::{
+    core::{
+        math::{
+            vec3::Vec3,
+            quat::Quat,
+            mat4::Mat4,
+            clampf,
+        },
+        pool::{
+            Pool,
+            Handle,
+            PoolIterator,
+            PoolIteratorMut,
+        },
+        visitor::{
+            Visit,
+            VisitResult,
+            Visitor,
+        },
+    },
+    scene::{
+        node::Node,
+        graph::Graph,
+        base::AsBase,
+    },
+    animation::machine::{
+        Machine,
+        PoseWeight,
+    },
+};
+
+/// Calculates actual global transform of a node using local transforms of its ancestors.
+pub(in crate::animation) fn calculate_global_transform(graph: &Graph, node: Handle<Node>) -> Mat4 {
+    let mut transform = graph.get(node).base().local_transform().matrix();
+    let mut parent = graph.get(node).base().parent();
+    while parent.is_some() {
+        let parent_base = graph.get(parent).base();
+        transform = parent_base.local_transform().matrix() * transform;
+        parent = parent_base.parent();
+    }
+    transform
+}
+
+pub(in crate::animation) fn calculate_global_position(graph: &Graph, node: Handle<Node>) -> Vec3 {
+    calculate_global_transform(graph, node).position()
+}
+
+/// Rotates node around given axis defined in world space.
+fn rotate_node(graph: &mut Graph, node: Handle<Node>, world_axis: Vec3, angle: f32) {
+    if angle.abs() <= std::f32::EPSILON {
+        return;
+    }
+
+    let parent = graph.get(node).base().parent();
+    let parent_transform = if parent.is_some() {
+        calculate_global_transform(graph, parent)
+    } else {
+        Mat4::IDENTITY
+    };
+
+    // Local rotation is placed between pre- and post-rotations, so axis must be transformed
+    // into space of pre-rotation.
+    let transform = graph.get_mut(node).base_mut().local_transform_mut();
+    let frame = parent_transform * Mat4::from_quat(transform.pre_rotation());
+    if let Ok(inv_frame) = frame.inverse() {
+        if let Some(local_axis) = inv_frame.transform_vector_normal(world_axis).normalized() {
+            let rotation = Quat::from_axis_angle(local_axis, angle) * transform.rotation();
+            transform.set_rotation(rotation);
+        }
+    }
+}
+
+/// Rotates node in a way that world space direction `from` will become `to`.
+pub(in crate::animation) fn rotate_node_from_to(graph: &mut Graph, node: Handle<Node>, from: Vec3, to: Vec3) {
+    if let (Some(from), Some(to)) = (from.normalized(), to.normalized()) {
+        if let Some(axis) = from.cross(&to).normalized() {
+            let angle = clampf(from.dot(&to), -1.0, 1.0).acos();
+            rotate_node(graph, node, axis, angle);
+        }
+    }
+}
+
+/// Blends current local rotations of nodes with given original rotations.
+fn blend_with_original(graph: &mut Graph, original: &[(Handle<Node>, Quat)], weight: f32) {
+    let weight = clampf(weight, 0.0, 1.0);
+    for (node, original_rotation) in original {
+        let transform = graph.get_mut(*node).base_mut().local_transform_mut();
+        let solved = transform.rotation();
+        transform.set_rotation(original_rotation.nlerp(&solved, weight));
+    }
+}
+
+fn snapshot_rotations(graph: &Graph, nodes: &[Handle<Node>]) -> Vec<(Handle<Node>, Quat)> {
+    nodes.iter()
+        .map(|node| (*node, graph.get(*node).base().local_transform().rotation()))
+        .collect()
+}
+
+/// Analytical solver for chain of two bones.
+#[derive(Default)]
+pub struct TwoBoneIk {
+    root: Handle<Node>,
+    middle: Handle<Node>,
+    effector: Handle<Node>,
+    target: Vec3,
+    /// Optional world space point which defines bend direction of middle joint
+    /// (i.e. where knee should point to).
+    pole: Option<Vec3>,
+    weight: PoseWeight,
+}
+
+impl TwoBoneIk {
+    /// Creates new two bone solver. `root` is upper bone (i.e. hip), `middle` is
+    /// middle joint (i.e. knee) and `effector` is end of chain (i.e. ankle).
+    pub fn new(root: Handle<Node>, middle: Handle<Node>, effector: Handle<Node>, weight: PoseWeight) -> Self {
+        Self {
+            root,
+            middle,
+            effector,
+            target: Vec3::ZERO,
+            pole: None,
+            weight,
+        }
+    }
+
+    pub fn set_target(&mut self, target: Vec3) -> &mut Self {
+        self.target = target;
+        self
+    }
+
+    pub fn target(&self) -> Vec3 {
+        self.target
+    }
+
+    pub fn set_pole(&mut self, pole: Option<Vec3>) -> &mut Self {
+        self.pole = pole;
+        self
+    }
+
+    pub fn pole(&self) -> Option<Vec3> {
+        self.pole
+    }
+
+    fn solve(&self, graph: &mut Graph) {
+        let a = calculate_global_position(graph, self.root);
+        let b = calculate_global_position(graph, self.middle);
+        let c = calculate_global_position(graph, self.effector);
+
+        let upper_len = (b - a).len();
+        let lower_len = (c - b).len();
+        if upper_len <= std::f32::EPSILON || lower_len <= std::f32::EPSILON {
+            return;
+        }
+
+        // Target can be unreachable, so clamp distance to it a bit less than chain length to
+        // prevent chain from being fully straight which leads to unstable bend direction.
+        let max_len = (upper_len + lower_len) * 0.9999;
+        let target_distance = clampf((self.target - a).len(), (upper_len - lower_len).abs() + 0.0001, max_len);
+
+        // First bend middle joint so distance between root and effector will be equal to
+        // distance to target.
+        let ba = a - b;
+        let bc = c - b;
+        let current_angle = clampf(ba.dot(&bc) / (upper_len * lower_len), -1.0, 1.0).acos();
+        let desired_angle = clampf(
+            (upper_len * upper_len + lower_len * lower_len - target_distance * target_distance) / (2.0 * upper_len * lower_len),
+            -1.0, 1.0).acos();
+
+        let bend_axis = bc.cross(&ba).normalized()
+            .or_else(|| self.pole.and_then(|pole| bc.cross(&(pole - b)).normalized()))
+            .or_else(|| bc.cross(&Vec3::RIGHT).normalized())
+            .or_else(|| bc.cross(&Vec3::UP).normalized());
+        if let Some(bend_axis) = bend_axis {
+            rotate_node(graph, self.middle, bend_axis, current_angle - desired_angle);
+        }
+
+        // Then rotate root so effector will point to target.
+        let c = calculate_global_position(graph, self.effector);
+        rotate_node_from_to(graph, self.root, c - a, self.target - a);
+
+        // Finally twist chain around root-target axis to make middle joint point to pole.
+        if let Some(pole) = self.pole {
+            if let Some(axis) = (self.target - a).normalized() {
+                let b = calculate_global_position(graph, self.middle);
+                let project = |v: Vec3| v - axis.scale(axis.dot(&v));
+                if let (Some(from), Some(to)) = (project(b - a).normalized(), project(pole - a).normalized()) {
+                    let mut angle = clampf(from.dot(&to), -1.0, 1.0).acos();
+                    if from.cross(&to).dot(&axis) < 0.0 {
+                        angle = -angle;
+                    }
+                    rotate_node(graph, self.root, axis, angle);
+                }
+            }
+        }
+    }
+}
+
+impl Visit for TwoBoneIk {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.root.visit("Root", visitor)?;
+        self.middle.visit("Middle", visitor)?;
+        self.effector.visit("Effector", visitor)?;
+        self.target.visit("Target", visitor)?;
+        self.pole.visit("Pole", visitor)?;
+        self.weight.visit("Weight", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Aim constraint - rotates node so given local axis of node will point to target.
+pub struct LookAt {
+    node: Handle<Node>,
+    /// Axis in local space of node which should point to target.
+    axis: Vec3,
+    target: Vec3,
+    /// Maximum angle (in radians) of rotation.
+    max_angle: f32,
+    weight: PoseWeight,
+}
+
+impl Default for LookAt {
+    fn default() -> Self {
+        Self {
+            node: Handle::NONE,
+            axis: Vec3::LOOK,
+            target: Vec3::ZERO,
+            max_angle: std::f32::consts::PI,
+            weight: PoseWeight::Constant(1.0),
+        }
+    }
+}
+
+impl LookAt {
+    /// Creates new look at constraint for given node. `axis` must be defined in local
+    /// space of node, in most cases it is `Vec3::LOOK`.
+    pub fn new(node: Handle<Node>, axis: Vec3, weight: PoseWeight) -> Self {
+        Self {
+            node,
+            axis,
+            weight,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_target(&mut self, target: Vec3) -> &mut Self {
+        self.target = target;
+        self
+    }
+
+    pub fn target(&self) -> Vec3 {
+        self.target
+    }
+
+    /// Sets maximum angle (in radians) of rotation, useful to prevent head from being
+    /// rotated by 180 degrees.
+    pub fn set_max_angle(&mut self, max_angle: f32) -> &mut Self {
+        self.max_angle = max_angle;
+        self
+    }
+
+    pub fn max_angle(&self) -> f32 {
+        self.max_angle
+    }
+
+    fn solve(&self, graph: &mut Graph) {
+        let transform = calculate_global_transform(graph, self.node);
+        let from = transform.transform_vector_normal(self.axis);
+        let to = self.target - transform.position();
+        if let (Some(from), Some(to)) = (from.normalized(), to.normalized()) {
+            if let Some(axis) = from.cross(&to).normalized() {
+                let angle = clampf(from.dot(&to), -1.0, 1.0).acos().min(self.max_angle);
+                rotate_node(graph, self.node, axis, angle);
+            }
+        }
+    }
+}
+
+impl Visit for LookAt {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.node.visit("Node", visitor)?;
+        self.axis.visit("Axis", visitor)?;
+        self.target.visit("Target", visitor)?;
+        self.max_angle.visit("MaxAngle", visitor)?;
+        self.weight.visit("Weight", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Forward And Backward Reaching Inverse Kinematics solver for chains of arbitrary length.
+pub struct Fabrik {
+    /// Chain of nodes from root to effector, each node must be a child of previous one.
+    chain: Vec<Handle<Node>>,
+    target: Vec3,
+    max_iterations: u32,
+    tolerance: f32,
+    weight: PoseWeight,
+}
+
+impl Default for Fabrik {
+    fn default() -> Self {
+        Self {
+            chain: Default::default(),
+            target: Vec3::ZERO,
+            max_iterations: 10,
+            tolerance: 0.001,
+            weight: PoseWeight::Constant(1.0),
+        }
+    }
+}
+
+impl Fabrik {
+    /// Creates new FABRIK solver for given chain of nodes. First node in chain is root,
+    /// last - effector.
+    pub fn new(chain: Vec<Handle<Node>>, weight: PoseWeight) -> Self {
+        Self {
+            chain,
+            weight,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_target(&mut self, target: Vec3) -> &mut Self {
+        self.target = target;
+        self
+    }
+
+    pub fn target(&self) -> Vec3 {
+        self.target
+    }
+
+    pub fn set_max_iterations(&mut self, max_iterations: u32) -> &mut Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn max_iterations(&self) -> u32 {
+        self.max_iterations
+    }
+
+    /// Sets distance between effector and target at which solver will stop iterating.
+    pub fn set_tolerance(&mut self, tolerance: f32) -> &mut Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    pub fn chain(&self) -> &[Handle<Node>] {
+        &self.chain
+    }
+
+    fn solve(&self, graph: &mut Graph) {
+        if self.chain.len() < 2 {
+            return;
+        }
+
+        let mut positions = self.chain.iter()
+            .map(|node| calculate_global_position(graph, *node))
+            .collect::<Vec<_>>();
+        let lengths = positions.windows(2)
+            .map(|pair| (pair[1] - pair[0]).len())
+            .collect::<Vec<_>>();
+        let root = positions[0];
+        let last = positions.len() - 1;
+
+        let total_length: f32 = lengths.iter().sum();
+        if (self.target - root).len() >= total_length {
+            // Target is unreachable - stretch chain towards target.
+            if let Some(dir) = (self.target - root).normalized() {
+                for i in 0..last {
+                    positions[i + 1] = positions[i] + dir.scale(lengths[i]);
+                }
+            }
+        } else {
+            for _ in 0..self.max_iterations {
+                if (positions[last] - self.target).len() <= self.tolerance {
+                    break;
+                }
+
+                // Backward reaching - move effector to target and pull rest of chain.
+                positions[last] = self.target;
+                for i in (0..last).rev() {
+                    if let Some(dir) = (positions[i] - positions[i + 1]).normalized() {
+                        positions[i] = positions[i + 1] + dir.scale(lengths[i]);
+                    }
+                }
+
+                // Forward reaching - move root back to its place and push rest of chain.
+                positions[0] = root;
+                for i in 0..last {
+                    if let Some(dir) = (positions[i + 1] - positions[i]).normalized() {
+                        positions[i + 1] = positions[i] + dir.scale(lengths[i]);
+                    }
+                }
+            }
+        }
+
+        // Convert positions to rotations of nodes.
+        for i in 0..last {
+            let current_start = calculate_global_position(graph, self.chain[i]);
+            let current_end = calculate_global_position(graph, self.chain[i + 1]);
+            rotate_node_from_to(graph, self.chain[i], current_end - current_start, positions[i + 1] - current_start);
+        }
+    }
+}
+
+impl Visit for Fabrik {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.chain.visit("Chain", visitor)?;
+        self.target.visit("Target", visitor)?;
+        self.max_iterations.visit("MaxIterations", visitor)?;
+        self.tolerance.visit("Tolerance", visitor)?;
+        self.weight.visit("Weight", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Inverse kinematics solver. See documentation for each variant.
+pub enum IkSolver {
+    /// See docs for `TwoBoneIk`.
+    TwoBone(TwoBoneIk),
+
+    /// See docs for `LookAt`.
+    LookAt(LookAt),
+
+    /// See docs for `Fabrik`.
+    Fabrik(Fabrik),
+}
+
+impl Default for IkSolver {
+    fn default() -> Self {
+        IkSolver::TwoBone(Default::default())
+    }
+}
+
+macro_rules! static_dispatch {
+    ($self:ident, $func:ident, $($args:expr),*) => {
+        match $self {
+            IkSolver::TwoBone(v) => v.$func($($args),*),
+            IkSolver::LookAt(v) => v.$func($($args),*),
+            IkSolver::Fabrik(v) => v.$func($($args),*),
+        }
+    };
+}
+
+impl IkSolver {
+    fn from_id(id: i32) -> Result<Self, String> {
+        match id {
+            0 => Ok(IkSolver::TwoBone(Default::default())),
+            1 => Ok(IkSolver::LookAt(Default::default())),
+            2 => Ok(IkSolver::Fabrik(Default::default())),
+            _ => Err(format!("Invalid ik solver id {}", id))
+        }
+    }
+
+    fn id(&self) -> i32 {
+        match self {
+            IkSolver::TwoBone(_) => 0,
+            IkSolver::LookAt(_) => 1,
+            IkSolver::Fabrik(_) => 2,
+        }
+    }
+
+    /// Sets new target position in world coordinates.
+    pub fn set_target(&mut self, target: Vec3) -> &mut Self {
+        match self {
+            IkSolver::TwoBone(v) => v.target = target,
+            IkSolver::LookAt(v) => v.target = target,
+            IkSolver::Fabrik(v) => v.target = target,
+        }
+        self
+    }
+
+    pub fn target(&self) -> Vec3 {
+        static_dispatch!(self, target, )
+    }
+
+    pub fn weight(&self) -> &PoseWeight {
+        match self {
+            IkSolver::TwoBone(v) => &v.weight,
+            IkSolver::LookAt(v) => &v.weight,
+            IkSolver::Fabrik(v) => &v.weight,
+        }
+    }
+
+    pub fn set_weight(&mut self, weight: PoseWeight) -> &mut Self {
+        match self {
+            IkSolver::TwoBone(v) => v.weight = weight,
+            IkSolver::LookAt(v) => v.weight = weight,
+            IkSolver::Fabrik(v) => v.weight = weight,
+        }
+        self
+    }
+
+    /// Returns list of nodes which local transforms are modified by solver.
+    fn affected_nodes(&self) -> Vec<Handle<Node>> {
+        match self {
+            IkSolver::TwoBone(v) => vec![v.root, v.middle],
+            IkSolver::LookAt(v) => vec![v.node],
+            IkSolver::Fabrik(v) => v.chain.clone(),
+        }
+    }
+
+    /// Solves chain with given weight. Weight of 0.0 leaves animated pose untouched, 1.0 fully
+    /// replaces it with solved pose.
+    pub fn solve(&self, graph: &mut Graph, weight: f32) {
+        if weight <= 0.0 {
+            return;
+        }
+
+        let original = snapshot_rotations(graph, &self.affected_nodes());
+
+        static_dispatch!(self, solve, graph);
+
+        if weight < 1.0 {
+            blend_with_original(graph, &original, weight);
+        }
+    }
+}
+
+impl Visit for IkSolver {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut kind_id = self.id();
+        kind_id.visit("KindId", visitor)?;
+        if visitor.is_reading() {
+            *self = IkSolver::from_id(kind_id)?;
+        }
+
+        static_dispatch!(self, visit, "Solver", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Set of solvers that applied one after another in order of addition.
+#[derive(Default)]
+pub struct IkSolverContainer {
+    pool: Pool<IkSolver>
+}
+
+impl IkSolverContainer {
+    pub fn new() -> Self {
+        Self {
+            pool: Pool::new()
+        }
+    }
+
+    #[inline]
+    pub fn add(&mut self, solver: IkSolver) -> Handle<IkSolver> {
+        self.pool.spawn(solver)
+    }
+
+    #[inline]
+    pub fn remove(&mut self, handle: Handle<IkSolver>) {
+        self.pool.free(handle)
+    }
+
+    #[inline]
+    pub fn get(&self, handle: Handle<IkSolver>) -> &IkSolver {
+        self.pool.borrow(handle)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, handle: Handle<IkSolver>) -> &mut IkSolver {
+        self.pool.borrow_mut(handle)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> PoolIterator<IkSolver> {
+        self.pool.iter()
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> PoolIteratorMut<IkSolver> {
+        self.pool.iter_mut()
+    }
+
+    /// Applies every solver to graph. Weights of solvers are evaluated using parameters of
+    /// given machine.
+    pub fn solve(&self, graph: &mut Graph, machine: &Machine) {
+        for solver in self.pool.iter() {
+            solver.solve(graph, machine.evaluate_weight(solver.weight()));
+        }
+    }
+}
+
+impl Visit for IkSolverContainer {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.pool.visit("Pool", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            math::vec3::Vec3,
+            pool::Handle,
+        },
+        scene::{
+            node::Node,
+            graph::Graph,
+            base::BaseBuilder,
+            transform::TransformBuilder,
+        },
+        animation::{
+            ik::{
+                calculate_global_position,
+                TwoBoneIk,
+                LookAt,
+                Fabrik,
+                IkSolver,
+            },
+            machine::PoseWeight,
+        },
+    };
+
+    /// Creates vertical chain of nodes with given count of bones of unit length, first node
+    /// of chain is placed at origin.
+    fn make_chain(graph: &mut Graph, bone_count: usize) -> Vec<Handle<Node>> {
+        let mut chain: Vec<Handle<Node>> = Vec::new();
+        for i in 0..=bone_count {
+            let position = if i == 0 { Vec3::ZERO } else { Vec3::new(0.0, 1.0, 0.0) };
+            let node = graph.add_node(Node::Base(BaseBuilder::new()
+                .with_local_transform(TransformBuilder::new()
+                    .with_local_position(position)
+                    .build())
+                .build()));
+            if let Some(parent) = chain.last() {
+                graph.link_nodes(node, *parent);
+            }
+            chain.push(node);
+        }
+        chain
+    }
+
+    fn bone_lengths(graph: &Graph, chain: &[Handle<Node>]) -> Vec<f32> {
+        chain.windows(2)
+            .map(|pair| (calculate_global_position(graph, pair[1]) - calculate_global_position(graph, pair[0])).len())
+            .collect()
+    }
+
+    fn assert_lengths_preserved(graph: &Graph, chain: &[Handle<Node>]) {
+        for length in bone_lengths(graph, chain) {
+            assert!((length - 1.0).abs() < 1.0e-3, "bone length changed: {}", length);
+        }
+    }
+
+    fn two_bone(chain: &[Handle<Node>], target: Vec3) -> IkSolver {
+        let mut solver = TwoBoneIk::new(chain[0], chain[1], chain[2], PoseWeight::Constant(1.0));
+        solver.set_target(target);
+        IkSolver::TwoBone(solver)
+    }
+
+    #[test]
+    fn two_bone_reaches_target() {
+        let mut graph = Graph::new();
+        let chain = make_chain(&mut graph, 2);
+        let target = Vec3::new(1.0, 1.0, 0.0);
+        two_bone(&chain, target).solve(&mut graph, 1.0);
+        let effector = calculate_global_position(&graph, chain[2]);
+        assert!((effector - target).len() < 1.0e-3);
+        assert_lengths_preserved(&graph, &chain);
+    }
+
+    #[test]
+    fn two_bone_unreachable_target() {
+        let mut graph = Graph::new();
+        let chain = make_chain(&mut graph, 2);
+        let target = Vec3::new(5.0, 0.0, 0.0);
+        two_bone(&chain, target).solve(&mut graph, 1.0);
+        // Chain must be stretched towards target.
+        let effector = calculate_global_position(&graph, chain[2]);
+        assert!((effector - Vec3::new(2.0, 0.0, 0.0)).len() < 1.0e-2);
+        assert_lengths_preserved(&graph, &chain);
+    }
+
+    #[test]
+    fn look_at_points_axis_to_target() {
+        let mut graph = Graph::new();
+        let chain = make_chain(&mut graph, 1);
+        let mut look_at = LookAt::new(chain[0], Vec3::UP, PoseWeight::Constant(1.0));
+        look_at.set_target(Vec3::new(0.0, 0.0, 3.0));
+        IkSolver::LookAt(look_at).solve(&mut graph, 1.0);
+        let tip = calculate_global_position(&graph, chain[1]);
+        assert!((tip - Vec3::new(0.0, 0.0, 1.0)).len() < 1.0e-3);
+    }
+
+    #[test]
+    fn fabrik_reaches_target() {
+        let mut graph = Graph::new();
+        let chain = make_chain(&mut graph, 3);
+        let target = Vec3::new(1.5, 1.5, 0.0);
+        let mut fabrik = Fabrik::new(chain.clone(), PoseWeight::Constant(1.0));
+        fabrik.set_target(target);
+        IkSolver::Fabrik(fabrik).solve(&mut graph, 1.0);
+        let effector = calculate_global_position(&graph, chain[3]);
+        assert!((effector - target).len() < 1.0e-2);
+        assert_lengths_preserved(&graph, &chain);
+    }
+
+    #[test]
+    fn fabrik_unreachable_target() {
+        let mut graph = Graph::new();
+        let chain = make_chain(&mut graph, 3);
+        let mut fabrik = Fabrik::new(chain.clone(), PoseWeight::Constant(1.0));
+        fabrik.set_target(Vec3::new(0.0, 0.0, 10.0));
+        IkSolver::Fabrik(fabrik).solve(&mut graph, 1.0);
+        for (i, node) in chain.iter().enumerate() {
+            let position = calculate_global_position(&graph, *node);
+            assert!((position - Vec3::new(0.0, 0.0, i as f32)).len() < 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn weight_blending() {
+        let target = Vec3::new(0.0, 0.0, 2.0);
+
+        // Zero weight must leave pose untouched.
+        let mut graph = Graph::new();
+        let chain = make_chain(&mut graph, 2);
+        two_bone(&chain, target).solve(&mut graph, 0.0);
+        assert!((calculate_global_position(&graph, chain[2]) - Vec3::new(0.0, 2.0, 0.0)).len() < 1.0e-6);
+
+        // Half weight must put effector between animated and solved positions.
+        let mut graph = Graph::new();
+        let chain = make_chain(&mut graph, 2);
+        two_bone(&chain, target).solve(&mut graph, 0.5);
+        let effector = calculate_global_position(&graph, chain[2]);
+        // Chain is almost straight, so it must be rotated by about 45 degrees.
+        let expected = Vec3::new(0.0, 2.0f32.sqrt(), 2.0f32.sqrt());
+        assert!((effector - expected).len() < 5.0e-2);
+        assert_lengths_preserved(&graph, &chain);
+    }
+}
@@ -0,0 +1,657 @@
+//! Text representation of animation blending machines.
+//!
+//! Machine definition allows to describe a machine in a [RON](https://github.com/ron-rs/ron)
+//! file instead of building it in code, so animators can tweak states, transitions and blend
+//! weights without recompiling a game. Animations are referenced by name (see `Animation::name`),
+//! pose nodes and states are referenced by names given in definition.
+//!
+//! Here is definition of simple locomotion machine:
+//!
+//! ```text
+//! (
+//!     parameters: [
+//!         (name: "Speed", value: Weight(0.0)),
+//!         (name: "Jump", value: Rule(false)),
+//!     ],
+//!     nodes: [
+//!         (name: "Idle", kind: PlayAnimation(animation: "idle")),
+//!         (name: "Jump", kind: PlayAnimation(animation: "jump")),
+//!         (name: "Locomotion", kind: BlendSpace(
+//!             x_parameter: "Speed",
+//!             points: [
+//!                 (x: 1.0, animation: "walk"),
+//!                 (x: 4.0, animation: "run"),
+//!             ],
+//!         )),
+//!         (name: "Aim", kind: BlendAnimations(poses: [
+//!             (weight: Constant(0.5), source: "Locomotion"),
+//!             (weight: Parameter("AimWeight"), source: "Idle"),
+//!         ])),
+//!     ],
+//!     states: [
+//!         (name: "Idle", root: "Idle"),
+//!         (name: "Move", root: "Locomotion"),
+//!         (name: "Jump", root: "Jump"),
+//!     ],
+//!     transitions: [
+//!         (name: "Idle->Move", source: "Idle", dest: "Move", time: 0.3,
+//!          condition: Compare(parameter: "Speed", comparison: Greater, threshold: 0.1)),
+//!         (name: "Move->Idle", source: "Move", dest: "Idle", time: 0.3,
+//!          condition: Compare(parameter: "Speed", comparison: LessOrEqual, threshold: 0.1)),
+//!         (name: "Move->Jump", source: "Move", dest: "Jump", time: 0.2, priority: 1,
+//!          condition: Rule("Jump")),
+//!         (name: "Jump->Idle", source: "Jump", dest: "Idle", time: 0.3,
+//!          condition: And([Not(Rule("Jump")), SourceFinished])),
+//!     ],
+//!     entry_state: Some("Idle"),
+//! )
+//! ```
+//!
+//! Fields `parameters`, `transitions`, `entry_state`, `priority`, `interruptible` and `y_parameter`
+//! and `y` of blend space are optional. Blend space is 2D if `y_parameter` is specified.
+//!
+//! Definition is instantiated against a set of animation handles, usually these are handles
+//! from `ModelInstance::animations` or results of `Model::retarget_animations`:
+//!
+//! Definitions are read and written through virtual file system of resource manager, so they
+//! can be packed into archives along with other resources.
+//!
+//! ```no_run
+//! use rg3d::{
+//!     animation::{
+//!         Animation,
+//!         machine::definition::MachineDefinition,
+//!     },
+//!     engine::resource_manager::ResourceManager,
+//!     scene::Scene,
+//!     core::pool::Handle,
+//! };
+//!
+//! fn create_machine(resource_manager: &ResourceManager, scene: &Scene, animations: &[Handle<Animation>]) {
+//!     let vfs = resource_manager.vfs().read().unwrap();
+//!     let machine = MachineDefinition::load(&vfs, "data/locomotion.ron")
+//!         .unwrap()
+//!         .instantiate(animations, &scene.animations)
+//!         .unwrap();
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    str::FromStr,
+};
+use serde::{Serialize, Deserialize};
+use crate::{
+    animation::{
+        Animation,
+        AnimationContainer,
+        machine::{
+            Machine,
+            PoseNode,
+            PlayAnimation,
+            BlendAnimation,
+            BlendPose,
+            BlendSpace,
+            BlendSpacePoint,
+            PoseWeight,
+            Parameter,
+            Comparison,
+            Condition,
+            State,
+            Transition,
+        },
+    },
+    core::{
+        pool::Handle,
+        math::vec2::Vec2,
+    },
+    resource::vfs::Vfs,
+};
+
+#[derive(Debug)]
+pub enum MachineDefinitionError {
+    Io(std::io::Error),
+    /// Text of definition is malformed.
+    Syntax(String),
+    /// There is no animation with given name among given animations.
+    UnknownAnimation(String),
+    /// There is no pose node with given name in definition.
+    UnknownNode(String),
+    /// There is no state with given name in definition.
+    UnknownState(String),
+    /// There are two nodes or two states with same name.
+    DuplicateName(String),
+    /// Pose node with given name of a machine refers to animation which does not exist.
+    InvalidAnimation(String),
+}
+
+impl std::fmt::Display for MachineDefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            MachineDefinitionError::Io(io) => write!(f, "Io error: {}", io),
+            MachineDefinitionError::Syntax(err) => write!(f, "Syntax error: {}", err),
+            MachineDefinitionError::UnknownAnimation(name) => write!(f, "Unknown animation {}", name),
+            MachineDefinitionError::UnknownNode(name) => write!(f, "Unknown pose node {}", name),
+            MachineDefinitionError::UnknownState(name) => write!(f, "Unknown state {}", name),
+            MachineDefinitionError::DuplicateName(name) => write!(f, "Duplicate name {}", name),
+            MachineDefinitionError::InvalidAnimation(name) => write!(f, "Pose node {} refers to invalid animation", name),
+        }
+    }
+}
+
+impl From<std::io::Error> for MachineDefinitionError {
+    fn from(err: std::io::Error) -> Self {
+        MachineDefinitionError::Io(err)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ParameterDefinition {
+    pub name: String,
+    pub value: Parameter,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum WeightDefinition {
+    Constant(f32),
+    Parameter(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BlendPoseDefinition {
+    pub weight: WeightDefinition,
+    /// Name of pose node.
+    pub source: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BlendSpacePointDefinition {
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    /// Name of animation.
+    pub animation: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum PoseNodeKindDefinition {
+    PlayAnimation {
+        animation: String
+    },
+    BlendAnimations {
+        poses: Vec<BlendPoseDefinition>
+    },
+    BlendSpace {
+        x_parameter: String,
+        #[serde(default)]
+        y_parameter: Option<String>,
+        points: Vec<BlendSpacePointDefinition>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PoseNodeDefinition {
+    pub name: String,
+    pub kind: PoseNodeKindDefinition,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StateDefinition {
+    pub name: String,
+    /// Name of pose node.
+    pub root: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ConditionDefinition {
+    Rule(String),
+    Compare {
+        parameter: String,
+        comparison: Comparison,
+        threshold: f32,
+    },
+    SourceFinished,
+    SourceNormalizedTime(f32),
+    And(Vec<ConditionDefinition>),
+    Or(Vec<ConditionDefinition>),
+    Not(Box<ConditionDefinition>),
+}
+
+impl ConditionDefinition {
+    fn from_condition(condition: &Condition) -> Self {
+        match condition {
+            Condition::Rule(parameter) => ConditionDefinition::Rule(parameter.clone()),
+            Condition::Compare { parameter, comparison, threshold } => ConditionDefinition::Compare {
+                parameter: parameter.clone(),
+                comparison: *comparison,
+                threshold: *threshold,
+            },
+            Condition::SourceFinished => ConditionDefinition::SourceFinished,
+            Condition::SourceNormalizedTime(time) => ConditionDefinition::SourceNormalizedTime(*time),
+            Condition::And(conditions) => ConditionDefinition::And(conditions.iter().map(Self::from_condition).collect()),
+            Condition::Or(conditions) => ConditionDefinition::Or(conditions.iter().map(Self::from_condition).collect()),
+            Condition::Not(condition) => ConditionDefinition::Not(Box::new(Self::from_condition(condition))),
+        }
+    }
+
+    fn to_condition(&self) -> Condition {
+        match self {
+            ConditionDefinition::Rule(parameter) => Condition::rule(parameter),
+            ConditionDefinition::Compare { parameter, comparison, threshold } => Condition::compare(parameter, *comparison, *threshold),
+            ConditionDefinition::SourceFinished => Condition::SourceFinished,
+            ConditionDefinition::SourceNormalizedTime(time) => Condition::SourceNormalizedTime(*time),
+            ConditionDefinition::And(conditions) => Condition::and(conditions.iter().map(|c| c.to_condition()).collect()),
+            ConditionDefinition::Or(conditions) => Condition::or(conditions.iter().map(|c| c.to_condition()).collect()),
+            ConditionDefinition::Not(condition) => Condition::not(condition.to_condition()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TransitionDefinition {
+    pub name: String,
+    /// Name of source state.
+    pub source: String,
+    /// Name of destination state.
+    pub dest: String,
+    pub time: f32,
+    pub condition: ConditionDefinition,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub interruptible: bool,
+}
+
+/// Serializable description of a machine. See module docs for format description.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct MachineDefinition {
+    #[serde(default)]
+    pub parameters: Vec<ParameterDefinition>,
+    pub nodes: Vec<PoseNodeDefinition>,
+    pub states: Vec<StateDefinition>,
+    #[serde(default)]
+    pub transitions: Vec<TransitionDefinition>,
+    #[serde(default)]
+    pub entry_state: Option<String>,
+}
+
+impl FromStr for MachineDefinition {
+    type Err = MachineDefinitionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ron::de::from_str(s).map_err(|e| MachineDefinitionError::Syntax(e.to_string()))
+    }
+}
+
+impl MachineDefinition {
+    /// Reads definition from file.
+    pub fn load<P: AsRef<Path>>(vfs: &Vfs, path: P) -> Result<Self, MachineDefinitionError> {
+        let data = vfs.read(path)?;
+        String::from_utf8(data)
+            .map_err(|e| MachineDefinitionError::Syntax(e.to_string()))?
+            .parse()
+    }
+
+    /// Writes definition into file.
+    pub fn save<P: AsRef<Path>>(&self, vfs: &Vfs, path: P) -> Result<(), MachineDefinitionError> {
+        let text = self.to_text()?;
+        vfs.write(path, text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns text representation of definition.
+    pub fn to_text(&self) -> Result<String, MachineDefinitionError> {
+        ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|e| MachineDefinitionError::Syntax(e.to_string()))
+    }
+
+    /// Creates definition from existing machine. Pose nodes will be named using their
+    /// position in machine, animations must have names, otherwise definition won't be
+    /// instantiated back. Fails if some pose node refers to animation which is not in given
+    /// container, or if some blend pose or state refers to pose node which is not in machine -
+    /// in this case error holds name of referring node or state.
+    pub fn from_machine(machine: &Machine, animations: &AnimationContainer) -> Result<Self, MachineDefinitionError> {
+        let node_names = machine.nodes.pair_iter()
+            .enumerate()
+            .map(|(i, (handle, _))| (handle, format!("Node{}", i)))
+            .collect::<HashMap<_, _>>();
+        let node_name = |handle: Handle<PoseNode>| node_names.get(&handle).cloned().unwrap_or_default();
+        let source_name = |handle: Handle<PoseNode>, referrer: String| {
+            node_names.get(&handle)
+                .cloned()
+                .ok_or(MachineDefinitionError::UnknownNode(referrer))
+        };
+        let animation_name = |node: Handle<PoseNode>, handle: Handle<Animation>| {
+            animations.try_get(handle)
+                .map(|animation| animation.name().to_owned())
+                .ok_or_else(|| MachineDefinitionError::InvalidAnimation(node_name(node)))
+        };
+
+        let mut nodes = Vec::new();
+        for (handle, node) in machine.nodes.pair_iter() {
+            nodes.push(PoseNodeDefinition {
+                name: node_name(handle),
+                kind: match node {
+                    PoseNode::PlayAnimation(play_animation) => PoseNodeKindDefinition::PlayAnimation {
+                        animation: animation_name(handle, play_animation.animation)?
+                    },
+                    PoseNode::BlendAnimations(blend_animation) => PoseNodeKindDefinition::BlendAnimations {
+                        poses: blend_animation.pose_sources
+                            .borrow()
+                            .iter()
+                            .map(|pose| Ok(BlendPoseDefinition {
+                                weight: match &pose.weight {
+                                    PoseWeight::Constant(value) => WeightDefinition::Constant(*value),
+                                    PoseWeight::Parameter(id) => WeightDefinition::Parameter(id.clone()),
+                                },
+                                source: source_name(pose.pose_source, node_name(handle))?,
+                            }))
+                            .collect::<Result<_, _>>()?
+                    },
+                    PoseNode::BlendSpace(blend_space) => {
+                        let mut points = Vec::new();
+                        for point in blend_space.points.iter() {
+                            points.push(BlendSpacePointDefinition {
+                                x: point.position.x,
+                                y: point.position.y,
+                                animation: animation_name(handle, point.animation)?,
+                            });
+                        }
+                        PoseNodeKindDefinition::BlendSpace {
+                            x_parameter: blend_space.x_parameter.clone(),
+                            y_parameter: if blend_space.is_2d() { Some(blend_space.y_parameter.clone()) } else { None },
+                            points,
+                        }
+                    }
+                },
+            });
+        }
+
+        let mut parameters = machine.parameters.iter()
+            .map(|(name, value)| ParameterDefinition {
+                name: name.clone(),
+                value: *value,
+            })
+            .collect::<Vec<_>>();
+        // Hash map has no stable order, sort parameters to get same output for same machine.
+        parameters.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let state_name = |handle: Handle<State>| machine.states.borrow(handle).name.clone();
+
+        Ok(Self {
+            parameters,
+            nodes,
+            states: machine.states.iter()
+                .map(|state| Ok(StateDefinition {
+                    name: state.name.clone(),
+                    root: source_name(state.root, state.name.clone())?,
+                }))
+                .collect::<Result<_, _>>()?,
+            transitions: machine.transitions.iter()
+                .map(|transition| TransitionDefinition {
+                    name: transition.name.clone(),
+                    source: state_name(transition.source),
+                    dest: state_name(transition.dest),
+                    time: transition.transition_time,
+                    condition: ConditionDefinition::from_condition(&transition.condition),
+                    priority: transition.priority,
+                    interruptible: transition.interruptible,
+                })
+                .collect(),
+            entry_state: if machine.entry_state.is_some() {
+                Some(state_name(machine.entry_state))
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Creates new machine using given animations. Animations are searched by names among
+    /// given handles, usually these are handles from `ModelInstance::animations`.
+    pub fn instantiate(&self, animation_handles: &[Handle<Animation>], animations: &AnimationContainer) -> Result<Machine, MachineDefinitionError> {
+        let find_animation = |name: &str| {
+            let handle = animations.find_by_name(animation_handles, name);
+            if handle.is_none() {
+                Err(MachineDefinitionError::UnknownAnimation(name.to_owned()))
+            } else {
+                Ok(handle)
+            }
+        };
+
+        let mut machine = Machine::new();
+
+        for parameter in self.parameters.iter() {
+            machine.set_parameter(&parameter.name, parameter.value);
+        }
+
+        // Reserve handles for every node first, because nodes can reference each other
+        // regardless of their order in definition.
+        let mut node_map = HashMap::new();
+        for node in self.nodes.iter() {
+            if node_map.insert(node.name.as_str(), machine.add_node(Default::default())).is_some() {
+                return Err(MachineDefinitionError::DuplicateName(node.name.clone()));
+            }
+        }
+        let find_node = |name: &str| {
+            node_map.get(name)
+                .copied()
+                .ok_or_else(|| MachineDefinitionError::UnknownNode(name.to_owned()))
+        };
+
+        for node in self.nodes.iter() {
+            let pose_node = match &node.kind {
+                PoseNodeKindDefinition::PlayAnimation { animation } => {
+                    PoseNode::PlayAnimation(PlayAnimation::new(find_animation(animation)?))
+                }
+                PoseNodeKindDefinition::BlendAnimations { poses } => {
+                    let mut blend_poses = Vec::new();
+                    for pose in poses.iter() {
+                        let weight = match &pose.weight {
+                            WeightDefinition::Constant(value) => PoseWeight::Constant(*value),
+                            WeightDefinition::Parameter(id) => PoseWeight::Parameter(id.clone()),
+                        };
+                        blend_poses.push(BlendPose::new(weight, find_node(&pose.source)?));
+                    }
+                    PoseNode::BlendAnimations(BlendAnimation::new(blend_poses))
+                }
+                PoseNodeKindDefinition::BlendSpace { x_parameter, y_parameter, points } => {
+                    let mut blend_space_points = Vec::new();
+                    for point in points.iter() {
+                        blend_space_points.push(BlendSpacePoint::new(Vec2::new(point.x, point.y), find_animation(&point.animation)?));
+                    }
+                    PoseNode::BlendSpace(match y_parameter {
+                        Some(y_parameter) => BlendSpace::new_2d(x_parameter, y_parameter, blend_space_points),
+                        None => BlendSpace::new_1d(x_parameter, blend_space_points),
+                    })
+                }
+            };
+            *machine.nodes.borrow_mut(find_node(&node.name)?) = pose_node;
+        }
+
+        let mut state_map = HashMap::new();
+        for state in self.states.iter() {
+            let handle = machine.add_state(State::new(&state.name, find_node(&state.root)?));
+            if state_map.insert(state.name.as_str(), handle).is_some() {
+                return Err(MachineDefinitionError::DuplicateName(state.name.clone()));
+            }
+        }
+        let find_state = |name: &str| {
+            state_map.get(name)
+                .copied()
+                .ok_or_else(|| MachineDefinitionError::UnknownState(name.to_owned()))
+        };
+
+        for transition in self.transitions.iter() {
+            machine.add_transition(Transition::with_condition(
+                &transition.name,
+                find_state(&transition.source)?,
+                find_state(&transition.dest)?,
+                transition.time,
+                transition.condition.to_condition())
+                .with_priority(transition.priority)
+                .with_interruptible(transition.interruptible));
+        }
+
+        if let Some(entry_state) = self.entry_state.as_ref() {
+            machine.set_entry_state(find_state(entry_state)?);
+        }
+
+        Ok(machine)
+    }
+}
+
+impl Machine {
+    /// Creates new machine from definition file. See `definition` module docs.
+    pub fn load_definition<P: AsRef<Path>>(vfs: &Vfs, path: P, animation_handles: &[Handle<Animation>], animations: &AnimationContainer) -> Result<Machine, MachineDefinitionError> {
+        MachineDefinition::load(vfs, path)?.instantiate(animation_handles, animations)
+    }
+
+    /// Writes machine into definition file. See `definition` module docs.
+    pub fn save_definition<P: AsRef<Path>>(&self, vfs: &Vfs, path: P, animations: &AnimationContainer) -> Result<(), MachineDefinitionError> {
+        MachineDefinition::from_machine(self, animations)?.save(vfs, path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{
+            Animation,
+            AnimationContainer,
+            machine::{
+                definition::{
+                    MachineDefinition,
+                    MachineDefinitionError,
+                    PoseNodeKindDefinition,
+                    ConditionDefinition,
+                },
+                BlendPose,
+                Comparison,
+                Machine,
+                Parameter,
+                PoseNode,
+                PoseWeight,
+                State,
+            },
+        },
+        core::pool::Handle,
+    };
+
+    fn make_animations(names: &[&str]) -> (AnimationContainer, Vec<Handle<Animation>>) {
+        let mut animations = AnimationContainer::new();
+        let handles = names.iter()
+            .map(|name| {
+                let mut animation = Animation::default();
+                animation.set_name(name);
+                animations.add(animation)
+            })
+            .collect();
+        (animations, handles)
+    }
+
+    const DEFINITION: &str = r#"(
+        parameters: [
+            (name: "Speed", value: Weight(0.0)),
+        ],
+        nodes: [
+            (name: "Idle", kind: PlayAnimation(animation: "idle")),
+            (name: "Move", kind: BlendSpace(x_parameter: "Speed", points: [(x: 1.0, animation: "walk"), (x: 4.0, animation: "run")])),
+        ],
+        states: [
+            (name: "Idle", root: "Idle"),
+            (name: "Move", root: "Move"),
+        ],
+        transitions: [
+            (name: "Idle->Move", source: "Idle", dest: "Move", time: 0.3,
+             condition: Compare(parameter: "Speed", comparison: Greater, threshold: 0.1)),
+        ],
+        entry_state: Some("Idle"),
+    )"#;
+
+    #[test]
+    fn parse_and_write_definition() {
+        let definition: MachineDefinition = DEFINITION.parse().unwrap();
+        assert_eq!(definition.nodes.len(), 2);
+        match &definition.nodes[1].kind {
+            PoseNodeKindDefinition::BlendSpace { y_parameter, points, .. } => {
+                assert!(y_parameter.is_none());
+                assert_eq!(points.len(), 2);
+            }
+            _ => panic!("Blend space expected!"),
+        }
+        assert_eq!(definition.transitions[0].condition, ConditionDefinition::Compare {
+            parameter: "Speed".to_owned(),
+            comparison: Comparison::Greater,
+            threshold: 0.1,
+        });
+
+        // Written definition must be parsed back into the same definition.
+        let text = definition.to_text().unwrap();
+        let other: MachineDefinition = text.parse().unwrap();
+        assert_eq!(definition, other);
+    }
+
+    #[test]
+    fn instantiate_definition() {
+        let (animations, handles) = make_animations(&["idle", "walk", "run"]);
+        let definition: MachineDefinition = DEFINITION.parse().unwrap();
+        let machine = definition.instantiate(&handles, &animations).unwrap();
+
+        assert_eq!(machine.get_parameter("Speed"), Some(Parameter::Weight(0.0)));
+        assert_eq!(machine.states.iter().count(), 2);
+        assert_eq!(machine.get_state(machine.entry_state).name(), "Idle");
+        let idle_root = machine.states.borrow(machine.entry_state).root;
+        match machine.nodes.borrow(idle_root) {
+            PoseNode::PlayAnimation(play_animation) => assert_eq!(play_animation.animation, handles[0]),
+            _ => panic!("Play animation expected!"),
+        }
+        let transition = machine.transitions.iter().next().unwrap();
+        assert_eq!(transition.name(), "Idle->Move");
+        assert_eq!(machine.get_state(transition.source()).name(), "Idle");
+        assert_eq!(machine.get_state(transition.dest()).name(), "Move");
+
+        // Definition of instantiated machine must produce same machine.
+        let other = MachineDefinition::from_machine(&machine, &animations).unwrap()
+            .instantiate(&handles, &animations)
+            .unwrap();
+        assert_eq!(MachineDefinition::from_machine(&other, &animations).unwrap(),
+                   MachineDefinition::from_machine(&machine, &animations).unwrap());
+    }
+
+    #[test]
+    fn instantiate_with_unknown_animation() {
+        let (animations, handles) = make_animations(&["idle", "walk"]);
+        let definition: MachineDefinition = DEFINITION.parse().unwrap();
+        match definition.instantiate(&handles, &animations) {
+            Err(MachineDefinitionError::UnknownAnimation(name)) => assert_eq!(name, "run"),
+            _ => panic!("Unknown animation error expected!"),
+        }
+    }
+
+    #[test]
+    fn machine_with_invalid_animation() {
+        let (animations, _) = make_animations(&["idle"]);
+        let mut machine = Machine::new();
+        let node = machine.add_node(PoseNode::make_play_animation(Handle::NONE));
+        machine.add_state(State::new("Idle", node));
+        match MachineDefinition::from_machine(&machine, &animations) {
+            Err(MachineDefinitionError::InvalidAnimation(name)) => assert_eq!(name, "Node0"),
+            _ => panic!("Invalid animation error expected!"),
+        }
+    }
+
+    #[test]
+    fn machine_with_invalid_pose_source() {
+        let (animations, _) = make_animations(&[]);
+        let mut machine = Machine::new();
+        let node = machine.add_node(PoseNode::make_blend_animations(vec![
+            BlendPose::new(PoseWeight::Constant(1.0), Handle::NONE)
+        ]));
+        machine.add_state(State::new("Blend", node));
+        match MachineDefinition::from_machine(&machine, &animations) {
+            Err(MachineDefinitionError::UnknownNode(name)) => assert_eq!(name, "Node0"),
+            _ => panic!("Unknown node error expected!"),
+        }
+    }
+}
@@ -0,0 +1,2145 @@
+//! Animation blending state machine.
+//!
+//! Machine is used to blend multiple animation as well as perform automatic "smooth transition
+//! between states. Let have a quick look at simple machine graph:
+//!
+//!                                                  +-------------+
+//!                                                  |  Idle Anim  |
+//!                                                  +------+------+
+//!                                                         |
+//!           Walk Weight                                   |
+//! +-----------+      +-------+           Walk->Idle Rule  |
+//! | Walk Anim +------+       |                            |
+//! +-----------+      |       |      +-------+         +---+---+
+//!                    | Blend |      |       +-------->+       |
+//!                    |       +------+ Walk  |         |  Idle |
+//! +-----------+      |       |      |       +<--------+       |
+//! | Aim Anim  +------+       |      +--+----+         +---+---+
+//! +-----------+      +-------+         |                  ^
+//!           Aim Weight                 | Idle->Walk Rule  |
+//!                                      |                  |
+//!                       Walk->Run Rule |    +---------+   | Run->Idle Rule
+//!                                      |    |         |   |
+//!                                      +--->+   Run   +---+
+//!                                           |         |
+//!                                           +----+----+
+//!                                                |
+//!                                                |
+//!                                         +------+------+
+//!                                         |  Run Anim   |
+//!                                         +-------------+
+//!
+//! Here we have Walk, Idle, Run states which uses different sources of poses:
+//! - Walk - is most complicated here - it uses result of blending between
+//!   Aim and Walk animations with different weights. This is useful if your
+//!   character can only walk or can walk *and* aim at the same time. Desired pose
+//!   determined by Walk Weight and Aim Weight parameters combination.
+//! - Run and idle both directly uses animation as pose source.
+//!
+//! There are four transitions between three states each with its own rule. Rule
+//! is just Rule parameter which can have boolean value that indicates that transition
+//! should be activated. Instead of single rule transition can use more complex `Condition`,
+//! which can compare Weight parameters with thresholds, check whether animation of source
+//! state has finished and combine other conditions using and/or/not. When several transitions
+//! can be activated at once, the one with highest priority wins. Interruptible transitions
+//! can be interrupted by a transition from their destination state.
+//!
+//! Example:
+//!
+//! ```no_run
+//! use rg3d::{
+//!     animation::machine::{
+//!         Machine, State, Transition, PoseNode, BlendPose,
+//!         Parameter, PlayAnimation, PoseWeight, BlendAnimation
+//!     },
+//!     core::pool::Handle
+//! };
+//!
+//! // Assume that these are correct handles.
+//! let idle_animation = Handle::default();
+//! let walk_animation = Handle::default();
+//! let aim_animation = Handle::default();
+//!
+//! let mut machine = Machine::new();
+//!
+//! machine.add_parameter("WalkToIdle", Parameter::Rule(false));
+//! machine.add_parameter("IdleToWalk", Parameter::Rule(false));
+//!
+//! let aim = machine.add_node(PoseNode::PlayAnimation(PlayAnimation::new(aim_animation)));
+//! let walk = machine.add_node(PoseNode::PlayAnimation(PlayAnimation::new(walk_animation)));
+//!
+//! // Blend two animations together
+//! let blend_aim_walk = machine.add_node(PoseNode::BlendAnimations(
+//!     BlendAnimation::new(vec![
+//!         BlendPose::new(PoseWeight::Constant(0.75), aim),
+//!         BlendPose::new(PoseWeight::Constant(0.25), walk)
+//!     ])
+//! ));
+//!
+//! let walk_state = machine.add_state(State::new("Walk", blend_aim_walk));
+//!
+//! let idle = machine.add_node(PoseNode::PlayAnimation(PlayAnimation::new(idle_animation)));
+//! let idle_state = machine.add_state(State::new("Idle", idle));
+//!
+//! machine.add_transition(Transition::new("Walk->Idle", walk_state, idle_state, 1.0, "WalkToIdle"));
+//! machine.add_transition(Transition::new("Idle->Walk", idle_state, walk_state, 1.0, "IdleToWalk"));
+//!
+//! ```
+//!
+//! Besides play and blend nodes there is a blend space node, which blends set of animations
+//! placed in 1D or 2D parameter space. It is handy for locomotion: walk, jog and run animations
+//! can be blended by speed of a character using single Weight parameter. See `BlendSpace` docs.
+//!
+//! Signals of animations (see `AnimationSignal`) are dispatched through the machine as well:
+//! machine emits `Event::Signal` for every signal of an animation which contributes to final
+//! pose with weight above `Machine::set_signal_weight_threshold`, so gameplay code does not
+//! need to poll every underlying animation. Machine must be evaluated once per update of
+//! animations, otherwise signals will be reported multiple times.
+//!
+//! You can use multiple machines to animation single model - for example one machine can be for
+//! locomotion and other is for combat. This means that locomotion machine will take control over
+//! lower body and combat machine will control upper body.
+
+pub mod definition;
+
+use std::{
+    cell::{RefCell, Ref},
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+};
+use crate::{
+    utils::log::Log,
+    animation::{
+        Animation,
+        AnimationContainer,
+        AnimationPose,
+        AnimationEvent,
+    },
+    core::{
+        math::vec2::Vec2,
+        pool::{
+            Pool,
+            Handle,
+            PoolIterator
+        },
+        visitor::{
+            Visit,
+            Visitor,
+            VisitError,
+            VisitResult,
+        },
+    }
+};
+use serde::{Serialize, Deserialize};
+
+/// Specific machine event.
+pub enum Event {
+    /// Occurs when enter some state. See module docs for example.
+    StateEnter(Handle<State>),
+
+    /// Occurs when leaving some state. See module docs for example.
+    StateLeave(Handle<State>),
+
+    /// Occurs when transition is done and new active state was set.
+    ActiveStateChanged(Handle<State>),
+
+    /// Occurs when time position of animation, which contributes to final pose of machine
+    /// with weight above threshold, passes a signal.
+    Signal {
+        /// State which uses animation.
+        state: Handle<State>,
+        animation: Handle<Animation>,
+        /// Total weight of animation in final pose.
+        weight: f32,
+        event: AnimationEvent,
+    },
+}
+
+/// Machine node that plays specified animation.
+#[derive(Default)]
+pub struct PlayAnimation {
+    pub animation: Handle<Animation>,
+    output_pose: RefCell<AnimationPose>,
+}
+
+impl PlayAnimation {
+    /// Creates new PlayAnimation node with given animation handle.
+
+    pub fn new(animation: Handle<Animation>) -> Self {
+        Self {
+            animation,
+            output_pose: Default::default(),
+        }
+    }
+}
+
+impl Visit for PlayAnimation {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.animation.visit("Animation", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Machine parameter.  Machine uses various parameters for specific actions. For example
+/// Rule parameter is used to check where transition from a state to state is possible.
+/// See module docs for example.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Parameter {
+    /// Weight parameter is used to control blend weight in BlendAnimation node.
+    Weight(f32),
+
+    /// Rule parameter is used to check where transition from a state to state is possible.
+    Rule(bool),
+}
+
+impl Default for Parameter {
+    fn default() -> Self {
+        Parameter::Weight(0.0)
+    }
+}
+
+impl Parameter {
+    fn from_id(id: i32) -> Result<Self, String> {
+        match id {
+            0 => Ok(Parameter::Weight(0.0)),
+            1 => Ok(Parameter::Rule(false)),
+            _ => Err(format!("Invalid parameter id {}", id))
+        }
+    }
+
+    fn id(self) -> i32 {
+        match self {
+            Parameter::Weight(_) => 0,
+            Parameter::Rule(_) => 1,
+        }
+    }
+}
+
+impl Visit for Parameter {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut id = self.id();
+        id.visit("Id", visitor)?;
+        if visitor.is_reading() {
+            *self = Self::from_id(id)?;
+        }
+
+        match self {
+            Parameter::Weight(weight) => weight.visit("Value", visitor)?,
+            Parameter::Rule(rule) => rule.visit("Value", visitor)?,
+        }
+
+        visitor.leave_region()
+    }
+}
+
+/// Specific animation pose weight.
+pub enum PoseWeight {
+    /// Fixed scalar value. Should not be negative (can't even realize what will happen
+    /// with negative weight here)
+    Constant(f32),
+
+    /// Reference to Weight parameter with given name.
+    Parameter(String),
+}
+
+impl Default for PoseWeight {
+    fn default() -> Self {
+        PoseWeight::Constant(0.0)
+    }
+}
+
+impl PoseWeight {
+    fn from_id(id: i32) -> Result<Self, String> {
+        match id {
+            0 => Ok(PoseWeight::Parameter(Default::default())),
+            1 => Ok(PoseWeight::Constant(0.0)),
+            _ => Err(format!("Invalid pose weight id {}", id))
+        }
+    }
+
+    fn id(&self) -> i32 {
+        match self {
+            PoseWeight::Constant(_) => 0,
+            PoseWeight::Parameter(_) => 1,
+        }
+    }
+}
+
+impl Visit for PoseWeight {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut id = self.id();
+        id.visit("Id", visitor)?;
+        if visitor.is_reading() {
+            *self = Self::from_id(id)?;
+        }
+
+        match self {
+            PoseWeight::Constant(constant) => constant.visit("Value", visitor)?,
+            PoseWeight::Parameter(param_id) => param_id.visit("ParamId", visitor)?,
+        }
+
+        visitor.leave_region()
+    }
+}
+
+/// Weighted proxy for animation pose.
+#[derive(Default)]
+pub struct BlendPose {
+    weight: PoseWeight,
+    pose_source: Handle<PoseNode>,
+}
+
+impl BlendPose {
+    /// Creates new instance of blend pose with given weight and animation pose.
+    pub fn new(weight: PoseWeight, pose_source: Handle<PoseNode>) -> Self {
+        Self {
+            weight,
+            pose_source,
+        }
+    }
+
+    /// Specialized constructor that creates blend pose with constant weight.
+    /// `weight` should be positive.
+    pub fn with_constant_weight(weight: f32, pose_source: Handle<PoseNode>) -> Self {
+        Self {
+            weight: PoseWeight::Constant(weight),
+            pose_source
+        }
+    }
+
+    /// Specialized constructor that creates blend pose with parametrized weight.
+    /// `param_id` must be name of Weight parameter in machine.
+    pub fn with_param_weight(param_id: &str, pose_source: Handle<PoseNode>) -> Self {
+        Self {
+            weight: PoseWeight::Parameter(param_id.to_owned()),
+            pose_source
+        }
+    }
+}
+
+impl Visit for BlendPose {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.weight.visit("Weight", visitor)?;
+        self.pose_source.visit("PoseSource", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Animation blend node. It takes multiple input poses and mixes them together into
+/// single pose with specified weights. Could be used to mix hit and run animations
+/// for example - once your character got hit, you set some significant weight for
+/// hit animation (0.8 for example) and lower weight for run animation (0.2) and it
+/// will look like your character got wounded while it still running (probably you
+/// should decrease speed here too). Weights can be parametrized, which means that
+/// you can dynamically change them in runtime. In our example we can decrease weight
+/// of hit animation over time and increase weight of run animation, so character will
+/// recover from his wounds.
+#[derive(Default)]
+pub struct BlendAnimation {
+    pose_sources: RefCell<Vec<BlendPose>>,
+    output_pose: RefCell<AnimationPose>,
+}
+
+impl BlendAnimation {
+    /// Creates new animation blend node with given poses.
+    pub fn new(poses: Vec<BlendPose>) -> Self {
+        Self {
+            pose_sources: RefCell::new(poses),
+            output_pose: Default::default(),
+        }
+    }
+}
+
+impl Visit for BlendAnimation {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> Result<(), VisitError> {
+        visitor.enter_region(name)?;
+
+        self.pose_sources.visit("PoseSources", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Sample point of a blend space - an animation placed at some position in parameter space.
+#[derive(Default)]
+pub struct BlendSpacePoint {
+    position: Vec2,
+    animation: Handle<Animation>,
+}
+
+impl BlendSpacePoint {
+    /// Creates new blend space point. For 1D blend spaces only `x` coordinate of position
+    /// is used.
+    pub fn new(position: Vec2, animation: Handle<Animation>) -> Self {
+        Self {
+            position,
+            animation,
+        }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn animation(&self) -> Handle<Animation> {
+        self.animation
+    }
+}
+
+impl Visit for BlendSpacePoint {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.position.visit("Position", visitor)?;
+        self.animation.visit("Animation", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Blend space node. It blends set of animations placed in 1D or 2D parameter space using
+/// values of Weight parameters as coordinates of sampling point. Typical usage is blending
+/// walk, jog and run animations by speed of a character (1D) or blending strafe animations
+/// by direction of movement (2D).
+///
+/// In 2D case sample points are triangulated and the pose is blended from animations at the
+/// vertices of the triangle which contains sampling point, if sampling point is outside of
+/// the triangulation, it will be clamped to closest triangle. In 1D case two closest points
+/// are blended.
+///
+/// Sampled animations are synchronized by normalized phase, which means that animations with
+/// different lengths (i.e. walk cycle of 1.2 seconds and run cycle of 0.8 seconds) will be
+/// stretched so feet of a character will be in sync. Time position of animations itself is
+/// not used, so you may disable them in animation container.
+#[derive(Default)]
+pub struct BlendSpace {
+    points: Vec<BlendSpacePoint>,
+    x_parameter: String,
+    /// Empty for 1D blend space.
+    y_parameter: String,
+    /// Triangulation of points of 2D blend space. Non-serializable, calculated on demand.
+    triangles: Vec<[usize; 3]>,
+    weights: Vec<f32>,
+    phase: f32,
+    /// Phase before last update, used to find passed signals. Non-serializable.
+    prev_phase: f32,
+    sample_pose: RefCell<AnimationPose>,
+    output_pose: RefCell<AnimationPose>,
+}
+
+impl BlendSpace {
+    /// Creates new 1D blend space. Position of each point is defined by `x` coordinate and
+    /// sampling point is defined by value of `x_parameter` Weight parameter.
+    pub fn new_1d(x_parameter: &str, points: Vec<BlendSpacePoint>) -> Self {
+        Self {
+            points,
+            x_parameter: x_parameter.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates new 2D blend space. Coordinates of sampling point are defined by values of
+    /// `x_parameter` and `y_parameter` Weight parameters.
+    pub fn new_2d(x_parameter: &str, y_parameter: &str, points: Vec<BlendSpacePoint>) -> Self {
+        let mut blend_space = Self {
+            points,
+            x_parameter: x_parameter.to_owned(),
+            y_parameter: y_parameter.to_owned(),
+            ..Default::default()
+        };
+        blend_space.triangulate();
+        blend_space
+    }
+
+    pub fn is_2d(&self) -> bool {
+        !self.y_parameter.is_empty()
+    }
+
+    pub fn points(&self) -> &[BlendSpacePoint] {
+        &self.points
+    }
+
+    pub fn x_parameter(&self) -> &str {
+        self.x_parameter.as_str()
+    }
+
+    pub fn y_parameter(&self) -> &str {
+        self.y_parameter.as_str()
+    }
+
+    /// Returns current normalized phase (0..1) which is used to sample animations.
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// Returns weights of points calculated on last update.
+    pub fn weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    fn triangulate(&mut self) {
+        let positions = self.points.iter().map(|p| p.position).collect::<Vec<_>>();
+        self.triangles = triangulate(&positions);
+    }
+
+    fn calculate_weights(&mut self, sample: Vec2) {
+        self.weights.clear();
+        self.weights.resize(self.points.len(), 0.0);
+
+        match self.points.len() {
+            0 => (),
+            1 => self.weights[0] = 1.0,
+            _ => {
+                if self.is_2d() {
+                    if self.triangles.is_empty() {
+                        self.triangulate();
+                    }
+                    self.calculate_weights_2d(sample);
+                } else {
+                    self.calculate_weights_1d(sample.x);
+                }
+            }
+        }
+    }
+
+    fn calculate_weights_1d(&mut self, x: f32) {
+        // Find closest points to the left and to the right of sampling point.
+        let mut left: Option<usize> = None;
+        let mut right: Option<usize> = None;
+        for (i, point) in self.points.iter().enumerate() {
+            let px = point.position.x;
+            if px <= x && left.map_or(true, |l| px > self.points[l].position.x) {
+                left = Some(i);
+            }
+            if px >= x && right.map_or(true, |r| px < self.points[r].position.x) {
+                right = Some(i);
+            }
+        }
+
+        match (left, right) {
+            (Some(left), Some(right)) => {
+                let lx = self.points[left].position.x;
+                let rx = self.points[right].position.x;
+                if left == right || rx - lx <= std::f32::EPSILON {
+                    self.weights[left] = 1.0;
+                } else {
+                    let t = (x - lx) / (rx - lx);
+                    self.weights[left] = 1.0 - t;
+                    self.weights[right] = t;
+                }
+            }
+            // Sampling point is out of range - clamp it.
+            (Some(index), None) | (None, Some(index)) => self.weights[index] = 1.0,
+            (None, None) => (),
+        }
+    }
+
+    fn calculate_weights_2d(&mut self, sample: Vec2) {
+        if self.triangles.is_empty() {
+            // Degenerated case - all points lies on the same line, use closest segment.
+            let mut closest = None;
+            let mut min_sqr_distance = std::f32::MAX;
+            for i in 0..self.points.len() {
+                for j in (i + 1)..self.points.len() {
+                    let a = self.points[i].position;
+                    let b = self.points[j].position;
+                    let t = project_on_segment(sample, a, b);
+                    let p = lerp_vec2(a, b, t);
+                    let sqr_distance = sqr_distance_vec2(sample, p);
+                    if sqr_distance < min_sqr_distance {
+                        min_sqr_distance = sqr_distance;
+                        closest = Some((i, j, t));
+                    }
+                }
+            }
+            if let Some((i, j, t)) = closest {
+                self.weights[i] = 1.0 - t;
+                self.weights[j] = t;
+            }
+            return;
+        }
+
+        // Find triangle that contains sampling point or closest one to it.
+        let mut closest = None;
+        let mut min_sqr_distance = std::f32::MAX;
+        for triangle in self.triangles.iter() {
+            let a = self.points[triangle[0]].position;
+            let b = self.points[triangle[1]].position;
+            let c = self.points[triangle[2]].position;
+            let p = closest_point_on_triangle(sample, a, b, c);
+            let sqr_distance = sqr_distance_vec2(sample, p);
+            if sqr_distance < min_sqr_distance {
+                min_sqr_distance = sqr_distance;
+                closest = Some((*triangle, p));
+            }
+            if sqr_distance <= std::f32::EPSILON {
+                // Sampling point is inside of triangle.
+                break;
+            }
+        }
+
+        if let Some((triangle, p)) = closest {
+            let (u, v, w) = barycentric_coords(
+                p,
+                self.points[triangle[0]].position,
+                self.points[triangle[1]].position,
+                self.points[triangle[2]].position,
+            );
+            self.weights[triangle[0]] = u;
+            self.weights[triangle[1]] = v;
+            self.weights[triangle[2]] = w;
+        }
+    }
+
+    fn update(&mut self, params: &ParameterContainer, animations: &AnimationContainer, dt: f32) {
+        let sample = Vec2::new(
+            fetch_weight(params, &self.x_parameter),
+            if self.is_2d() { fetch_weight(params, &self.y_parameter) } else { 0.0 },
+        );
+
+        self.calculate_weights(sample);
+
+        // Calculate length of blended animation to get phase speed.
+        let mut length = 0.0;
+        for (point, weight) in self.points.iter().zip(self.weights.iter()) {
+            if let Some(animation) = animations.try_get(point.animation) {
+                if animation.get_speed() > 0.0 {
+                    length += weight * animation.get_length() / animation.get_speed();
+                }
+            }
+        }
+
+        self.prev_phase = self.phase;
+        if length > 0.0 {
+            self.phase += dt / length;
+            self.phase -= self.phase.floor();
+        }
+    }
+
+    /// Returns events of signals of given animation passed during last update. Blend space
+    /// samples animations by its own phase, so signals are searched in the phase range.
+    fn passed_signals(&self, animation: &Animation) -> Vec<AnimationEvent> {
+        let length = animation.get_length();
+        if self.phase >= self.prev_phase {
+            animation.signals_in_range(self.prev_phase * length, self.phase * length).collect()
+        } else {
+            // Phase was wrapped.
+            animation.signals_in_range(self.prev_phase * length, length)
+                .chain(animation.signals_in_range(-1.0, self.phase * length))
+                .collect()
+        }
+    }
+}
+
+impl Visit for BlendSpace {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.points.visit("Points", visitor)?;
+        self.x_parameter.visit("XParameter", visitor)?;
+        self.y_parameter.visit("YParameter", visitor)?;
+        self.phase.visit("Phase", visitor)?;
+
+        if visitor.is_reading() && self.is_2d() {
+            self.triangulate();
+        }
+
+        visitor.leave_region()
+    }
+}
+
+fn lerp_vec2(a: Vec2, b: Vec2, t: f32) -> Vec2 {
+    Vec2::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+fn sqr_distance_vec2(a: Vec2, b: Vec2) -> f32 {
+    (a.x - b.x) * (a.x - b.x) + (a.y - b.y) * (a.y - b.y)
+}
+
+/// Returns parameter of projection of point `p` on segment `ab` clamped to 0..1 range.
+fn project_on_segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let abx = b.x - a.x;
+    let aby = b.y - a.y;
+    let sqr_len = abx * abx + aby * aby;
+    if sqr_len <= std::f32::EPSILON {
+        0.0
+    } else {
+        (((p.x - a.x) * abx + (p.y - a.y) * aby) / sqr_len).max(0.0).min(1.0)
+    }
+}
+
+fn barycentric_coords(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> (f32, f32, f32) {
+    let denom = (b.y - c.y) * (a.x - c.x) + (c.x - b.x) * (a.y - c.y);
+    if denom.abs() <= std::f32::EPSILON {
+        return (1.0, 0.0, 0.0);
+    }
+    let u = ((b.y - c.y) * (p.x - c.x) + (c.x - b.x) * (p.y - c.y)) / denom;
+    let v = ((c.y - a.y) * (p.x - c.x) + (a.x - c.x) * (p.y - c.y)) / denom;
+    (u, v, 1.0 - u - v)
+}
+
+fn closest_point_on_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> Vec2 {
+    let (u, v, w) = barycentric_coords(p, a, b, c);
+    if u >= 0.0 && v >= 0.0 && w >= 0.0 {
+        return p;
+    }
+
+    let mut closest = a;
+    let mut min_sqr_distance = std::f32::MAX;
+    for (s, e) in [(a, b), (b, c), (c, a)].iter() {
+        let point = lerp_vec2(*s, *e, project_on_segment(p, *s, *e));
+        let sqr_distance = sqr_distance_vec2(p, point);
+        if sqr_distance < min_sqr_distance {
+            min_sqr_distance = sqr_distance;
+            closest = point;
+        }
+    }
+    closest
+}
+
+fn is_in_circumcircle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    let ax = a.x - p.x;
+    let ay = a.y - p.y;
+    let bx = b.x - p.x;
+    let by = b.y - p.y;
+    let cx = c.x - p.x;
+    let cy = c.y - p.y;
+    let det = (ax * ax + ay * ay) * (bx * cy - cx * by)
+        - (bx * bx + by * by) * (ax * cy - cx * ay)
+        + (cx * cx + cy * cy) * (ax * by - bx * ay);
+    // Sign of determinant depends on winding of triangle.
+    let orientation = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+    if orientation > 0.0 { det > 0.0 } else { det < 0.0 }
+}
+
+/// Delaunay triangulation of given set of points using Bowyer-Watson algorithm. Returns empty
+/// triangulation if points are degenerated (less than three or all on the same line).
+fn triangulate(points: &[Vec2]) -> Vec<[usize; 3]> {
+    if points.len() < 3 {
+        return Vec::new();
+    }
+
+    let mut min = Vec2::new(std::f32::MAX, std::f32::MAX);
+    let mut max = Vec2::new(-std::f32::MAX, -std::f32::MAX);
+    for p in points {
+        min.x = min.x.min(p.x);
+        min.y = min.y.min(p.y);
+        max.x = max.x.max(p.x);
+        max.y = max.y.max(p.y);
+    }
+    let size = (max.x - min.x).max(max.y - min.y).max(1.0);
+    let center = Vec2::new((min.x + max.x) * 0.5, (min.y + max.y) * 0.5);
+
+    // Super triangle which contains all points, its vertices are placed after input points.
+    let mut vertices = points.to_vec();
+    vertices.push(Vec2::new(center.x - 20.0 * size, center.y - size));
+    vertices.push(Vec2::new(center.x, center.y + 20.0 * size));
+    vertices.push(Vec2::new(center.x + 20.0 * size, center.y - size));
+    let super_a = points.len();
+
+    let mut triangles = vec![[super_a, super_a + 1, super_a + 2]];
+    for (i, p) in points.iter().enumerate() {
+        let mut edges: Vec<[usize; 2]> = Vec::new();
+        triangles.retain(|t| {
+            if is_in_circumcircle(*p, vertices[t[0]], vertices[t[1]], vertices[t[2]]) {
+                edges.push([t[0], t[1]]);
+                edges.push([t[1], t[2]]);
+                edges.push([t[2], t[0]]);
+                false
+            } else {
+                true
+            }
+        });
+
+        // Boundary of polygonal hole consists of edges that are not shared between bad triangles.
+        for (j, edge) in edges.iter().enumerate() {
+            let shared = edges.iter().enumerate().any(|(k, other)| {
+                k != j && ((edge[0] == other[0] && edge[1] == other[1]) || (edge[0] == other[1] && edge[1] == other[0]))
+            });
+            if !shared {
+                triangles.push([edge[0], edge[1], i]);
+            }
+        }
+    }
+
+    // Remove every triangle that shares vertex with super triangle.
+    triangles.retain(|t| t.iter().all(|v| *v < super_a));
+    triangles
+}
+
+/// Specialized node that provides animation pose. See documentation for each variant.
+pub enum PoseNode {
+    /// See docs for `PlayAnimation`.
+    PlayAnimation(PlayAnimation),
+
+    /// See docs for `BlendAnimation`.
+    BlendAnimations(BlendAnimation),
+
+    /// See docs for `BlendSpace`.
+    BlendSpace(BlendSpace),
+}
+
+impl Default for PoseNode {
+    fn default() -> Self {
+        PoseNode::PlayAnimation(Default::default())
+    }
+}
+
+impl PoseNode {
+    /// Creates new node that plays animation.
+    pub fn make_play_animation(animation: Handle<Animation>) -> Self {
+        PoseNode::PlayAnimation(PlayAnimation::new(animation))
+    }
+
+    /// Creates new node that blends multiple poses.
+    pub fn make_blend_animations(poses: Vec<BlendPose>) -> Self {
+        PoseNode::BlendAnimations(BlendAnimation::new(poses))
+    }
+
+    /// Creates new 1D blend space node.
+    pub fn make_blend_space_1d(x_parameter: &str, points: Vec<BlendSpacePoint>) -> Self {
+        PoseNode::BlendSpace(BlendSpace::new_1d(x_parameter, points))
+    }
+
+    /// Creates new 2D blend space node.
+    pub fn make_blend_space_2d(x_parameter: &str, y_parameter: &str, points: Vec<BlendSpacePoint>) -> Self {
+        PoseNode::BlendSpace(BlendSpace::new_2d(x_parameter, y_parameter, points))
+    }
+
+    fn normalized_time(&self, nodes: &Pool<PoseNode>, animations: &AnimationContainer) -> Option<f32> {
+        match self {
+            PoseNode::PlayAnimation(play_animation) => {
+                let animation = animations.get(play_animation.animation);
+                if animation.get_length() > 0.0 {
+                    Some(animation.get_time_position() / animation.get_length())
+                } else {
+                    None
+                }
+            }
+            PoseNode::BlendAnimations(blend_animation) => {
+                blend_animation.pose_sources
+                    .borrow()
+                    .iter()
+                    .filter_map(|p| nodes.borrow(p.pose_source).normalized_time(nodes, animations))
+                    .next()
+            }
+            PoseNode::BlendSpace(blend_space) => Some(blend_space.phase),
+        }
+    }
+
+    fn has_ended(&self, nodes: &Pool<PoseNode>, animations: &AnimationContainer) -> bool {
+        match self {
+            PoseNode::PlayAnimation(play_animation) => animations.get(play_animation.animation).has_ended(),
+            PoseNode::BlendAnimations(blend_animation) => {
+                let pose_sources = blend_animation.pose_sources.borrow();
+                !pose_sources.is_empty() && pose_sources.iter()
+                    .all(|p| nodes.borrow(p.pose_source).has_ended(nodes, animations))
+            }
+            // Blend space is always looped.
+            PoseNode::BlendSpace(_) => false,
+        }
+    }
+
+    fn from_id(id: i32) -> Result<Self, String> {
+        match id {
+            0 => Ok(PoseNode::PlayAnimation(Default::default())),
+            1 => Ok(PoseNode::BlendAnimations(Default::default())),
+            2 => Ok(PoseNode::BlendSpace(Default::default())),
+            _ => Err(format!("Invalid pose node id {}", id))
+        }
+    }
+
+    fn id(&self) -> i32 {
+        match self {
+            PoseNode::PlayAnimation(_) => 0,
+            PoseNode::BlendAnimations(_) => 1,
+            PoseNode::BlendSpace(_) => 2,
+        }
+    }
+}
+
+macro_rules! static_dispatch {
+    ($self:ident, $func:ident, $($args:expr),*) => {
+        match $self {
+            PoseNode::PlayAnimation(v) => v.$func($($args),*),
+            PoseNode::BlendAnimations(v) => v.$func($($args),*),
+            PoseNode::BlendSpace(v) => v.$func($($args),*),
+        }
+    };
+}
+
+impl Visit for PoseNode {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        let mut kind_id = self.id();
+        kind_id.visit("KindId", visitor)?;
+        if visitor.is_reading() {
+            *self = PoseNode::from_id(kind_id)?;
+        }
+
+        static_dispatch!(self, visit, name, visitor)
+    }
+}
+
+/// State is a
+#[derive(Default)]
+pub struct State {
+    name: String,
+    root: Handle<PoseNode>,
+    pose: AnimationPose,
+}
+
+type ParameterContainer = HashMap<String, Parameter>;
+
+/// Returns value of Weight parameter with given name or 0.0 if there is no such parameter.
+fn fetch_weight(params: &ParameterContainer, id: &str) -> f32 {
+    if let Some(Parameter::Weight(weight)) = params.get(id) {
+        *weight
+    } else {
+        0.0
+    }
+}
+
+trait EvaluatePose {
+    fn eval_pose(&self, nodes: &Pool<PoseNode>, params: &ParameterContainer, animations: &AnimationContainer) -> Ref<AnimationPose>;
+}
+
+impl EvaluatePose for PlayAnimation {
+    fn eval_pose(&self, _nodes: &Pool<PoseNode>, _params: &ParameterContainer, animations: &AnimationContainer) -> Ref<AnimationPose> {
+        animations.get(self.animation)
+            .get_pose()
+            .clone_into(&mut self.output_pose.borrow_mut());
+        self.output_pose.borrow()
+    }
+}
+
+impl EvaluatePose for BlendAnimation {
+    fn eval_pose(&self, nodes: &Pool<PoseNode>, params: &ParameterContainer, animations: &AnimationContainer) -> Ref<AnimationPose> {
+        self.output_pose.borrow_mut().reset();
+        for blend_pose in self.pose_sources.borrow_mut().iter_mut() {
+            let weight = match blend_pose.weight {
+                PoseWeight::Constant(value) => value,
+                PoseWeight::Parameter(ref param_id) => fetch_weight(params, param_id),
+            };
+
+            let pose_source = nodes.borrow(blend_pose.pose_source).eval_pose(nodes, params, animations);
+            self.output_pose.borrow_mut().blend_with(&pose_source, weight);
+        }
+        self.output_pose.borrow()
+    }
+}
+
+impl EvaluatePose for BlendSpace {
+    fn eval_pose(&self, _nodes: &Pool<PoseNode>, _params: &ParameterContainer, animations: &AnimationContainer) -> Ref<AnimationPose> {
+        self.output_pose.borrow_mut().reset();
+        let mut accumulated_weight = 0.0;
+        for (point, weight) in self.points.iter().zip(self.weights.iter()) {
+            if *weight > 0.0 {
+                // Points without animation are skipped.
+                let animation = match animations.try_get(point.animation) {
+                    Some(animation) => animation,
+                    None => continue,
+                };
+                animation.sample_pose(self.phase * animation.get_length(), &mut self.sample_pose.borrow_mut());
+                self.output_pose.borrow_mut().blend_weighted(&self.sample_pose.borrow(), *weight, accumulated_weight);
+                accumulated_weight += *weight;
+            }
+        }
+        self.output_pose.borrow()
+    }
+}
+
+impl EvaluatePose for PoseNode {
+    fn eval_pose(&self, nodes: &Pool<PoseNode>, params: &ParameterContainer, animations: &AnimationContainer) -> Ref<AnimationPose> {
+        static_dispatch!(self, eval_pose, nodes, params, animations)
+    }
+}
+
+impl State {
+    /// Creates new instance of state with a given pose.
+    pub fn new(name: &str, root: Handle<PoseNode>) -> Self {
+        Self {
+            name: name.to_owned(),
+            root,
+            pose: Default::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn root(&self) -> Handle<PoseNode> {
+        self.root
+    }
+
+    /// Returns normalized time (0..1) of the state. It is taken from first animation that
+    /// found in pose nodes tree of the state.
+    fn normalized_time(&self, nodes: &Pool<PoseNode>, animations: &AnimationContainer) -> Option<f32> {
+        nodes.borrow(self.root).normalized_time(nodes, animations)
+    }
+
+    /// Returns true if every animation used by the state has ended.
+    fn has_ended(&self, nodes: &Pool<PoseNode>, animations: &AnimationContainer) -> bool {
+        nodes.borrow(self.root).has_ended(nodes, animations)
+    }
+
+    fn update(&mut self, nodes: &Pool<PoseNode>, params: &ParameterContainer, animations: &AnimationContainer) {
+        self.pose.reset();
+        nodes.borrow(self.root)
+            .eval_pose(nodes, params, animations)
+            .clone_into(&mut self.pose);
+    }
+}
+
+impl Visit for State {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.name.visit("Name", visitor)?;
+        self.root.visit("Root", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Comparison operator which is used to compare Weight parameter with a threshold.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Default for Comparison {
+    fn default() -> Self {
+        Comparison::Greater
+    }
+}
+
+impl Comparison {
+    fn from_id(id: i32) -> Result<Self, String> {
+        match id {
+            0 => Ok(Comparison::Less),
+            1 => Ok(Comparison::LessOrEqual),
+            2 => Ok(Comparison::Greater),
+            3 => Ok(Comparison::GreaterOrEqual),
+            4 => Ok(Comparison::Equal),
+            5 => Ok(Comparison::NotEqual),
+            _ => Err(format!("Invalid comparison id {}", id))
+        }
+    }
+
+    fn id(self) -> i32 {
+        match self {
+            Comparison::Less => 0,
+            Comparison::LessOrEqual => 1,
+            Comparison::Greater => 2,
+            Comparison::GreaterOrEqual => 3,
+            Comparison::Equal => 4,
+            Comparison::NotEqual => 5,
+        }
+    }
+
+    /// Compares `value` with `threshold`, `value` is always on the left side.
+    pub fn compare(self, value: f32, threshold: f32) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Equal => (value - threshold).abs() <= std::f32::EPSILON,
+            Comparison::NotEqual => (value - threshold).abs() > std::f32::EPSILON,
+        }
+    }
+}
+
+impl Visit for Comparison {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        let mut id = self.id();
+        id.visit(name, visitor)?;
+        if visitor.is_reading() {
+            *self = Self::from_id(id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Condition of a transition. Conditions can be combined together using And, Or and Not
+/// variants, for example transition from Run to Walk state could use such condition:
+///
+/// ```
+/// use rg3d::animation::machine::{Condition, Comparison};
+///
+/// let condition = Condition::and(vec![
+///     Condition::compare("Speed", Comparison::Less, 3.0),
+///     Condition::not(Condition::rule("IsJumping")),
+/// ]);
+/// ```
+pub enum Condition {
+    /// Rule parameter with given name is true.
+    Rule(String),
+
+    /// Weight parameter with given name satisfies comparison with threshold.
+    Compare {
+        parameter: String,
+        comparison: Comparison,
+        threshold: f32,
+    },
+
+    /// Every animation of source state has ended. Looped animations never ends.
+    SourceFinished,
+
+    /// Normalized time (0..1) of source state is greater than given value. Normalized
+    /// time of state is taken from first animation used by the state.
+    SourceNormalizedTime(f32),
+
+    /// Every condition is true.
+    And(Vec<Condition>),
+
+    /// At least one condition is true.
+    Or(Vec<Condition>),
+
+    /// Inverts inner condition.
+    Not(Box<Condition>),
+}
+
+impl Default for Condition {
+    fn default() -> Self {
+        Condition::Rule(Default::default())
+    }
+}
+
+impl Condition {
+    pub fn rule(parameter: &str) -> Self {
+        Condition::Rule(parameter.to_owned())
+    }
+
+    pub fn compare(parameter: &str, comparison: Comparison, threshold: f32) -> Self {
+        Condition::Compare {
+            parameter: parameter.to_owned(),
+            comparison,
+            threshold,
+        }
+    }
+
+    pub fn and(conditions: Vec<Condition>) -> Self {
+        Condition::And(conditions)
+    }
+
+    pub fn or(conditions: Vec<Condition>) -> Self {
+        Condition::Or(conditions)
+    }
+
+    pub fn not(condition: Condition) -> Self {
+        Condition::Not(Box::new(condition))
+    }
+
+    fn from_id(id: i32) -> Result<Self, String> {
+        match id {
+            0 => Ok(Condition::Rule(Default::default())),
+            1 => Ok(Condition::Compare {
+                parameter: Default::default(),
+                comparison: Default::default(),
+                threshold: 0.0,
+            }),
+            2 => Ok(Condition::SourceFinished),
+            3 => Ok(Condition::SourceNormalizedTime(0.0)),
+            4 => Ok(Condition::And(Default::default())),
+            5 => Ok(Condition::Or(Default::default())),
+            6 => Ok(Condition::Not(Default::default())),
+            _ => Err(format!("Invalid condition id {}", id))
+        }
+    }
+
+    fn id(&self) -> i32 {
+        match self {
+            Condition::Rule(_) => 0,
+            Condition::Compare { .. } => 1,
+            Condition::SourceFinished => 2,
+            Condition::SourceNormalizedTime(_) => 3,
+            Condition::And(_) => 4,
+            Condition::Or(_) => 5,
+            Condition::Not(_) => 6,
+        }
+    }
+
+    fn evaluate(&self, params: &ParameterContainer, source: &State, nodes: &Pool<PoseNode>, animations: &AnimationContainer) -> bool {
+        match self {
+            Condition::Rule(parameter) => {
+                if let Some(Parameter::Rule(active)) = params.get(parameter) {
+                    *active
+                } else {
+                    false
+                }
+            }
+            Condition::Compare { parameter, comparison, threshold } => {
+                if let Some(Parameter::Weight(weight)) = params.get(parameter) {
+                    comparison.compare(*weight, *threshold)
+                } else {
+                    false
+                }
+            }
+            Condition::SourceFinished => source.has_ended(nodes, animations),
+            Condition::SourceNormalizedTime(time) => {
+                source.normalized_time(nodes, animations).map_or(false, |t| t > *time)
+            }
+            Condition::And(conditions) => {
+                !conditions.is_empty() && conditions.iter().all(|c| c.evaluate(params, source, nodes, animations))
+            }
+            Condition::Or(conditions) => {
+                conditions.iter().any(|c| c.evaluate(params, source, nodes, animations))
+            }
+            Condition::Not(condition) => !condition.evaluate(params, source, nodes, animations),
+        }
+    }
+}
+
+impl Visit for Condition {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut id = self.id();
+        id.visit("Id", visitor)?;
+        if visitor.is_reading() {
+            *self = Self::from_id(id)?;
+        }
+
+        match self {
+            Condition::Rule(parameter) => parameter.visit("Parameter", visitor)?,
+            Condition::Compare { parameter, comparison, threshold } => {
+                parameter.visit("Parameter", visitor)?;
+                comparison.visit("Comparison", visitor)?;
+                threshold.visit("Threshold", visitor)?;
+            }
+            Condition::SourceFinished => (),
+            Condition::SourceNormalizedTime(time) => time.visit("Time", visitor)?,
+            Condition::And(conditions) => conditions.visit("Conditions", visitor)?,
+            Condition::Or(conditions) => conditions.visit("Conditions", visitor)?,
+            Condition::Not(condition) => condition.visit("Condition", visitor)?,
+        }
+
+        visitor.leave_region()
+    }
+}
+
+/// Transition is a connection between two states with a condition that defines possibility
+/// of actual transition with blending.
+#[derive(Default)]
+pub struct Transition {
+    name: String,
+    /// Total amount of time to transition from `src` to `dst` state.
+    transition_time: f32,
+    elapsed_time: f32,
+    source: Handle<State>,
+    dest: Handle<State>,
+    /// Condition which defines is transition should be activated or not.
+    condition: Condition,
+    /// If there are multiple transitions which can be activated, the one with highest
+    /// priority will be chosen.
+    priority: i32,
+    /// Interruptible transition can be interrupted by a transition from its destination
+    /// state. In this case new transition will blend from current blended pose.
+    interruptible: bool,
+    /// 0 - evaluates `src` pose, 1 - `dest`, 0..1 - blends `src` and `dest`
+    blend_factor: f32,
+}
+
+impl Visit for Transition {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.name.visit("Name", visitor)?;
+        self.transition_time.visit("TransitionTime", visitor)?;
+        self.elapsed_time.visit("ElapsedTime", visitor)?;
+        self.source.visit("Source", visitor)?;
+        self.dest.visit("Dest", visitor)?;
+        // Transitions saved by older versions have name of Rule parameter instead of condition.
+        let condition = self.condition.visit("Condition", visitor);
+        if condition.is_err() && visitor.is_reading() {
+            let mut rule = String::new();
+            rule.visit("Rule", visitor)?;
+            self.condition = Condition::Rule(rule);
+        } else {
+            condition?;
+        }
+        // Older versions have no priority and interruptibility, defaults are used then.
+        let priority = self.priority.visit("Priority", visitor);
+        let interruptible = self.interruptible.visit("Interruptible", visitor);
+        if !visitor.is_reading() {
+            priority?;
+            interruptible?;
+        }
+        self.blend_factor.visit("BlendFactor", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl Transition {
+    /// Creates new transition which will be activated when Rule parameter with given name
+    /// is true.
+    pub fn new(name: &str, src: Handle<State>, dest: Handle<State>, time: f32, rule: &str) -> Transition {
+        Self::with_condition(name, src, dest, time, Condition::rule(rule))
+    }
+
+    /// Creates new transition which will be activated when given condition is true.
+    pub fn with_condition(name: &str, src: Handle<State>, dest: Handle<State>, time: f32, condition: Condition) -> Transition {
+        Self {
+            name: name.to_owned(),
+            transition_time: time,
+            elapsed_time: 0.0,
+            source: src,
+            dest,
+            condition,
+            priority: 0,
+            interruptible: false,
+            blend_factor: 0.0,
+        }
+    }
+
+    /// Sets priority of transition. If there are multiple transitions which can be activated,
+    /// the one with highest priority will be chosen, on equal priorities the one that was added
+    /// first wins.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets whether transition can be interrupted by a transition from its destination state.
+    pub fn with_interruptible(mut self, interruptible: bool) -> Self {
+        self.interruptible = interruptible;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn transition_time(&self) -> f32 {
+        self.transition_time
+    }
+
+    pub fn source(&self) -> Handle<State> {
+        self.source
+    }
+
+    pub fn dest(&self) -> Handle<State> {
+        self.dest
+    }
+
+    pub fn condition(&self) -> &Condition {
+        &self.condition
+    }
+
+    pub fn set_condition(&mut self, condition: Condition) -> &mut Self {
+        self.condition = condition;
+        self
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn set_priority(&mut self, priority: i32) -> &mut Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn is_interruptible(&self) -> bool {
+        self.interruptible
+    }
+
+    pub fn set_interruptible(&mut self, interruptible: bool) -> &mut Self {
+        self.interruptible = interruptible;
+        self
+    }
+
+    fn reset(&mut self) {
+        self.elapsed_time = 0.0;
+        self.blend_factor = 0.0;
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.elapsed_time += dt;
+        if self.elapsed_time > self.transition_time {
+            self.elapsed_time = self.transition_time;
+        }
+        self.blend_factor = self.elapsed_time / self.transition_time;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.transition_time == self.elapsed_time
+    }
+}
+
+/// Default minimal weight of animation at which its signals are dispatched by machine.
+const DEFAULT_SIGNAL_WEIGHT_THRESHOLD: f32 = 0.5;
+
+#[derive(Default)]
+pub struct Machine {
+    nodes: Pool<PoseNode>,
+    states: Pool<State>,
+    transitions: Pool<Transition>,
+    final_pose: AnimationPose,
+    active_state: Handle<State>,
+    entry_state: Handle<State>,
+    active_transition: Handle<Transition>,
+    parameters: ParameterContainer,
+    events: LimitedEventQueue,
+    /// Snapshot of blended pose at the moment when active transition was interrupted.
+    /// Used as source pose for interrupting transition.
+    interrupted_pose: AnimationPose,
+    blend_from_interrupted: bool,
+    signal_weight_threshold: f32,
+    debug: bool,
+}
+
+struct LimitedEventQueue {
+    queue: VecDeque<Event>,
+    limit: u32,
+}
+
+impl Default for LimitedEventQueue {
+    fn default() -> Self {
+        Self {
+            queue: Default::default(),
+            limit: std::u32::MAX,
+        }
+    }
+}
+
+impl LimitedEventQueue {
+    fn new(limit: u32) -> Self {
+        Self {
+            queue: VecDeque::with_capacity(limit as usize),
+            limit
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        if self.queue.len() < (self.limit as usize) {
+            self.queue.push_back(event);
+        }
+    }
+
+    fn pop(&mut self) -> Option<Event> {
+        self.queue.pop_front()
+    }
+}
+
+impl Machine {
+    pub fn new() -> Self {
+        Self {
+            nodes: Default::default(),
+            states: Default::default(),
+            transitions: Default::default(),
+            final_pose: Default::default(),
+            active_state: Default::default(),
+            entry_state: Default::default(),
+            active_transition: Default::default(),
+            parameters: Default::default(),
+            events: LimitedEventQueue::new(2048),
+            interrupted_pose: Default::default(),
+            blend_from_interrupted: false,
+            signal_weight_threshold: DEFAULT_SIGNAL_WEIGHT_THRESHOLD,
+            debug: false,
+        }
+    }
+
+    pub fn add_node(&mut self, node: PoseNode) -> Handle<PoseNode> {
+        self.nodes.spawn(node)
+    }
+
+    pub fn set_parameter(&mut self, id: &str, parameter: Parameter) -> &mut Self {
+        self.parameters.entry(id.to_owned())
+            .and_modify(|p| *p = parameter)
+            .or_insert(parameter);
+        self
+    }
+
+    /// Returns copy of parameter with given name, if any.
+    pub fn get_parameter(&self, id: &str) -> Option<Parameter> {
+        self.parameters.get(id).cloned()
+    }
+
+    /// Calculates actual value of given weight - constant weights returned as is, parametrized
+    /// weights are taken from Weight parameters of this machine. Could be useful to drive weights
+    /// of external entities (like IK solvers) by the machine.
+    pub fn evaluate_weight(&self, weight: &PoseWeight) -> f32 {
+        match weight {
+            PoseWeight::Constant(value) => *value,
+            PoseWeight::Parameter(param_id) => fetch_weight(&self.parameters, param_id),
+        }
+    }
+
+    pub fn set_entry_state(&mut self, entry_state: Handle<State>) {
+        self.active_state = entry_state;
+        self.entry_state = entry_state;
+    }
+
+    /// Sets minimal weight of animation in final pose at which signals of the animation will be
+    /// dispatched as `Event::Signal`. Default is 0.5, so during transitions only signals of
+    /// dominating state are reported.
+    pub fn set_signal_weight_threshold(&mut self, threshold: f32) -> &mut Self {
+        self.signal_weight_threshold = threshold;
+        self
+    }
+
+    pub fn signal_weight_threshold(&self) -> f32 {
+        self.signal_weight_threshold
+    }
+
+    pub fn debug(&mut self, state: bool) {
+        self.debug = state;
+    }
+
+    pub fn add_state(&mut self, state: State) -> Handle<State> {
+        let state = self.states.spawn(state);
+        if self.active_state.is_none() {
+            self.active_state = state;
+        }
+        state
+    }
+
+    pub fn add_transition(&mut self, transition: Transition) -> &mut Self {
+        let _ = self.transitions.spawn(transition);
+        self
+    }
+
+    pub fn get_state(&self, state: Handle<State>) -> &State {
+        self.states.borrow(state)
+    }
+
+    pub fn pop_event(&mut self) -> Option<Event> {
+        self.events.pop()
+    }
+
+    pub fn reset(&mut self) {
+        for transition in self.transitions.iter_mut() {
+            transition.reset();
+        }
+
+        self.active_state = self.entry_state;
+        self.active_transition = Handle::NONE;
+        self.blend_from_interrupted = false;
+    }
+
+    pub fn nodes(&self) -> PoolIterator<PoseNode> {
+        self.nodes.iter()
+    }
+
+    pub fn active_state(&self) -> Handle<State> {
+        self.active_state
+    }
+
+    pub fn active_transition(&self) -> Handle<Transition> {
+        self.active_transition
+    }
+
+    pub fn transitions(&self) -> &Pool<Transition> {
+        &self.transitions
+    }
+
+    /// Searches for a transition from given state which condition is true. If there are
+    /// multiple such transitions, the one with highest priority is returned.
+    fn find_transition(&self, source: Handle<State>, animations: &AnimationContainer) -> Option<Handle<Transition>> {
+        if source.is_none() {
+            return None;
+        }
+
+        let source_state = self.states.borrow(source);
+        let mut result: Option<(Handle<Transition>, i32)> = None;
+        for (handle, transition) in self.transitions.pair_iter() {
+            if transition.source != source || transition.dest == source {
+                continue;
+            }
+            if result.map_or(false, |(_, priority)| priority >= transition.priority) {
+                continue;
+            }
+            if transition.condition.evaluate(&self.parameters, source_state, &self.nodes, animations) {
+                result = Some((handle, transition.priority));
+            }
+        }
+        result.map(|(handle, _)| handle)
+    }
+
+    fn begin_transition(&mut self, handle: Handle<Transition>) {
+        let dest = self.transitions.borrow(handle).dest;
+        let source = if self.active_state.is_some() {
+            self.active_state
+        } else {
+            self.transitions.borrow(self.active_transition).dest
+        };
+
+        self.events.push(Event::StateLeave(source));
+        if self.debug {
+            Log::writeln(format!("Leaving state: {}", self.states.borrow(source).name));
+        }
+
+        self.events.push(Event::StateEnter(dest));
+        if self.debug {
+            Log::writeln(format!("Entering state: {}", self.states.borrow(dest).name));
+        }
+
+        self.active_state = Handle::NONE;
+        self.active_transition = handle;
+    }
+
+    /// Blends poses of source and dest states of active transition into given pose.
+    fn blend_transition(&self, pose: &mut AnimationPose) {
+        let transition = self.transitions.borrow(self.active_transition);
+        let source_pose = if self.blend_from_interrupted {
+            &self.interrupted_pose
+        } else {
+            &self.states.borrow(transition.source).pose
+        };
+        pose.reset();
+        pose.blend_with(source_pose, 1.0 - transition.blend_factor);
+        pose.blend_with(&self.states.borrow(transition.dest).pose, transition.blend_factor);
+    }
+
+    /// Emits signal events of animations used by given node, weight of node is propagated down
+    /// to animations.
+    fn gather_signals(&self, animations: &AnimationContainer, node: Handle<PoseNode>, state: Handle<State>, weight: f32, events: &mut Vec<Event>) {
+        match self.nodes.borrow(node) {
+            PoseNode::PlayAnimation(play_animation) => {
+                if weight > self.signal_weight_threshold {
+                    for event in animations.get(play_animation.animation).last_tick_events() {
+                        events.push(Event::Signal { state, animation: play_animation.animation, weight, event: event.clone() });
+                    }
+                }
+            }
+            PoseNode::BlendAnimations(blend_animation) => {
+                for blend_pose in blend_animation.pose_sources.borrow().iter() {
+                    let pose_weight = self.evaluate_weight(&blend_pose.weight);
+                    self.gather_signals(animations, blend_pose.pose_source, state, weight * pose_weight, events);
+                }
+            }
+            PoseNode::BlendSpace(blend_space) => {
+                for (point, point_weight) in blend_space.points.iter().zip(blend_space.weights.iter()) {
+                    let weight = weight * point_weight;
+                    if weight > self.signal_weight_threshold {
+                        if let Some(animation) = animations.try_get(point.animation) {
+                            for event in blend_space.passed_signals(animation) {
+                                events.push(Event::Signal { state, animation: point.animation, weight, event });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns handles of blend space nodes used by active state or by source and dest
+    /// states of active transition.
+    fn active_blend_spaces(&self) -> Vec<Handle<PoseNode>> {
+        let mut stack = Vec::new();
+        if self.active_transition.is_some() {
+            let transition = self.transitions.borrow(self.active_transition);
+            stack.push(self.states.borrow(transition.source).root);
+            stack.push(self.states.borrow(transition.dest).root);
+        } else if self.active_state.is_some() {
+            stack.push(self.states.borrow(self.active_state).root);
+        }
+
+        let mut blend_spaces = Vec::new();
+        while let Some(handle) = stack.pop() {
+            if handle.is_none() {
+                continue;
+            }
+            match self.nodes.borrow(handle) {
+                PoseNode::PlayAnimation(_) => (),
+                PoseNode::BlendAnimations(blend_animation) => {
+                    stack.extend(blend_animation.pose_sources.borrow().iter().map(|p| p.pose_source));
+                }
+                PoseNode::BlendSpace(_) => {
+                    if !blend_spaces.contains(&handle) {
+                        blend_spaces.push(handle);
+                    }
+                }
+            }
+        }
+        blend_spaces
+    }
+
+    fn dispatch_signals(&mut self, animations: &AnimationContainer) {
+        let mut contributors = Vec::new();
+        if self.active_transition.is_some() {
+            let transition = self.transitions.borrow(self.active_transition);
+            // Pose of interrupted transition is frozen, it has no signals.
+            if !self.blend_from_interrupted {
+                contributors.push((transition.source, 1.0 - transition.blend_factor));
+            }
+            contributors.push((transition.dest, transition.blend_factor));
+        } else if self.active_state.is_some() {
+            contributors.push((self.active_state, 1.0));
+        }
+
+        let mut events = Vec::new();
+        for (state, weight) in contributors {
+            let root = self.states.borrow(state).root;
+            self.gather_signals(animations, root, state, weight, &mut events);
+        }
+
+        for event in events {
+            self.events.push(event);
+        }
+    }
+
+    pub fn evaluate_pose(&mut self, animations: &AnimationContainer, dt: f32) -> &AnimationPose {
+        self.final_pose.reset();
+
+        if self.active_state.is_some() || self.active_transition.is_some() {
+            // Blend spaces have their own time line, advance it first. Only blend spaces of
+            // states that contribute to final pose are advanced, others are paused.
+            let active_blend_spaces = self.active_blend_spaces();
+            for (handle, node) in self.nodes.pair_iter_mut() {
+                if let PoseNode::BlendSpace(blend_space) = node {
+                    if active_blend_spaces.contains(&handle) {
+                        blend_space.update(&self.parameters, animations, dt);
+                    } else {
+                        // Paused blend space has passed no signals.
+                        blend_space.prev_phase = blend_space.phase;
+                    }
+                }
+            }
+
+            // Gather actual poses for each state.
+            for state in self.states.iter_mut() {
+                state.update(&self.nodes, &self.parameters, animations);
+            }
+
+            if self.active_transition.is_none() {
+                if let Some(handle) = self.find_transition(self.active_state, animations) {
+                    self.begin_transition(handle);
+                }
+            } else {
+                let current = self.transitions.borrow(self.active_transition);
+                if current.interruptible {
+                    if let Some(handle) = self.find_transition(current.dest, animations) {
+                        // Freeze current blended pose, interrupting transition will blend from it.
+                        let mut pose = AnimationPose::default();
+                        self.blend_transition(&mut pose);
+                        self.interrupted_pose = pose;
+                        self.blend_from_interrupted = true;
+
+                        self.transitions.borrow_mut(self.active_transition).reset();
+                        self.begin_transition(handle);
+                    }
+                }
+            }
+
+            // Double check for active transition because we can have empty machine.
+            if self.active_transition.is_some() {
+                // Blend between source and dest states.
+                let mut final_pose = std::mem::take(&mut self.final_pose);
+                self.blend_transition(&mut final_pose);
+                self.final_pose = final_pose;
+
+                let transition = self.transitions.borrow_mut(self.active_transition);
+                transition.update(dt);
+
+                if transition.is_done() {
+                    transition.reset();
+                    self.active_transition = Handle::NONE;
+                    self.active_state = transition.dest;
+                    self.blend_from_interrupted = false;
+                    self.events.push(Event::ActiveStateChanged(self.active_state));
+
+                    if self.debug {
+                        Log::writeln(format!("Active state changed: {}", self.states.borrow(self.active_state).name));
+                    }
+                }
+            } else {
+                // We must have active state all the time when we do not have any active transition.
+                let state = self.states.borrow_mut(self.active_state);
+
+                // Just get pose from active state.
+                state.pose.clone_into(&mut self.final_pose);
+            }
+
+            self.dispatch_signals(animations);
+        }
+
+        &self.final_pose
+    }
+}
+
+impl Visit for Machine {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.parameters.visit("Parameters", visitor)?;
+        self.nodes.visit("Nodes", visitor)?;
+        self.transitions.visit("Transitions", visitor)?;
+        self.states.visit("States", visitor)?;
+        self.active_state.visit("ActiveState", visitor)?;
+        self.entry_state.visit("EntryState", visitor)?;
+        self.active_transition.visit("ActiveTransition", visitor)?;
+        // Machines saved by older versions have no threshold, default one is used then.
+        let threshold_result = self.signal_weight_threshold.visit("SignalWeightThreshold", visitor);
+        if !visitor.is_reading() {
+            threshold_result?;
+        } else if threshold_result.is_err() {
+            self.signal_weight_threshold = DEFAULT_SIGNAL_WEIGHT_THRESHOLD;
+        }
+
+        visitor.leave_region()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{
+            Animation,
+            AnimationContainer,
+            AnimationSignal,
+            Track,
+            KeyFrame,
+            AnimationPose,
+            machine::{
+                BlendSpace,
+                BlendSpacePoint,
+                Comparison,
+                Condition,
+                Event,
+                Machine,
+                Parameter,
+                PoseNode,
+                State,
+                Transition,
+            },
+        },
+        core::{
+            math::{
+                vec2::Vec2,
+                vec3::Vec3,
+                quat::Quat,
+            },
+            pool::Handle,
+        },
+    };
+
+    fn sum(weights: &[f32]) -> f32 {
+        weights.iter().sum()
+    }
+
+    /// Animation of given length with single track.
+    fn make_animation(length: f32) -> Animation {
+        let mut track = Track::new();
+        track.add_key_frame(KeyFrame::new(0.0, Vec3::ZERO, Vec3::UNIT, Quat::IDENTITY));
+        track.add_key_frame(KeyFrame::new(length, Vec3::new(1.0, 0.0, 0.0), Vec3::UNIT, Quat::IDENTITY));
+        let mut animation = Animation::default();
+        animation.add_track(track);
+        animation
+    }
+
+    /// Animation that holds its single track at given position along X axis.
+    fn make_static_animation(x: f32) -> Animation {
+        let mut track = Track::new();
+        track.add_key_frame(KeyFrame::new(0.0, Vec3::new(x, 0.0, 0.0), Vec3::UNIT, Quat::IDENTITY));
+        track.add_key_frame(KeyFrame::new(1.0, Vec3::new(x, 0.0, 0.0), Vec3::UNIT, Quat::IDENTITY));
+        let mut animation = Animation::default();
+        animation.add_track(track);
+        animation
+    }
+
+    fn pose_x(pose: &AnimationPose) -> f32 {
+        pose.local_poses.get(&Handle::NONE).map_or(0.0, |local_pose| local_pose.position.x)
+    }
+
+    fn add_state(machine: &mut Machine, name: &str, animation: Handle<Animation>) -> Handle<State> {
+        let node = machine.add_node(PoseNode::make_play_animation(animation));
+        machine.add_state(State::new(name, node))
+    }
+
+    fn evaluate(machine: &Machine, state: Handle<State>, animations: &AnimationContainer, condition: Condition) -> bool {
+        condition.evaluate(&machine.parameters, machine.states.borrow(state), &machine.nodes, animations)
+    }
+
+    fn blend_space_phase(machine: &Machine, handle: Handle<PoseNode>) -> f32 {
+        match machine.nodes.borrow(handle) {
+            PoseNode::BlendSpace(blend_space) => blend_space.phase(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn blend_space_1d_weights() {
+        let mut blend_space = BlendSpace::new_1d("Speed", vec![
+            BlendSpacePoint::new(Vec2::new(0.0, 0.0), Handle::NONE),
+            BlendSpacePoint::new(Vec2::new(2.0, 0.0), Handle::NONE),
+            BlendSpacePoint::new(Vec2::new(1.0, 0.0), Handle::NONE),
+        ]);
+
+        blend_space.calculate_weights(Vec2::new(1.5, 0.0));
+        assert!((blend_space.weights()[2] - 0.5).abs() < 0.0001);
+        assert!((blend_space.weights()[1] - 0.5).abs() < 0.0001);
+        assert_eq!(blend_space.weights()[0], 0.0);
+
+        // Out of range must be clamped.
+        blend_space.calculate_weights(Vec2::new(5.0, 0.0));
+        assert_eq!(blend_space.weights()[1], 1.0);
+        assert!((sum(blend_space.weights()) - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn blend_space_2d_weights() {
+        let mut blend_space = BlendSpace::new_2d("X", "Y", vec![
+            BlendSpacePoint::new(Vec2::new(-1.0, -1.0), Handle::NONE),
+            BlendSpacePoint::new(Vec2::new(1.0, -1.0), Handle::NONE),
+            BlendSpacePoint::new(Vec2::new(1.0, 1.0), Handle::NONE),
+            BlendSpacePoint::new(Vec2::new(-1.0, 1.0), Handle::NONE),
+            BlendSpacePoint::new(Vec2::new(0.0, 0.0), Handle::NONE),
+        ]);
+        assert_eq!(blend_space.triangles.len(), 4);
+
+        // Exactly at sample point.
+        blend_space.calculate_weights(Vec2::new(1.0, 1.0));
+        assert!((blend_space.weights()[2] - 1.0).abs() < 0.0001);
+
+        // Inside of the triangulation.
+        blend_space.calculate_weights(Vec2::new(0.25, -0.5));
+        assert!((sum(blend_space.weights()) - 1.0).abs() < 0.0001);
+        assert!(blend_space.weights().iter().all(|w| *w >= 0.0));
+
+        // Outside of the triangulation - must be clamped to the closest edge.
+        blend_space.calculate_weights(Vec2::new(3.0, 0.0));
+        assert!((blend_space.weights()[1] - 0.5).abs() < 0.0001);
+        assert!((blend_space.weights()[2] - 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn blend_space_skips_points_without_animation() {
+        let mut animations = AnimationContainer::new();
+        let walk = animations.add(make_animation(1.0));
+
+        let mut machine = Machine::new();
+        machine.set_parameter("Speed", Parameter::Weight(0.5));
+        let node = machine.add_node(PoseNode::make_blend_space_1d("Speed", vec![
+            BlendSpacePoint::new(Vec2::new(0.0, 0.0), Handle::NONE),
+            BlendSpacePoint::new(Vec2::new(1.0, 0.0), walk),
+        ]));
+        machine.add_state(State::new("Move", node));
+
+        machine.evaluate_pose(&animations, 0.1);
+        assert!(blend_space_phase(&machine, node) > 0.0);
+    }
+
+    #[test]
+    fn blend_space_point_without_animation_does_not_shrink_pose() {
+        let mut animations = AnimationContainer::new();
+        let idle = animations.add(make_static_animation(2.0));
+        let run = animations.add(make_static_animation(4.0));
+
+        let mut machine = Machine::new();
+        machine.set_parameter("Speed", Parameter::Weight(0.5));
+        let node = machine.add_node(PoseNode::make_blend_space_1d("Speed", vec![
+            BlendSpacePoint::new(Vec2::new(0.0, 0.0), Handle::NONE),
+            BlendSpacePoint::new(Vec2::new(1.0, 0.0), idle),
+        ]));
+        machine.add_state(State::new("Move", node));
+        assert!((pose_x(machine.evaluate_pose(&animations, 0.0)) - 2.0).abs() < 0.0001);
+
+        // Points with animations are still averaged by their weights.
+        let mut machine = Machine::new();
+        machine.set_parameter("Speed", Parameter::Weight(0.75));
+        let node = machine.add_node(PoseNode::make_blend_space_1d("Speed", vec![
+            BlendSpacePoint::new(Vec2::new(0.0, 0.0), Handle::NONE),
+            BlendSpacePoint::new(Vec2::new(0.5, 0.0), idle),
+            BlendSpacePoint::new(Vec2::new(1.0, 0.0), run),
+        ]));
+        machine.add_state(State::new("Move", node));
+        assert!((pose_x(machine.evaluate_pose(&animations, 0.0)) - 3.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn blend_space_of_inactive_state_is_paused() {
+        let mut animations = AnimationContainer::new();
+        let walk = animations.add(make_animation(1.0));
+
+        let mut machine = Machine::new();
+        machine.set_parameter("Speed", Parameter::Weight(0.0));
+        let active = machine.add_node(PoseNode::make_blend_space_1d("Speed", vec![
+            BlendSpacePoint::new(Vec2::new(0.0, 0.0), walk),
+        ]));
+        let inactive = machine.add_node(PoseNode::make_blend_space_1d("Speed", vec![
+            BlendSpacePoint::new(Vec2::new(0.0, 0.0), walk),
+        ]));
+        machine.add_state(State::new("Active", active));
+        machine.add_state(State::new("Inactive", inactive));
+
+        machine.evaluate_pose(&animations, 0.25);
+        assert!((blend_space_phase(&machine, active) - 0.25).abs() < 0.0001);
+        assert_eq!(blend_space_phase(&machine, inactive), 0.0);
+    }
+
+    #[test]
+    fn condition_evaluation() {
+        let mut animations = AnimationContainer::new();
+        let mut animation = make_animation(1.0);
+        animation.set_loop(false);
+        let animation = animations.add(animation);
+
+        let mut machine = Machine::new();
+        let state = add_state(&mut machine, "Idle", animation);
+        machine.set_parameter("Run", Parameter::Rule(true));
+        machine.set_parameter("Jump", Parameter::Rule(false));
+        machine.set_parameter("Speed", Parameter::Weight(0.7));
+
+        assert!(evaluate(&machine, state, &animations, Condition::rule("Run")));
+        assert!(!evaluate(&machine, state, &animations, Condition::rule("Jump")));
+        // Unknown and mistyped parameters are never satisfied.
+        assert!(!evaluate(&machine, state, &animations, Condition::rule("Unknown")));
+        assert!(!evaluate(&machine, state, &animations, Condition::rule("Speed")));
+        assert!(!evaluate(&machine, state, &animations, Condition::compare("Run", Comparison::Less, 1.0)));
+
+        assert!(evaluate(&machine, state, &animations, Condition::compare("Speed", Comparison::Greater, 0.5)));
+        assert!(!evaluate(&machine, state, &animations, Condition::compare("Speed", Comparison::LessOrEqual, 0.5)));
+
+        assert!(evaluate(&machine, state, &animations, Condition::and(vec![Condition::rule("Run"), Condition::not(Condition::rule("Jump"))])));
+        assert!(!evaluate(&machine, state, &animations, Condition::and(vec![Condition::rule("Run"), Condition::rule("Jump")])));
+        assert!(!evaluate(&machine, state, &animations, Condition::and(vec![])));
+        assert!(evaluate(&machine, state, &animations, Condition::or(vec![Condition::rule("Jump"), Condition::rule("Run")])));
+        assert!(!evaluate(&machine, state, &animations, Condition::or(vec![])));
+
+        assert!(!evaluate(&machine, state, &animations, Condition::SourceFinished));
+        assert!(!evaluate(&machine, state, &animations, Condition::SourceNormalizedTime(0.5)));
+        animations.get_mut(animation).set_time_position(0.75);
+        assert!(evaluate(&machine, state, &animations, Condition::SourceNormalizedTime(0.5)));
+        assert!(!evaluate(&machine, state, &animations, Condition::SourceFinished));
+        animations.get_mut(animation).set_time_position(1.0);
+        assert!(evaluate(&machine, state, &animations, Condition::SourceFinished));
+    }
+
+    #[test]
+    fn transition_priority() {
+        let mut animations = AnimationContainer::new();
+        let animation = animations.add(make_animation(1.0));
+
+        let mut machine = Machine::new();
+        let idle = add_state(&mut machine, "Idle", animation);
+        let walk = add_state(&mut machine, "Walk", animation);
+        let run = add_state(&mut machine, "Run", animation);
+        let jump = add_state(&mut machine, "Jump", animation);
+        machine.add_transition(Transition::new("IdleToWalk", idle, walk, 1.0, "Go"));
+        machine.add_transition(Transition::new("IdleToRun", idle, run, 1.0, "Go").with_priority(1));
+        // Equal priority, added later - must lose.
+        machine.add_transition(Transition::new("IdleToJump", idle, jump, 1.0, "Go").with_priority(1));
+        machine.set_parameter("Go", Parameter::Rule(true));
+
+        machine.evaluate_pose(&animations, 0.1);
+        let transition = machine.transitions().borrow(machine.active_transition());
+        assert_eq!(transition.name(), "IdleToRun");
+    }
+
+    /// Creates machine with states A, B and C which hold single node at X equal to 0, 10 and
+    /// 20 respectively. There are A -> B transition by ToB rule and B -> C transition by ToC.
+    fn make_interruption_machine(animations: &mut AnimationContainer, interruptible: bool) -> Machine {
+        let mut machine = Machine::new();
+        let a = add_state(&mut machine, "A", animations.add(make_static_animation(0.0)));
+        let b = add_state(&mut machine, "B", animations.add(make_static_animation(10.0)));
+        let c = add_state(&mut machine, "C", animations.add(make_static_animation(20.0)));
+        machine.add_transition(Transition::new("AToB", a, b, 1.0, "ToB").with_interruptible(interruptible));
+        machine.add_transition(Transition::new("BToC", b, c, 1.0, "ToC"));
+        animations.update_animations(0.0);
+        machine
+    }
+
+    #[test]
+    fn interruptible_transition() {
+        let mut animations = AnimationContainer::new();
+        let mut machine = make_interruption_machine(&mut animations, true);
+
+        machine.set_parameter("ToB", Parameter::Rule(true));
+        assert_eq!(pose_x(machine.evaluate_pose(&animations, 0.5)), 0.0);
+
+        // Transition is halfway done, pose at the moment of interruption must be frozen and
+        // used as source of new transition.
+        machine.set_parameter("ToC", Parameter::Rule(true));
+        assert!((pose_x(machine.evaluate_pose(&animations, 0.0)) - 5.0).abs() < 0.0001);
+        assert_eq!(machine.transitions().borrow(machine.active_transition()).name(), "BToC");
+
+        // Blends frozen pose with C, pose of B is not used.
+        machine.evaluate_pose(&animations, 0.5);
+        assert!((pose_x(machine.evaluate_pose(&animations, 0.0)) - 12.5).abs() < 0.0001);
+
+        machine.evaluate_pose(&animations, 0.5);
+        assert!((pose_x(machine.evaluate_pose(&animations, 0.0)) - 20.0).abs() < 0.0001);
+        assert_eq!(machine.get_state(machine.active_state()).name(), "C");
+    }
+
+    #[test]
+    fn non_interruptible_transition() {
+        let mut animations = AnimationContainer::new();
+        let mut machine = make_interruption_machine(&mut animations, false);
+
+        machine.set_parameter("ToB", Parameter::Rule(true));
+        machine.evaluate_pose(&animations, 0.5);
+        machine.set_parameter("ToC", Parameter::Rule(true));
+        assert!((pose_x(machine.evaluate_pose(&animations, 0.0)) - 5.0).abs() < 0.0001);
+        assert_eq!(machine.transitions().borrow(machine.active_transition()).name(), "AToB");
+
+        // Transition to C begins only when B becomes active.
+        machine.evaluate_pose(&animations, 0.5);
+        assert_eq!(machine.get_state(machine.active_state()).name(), "B");
+        machine.evaluate_pose(&animations, 0.0);
+        assert_eq!(machine.transitions().borrow(machine.active_transition()).name(), "BToC");
+    }
+
+    fn signal_count(machine: &mut Machine) -> usize {
+        let mut count = 0;
+        while let Some(event) = machine.pop_event() {
+            if let Event::Signal { .. } = event {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn disabled_animation_has_no_signals() {
+        let mut animations = AnimationContainer::new();
+        let mut animation = make_animation(1.0);
+        animation.add_signal(AnimationSignal::new(1, 0.5));
+        let animation = animations.add(animation);
+
+        let mut machine = Machine::new();
+        add_state(&mut machine, "Idle", animation);
+
+        animations.update_animations(0.6);
+        machine.evaluate_pose(&animations, 0.6);
+        assert_eq!(signal_count(&mut machine), 1);
+
+        // Signal was passed on previous tick only.
+        animations.update_animations(0.1);
+        machine.evaluate_pose(&animations, 0.1);
+        assert_eq!(signal_count(&mut machine), 0);
+
+        // Disabled animation must not report signals of its last tick again.
+        animations.get_mut(animation).set_time_position(0.0);
+        animations.update_animations(0.6);
+        animations.get_mut(animation).set_enabled(false);
+        for _ in 0..3 {
+            animations.update_animations(0.1);
+            machine.evaluate_pose(&animations, 0.1);
+            assert_eq!(signal_count(&mut machine), 0);
+        }
+    }
+}
@@ -1,4 +1,8 @@
 pub mod machine;
+pub mod ik;
+pub mod property;
+pub mod channel;
+pub mod ragdoll;
 
 use crate::{
     core::{
@@ -28,7 +32,20 @@ use crate::{
         base::AsBase,
     },
     resource::model::Model,
-    utils::log::Log
+    utils::log::Log,
+    animation::{
+        property::{
+            PropertyTrack,
+            Property,
+            PropertyValue,
+            PropertyBlend,
+        },
+        channel::{
+            TrackChannel,
+            KeyFrameReductionSettings,
+            KeyFrameReductionReport,
+        },
+    },
 };
 use std::{
     sync::{
@@ -85,9 +102,11 @@ impl Visit for KeyFrame {
 }
 
 pub struct Track {
-    // Frames are not serialized, because it makes no sense to store them in save file,
+    // Channels are not serialized, because it makes no sense to store them in save file,
     // they will be taken from resource on Resolve stage.
-    frames: Vec<KeyFrame>,
+    position: TrackChannel<Vec3>,
+    scale: TrackChannel<Vec3>,
+    rotation: TrackChannel<Quat>,
     enabled: bool,
     max_time: f32,
     node: Handle<Node>,
@@ -96,7 +115,9 @@ pub struct Track {
 impl Clone for Track {
     fn clone(&self) -> Self {
         Self {
-            frames: self.frames.clone(),
+            position: self.position.clone(),
+            scale: self.scale.clone(),
+            rotation: self.rotation.clone(),
             enabled: self.enabled,
             max_time: self.max_time,
             node: self.node,
@@ -107,7 +128,9 @@ impl Clone for Track {
 impl Default for Track {
     fn default() -> Self {
         Self {
-            frames: Vec::new(),
+            position: Default::default(),
+            scale: Default::default(),
+            rotation: Default::default(),
             enabled: true,
             max_time: 0.0,
             node: Default::default(),
@@ -142,21 +165,12 @@ impl Track {
 
     pub fn add_key_frame(&mut self, key_frame: KeyFrame) {
         if key_frame.time > self.max_time {
-            self.frames.push(key_frame);
-
             self.max_time = key_frame.time;
-        } else {
-            // Find a place to insert
-            let mut index = 0;
-            for (i, other_key_frame) in self.frames.iter().enumerate() {
-                if key_frame.time < other_key_frame.time {
-                    index = i;
-                    break;
-                }
-            }
-
-            self.frames.insert(index, key_frame)
         }
+
+        self.position.add_key(key_frame.time, key_frame.position);
+        self.scale.add_key(key_frame.time, key_frame.scale);
+        self.rotation.add_key(key_frame.time, key_frame.rotation);
     }
 
     pub fn enable(&mut self, enabled: bool) {
@@ -168,80 +182,120 @@ impl Track {
     }
 
     pub fn set_key_frames(&mut self, key_frames: &[KeyFrame]) {
-        self.frames = key_frames.to_vec();
+        self.position.clear();
+        self.scale.clear();
+        self.rotation.clear();
         self.max_time = 0.0;
 
-        for key_frame in self.frames.iter() {
-            if key_frame.time > self.max_time {
-                self.max_time = key_frame.time;
-            }
+        for key_frame in key_frames {
+            self.add_key_frame(*key_frame);
         }
     }
 
-    pub fn get_key_frames(&self) -> &[KeyFrame] {
-        &self.frames
+    /// Builds key frames of track. Channels of track can have keys at different times
+    /// (for example after key frame reduction), so key frames are built for every unique
+    /// time of every channel. Use channels of track to access keys without allocations.
+    pub fn build_key_frames(&self) -> Vec<KeyFrame> {
+        let mut times = self.position.keys().iter()
+            .map(|k| k.time)
+            .chain(self.scale.keys().iter().map(|k| k.time))
+            .chain(self.rotation.keys().iter().map(|k| k.time))
+            .collect::<Vec<f32>>();
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        times.dedup();
+
+        times.into_iter()
+            .filter_map(|time| {
+                self.get_local_pose(time).map(|pose| {
+                    KeyFrame::new(time, pose.position, pose.scale, pose.rotation)
+                })
+            })
+            .collect()
+    }
+
+    /// Creates track from channels with keys, channels can have keys at different times.
+    pub(in crate) fn from_channels(node: Handle<Node>,
+                                   position: TrackChannel<Vec3>,
+                                   scale: TrackChannel<Vec3>,
+                                   rotation: TrackChannel<Quat>) -> Self {
+        let max_time = position.keys().iter()
+            .chain(scale.keys().iter())
+            .map(|k| k.time)
+            .chain(rotation.keys().iter().map(|k| k.time))
+            .fold(0.0, f32::max);
+        Self {
+            position,
+            scale,
+            rotation,
+            enabled: true,
+            max_time,
+            node,
+        }
     }
 
-    pub fn get_local_pose(&self, mut time: f32) -> Option<LocalPose> {
-        if self.frames.is_empty() {
-            return None;
-        }
+    pub fn position_channel(&self) -> &TrackChannel<Vec3> {
+        &self.position
+    }
 
-        if time >= self.max_time {
-            return self.frames.last().map(|k| {
-                LocalPose {
-                    node: self.node,
-                    position: k.position,
-                    scale: k.scale,
-                    rotation: k.rotation,
-                }
-            });
-        }
+    pub fn scale_channel(&self) -> &TrackChannel<Vec3> {
+        &self.scale
+    }
 
-        time = clampf(time, 0.0, self.max_time);
+    pub fn rotation_channel(&self) -> &TrackChannel<Quat> {
+        &self.rotation
+    }
 
-        let mut right_index = 0;
-        for (i, keyframe) in self.frames.iter().enumerate() {
-            if keyframe.time >= time {
-                right_index = i;
-                break;
-            }
-        }
+    /// Copies channels from other track, node of this track stays the same.
+    pub fn copy_channels(&mut self, other: &Track) {
+        self.position = other.position.clone();
+        self.scale = other.scale.clone();
+        self.rotation = other.rotation.clone();
+        self.max_time = other.max_time;
+    }
 
-        if right_index == 0 {
-            return self.frames.first().map(|k| {
-                LocalPose {
-                    node: self.node,
-                    position: k.position,
-                    scale: k.scale,
-                    rotation: k.rotation,
-                }
-            });
-        } else if let Some(left) = self.frames.get(right_index - 1) {
-            if let Some(right) = self.frames.get(right_index) {
-                let interpolator = (time - left.time) / (right.time - left.time);
-
-                return Some(LocalPose {
-                    node: self.node,
-                    position: left.position.lerp(&right.position, interpolator),
-                    scale: left.scale.lerp(&right.scale, interpolator),
-                    rotation: left.rotation.slerp(&right.rotation, interpolator),
-                });
-            }
+    fn key_count(&self) -> usize {
+        self.position.keys().len() + self.scale.keys().len() + self.rotation.keys().len()
+    }
+
+    /// Removes redundant keys of each channel of track. Length of track is preserved.
+    pub fn reduce_key_frames(&mut self, settings: &KeyFrameReductionSettings) -> KeyFrameReductionReport {
+        let keys_before = self.key_count();
+
+        self.position.reduce(settings.position_tolerance);
+        self.scale.reduce(settings.scale_tolerance);
+        self.rotation.reduce(settings.rotation_tolerance);
+
+        KeyFrameReductionReport {
+            keys_before,
+            keys_after: self.key_count(),
         }
+    }
 
-        None
+    pub fn get_local_pose(&self, time: f32) -> Option<LocalPose> {
+        let time = clampf(time, 0.0, self.max_time);
+
+        Some(LocalPose {
+            node: self.node,
+            position: self.position.value(time)?,
+            scale: self.scale.value(time)?,
+            rotation: self.rotation.value(time)?,
+        })
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct AnimationEvent {
-    pub signal_id: u64
+    pub signal_id: u64,
+    /// Name of signal, empty for signals created by `AnimationSignal::new`.
+    pub signal_name: String,
 }
 
+/// Signal is a mark on animation timeline, animation emits event when its time position
+/// passes the signal. Signal can be identified either by numeric id or by name.
 #[derive(Clone)]
 pub struct AnimationSignal {
     id: u64,
+    name: String,
     time: f32,
     enabled: bool,
 }
@@ -250,11 +304,34 @@ impl AnimationSignal {
     pub fn new(id: u64, time: f32) -> Self {
         Self {
             id,
+            name: Default::default(),
+            time,
+            enabled: true
+        }
+    }
+
+    /// Creates new signal identified by name, id of signal will be zero.
+    pub fn named(name: &str, time: f32) -> Self {
+        Self {
+            id: 0,
+            name: name.to_owned(),
             time,
             enabled: true
         }
     }
 
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
     pub fn set_enabled(&mut self, value: bool) {
         self.enabled = value;
     }
@@ -262,12 +339,20 @@ impl AnimationSignal {
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    fn make_event(&self) -> AnimationEvent {
+        AnimationEvent {
+            signal_id: self.id,
+            signal_name: self.name.clone(),
+        }
+    }
 }
 
 impl Default for AnimationSignal {
     fn default() -> Self {
         Self {
             id: 0,
+            name: Default::default(),
             time: 0.0,
             enabled: true,
         }
@@ -279,6 +364,11 @@ impl Visit for AnimationSignal {
         visitor.enter_region(name)?;
 
         self.id.visit("Id", visitor)?;
+        // Signals saved by older versions have no name.
+        let name_result = self.name.visit("Name", visitor);
+        if !visitor.is_reading() {
+            name_result?;
+        }
         self.time.visit("Time", visitor)?;
         self.enabled.visit("Enabled", visitor)?;
 
@@ -287,8 +377,10 @@ impl Visit for AnimationSignal {
 }
 
 pub struct Animation {
+    name: String,
     // TODO: Extract into separate struct AnimationTimeline
     tracks: Vec<Track>,
+    property_tracks: Vec<PropertyTrack>,
     length: f32,
     time_position: f32,
     ///////////////////////////////////////////////////////
@@ -298,7 +390,10 @@ pub struct Animation {
     pub(in crate) resource: Option<Arc<Mutex<Model>>>,
     pose: AnimationPose,
     signals: Vec<AnimationSignal>,
-    events: VecDeque<AnimationEvent>
+    events: VecDeque<AnimationEvent>,
+    // Events emitted during last tick, used by animation blending machine to dispatch
+    // signals of animations that contribute to final pose.
+    last_tick_events: Vec<AnimationEvent>,
 }
 
 /// Snapshot of scene node local transform state.
@@ -340,7 +435,8 @@ impl LocalPose {
 
 #[derive(Default)]
 pub struct AnimationPose {
-    local_poses: HashMap<Handle<Node>, LocalPose>
+    local_poses: HashMap<Handle<Node>, LocalPose>,
+    properties: HashMap<(Handle<Node>, Property), PropertyBlend>,
 }
 
 impl AnimationPose {
@@ -349,6 +445,25 @@ impl AnimationPose {
         for (handle, local_pose) in self.local_poses.iter() {
             dest.local_poses.insert(*handle, local_pose.clone());
         }
+        for (key, blend) in self.properties.iter() {
+            dest.properties.insert(key.clone(), *blend);
+        }
+    }
+
+    /// Returns value of animated property of given node, if any. Could be useful to fetch
+    /// values of `Property::Custom` properties.
+    pub fn property_value(&self, node: Handle<Node>, property: &Property) -> Option<PropertyValue> {
+        self.properties.get(&(node, property.clone())).map(|blend| blend.value())
+    }
+
+    fn blend_properties(&mut self, other: &AnimationPose, weight: f32) {
+        for (key, other_blend) in other.properties.iter() {
+            if let Some(blend) = self.properties.get_mut(key) {
+                blend.add_weighted(other_blend, weight);
+            } else {
+                self.properties.insert(key.clone(), PropertyBlend::weighted(other_blend, weight));
+            }
+        }
     }
 
     pub fn blend_with(&mut self, other: &AnimationPose, weight: f32) {
@@ -361,14 +476,43 @@ impl AnimationPose {
                 self.add_local_pose(other_pose.weighted_clone(weight));
             }
         }
+        self.blend_properties(other, weight);
+    }
+
+    /// Blends other pose into current as a part of weighted average of multiple poses.
+    /// `accumulated_weight` is the sum of weights of all poses that were blended in before.
+    /// Weights are not required to sum to 1.0 - every component is interpolated towards
+    /// the other pose, so skipped poses or nodes that are animated only by some of the
+    /// poses do not shrink the result. Nodes that weren't blended in before take the
+    /// other pose as is.
+    pub(in crate) fn blend_weighted(&mut self, other: &AnimationPose, weight: f32, accumulated_weight: f32) {
+        let total_weight = accumulated_weight + weight;
+        let t = if total_weight > 0.0 { weight / total_weight } else { 0.0 };
+        for (handle, other_pose) in other.local_poses.iter() {
+            if let Some(current_pose) = self.local_poses.get_mut(handle) {
+                current_pose.position = current_pose.position.lerp(&other_pose.position, t);
+                current_pose.rotation = current_pose.rotation.nlerp(&other_pose.rotation, t);
+                current_pose.scale = current_pose.scale.lerp(&other_pose.scale, t);
+            } else {
+                self.add_local_pose(other_pose.clone());
+            }
+        }
+        self.blend_properties(other, weight);
     }
 
     fn add_local_pose(&mut self, local_pose: LocalPose) {
         self.local_poses.insert(local_pose.node, local_pose);
     }
 
+    fn add_property(&mut self, track: &PropertyTrack, time: f32) {
+        if let Some(value) = track.value(time) {
+            self.properties.insert((track.node(), track.property().clone()), PropertyBlend::new(value));
+        }
+    }
+
     pub fn reset(&mut self) {
         self.local_poses.clear();
+        self.properties.clear();
     }
 
     pub fn apply(&self, graph: &mut Graph) {
@@ -384,13 +528,20 @@ impl AnimationPose {
                     .set_scale(local_pose.scale);
             }
         }
+        for ((node, property), blend) in self.properties.iter() {
+            if node.is_some() {
+                property.apply(graph.get_mut(*node), &blend.value());
+            }
+        }
     }
 }
 
 impl Clone for Animation {
     fn clone(&self) -> Self {
         Self {
+            name: self.name.clone(),
             tracks: self.tracks.clone(),
+            property_tracks: self.property_tracks.clone(),
             speed: self.speed,
             length: self.length,
             time_position: self.time_position,
@@ -399,12 +550,25 @@ impl Clone for Animation {
             resource: self.resource.clone(),
             pose: Default::default(),
             signals: self.signals.clone(),
-            events: Default::default()
+            events: Default::default(),
+            last_tick_events: Default::default(),
         }
     }
 }
 
 impl Animation {
+    /// Sets name of animation. Name is used to reference animations from data files, for
+    /// example from machine definitions. Animations loaded from model resources are named
+    /// by the file name of resource without extension.
+    pub fn set_name(&mut self, name: &str) -> &mut Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
     pub fn add_track(&mut self, track: Track) {
         self.tracks.push(track);
 
@@ -419,6 +583,23 @@ impl Animation {
         &self.tracks
     }
 
+    /// Adds track that animates a property of a node. See `property` module docs.
+    pub fn add_property_track(&mut self, track: PropertyTrack) -> &mut Self {
+        if track.max_time() > self.length {
+            self.length = track.max_time();
+        }
+        self.property_tracks.push(track);
+        self
+    }
+
+    pub fn get_property_tracks(&self) -> &[PropertyTrack] {
+        &self.property_tracks
+    }
+
+    pub fn get_property_tracks_mut(&mut self) -> &mut [PropertyTrack] {
+        &mut self.property_tracks
+    }
+
     pub fn set_time_position(&mut self, time: f32) -> &mut Self {
         if self.looped {
             self.time_position = wrapf(time, 0.0, self.length);
@@ -438,12 +619,14 @@ impl Animation {
         let current_time_position = self.get_time_position();
         let new_time_position = current_time_position + dt * self.get_speed();
 
-        for signal in self.signals.iter_mut() {
+        self.last_tick_events.clear();
+        for signal in self.signals.iter().filter(|s| s.enabled) {
             if current_time_position < signal.time && new_time_position >= signal.time {
                 // TODO: Make this configurable.
                 if self.events.len() < 32 {
-                    self.events.push_back(AnimationEvent { signal_id: signal.id });
+                    self.events.push_back(signal.make_event());
                 }
+                self.last_tick_events.push(signal.make_event());
             }
         }
 
@@ -454,6 +637,25 @@ impl Animation {
         self.events.pop_front()
     }
 
+    /// Returns events emitted during last update of animation. Unlike `pop_event` this
+    /// does not consume events, so multiple listeners can observe them.
+    pub fn last_tick_events(&self) -> &[AnimationEvent] {
+        &self.last_tick_events
+    }
+
+    /// Returns events of signals which are in (from; to] time range of animation timeline.
+    /// Used to get signals of animations which are sampled directly, not played.
+    pub(in crate) fn signals_in_range(&self, from: f32, to: f32) -> impl Iterator<Item=AnimationEvent> + '_ {
+        self.signals
+            .iter()
+            .filter(move |s| s.enabled && from < s.time && to >= s.time)
+            .map(|s| s.make_event())
+    }
+
+    pub fn signals(&self) -> &[AnimationSignal] {
+        &self.signals
+    }
+
     pub fn get_time_position(&self) -> f32 {
         self.time_position
     }
@@ -462,6 +664,10 @@ impl Animation {
         self.speed
     }
 
+    pub fn get_length(&self) -> f32 {
+        self.length
+    }
+
     pub fn set_loop(&mut self, state: bool) -> &mut Self {
         self.looped = state;
         self
@@ -493,6 +699,15 @@ impl Animation {
         &mut self.tracks
     }
 
+    /// Removes redundant keys of every track, see `Track::reduce_key_frames`.
+    pub fn reduce_key_frames(&mut self, settings: &KeyFrameReductionSettings) -> KeyFrameReductionReport {
+        let mut report = KeyFrameReductionReport::default();
+        for track in self.tracks.iter_mut() {
+            report.merge(&track.reduce_key_frames(settings));
+        }
+        report
+    }
+
     pub fn get_resource(&self) -> Option<Arc<Mutex<Model>>> {
         self.resource.clone()
     }
@@ -570,7 +785,7 @@ impl Animation {
                     let mut found = false;
                     for ref_track in ref_animation.get_tracks().iter() {
                         if track_node.name() == resource.get_scene().graph.get(ref_track.get_node()).base().name() {
-                            track.set_key_frames(ref_track.get_key_frames());
+                            track.copy_channels(ref_track);
                             found = true;
                             break;
                         }
@@ -592,6 +807,25 @@ impl Animation {
                 }
             }
         }
+        for track in self.property_tracks.iter().filter(|t| t.is_enabled()) {
+            self.pose.add_property(track, self.time_position);
+        }
+    }
+
+    /// Samples enabled tracks at given time and writes result into given pose. Unlike `tick`
+    /// it does not modify state of animation, so it can be used to get pose at any time.
+    pub fn sample_pose(&self, time: f32, pose: &mut AnimationPose) {
+        pose.reset();
+        for track in self.tracks.iter() {
+            if track.is_enabled() {
+                if let Some(local_pose) = track.get_local_pose(time) {
+                    pose.add_local_pose(local_pose);
+                }
+            }
+        }
+        for track in self.property_tracks.iter().filter(|t| t.is_enabled()) {
+            pose.add_property(track, time);
+        }
     }
 
     pub fn get_pose(&self) -> &AnimationPose {
@@ -602,7 +836,9 @@ impl Animation {
 impl Default for Animation {
     fn default() -> Self {
         Self {
+            name: Default::default(),
             tracks: Vec::new(),
+            property_tracks: Vec::new(),
             speed: 1.0,
             length: 0.0,
             time_position: 0.0,
@@ -611,7 +847,8 @@ impl Default for Animation {
             resource: Default::default(),
             pose: Default::default(),
             signals: Default::default(),
-            events: Default::default()
+            events: Default::default(),
+            last_tick_events: Default::default(),
         }
     }
 }
@@ -620,6 +857,11 @@ impl Visit for Animation {
     fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
         visitor.enter_region(name)?;
 
+        // Animations saved by older versions have no name.
+        let name_result = self.name.visit("Name", visitor);
+        if !visitor.is_reading() {
+            name_result?;
+        }
         self.tracks.visit("Tracks", visitor)?;
         self.speed.visit("Speed", visitor)?;
         self.length.visit("Length", visitor)?;
@@ -628,6 +870,11 @@ impl Visit for Animation {
         self.looped.visit("Looped", visitor)?;
         self.enabled.visit("Enabled", visitor)?;
         self.signals.visit("Signals", visitor)?;
+        // Animations saved by older versions have no property tracks.
+        let property_tracks_result = self.property_tracks.visit("PropertyTracks", visitor);
+        if !visitor.is_reading() {
+            property_tracks_result?;
+        }
 
         visitor.leave_region()
     }
@@ -690,11 +937,30 @@ impl AnimationContainer {
         self.pool.borrow(handle)
     }
 
+    /// Returns animation by given handle or None if handle is invalid, i.e. `Handle::NONE`.
+    #[inline]
+    pub fn try_get(&self, handle: Handle<Animation>) -> Option<&Animation> {
+        if self.pool.is_valid_handle(handle) {
+            Some(self.pool.borrow(handle))
+        } else {
+            None
+        }
+    }
+
     #[inline]
     pub fn get_mut(&mut self, handle: Handle<Animation>) -> &mut Animation {
         self.pool.borrow_mut(handle)
     }
 
+    /// Searches animation with given name among given handles. Returns `Handle::NONE` if
+    /// nothing was found.
+    pub fn find_by_name(&self, handles: &[Handle<Animation>], name: &str) -> Handle<Animation> {
+        handles.iter()
+            .find(|h| self.pool.try_get(**h).map_or(false, |animation| animation.name() == name))
+            .copied()
+            .unwrap_or(Handle::NONE)
+    }
+
     #[inline]
     pub fn retain<P>(&mut self, pred: P) where P: FnMut(&Animation) -> bool {
         self.pool.retain(pred)
@@ -709,8 +975,14 @@ impl AnimationContainer {
     }
 
     pub fn update_animations(&mut self, dt: f32) {
-        for animation in self.pool.iter_mut().filter(|anim| anim.enabled) {
-            animation.tick(dt);
+        for animation in self.pool.iter_mut() {
+            if animation.enabled {
+                animation.tick(dt);
+            } else {
+                // Disabled animation passes no signals, otherwise events of its last tick
+                // would be reported on every frame.
+                animation.last_tick_events.clear();
+            }
         }
     }
 }
@@ -0,0 +1,564 @@
+//! Property tracks allows to animate properties of scene nodes other than local transform.
+//!
+//! Each property track is addressed by a node handle and a property identifier, key frames
+//! of the track store typed values (float, vector, color or bool). Property tracks are
+//! evaluated by animation alongside ordinary transform tracks, their values are stored in
+//! animation pose and blended by animation blending machine. Numeric values are blended
+//! as weighted average of poses which animate the property, so property animated by only one
+//! of blended poses keeps its value. Boolean values are taken from pose with highest weight.
+//!
+//! ```no_run
+//! use rg3d::{
+//!     animation::{
+//!         Animation,
+//!         property::{PropertyTrack, PropertyKeyFrame, Property, PropertyValue},
+//!     },
+//!     core::{color::Color, pool::Handle},
+//! };
+//!
+//! // Assume that this is correct handle of a light.
+//! let light = Handle::NONE;
+//!
+//! let mut track = PropertyTrack::new(light, Property::LightColor);
+//! track.add_key_frame(PropertyKeyFrame::new(0.0, PropertyValue::Color(Color::opaque(255, 0, 0))));
+//! track.add_key_frame(PropertyKeyFrame::new(1.0, PropertyValue::Color(Color::opaque(0, 0, 255))));
+//!
+//! let mut animation = Animation::default();
+//! animation.add_property_track(track);
+//! ```
+
+use crate::{
+    core::{
+        math::vec3::Vec3,
+        color::Color,
+        pool::Handle,
+        visitor::{
+            Visit,
+            VisitResult,
+            Visitor,
+            VisitError,
+        },
+    },
+    scene::{
+        node::Node,
+        light::LightKind,
+        base::AsBase,
+    },
+};
+
+/// Identifier of animated property of a node.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Property {
+    /// Visibility of any node, bool value.
+    Visibility,
+    /// Color of a light, color value.
+    LightColor,
+    /// Radius of a point light, float value.
+    LightRadius,
+    /// Color of a sprite, color value.
+    SpriteColor,
+    /// Size of a sprite, float value.
+    SpriteSize,
+    /// Field of view of a camera in radians, float value.
+    CameraFov,
+    /// Weight of blend shape of a mesh with given index, float value.
+    BlendShapeWeight(usize),
+    /// User-defined property, engine does not apply it to nodes. Its value can be fetched
+    /// from animation pose by `AnimationPose::property_value`.
+    Custom(String),
+}
+
+impl Default for Property {
+    fn default() -> Self {
+        Property::Visibility
+    }
+}
+
+impl Property {
+    pub(in crate) fn id(&self) -> u32 {
+        match self {
+            Property::Visibility => 0,
+            Property::LightColor => 1,
+            Property::LightRadius => 2,
+            Property::SpriteColor => 3,
+            Property::SpriteSize => 4,
+            Property::CameraFov => 5,
+            Property::Custom(_) => 6,
+            Property::BlendShapeWeight(_) => 7,
+        }
+    }
+
+    pub(in crate) fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(Property::Visibility),
+            1 => Ok(Property::LightColor),
+            2 => Ok(Property::LightRadius),
+            3 => Ok(Property::SpriteColor),
+            4 => Ok(Property::SpriteSize),
+            5 => Ok(Property::CameraFov),
+            6 => Ok(Property::Custom(Default::default())),
+            7 => Ok(Property::BlendShapeWeight(0)),
+            _ => Err(format!("Invalid property id {}", id))
+        }
+    }
+
+    /// Writes value into corresponding property of given node. Value of mismatching
+    /// type or property which does not exist in the node are ignored.
+    pub(in crate) fn apply(&self, node: &mut Node, value: &PropertyValue) {
+        match (self, node, value) {
+            (Property::Visibility, node, PropertyValue::Bool(visibility)) => {
+                node.base_mut().set_visibility(*visibility);
+            }
+            (Property::LightColor, Node::Light(light), PropertyValue::Color(color)) => {
+                light.set_color(*color);
+            }
+            (Property::LightRadius, Node::Light(light), PropertyValue::Float(radius)) => {
+                if let LightKind::Point(point_light) = light.get_kind_mut() {
+                    point_light.set_radius(*radius);
+                }
+            }
+            (Property::SpriteColor, Node::Sprite(sprite), PropertyValue::Color(color)) => {
+                sprite.set_color(*color);
+            }
+            (Property::SpriteSize, Node::Sprite(sprite), PropertyValue::Float(size)) => {
+                sprite.set_size(*size);
+            }
+            (Property::CameraFov, Node::Camera(camera), PropertyValue::Float(fov)) => {
+                camera.set_fov(*fov);
+            }
+            (Property::BlendShapeWeight(index), Node::Mesh(mesh), PropertyValue::Float(weight)) => {
+                mesh.set_blend_shape_weight(*index, *weight);
+            }
+            _ => ()
+        }
+    }
+}
+
+impl Visit for Property {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut id = self.id();
+        id.visit("Id", visitor)?;
+        if visitor.is_reading() {
+            *self = Property::from_id(id)?;
+        }
+        match self {
+            Property::Custom(name) => name.visit("Name", visitor)?,
+            Property::BlendShapeWeight(index) => {
+                let mut value = *index as u32;
+                value.visit("Index", visitor)?;
+                *index = value as usize;
+            }
+            _ => ()
+        }
+
+        visitor.leave_region()
+    }
+}
+
+/// Value of animated property.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    Float(f32),
+    Vector(Vec3),
+    Color(Color),
+    Bool(bool),
+}
+
+impl Default for PropertyValue {
+    fn default() -> Self {
+        PropertyValue::Float(0.0)
+    }
+}
+
+fn lerpf(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn color_channel(value: f32) -> u8 {
+    value.round().max(0.0).min(255.0) as u8
+}
+
+impl PropertyValue {
+    fn id(&self) -> u32 {
+        match self {
+            PropertyValue::Float(_) => 0,
+            PropertyValue::Vector(_) => 1,
+            PropertyValue::Color(_) => 2,
+            PropertyValue::Bool(_) => 3,
+        }
+    }
+
+    fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(PropertyValue::Float(0.0)),
+            1 => Ok(PropertyValue::Vector(Vec3::ZERO)),
+            2 => Ok(PropertyValue::Color(Color::WHITE)),
+            3 => Ok(PropertyValue::Bool(false)),
+            _ => Err(format!("Invalid property value id {}", id))
+        }
+    }
+
+    /// Interpolates between two values. Values of different types and bool values are not
+    /// interpolated, they are switched to other value when `t` reaches 1.0.
+    pub fn interpolate(&self, other: &PropertyValue, t: f32) -> PropertyValue {
+        match (self, other) {
+            (PropertyValue::Float(a), PropertyValue::Float(b)) => PropertyValue::Float(lerpf(*a, *b, t)),
+            (PropertyValue::Vector(a), PropertyValue::Vector(b)) => PropertyValue::Vector(a.lerp(b, t)),
+            (PropertyValue::Color(a), PropertyValue::Color(b)) => PropertyValue::Color(Color::from_rgba(
+                color_channel(lerpf(a.r as f32, b.r as f32, t)),
+                color_channel(lerpf(a.g as f32, b.g as f32, t)),
+                color_channel(lerpf(a.b as f32, b.b as f32, t)),
+                color_channel(lerpf(a.a as f32, b.a as f32, t)))),
+            _ => if t >= 1.0 { *other } else { *self }
+        }
+    }
+}
+
+/// Weighted average of values of a property from multiple blended poses. Values are accumulated
+/// in floating point and normalized by total weight, so property which is animated only by some
+/// of blended poses keeps its value instead of fading to zero.
+#[derive(Copy, Clone, Debug)]
+pub(in crate) struct PropertyBlend {
+    /// Value with highest weight, it defines type of result and value of bool property.
+    dominant: PropertyValue,
+    dominant_weight: f32,
+    /// Weighted sum of components of values.
+    sum: [f32; 4],
+    total_weight: f32,
+}
+
+impl PropertyBlend {
+    pub(in crate) fn new(value: PropertyValue) -> Self {
+        Self {
+            dominant: value,
+            dominant_weight: 1.0,
+            sum: Self::components(&value),
+            total_weight: 1.0,
+        }
+    }
+
+    fn components(value: &PropertyValue) -> [f32; 4] {
+        match value {
+            PropertyValue::Float(value) => [*value, 0.0, 0.0, 0.0],
+            PropertyValue::Vector(value) => [value.x, value.y, value.z, 0.0],
+            PropertyValue::Color(value) => [value.r as f32, value.g as f32, value.b as f32, value.a as f32],
+            PropertyValue::Bool(_) => [0.0; 4],
+        }
+    }
+
+    /// Returns normalized components, components of dominant value are used if there is
+    /// no weight at all.
+    fn average(&self) -> [f32; 4] {
+        if self.total_weight > 0.0 {
+            let k = 1.0 / self.total_weight;
+            [self.sum[0] * k, self.sum[1] * k, self.sum[2] * k, self.sum[3] * k]
+        } else {
+            Self::components(&self.dominant)
+        }
+    }
+
+    /// Creates blend of single value of other blend with given weight.
+    pub(in crate) fn weighted(other: &PropertyBlend, weight: f32) -> Self {
+        let average = other.average();
+        Self {
+            dominant: other.dominant,
+            dominant_weight: weight,
+            sum: [average[0] * weight, average[1] * weight, average[2] * weight, average[3] * weight],
+            total_weight: weight,
+        }
+    }
+
+    /// Adds value of other blend with given weight. Bool value is replaced by other value if
+    /// weight of other value is not less than weight of any previous value. Values of
+    /// different types are not blended.
+    pub(in crate) fn add_weighted(&mut self, other: &PropertyBlend, weight: f32) {
+        if self.dominant.id() != other.dominant.id() {
+            return;
+        }
+        let average = other.average();
+        for (sum, component) in self.sum.iter_mut().zip(average.iter()) {
+            *sum += component * weight;
+        }
+        self.total_weight += weight;
+        if weight >= self.dominant_weight {
+            self.dominant = other.dominant;
+            self.dominant_weight = weight;
+        }
+    }
+
+    /// Returns blended value.
+    pub(in crate) fn value(&self) -> PropertyValue {
+        let [x, y, z, w] = self.average();
+        match self.dominant {
+            PropertyValue::Float(_) => PropertyValue::Float(x),
+            PropertyValue::Vector(_) => PropertyValue::Vector(Vec3::new(x, y, z)),
+            PropertyValue::Color(_) => PropertyValue::Color(Color::from_rgba(
+                color_channel(x), color_channel(y), color_channel(z), color_channel(w))),
+            PropertyValue::Bool(_) => self.dominant,
+        }
+    }
+}
+
+impl Visit for PropertyValue {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut id = self.id();
+        id.visit("Id", visitor)?;
+        if visitor.is_reading() {
+            *self = PropertyValue::from_id(id)?;
+        }
+        match self {
+            PropertyValue::Float(value) => value.visit("Value", visitor)?,
+            PropertyValue::Vector(value) => value.visit("Value", visitor)?,
+            PropertyValue::Color(value) => value.visit("Value", visitor)?,
+            PropertyValue::Bool(value) => value.visit("Value", visitor)?,
+        }
+
+        visitor.leave_region()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PropertyKeyFrame {
+    pub time: f32,
+    pub value: PropertyValue,
+}
+
+impl PropertyKeyFrame {
+    pub fn new(time: f32, value: PropertyValue) -> Self {
+        Self {
+            time,
+            value,
+        }
+    }
+}
+
+impl Visit for PropertyKeyFrame {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.time.visit("Time", visitor)?;
+        self.value.visit("Value", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Track that animates single property of a node. Unlike transform tracks, key frames of
+/// property tracks are serialized, because property tracks are usually created in code.
+#[derive(Clone, Debug)]
+pub struct PropertyTrack {
+    frames: Vec<PropertyKeyFrame>,
+    enabled: bool,
+    max_time: f32,
+    node: Handle<Node>,
+    property: Property,
+}
+
+impl Default for PropertyTrack {
+    fn default() -> Self {
+        Self {
+            frames: Vec::new(),
+            enabled: true,
+            max_time: 0.0,
+            node: Handle::NONE,
+            property: Default::default(),
+        }
+    }
+}
+
+impl PropertyTrack {
+    pub fn new(node: Handle<Node>, property: Property) -> Self {
+        Self {
+            node,
+            property,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_node(&mut self, node: Handle<Node>) {
+        self.node = node;
+    }
+
+    pub fn node(&self) -> Handle<Node> {
+        self.node
+    }
+
+    pub fn property(&self) -> &Property {
+        &self.property
+    }
+
+    pub fn enable(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn max_time(&self) -> f32 {
+        self.max_time
+    }
+
+    /// Adds new key frame keeping key frames sorted by time.
+    pub fn add_key_frame(&mut self, key_frame: PropertyKeyFrame) {
+        let index = self.frames
+            .iter()
+            .position(|k| key_frame.time < k.time)
+            .unwrap_or_else(|| self.frames.len());
+        self.frames.insert(index, key_frame);
+        if key_frame.time > self.max_time {
+            self.max_time = key_frame.time;
+        }
+    }
+
+    pub fn key_frames(&self) -> &[PropertyKeyFrame] {
+        &self.frames
+    }
+
+    /// Returns value of property at given time.
+    pub fn value(&self, time: f32) -> Option<PropertyValue> {
+        let first = self.frames.first()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+
+        for (left, right) in self.frames.iter().zip(self.frames.iter().skip(1)) {
+            if time <= right.time {
+                let span = right.time - left.time;
+                let t = if span > 0.0 { (time - left.time) / span } else { 1.0 };
+                return Some(left.value.interpolate(&right.value, t));
+            }
+        }
+
+        self.frames.last().map(|k| k.value)
+    }
+}
+
+impl Visit for PropertyTrack {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.frames.visit("Frames", visitor)?;
+        self.enabled.visit("Enabled", visitor)?;
+        self.max_time.visit("MaxTime", visitor)?;
+        self.node.visit("Node", visitor)?;
+        self.property.visit("Property", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{
+            AnimationPose,
+            property::{PropertyTrack, PropertyKeyFrame, PropertyValue, Property, PropertyBlend},
+        },
+        core::{
+            pool::Handle,
+            color::Color,
+        },
+    };
+
+    fn make_pose(values: &[(Property, PropertyValue)]) -> AnimationPose {
+        let mut pose = AnimationPose::default();
+        for (property, value) in values {
+            let mut track = PropertyTrack::new(Handle::NONE, property.clone());
+            track.add_key_frame(PropertyKeyFrame::new(0.0, *value));
+            pose.add_property(&track, 0.0);
+        }
+        pose
+    }
+
+    /// Blends two poses like transition of animation blending machine does.
+    fn blend(a: &AnimationPose, b: &AnimationPose, t: f32) -> AnimationPose {
+        let mut pose = AnimationPose::default();
+        pose.blend_with(a, 1.0 - t);
+        pose.blend_with(b, t);
+        pose
+    }
+
+    #[test]
+    fn property_track_interpolation() {
+        let mut track = PropertyTrack::new(Handle::NONE, Property::CameraFov);
+        track.add_key_frame(PropertyKeyFrame::new(1.0, PropertyValue::Float(2.0)));
+        track.add_key_frame(PropertyKeyFrame::new(0.0, PropertyValue::Float(0.0)));
+
+        assert_eq!(track.value(-1.0), Some(PropertyValue::Float(0.0)));
+        assert_eq!(track.value(0.5), Some(PropertyValue::Float(1.0)));
+        assert_eq!(track.value(2.0), Some(PropertyValue::Float(2.0)));
+
+        let mut visibility = PropertyTrack::new(Handle::NONE, Property::Visibility);
+        visibility.add_key_frame(PropertyKeyFrame::new(0.0, PropertyValue::Bool(true)));
+        visibility.add_key_frame(PropertyKeyFrame::new(1.0, PropertyValue::Bool(false)));
+
+        assert_eq!(visibility.value(0.9), Some(PropertyValue::Bool(true)));
+        assert_eq!(visibility.value(1.0), Some(PropertyValue::Bool(false)));
+    }
+
+    #[test]
+    fn property_blending() {
+        let a = make_pose(&[
+            (Property::CameraFov, PropertyValue::Float(1.0)),
+            (Property::SpriteColor, PropertyValue::Color(Color::from_rgba(255, 0, 100, 255))),
+            (Property::Visibility, PropertyValue::Bool(true)),
+        ]);
+        let b = make_pose(&[
+            (Property::CameraFov, PropertyValue::Float(2.0)),
+            (Property::SpriteColor, PropertyValue::Color(Color::from_rgba(0, 255, 100, 255))),
+            (Property::Visibility, PropertyValue::Bool(false)),
+        ]);
+
+        let pose = blend(&a, &b, 0.25);
+        assert_eq!(pose.property_value(Handle::NONE, &Property::CameraFov), Some(PropertyValue::Float(1.25)));
+        assert_eq!(pose.property_value(Handle::NONE, &Property::SpriteColor),
+                   Some(PropertyValue::Color(Color::from_rgba(191, 64, 100, 255))));
+        assert_eq!(pose.property_value(Handle::NONE, &Property::Visibility), Some(PropertyValue::Bool(true)));
+
+        let pose = blend(&a, &b, 0.5);
+        assert_eq!(pose.property_value(Handle::NONE, &Property::Visibility), Some(PropertyValue::Bool(false)));
+    }
+
+    #[test]
+    fn property_of_single_pose_keeps_its_value() {
+        let a = make_pose(&[
+            (Property::CameraFov, PropertyValue::Float(1.5)),
+            (Property::SpriteColor, PropertyValue::Color(Color::from_rgba(200, 100, 50, 255))),
+        ]);
+        let b = make_pose(&[]);
+
+        let fov = |pose: &AnimationPose| match pose.property_value(Handle::NONE, &Property::CameraFov) {
+            Some(PropertyValue::Float(fov)) => fov,
+            _ => panic!("Float value expected!"),
+        };
+        for t in [0.0, 0.3, 0.7, 1.0].iter() {
+            let pose = blend(&a, &b, *t);
+            assert!((fov(&pose) - 1.5).abs() < 0.0001);
+            assert_eq!(pose.property_value(Handle::NONE, &Property::SpriteColor),
+                       Some(PropertyValue::Color(Color::from_rgba(200, 100, 50, 255))));
+            // Other order of poses.
+            let pose = blend(&b, &a, *t);
+            assert!((fov(&pose) - 1.5).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn color_blending_has_no_accumulated_error() {
+        // Same color blended from many poses must stay the same.
+        let color = PropertyValue::Color(Color::from_rgba(201, 99, 3, 255));
+        let mut blend = PropertyBlend::weighted(&PropertyBlend::new(color), 0.1);
+        for _ in 0..9 {
+            blend.add_weighted(&PropertyBlend::new(color), 0.1);
+        }
+        assert_eq!(blend.value(), color);
+
+        // Values of different types are not blended.
+        blend.add_weighted(&PropertyBlend::new(PropertyValue::Float(1.0)), 1.0);
+        assert_eq!(blend.value(), color);
+    }
+}
@@ -0,0 +1,603 @@
+//! Ragdoll - physics driven skeleton.
+//!
+//! Ragdoll is built from a skeleton of a skinned model: every bone gets a capsule rigid body
+//! and every pair of parent and child bones is connected by a distance joint. Physics engine
+//! does not know anything about joints, so they are solved by ragdoll itself after each
+//! physics step.
+//!
+//! Ragdoll has two modes:
+//! - Animated - bones are driven by animation pose, bodies just follow bones so ragdoll is
+//!   ready to be switched to physics mode at any time.
+//! - Physics - bones are driven by bodies, position of root bone and rotations of every bone
+//!   are written into local transforms of bones.
+//!
+//! When ragdoll is switched back to animated mode, physics pose is smoothly blended into
+//! animation pose during blend time.
+//!
+//! Like inverse kinematics solvers, ragdoll must be updated *after* animation pose was applied
+//! to graph.
+//!
+//! Example:
+//!
+//! ```no_run
+//! use rg3d::{
+//!     animation::ragdoll::{RagdollBuilder, RagdollMode, CapsuleSize},
+//!     core::pool::Handle,
+//!     scene::Scene,
+//! };
+//!
+//! fn create(scene: &mut Scene) {
+//!     let hips = Handle::default(); // Assume that this is correct handle.
+//!     let mut ragdoll = RagdollBuilder::new(hips)
+//!         .with_capsule_size(CapsuleSize { radius: 0.1, height: 0.2 })
+//!         .with_bone_capsule_size("Head", CapsuleSize { radius: 0.15, height: 0.0 })
+//!         .build(&scene.graph, &mut scene.physics);
+//!     ragdoll.set_mode(RagdollMode::Physics, &scene.graph);
+//!     // Then each frame after animation pose was applied:
+//!     ragdoll.update(&mut scene.graph, &mut scene.physics, 1.0 / 60.0);
+//! }
+//! ```
+
+use crate::{
+    core::{
+        math::{
+            vec3::Vec3,
+            quat::Quat,
+        },
+        pool::Handle,
+        visitor::{
+            Visit,
+            VisitResult,
+            Visitor,
+        },
+    },
+    physics::{
+        Physics,
+        rigid_body::RigidBody,
+        convex_shape::{
+            ConvexShape,
+            CapsuleShape,
+            Axis,
+        },
+    },
+    scene::{
+        node::Node,
+        graph::Graph,
+        base::AsBase,
+    },
+    animation::ik::{
+        calculate_global_transform,
+        calculate_global_position,
+        rotate_node_from_to,
+    },
+};
+use std::collections::HashMap;
+
+/// Size of capsule of a bone body.
+#[derive(Copy, Clone, Debug)]
+pub struct CapsuleSize {
+    pub radius: f32,
+    pub height: f32,
+}
+
+impl Default for CapsuleSize {
+    fn default() -> Self {
+        Self {
+            radius: 0.05,
+            height: 0.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RagdollMode {
+    /// Bones are driven by animation, bodies follow bones.
+    Animated,
+    /// Bones are driven by bodies.
+    Physics,
+}
+
+impl RagdollMode {
+    fn id(self) -> u32 {
+        match self {
+            RagdollMode::Animated => 0,
+            RagdollMode::Physics => 1,
+        }
+    }
+
+    fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(RagdollMode::Animated),
+            1 => Ok(RagdollMode::Physics),
+            _ => Err(format!("Invalid ragdoll mode id {}", id))
+        }
+    }
+}
+
+#[derive(Default)]
+struct RagdollBone {
+    node: Handle<Node>,
+    body: Handle<RigidBody>,
+    /// Index of parent bone in ragdoll, root bone has no parent.
+    parent: Option<u32>,
+}
+
+impl Visit for RagdollBone {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.node.visit("Node", visitor)?;
+        self.body.visit("Body", visitor)?;
+        self.parent.visit("Parent", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Distance constraint between two bones.
+#[derive(Default)]
+struct RagdollJoint {
+    a: u32,
+    b: u32,
+    distance: f32,
+}
+
+impl Visit for RagdollJoint {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.a.visit("A", visitor)?;
+        self.b.visit("B", visitor)?;
+        self.distance.visit("Distance", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Returns axis of capsule which is closest to given direction of bone. Bodies cannot
+/// rotate, so capsules are aligned to world axes.
+fn capsule_axis(direction: Vec3) -> Axis {
+    let (x, y, z) = (direction.x.abs(), direction.y.abs(), direction.z.abs());
+    if x > y && x > z {
+        Axis::X
+    } else if z > y && z > x {
+        Axis::Z
+    } else {
+        Axis::Y
+    }
+}
+
+pub struct RagdollBuilder {
+    root: Handle<Node>,
+    capsule_size: CapsuleSize,
+    bone_capsule_sizes: HashMap<String, CapsuleSize>,
+    blend_time: f32,
+    solver_iterations: u32,
+}
+
+impl RagdollBuilder {
+    /// Creates new builder, `root` is a root bone of skeleton (usually hips).
+    pub fn new(root: Handle<Node>) -> Self {
+        Self {
+            root,
+            capsule_size: Default::default(),
+            bone_capsule_sizes: Default::default(),
+            blend_time: 0.5,
+            solver_iterations: 8,
+        }
+    }
+
+    /// Sets capsule size for every bone which has no individual size.
+    pub fn with_capsule_size(mut self, size: CapsuleSize) -> Self {
+        self.capsule_size = size;
+        self
+    }
+
+    /// Sets capsule size of a bone with given name.
+    pub fn with_bone_capsule_size(mut self, bone: &str, size: CapsuleSize) -> Self {
+        self.bone_capsule_sizes.insert(bone.to_owned(), size);
+        self
+    }
+
+    /// Sets time in seconds in which physics pose will be blended into animation pose
+    /// when ragdoll switched back to animated mode.
+    pub fn with_blend_time(mut self, blend_time: f32) -> Self {
+        self.blend_time = blend_time;
+        self
+    }
+
+    /// Sets amount of iterations of joint solver, more iterations - less stretching.
+    pub fn with_solver_iterations(mut self, iterations: u32) -> Self {
+        self.solver_iterations = iterations;
+        self
+    }
+
+    /// Creates bodies and joints for every bone of skeleton. Bodies are placed at actual
+    /// positions of bones.
+    pub fn build(self, graph: &Graph, physics: &mut Physics) -> Ragdoll {
+        let mut bones = Vec::new();
+        let mut joints = Vec::new();
+        let mut node_to_bone = HashMap::new();
+
+        // Traversal goes from parents to children, so parent bones always have smaller indices.
+        for node_handle in graph.traverse_handle_iter(self.root) {
+            let node = graph.get(node_handle);
+            if let Node::Base(base) = node {
+                let size = self.bone_capsule_sizes
+                    .get(base.name())
+                    .cloned()
+                    .unwrap_or(self.capsule_size);
+
+                let position = calculate_global_position(graph, node_handle);
+                // Bone is directed to its first child, leaf bones continue direction of parent.
+                let direction = match base.children().first() {
+                    Some(child) => calculate_global_position(graph, *child) - position,
+                    None if base.parent().is_some() => position - calculate_global_position(graph, base.parent()),
+                    None => Vec3::new(0.0, 1.0, 0.0),
+                };
+                let axis = capsule_axis(direction);
+                let mut body = RigidBody::new(ConvexShape::Capsule(CapsuleShape::new(size.radius, size.height, axis)));
+                body.set_position(position);
+                let body = physics.add_body(body);
+
+                let index = bones.len() as u32;
+                let parent = node_to_bone.get(&base.parent()).cloned();
+                if let Some(parent) = parent {
+                    let parent_bone: &RagdollBone = &bones[parent as usize];
+                    joints.push(RagdollJoint {
+                        a: parent,
+                        b: index,
+                        distance: calculate_global_position(graph, parent_bone.node).distance(&position),
+                    });
+                }
+
+                node_to_bone.insert(node_handle, index);
+                bones.push(RagdollBone {
+                    node: node_handle,
+                    body,
+                    parent,
+                });
+            }
+        }
+
+        Ragdoll {
+            bones,
+            joints,
+            mode: RagdollMode::Animated,
+            blend_time: self.blend_time,
+            blend_timer: 0.0,
+            solver_iterations: self.solver_iterations,
+            physics_rotations: Vec::new(),
+            physics_root_position: Vec3::ZERO,
+        }
+    }
+}
+
+pub struct Ragdoll {
+    bones: Vec<RagdollBone>,
+    joints: Vec<RagdollJoint>,
+    mode: RagdollMode,
+    blend_time: f32,
+    blend_timer: f32,
+    solver_iterations: u32,
+    /// Local rotations of bones at the moment when ragdoll was switched back to animated mode.
+    physics_rotations: Vec<Quat>,
+    /// Local position of root bone at the moment when ragdoll was switched back to animated mode.
+    physics_root_position: Vec3,
+}
+
+impl Default for Ragdoll {
+    fn default() -> Self {
+        Self {
+            bones: Default::default(),
+            joints: Default::default(),
+            mode: RagdollMode::Animated,
+            blend_time: 0.5,
+            blend_timer: 0.0,
+            solver_iterations: 8,
+            physics_rotations: Default::default(),
+            physics_root_position: Vec3::ZERO,
+        }
+    }
+}
+
+impl Visit for Ragdoll {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut mode_id = self.mode.id();
+        mode_id.visit("Mode", visitor)?;
+        if visitor.is_reading() {
+            self.mode = RagdollMode::from_id(mode_id)?;
+        }
+
+        self.bones.visit("Bones", visitor)?;
+        self.joints.visit("Joints", visitor)?;
+        self.blend_time.visit("BlendTime", visitor)?;
+        self.solver_iterations.visit("SolverIterations", visitor)?;
+
+        // Ragdolls saved by older versions have no blending state.
+        let blend_result = self.blend_timer.visit("BlendTimer", visitor)
+            .and_then(|_| self.physics_rotations.visit("PhysicsRotations", visitor))
+            .and_then(|_| self.physics_root_position.visit("PhysicsRootPosition", visitor));
+        if !visitor.is_reading() {
+            blend_result?;
+        } else if blend_result.is_err() {
+            self.blend_timer = 0.0;
+            self.physics_rotations.clear();
+        }
+
+        visitor.leave_region()
+    }
+}
+
+impl Ragdoll {
+    pub fn mode(&self) -> RagdollMode {
+        self.mode
+    }
+
+    /// Switches ragdoll mode. When switched to physics mode, bodies continue to move from
+    /// current positions of bones. When switched to animated mode, current pose of bones
+    /// will be blended into animation pose.
+    pub fn set_mode(&mut self, mode: RagdollMode, graph: &Graph) {
+        if self.mode == mode {
+            return;
+        }
+
+        if mode == RagdollMode::Animated {
+            self.physics_rotations = self.bones
+                .iter()
+                .map(|bone| graph.get(bone.node).base().local_transform().rotation())
+                .collect();
+            if let Some(root) = self.bones.first() {
+                self.physics_root_position = graph.get(root.node).base().local_transform().position();
+            }
+            self.blend_timer = self.blend_time;
+        }
+
+        self.mode = mode;
+    }
+
+    /// Returns true if ragdoll is blending physics pose into animation pose.
+    pub fn is_blending(&self) -> bool {
+        self.mode == RagdollMode::Animated && self.blend_timer > 0.0
+    }
+
+    /// Returns handles of rigid bodies of ragdoll. Can be used to apply impulses to bodies.
+    pub fn bodies(&self) -> impl Iterator<Item=Handle<RigidBody>> + '_ {
+        self.bones.iter().map(|bone| bone.body)
+    }
+
+    /// Returns rigid body of given bone if any.
+    pub fn bone_body(&self, node: Handle<Node>) -> Option<Handle<RigidBody>> {
+        self.bones.iter().find(|bone| bone.node == node).map(|bone| bone.body)
+    }
+
+    /// Removes every body of ragdoll from physics. Ragdoll must not be used after this call.
+    pub fn remove_bodies(&mut self, physics: &mut Physics) {
+        for bone in self.bones.iter() {
+            physics.remove_body(bone.body);
+        }
+        self.bones.clear();
+        self.joints.clear();
+    }
+
+    pub fn update(&mut self, graph: &mut Graph, physics: &mut Physics, dt: f32) {
+        match self.mode {
+            RagdollMode::Animated => {
+                if self.blend_timer > 0.0 {
+                    self.blend_physics_pose(graph);
+                    self.blend_timer -= dt;
+                }
+                self.sync_bodies(graph, physics);
+            }
+            RagdollMode::Physics => {
+                self.solve_joints(physics);
+                self.apply_bodies(graph, physics);
+            }
+        }
+    }
+
+    /// Moves bodies to actual positions of bones.
+    fn sync_bodies(&self, graph: &Graph, physics: &mut Physics) {
+        for bone in self.bones.iter() {
+            let position = calculate_global_position(graph, bone.node);
+            physics.borrow_body_mut(bone.body).set_position(position);
+        }
+    }
+
+    /// Keeps distance between bodies of connected bones. Bodies are moved, not teleported,
+    /// so they will gain velocity from correction.
+    fn solve_joints(&self, physics: &mut Physics) {
+        for _ in 0..self.solver_iterations {
+            for joint in self.joints.iter() {
+                let a = self.bones[joint.a as usize].body;
+                let b = self.bones[joint.b as usize].body;
+                let delta = physics.borrow_body(b).get_position() - physics.borrow_body(a).get_position();
+                let length = delta.len();
+                if length <= std::f32::EPSILON {
+                    continue;
+                }
+                let correction = delta.scale(0.5 * (length - joint.distance) / length);
+                physics.borrow_body_mut(a).move_by(correction);
+                physics.borrow_body_mut(b).move_by(correction.scale(-1.0));
+            }
+        }
+    }
+
+    /// Writes position of root body and rotations defined by bodies into local transforms
+    /// of bones. Bones are processed from parents to children, so every rotation is calculated
+    /// using already modified transforms of ancestors.
+    fn apply_bodies(&self, graph: &mut Graph, physics: &Physics) {
+        if let Some(root) = self.bones.first() {
+            let current = calculate_global_position(graph, root.node);
+            let target = physics.borrow_body(root.body).get_position();
+            let parent = graph.get(root.node).base().parent();
+            let offset = if parent.is_some() {
+                calculate_global_transform(graph, parent)
+                    .inverse()
+                    .map(|inv| inv.transform_vector_normal(target - current))
+                    .unwrap_or_default()
+            } else {
+                target - current
+            };
+            graph.get_mut(root.node).base_mut().local_transform_mut().offset(offset);
+        }
+
+        for (index, bone) in self.bones.iter().enumerate() {
+            let mut children = self.bones
+                .iter()
+                .filter(|child| child.parent == Some(index as u32));
+
+            let bone_position = physics.borrow_body(bone.body).get_position();
+
+            // First child defines direction of bone (swing).
+            let first = match children.next() {
+                Some(first) => first,
+                None => continue,
+            };
+            let bone_actual = calculate_global_position(graph, bone.node);
+            let direction = calculate_global_position(graph, first.node) - bone_actual;
+            let target_direction = physics.borrow_body(first.body).get_position() - bone_position;
+            rotate_node_from_to(graph, bone.node, direction, target_direction);
+
+            // Second child (if any) defines rotation around direction of bone (twist).
+            if let (Some(second), Some(axis)) = (children.next(), target_direction.normalized()) {
+                let bone_actual = calculate_global_position(graph, bone.node);
+                let direction = calculate_global_position(graph, second.node) - bone_actual;
+                let target_direction = physics.borrow_body(second.body).get_position() - bone_position;
+                rotate_node_from_to(graph, bone.node,
+                                    direction - axis.scale(direction.dot(&axis)),
+                                    target_direction - axis.scale(target_direction.dot(&axis)));
+            }
+        }
+    }
+
+    /// Blends physics pose captured at the moment of switch into current animation pose.
+    fn blend_physics_pose(&self, graph: &mut Graph) {
+        let t = if self.blend_time > 0.0 {
+            1.0 - self.blend_timer / self.blend_time
+        } else {
+            1.0
+        };
+
+        for (bone, physics_rotation) in self.bones.iter().zip(self.physics_rotations.iter()) {
+            let transform = graph.get_mut(bone.node).base_mut().local_transform_mut();
+            let animated = transform.rotation();
+            transform.set_rotation(physics_rotation.nlerp(&animated, t));
+        }
+
+        if let Some(root) = self.bones.first() {
+            let transform = graph.get_mut(root.node).base_mut().local_transform_mut();
+            let animated = transform.position();
+            transform.set_position(self.physics_root_position.lerp(&animated, t));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            math::{
+                vec3::Vec3,
+                quat::Quat,
+            },
+            pool::Handle,
+        },
+        physics::{
+            Physics,
+            convex_shape::Axis,
+        },
+        scene::{
+            node::Node,
+            graph::Graph,
+            base::{BaseBuilder, AsBase},
+            transform::TransformBuilder,
+        },
+        animation::{
+            ik::calculate_global_position,
+            ragdoll::{
+                capsule_axis,
+                RagdollBuilder,
+                RagdollMode,
+            },
+        },
+    };
+
+    /// Creates skeleton of root bone at origin with two children: one along Y and one along X.
+    fn make_skeleton(graph: &mut Graph) -> (Handle<Node>, Handle<Node>, Handle<Node>) {
+        let mut add_bone = |position: Vec3| {
+            graph.add_node(Node::Base(BaseBuilder::new()
+                .with_local_transform(TransformBuilder::new()
+                    .with_local_position(position)
+                    .build())
+                .build()))
+        };
+        let root = add_bone(Vec3::ZERO);
+        let up = add_bone(Vec3::new(0.0, 1.0, 0.0));
+        let side = add_bone(Vec3::new(2.0, 0.0, 0.0));
+        graph.link_nodes(up, root);
+        graph.link_nodes(side, root);
+        (root, up, side)
+    }
+
+    #[test]
+    fn capsule_axis_follows_bone() {
+        assert!(matches!(capsule_axis(Vec3::new(1.0, 0.2, -0.3)), Axis::X));
+        assert!(matches!(capsule_axis(Vec3::new(0.0, -2.0, 0.5)), Axis::Y));
+        assert!(matches!(capsule_axis(Vec3::new(0.1, 0.2, -0.9)), Axis::Z));
+        assert!(matches!(capsule_axis(Vec3::ZERO), Axis::Y));
+    }
+
+    #[test]
+    fn build_creates_body_per_bone_and_joint_per_link() {
+        let mut graph = Graph::new();
+        let mut physics = Physics::default();
+        let (root, up, side) = make_skeleton(&mut graph);
+
+        let ragdoll = RagdollBuilder::new(root).build(&graph, &mut physics);
+
+        assert_eq!(ragdoll.bodies().count(), 3);
+        assert_eq!(ragdoll.joints.len(), 2);
+        for bone in [root, up, side].iter() {
+            let body = ragdoll.bone_body(*bone).unwrap();
+            assert_eq!(physics.borrow_body(body).get_position(), calculate_global_position(&graph, *bone));
+        }
+        let mut distances = ragdoll.joints.iter().map(|joint| joint.distance).collect::<Vec<_>>();
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!((distances[0] - 1.0).abs() < 1.0e-5);
+        assert!((distances[1] - 2.0).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn physics_pose_is_blended_into_animation_pose() {
+        let mut graph = Graph::new();
+        let mut physics = Physics::default();
+        let (root, _, _) = make_skeleton(&mut graph);
+
+        let mut ragdoll = RagdollBuilder::new(root)
+            .with_blend_time(1.0)
+            .build(&graph, &mut physics);
+        ragdoll.set_mode(RagdollMode::Physics, &graph);
+
+        // Physics pose differs from animation pose by rotation of root bone.
+        let physics_rotation = Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), 1.0);
+        graph.get_mut(root).base_mut().local_transform_mut().set_rotation(physics_rotation);
+        ragdoll.set_mode(RagdollMode::Animated, &graph);
+        assert!(ragdoll.is_blending());
+
+        let angle = |graph: &Graph| 2.0 * graph.get(root).base().local_transform().rotation().w.abs().min(1.0).acos();
+        let mut previous = angle(&graph);
+        for _ in 0..5 {
+            // Animation pose is identity rotation.
+            graph.get_mut(root).base_mut().local_transform_mut().set_rotation(Quat::IDENTITY);
+            ragdoll.update(&mut graph, &mut physics, 0.25);
+            let current = angle(&graph);
+            assert!(current <= previous + 1.0e-5);
+            previous = current;
+        }
+        assert!(!ragdoll.is_blending());
+        assert!(previous < 1.0e-3);
+    }
+}
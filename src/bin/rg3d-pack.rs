@@ -0,0 +1,45 @@
+//! Packs directory with game data into single archive that can be mounted to VFS of
+//! resource manager.
+//!
+//! Usage: rg3d-pack <input directory> <output archive> [--no-compression]
+
+extern crate rg3d;
+
+use std::{
+    env,
+    process,
+    time::Instant,
+};
+use rg3d::resource::archive::{ArchiveBuilder, Compression};
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() < 3 {
+        eprintln!("Usage: {} <input directory> <output archive> [--no-compression]", args[0]);
+        process::exit(1);
+    }
+
+    let input = &args[1];
+    let output = &args[2];
+    let compression = if args.iter().skip(3).any(|arg| arg == "--no-compression") {
+        Compression::None
+    } else {
+        Compression::Zlib
+    };
+
+    let time = Instant::now();
+    let result = ArchiveBuilder::new(output).and_then(|builder| {
+        let mut builder = builder.with_compression(compression);
+        let count = builder.add_directory(input)?;
+        builder.finish()?;
+        Ok(count)
+    });
+
+    match result {
+        Ok(count) => println!("{} files packed into {} in {:?}", count, output, time.elapsed()),
+        Err(e) => {
+            eprintln!("Unable to pack {} into {}! Reason: {}", input, output, e);
+            process::exit(1);
+        }
+    }
+}
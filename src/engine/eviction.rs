@@ -0,0 +1,198 @@
+//! Policies that decide when unused resources are destroyed.
+//!
+//! Both resource manager and renderer keep resources in [TimedEntry](TimedEntry) containers.
+//! Every entry is aged each update and its `time_to_live` is refreshed when entry is used, so
+//! the entry with least `time_to_live` is the least recently used one. Policy then decides
+//! which unused entries must be destroyed.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+};
+use crate::engine::resource_manager::TimedEntry;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EvictionPolicy {
+    /// Unused resource is destroyed after given amount of seconds.
+    TimeToLive(f32),
+    /// Unused resources are kept while total size of resources is less than budget (in bytes),
+    /// least recently used resources are destroyed first once budget is exceeded.
+    LeastRecentlyUsed {
+        budget: usize,
+    },
+    /// Resources are never destroyed automatically.
+    Pinned,
+}
+
+impl Default for EvictionPolicy {
+    fn default() -> Self {
+        EvictionPolicy::TimeToLive(Self::DEFAULT_TIME_TO_LIVE)
+    }
+}
+
+impl EvictionPolicy {
+    /// Lifetime of unused resource in seconds for default policy.
+    pub const DEFAULT_TIME_TO_LIVE: f32 = 20.0;
+
+    /// Returns value of `time_to_live` of entry that was just used.
+    pub fn refresh_value(self) -> f32 {
+        match self {
+            EvictionPolicy::TimeToLive(time_to_live) => time_to_live,
+            // Time to live of other policies is just a measure of time since last use.
+            EvictionPolicy::LeastRecentlyUsed { .. } | EvictionPolicy::Pinned => 0.0,
+        }
+    }
+
+    /// Selects entries that must be destroyed. Every candidate is (id, time to live, size,
+    /// in use), `total_size` is size of every entry including candidates.
+    fn select<I: Copy>(self, candidates: &mut Vec<(I, f32, usize, bool)>, mut total_size: usize) -> Vec<I> {
+        match self {
+            EvictionPolicy::TimeToLive(_) => {
+                candidates.iter()
+                    .filter(|(_, time_to_live, _, in_use)| !*in_use && *time_to_live <= 0.0)
+                    .map(|(id, ..)| *id)
+                    .collect()
+            }
+            EvictionPolicy::LeastRecentlyUsed { budget } => {
+                let mut victims = Vec::new();
+                if total_size > budget {
+                    candidates.retain(|(.., in_use)| !*in_use);
+                    candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+                    for (id, _, size, _) in candidates.iter() {
+                        if total_size <= budget {
+                            break;
+                        }
+                        total_size -= *size;
+                        victims.push(*id);
+                    }
+                }
+                victims
+            }
+            EvictionPolicy::Pinned => Vec::new(),
+        }
+    }
+
+    /// Ages entries of resource manager and removes entries selected by policy. Entries that
+    /// are `in_use` get their time to live refreshed and are never destroyed. `on_evict` is
+    /// called for every destroyed entry.
+    pub(in crate) fn update_entries<T, U, S, E>(self,
+                                                entries: &mut Vec<TimedEntry<T>>,
+                                                dt: f32,
+                                                mut in_use: U,
+                                                mut size_of: S,
+                                                mut on_evict: E)
+        where U: FnMut(&T) -> bool,
+              S: FnMut(&T) -> usize,
+              E: FnMut(&T) {
+        let mut total_size = 0;
+        let mut candidates = Vec::with_capacity(entries.len());
+        for (i, entry) in entries.iter_mut().enumerate() {
+            let used = in_use(&entry.value);
+            if used {
+                entry.time_to_live = self.refresh_value();
+            } else {
+                entry.time_to_live -= dt;
+            }
+            let size = size_of(&entry.value);
+            total_size += size;
+            candidates.push((i, entry.time_to_live, size, used));
+        }
+
+        let victims = self.select(&mut candidates, total_size);
+        if victims.is_empty() {
+            return;
+        }
+
+        let mut keep = vec![true; entries.len()];
+        for victim in victims {
+            keep[victim] = false;
+        }
+        let mut i = 0;
+        entries.retain(|entry| {
+            let retain = keep[i];
+            i += 1;
+            if !retain {
+                on_evict(&entry.value);
+            }
+            retain
+        });
+    }
+
+    /// Ages entries of renderer cache and removes entries selected by policy. Cache entries
+    /// refresh their time to live by themselves when they're used, entries that were used
+    /// since previous update are never destroyed.
+    pub(in crate) fn update_cache<K, T, S>(self, map: &mut HashMap<K, TimedEntry<T>>, dt: f32, mut size_of: S)
+        where K: Copy + Eq + Hash,
+              S: FnMut(&T) -> usize {
+        let mut total_size = 0;
+        let mut candidates = Vec::with_capacity(map.len());
+        for (key, entry) in map.iter_mut() {
+            // Entry has refreshed time to live only if it was used since previous update.
+            let used = entry.time_to_live >= self.refresh_value();
+            entry.time_to_live -= dt;
+            let size = size_of(&entry.value);
+            total_size += size;
+            candidates.push((*key, entry.time_to_live, size, used));
+        }
+
+        for victim in self.select(&mut candidates, total_size) {
+            map.remove(&victim);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use crate::engine::{
+        eviction::EvictionPolicy,
+        resource_manager::TimedEntry,
+    };
+
+    #[test]
+    fn least_recently_used_eviction() {
+        let policy = EvictionPolicy::LeastRecentlyUsed { budget: 250 };
+        let mut entries = vec![
+            TimedEntry { value: 100usize, time_to_live: -3.0 },
+            TimedEntry { value: 100usize, time_to_live: -1.0 },
+            TimedEntry { value: 100usize, time_to_live: -2.0 },
+            TimedEntry { value: 100usize, time_to_live: -9.0 },
+        ];
+        let mut evicted = 0;
+        // Two least recently used entries must be destroyed to fit in budget.
+        policy.update_entries(&mut entries, 1.0, |_| false, |size| *size, |_| evicted += 1);
+        assert_eq!(evicted, 2);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].time_to_live, -2.0);
+        assert_eq!(entries[1].time_to_live, -3.0);
+
+        let mut entries = vec![
+            TimedEntry { value: 100usize, time_to_live: 0.5 },
+            TimedEntry { value: 100usize, time_to_live: 2.0 },
+        ];
+        EvictionPolicy::TimeToLive(2.0).update_entries(&mut entries, 1.0, |_| false, |size| *size, |_| ());
+        assert_eq!(entries.len(), 1);
+
+        EvictionPolicy::Pinned.update_entries(&mut entries, 100.0, |_| false, |size| *size, |_| ());
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn used_cache_entries_are_kept() {
+        let policy = EvictionPolicy::LeastRecentlyUsed { budget: 150 };
+        let mut map = HashMap::new();
+        // Entries with refreshed time to live were used in this frame.
+        map.insert(0, TimedEntry { value: 100usize, time_to_live: policy.refresh_value() });
+        map.insert(1, TimedEntry { value: 100usize, time_to_live: policy.refresh_value() });
+        map.insert(2, TimedEntry { value: 100usize, time_to_live: -5.0 });
+
+        // Only unused entry can be destroyed, even if budget is still exceeded.
+        policy.update_cache(&mut map, 1.0, |size| *size);
+        assert_eq!(map.len(), 2);
+        assert!(map.contains_key(&0) && map.contains_key(&1));
+
+        // Now both entries were not used in last frame.
+        policy.update_cache(&mut map, 1.0, |size| *size);
+        assert_eq!(map.len(), 1);
+    }
+}
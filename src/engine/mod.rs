@@ -1,5 +1,8 @@
 pub mod resource_manager;
 pub mod error;
+pub mod worker_pool;
+pub mod eviction;
+pub mod preload;
 
 use crate::{
     core::{
@@ -113,6 +116,13 @@ impl<M, C: 'static + Control<M, C>> Engine<M, C> {
         // resource so it is not problem to defer update call.
         if let Ok(mut resource_manager) = self.resource_manager.try_lock() {
             resource_manager.update(dt);
+
+            // Instances of reloaded models must take new surfaces from their resources.
+            for model in resource_manager.take_reloaded_models() {
+                for scene in self.scenes.iter_mut() {
+                    scene.graph.resync_model_instances(&model);
+                }
+            }
         }
 
         for scene in self.scenes.iter_mut() {
@@ -149,7 +159,11 @@ impl<M: 'static, C: 'static + Control<M, C>> Visit for Engine<M, C> {
         self.sound_context.lock()?.visit("SoundContext", visitor)?;
 
         if visitor.is_reading() {
-            self.resource_manager.lock()?.reload_resources();
+            let mut resource_manager = self.resource_manager.lock()?;
+            resource_manager.reload_resources();
+            // Scenes are fully resolved below, no need to re-sync instances of reloaded models.
+            resource_manager.take_reloaded_models();
+            drop(resource_manager);
             for scene in self.scenes.iter_mut() {
                 scene.resolve();
             }
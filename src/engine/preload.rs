@@ -0,0 +1,386 @@
+//! Preloading of groups of resources.
+//!
+//! Manifest lists resources that must be loaded before they're needed, for example while
+//! loading screen is shown. [ResourceManager::preload](ResourceManager::preload) starts loading
+//! of every resource of manifest and returns [PreloadGroup](PreloadGroup) which tracks progress
+//! of whole group, including textures requested by models of group.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use serde::{Serialize, Deserialize};
+use crate::{
+    resource::{
+        texture::TextureKind,
+        vfs::Vfs,
+        ResourceState,
+    },
+    engine::resource_manager::{
+        ResourceManager,
+        ResourceKind,
+        SharedTexture,
+        SharedModel,
+        ModelRequest,
+        SoundBufferRequest,
+    },
+    scene::node::Node,
+};
+
+fn default_texture_kind() -> TextureKind {
+    TextureKind::RGBA8
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PreloadItem {
+    Texture {
+        path: PathBuf,
+        #[serde(default = "default_texture_kind")]
+        kind: TextureKind,
+    },
+    Model {
+        path: PathBuf,
+    },
+    SoundBuffer {
+        path: PathBuf,
+        #[serde(default)]
+        stream: bool,
+    },
+}
+
+/// List of resources to preload. Manifest can be built in code:
+///
+/// ```no_run
+/// use rg3d::{engine::preload::PreloadManifest, resource::texture::TextureKind};
+///
+/// let manifest = PreloadManifest::new()
+///     .with_texture("data/textures/hud.png", TextureKind::RGBA8)
+///     .with_model("data/models/level.fbx")
+///     .with_sound_buffer("data/sounds/music.ogg", true);
+/// ```
+///
+/// or read from file, see [parse](PreloadManifest::parse).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PreloadManifest {
+    items: Vec<PreloadItem>,
+}
+
+impl PreloadManifest {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_texture<P: AsRef<Path>>(mut self, path: P, kind: TextureKind) -> Self {
+        self.add(PreloadItem::Texture { path: path.as_ref().to_path_buf(), kind });
+        self
+    }
+
+    pub fn with_model<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.add(PreloadItem::Model { path: path.as_ref().to_path_buf() });
+        self
+    }
+
+    pub fn with_sound_buffer<P: AsRef<Path>>(mut self, path: P, stream: bool) -> Self {
+        self.add(PreloadItem::SoundBuffer { path: path.as_ref().to_path_buf(), stream });
+        self
+    }
+
+    pub fn add(&mut self, item: PreloadItem) {
+        self.items.push(item)
+    }
+
+    pub fn items(&self) -> &[PreloadItem] {
+        &self.items
+    }
+
+    /// Parses manifest from text in RON format. Texture kind is RGBA8 and sound buffers are
+    /// not streamed unless specified otherwise:
+    ///
+    /// ```text
+    /// // Main menu
+    /// (
+    ///     items: [
+    ///         Texture(path: "data/textures/hud.png"),
+    ///         Texture(path: "data/textures/mask.png", kind: R8),
+    ///         Model(path: "data/models/menu.fbx"),
+    ///         SoundBuffer(path: "data/sounds/click.wav"),
+    ///         SoundBuffer(path: "data/sounds/music.ogg", stream: true),
+    ///     ],
+    /// )
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, String> {
+        ron::de::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// Returns text representation of manifest, see [parse](PreloadManifest::parse).
+    pub fn to_text(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, Default::default()).map_err(|e| e.to_string())
+    }
+
+    /// Reads manifest from file of virtual file system of resource manager.
+    pub fn load<P: AsRef<Path>>(vfs: &Vfs, path: P) -> Result<Self, String> {
+        vfs.read(path.as_ref())
+            .map_err(|e| e.to_string())
+            .and_then(|data| String::from_utf8(data).map_err(|e| e.to_string()))
+            .and_then(|text| Self::parse(&text))
+            .map_err(|e| format!("Invalid preload manifest {:?}: {}", path.as_ref(), e))
+    }
+}
+
+#[derive(Clone)]
+enum GroupEntry {
+    Texture(SharedTexture),
+    Model(ModelRequest),
+    SoundBuffer(SoundBufferRequest),
+}
+
+/// Status of a single resource of preload group.
+#[derive(Clone, Debug)]
+pub struct PreloadItemStatus {
+    pub kind: ResourceKind,
+    pub path: PathBuf,
+    pub state: ResourceState,
+    /// Path of model which requested the texture, None for resources listed in manifest.
+    pub requested_by: Option<PathBuf>,
+}
+
+/// Handle to resources started by [ResourceManager::preload](ResourceManager::preload). It is
+/// cheap to clone. Group holds strong references to its resources, so they won't be destroyed
+/// by resource manager while group is alive.
+#[derive(Clone)]
+pub struct PreloadGroup {
+    entries: Arc<Vec<GroupEntry>>,
+}
+
+fn texture_state(texture: &SharedTexture) -> ResourceState {
+    texture.lock().unwrap().state().clone()
+}
+
+/// Collects unique textures of every surface of model.
+fn model_textures(model: &SharedModel) -> Vec<SharedTexture> {
+    let mut textures: Vec<SharedTexture> = Vec::new();
+    let model = model.lock().unwrap();
+    for node in model.get_scene().graph.linear_iter() {
+        if let Node::Mesh(mesh) = node {
+            for surface in mesh.surfaces() {
+                for texture in surface.get_diffuse_texture()
+                    .into_iter()
+                    .chain(surface.get_normal_texture())
+                    .chain(surface.get_specular_texture()) {
+                    if !textures.iter().any(|t| Arc::ptr_eq(t, &texture)) {
+                        textures.push(texture);
+                    }
+                }
+            }
+        }
+    }
+    textures
+}
+
+impl PreloadGroup {
+    /// Returns status of every resource of group. Textures of a model are known only when
+    /// model is loaded, they're listed right after their model.
+    pub fn status(&self) -> Vec<PreloadItemStatus> {
+        let mut status = Vec::with_capacity(self.entries.len());
+        for entry in self.entries.iter() {
+            match entry {
+                GroupEntry::Texture(texture) => status.push(PreloadItemStatus {
+                    kind: ResourceKind::Texture,
+                    path: texture.lock().unwrap().path.clone(),
+                    state: texture_state(texture),
+                    requested_by: None,
+                }),
+                GroupEntry::Model(request) => {
+                    status.push(PreloadItemStatus {
+                        kind: ResourceKind::Model,
+                        path: request.path().to_path_buf(),
+                        state: request.state(),
+                        requested_by: None,
+                    });
+                    if let Some(model) = request.model() {
+                        for texture in model_textures(&model) {
+                            status.push(PreloadItemStatus {
+                                kind: ResourceKind::Texture,
+                                path: texture.lock().unwrap().path.clone(),
+                                state: texture_state(&texture),
+                                requested_by: Some(request.path().to_path_buf()),
+                            });
+                        }
+                    }
+                }
+                GroupEntry::SoundBuffer(request) => status.push(PreloadItemStatus {
+                    kind: ResourceKind::SoundBuffer,
+                    path: request.path().to_path_buf(),
+                    state: request.state(),
+                    requested_by: None,
+                }),
+            }
+        }
+        status
+    }
+
+    /// Returns fraction of resources that are loaded or failed to load, in [0; 1] range.
+    /// Fraction can decrease a bit when a model is loaded, because its textures are added
+    /// to the group at this moment.
+    pub fn progress(&self) -> f32 {
+        let status = self.status();
+        if status.is_empty() {
+            return 1.0;
+        }
+        let done = status.iter().filter(|item| item.state != ResourceState::Pending).count();
+        done as f32 / status.len() as f32
+    }
+
+    /// Returns true if every resource of group is loaded or failed to load.
+    pub fn is_finished(&self) -> bool {
+        self.status().iter().all(|item| item.state != ResourceState::Pending)
+    }
+
+    /// Returns status of resources that failed to load.
+    pub fn errors(&self) -> Vec<PreloadItemStatus> {
+        self.status()
+            .into_iter()
+            .filter(|item| match item.state {
+                ResourceState::LoadError(_) => true,
+                _ => false,
+            })
+            .collect()
+    }
+}
+
+impl ResourceManager {
+    /// Starts loading of every resource of manifest, every resource is loaded asynchronously.
+    /// Resources that are already loaded are shared with group.
+    pub fn preload(&mut self, manifest: &PreloadManifest) -> PreloadGroup {
+        let entries = manifest.items
+            .iter()
+            .map(|item| match item {
+                PreloadItem::Texture { path, kind } => GroupEntry::Texture(self.request_texture_async(path, *kind)),
+                PreloadItem::Model { path } => GroupEntry::Model(self.request_model_async(path)),
+                PreloadItem::SoundBuffer { path, stream } => GroupEntry::SoundBuffer(self.request_sound_buffer_async(path, *stream)),
+            })
+            .collect();
+
+        PreloadGroup {
+            entries: Arc::new(entries),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+    };
+    use crate::{
+        engine::{
+            preload::{PreloadManifest, PreloadItem, PreloadGroup, GroupEntry},
+            resource_manager::{ModelRequest, SoundBufferRequest, ResourceKind, SharedTexture},
+        },
+        resource::{
+            texture::{Texture, TextureKind},
+            model::Model,
+            ResourceState,
+        },
+        renderer::surface::{Surface, SurfaceSharedData},
+        scene::{
+            node::Node,
+            mesh::Mesh,
+        },
+    };
+
+    #[test]
+    fn parse_manifest() {
+        let text = r#"
+            // Menu
+            (
+                items: [
+                    Texture(path: "data/hud.png"),
+                    Texture(path: "data/mask.png", kind: R8),
+                    Model(path: "data/menu.fbx"),
+                    SoundBuffer(path: "data/music.ogg", stream: true),
+                ],
+            )
+        "#;
+        let manifest = PreloadManifest::parse(text).unwrap();
+        assert_eq!(manifest, PreloadManifest::new()
+            .with_texture("data/hud.png", TextureKind::RGBA8)
+            .with_texture("data/mask.png", TextureKind::R8)
+            .with_model("data/menu.fbx")
+            .with_sound_buffer("data/music.ogg", true));
+        assert_eq!(manifest.items()[2], PreloadItem::Model { path: PathBuf::from("data/menu.fbx") });
+        assert_eq!(PreloadManifest::parse(&manifest.to_text().unwrap()).unwrap(), manifest);
+
+        assert!(PreloadManifest::parse("(items: [Texture()])").is_err());
+        assert!(PreloadManifest::parse("(items: [Mesh(path: \"data/a.fbx\")])").is_err());
+        assert!(PreloadManifest::parse("(items: [SoundBuffer(path: \"data/a.wav\", stream: 1)])").is_err());
+    }
+
+    fn make_texture(path: &str, state: ResourceState) -> SharedTexture {
+        let mut texture = Texture::new(1, 1, TextureKind::RGBA8);
+        texture.path = PathBuf::from(path);
+        texture.state = state;
+        Arc::new(Mutex::new(texture))
+    }
+
+    /// Creates model with one surface which uses given textures.
+    fn make_model(diffuse: SharedTexture, normal: SharedTexture) -> Model {
+        let mut model = Model::default();
+        let mut surface = Surface::new(Arc::new(Mutex::new(SurfaceSharedData::make_cube())));
+        surface.set_diffuse_texture(diffuse);
+        surface.set_normal_texture(normal);
+        let mut mesh = Mesh::default();
+        mesh.add_surface(surface);
+        model.get_scene_mut().graph.add_node(Node::Mesh(mesh));
+        model
+    }
+
+    #[test]
+    fn group_progress_and_status() {
+        let hud = make_texture("hud.png", ResourceState::Ok);
+        let diffuse = make_texture("diffuse.png", ResourceState::Pending);
+        // Same texture is listed in manifest and used by model.
+        let normal = hud.clone();
+        let model_request = ModelRequest::new(PathBuf::from("level.fbx"), None);
+        let sound_request = SoundBufferRequest::new(PathBuf::from("music.ogg"), None);
+
+        let group = PreloadGroup {
+            entries: Arc::new(vec![
+                GroupEntry::Texture(hud.clone()),
+                GroupEntry::Model(model_request.clone()),
+                GroupEntry::SoundBuffer(sound_request.clone()),
+            ]),
+        };
+
+        // Textures of model are unknown until model is loaded.
+        assert_eq!(group.status().len(), 3);
+        assert!((group.progress() - 1.0 / 3.0).abs() < 1.0e-6);
+        assert!(!group.is_finished());
+
+        let model = Arc::new(Mutex::new(make_model(diffuse.clone(), normal)));
+        model_request.finish(Ok(model));
+        sound_request.finish(Err("Invalid sound buffer".to_owned()));
+
+        let status = group.status();
+        assert_eq!(status.len(), 5);
+        assert_eq!(status[1].kind, ResourceKind::Model);
+        assert_eq!(status[1].state, ResourceState::Ok);
+        let model_textures = status.iter()
+            .filter(|item| item.requested_by.as_ref().map(|path| path.as_path()) == Some(Path::new("level.fbx")))
+            .collect::<Vec<_>>();
+        assert_eq!(model_textures.len(), 2);
+        assert!(model_textures.iter().all(|item| item.kind == ResourceKind::Texture));
+        assert_eq!(model_textures[0].path, Path::new("diffuse.png"));
+        assert_eq!(model_textures[0].state, ResourceState::Pending);
+        assert!((group.progress() - 4.0 / 5.0).abs() < 1.0e-6);
+
+        diffuse.lock().unwrap().state = ResourceState::Ok;
+        assert!(group.is_finished());
+        assert_eq!(group.progress(), 1.0);
+        let errors = group.errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ResourceKind::SoundBuffer);
+        assert_eq!(errors[0].path, Path::new("music.ogg"));
+    }
+}
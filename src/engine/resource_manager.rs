@@ -1,7 +1,14 @@
 use std::{
     path::{PathBuf, Path},
-    sync::{Arc, Mutex},
-    time,
+    sync::{
+        Arc,
+        Mutex,
+        RwLock,
+        mpsc::{self, Sender, Receiver, TryRecvError},
+    },
+    io::Cursor,
+    time::{self, SystemTime},
+    collections::HashMap,
 };
 use crate::{
     sound::buffer::{SoundBuffer, DataSource},
@@ -9,10 +16,17 @@ use crate::{
         visitor::{Visitor, VisitResult, Visit}
     },
     resource::{
-        texture::Texture,
-        model::Model,
-        texture::TextureKind,
+        texture::{Texture, TextureKind, TextureImportOptions, TextureFile},
+        model::{Model, ModelImportOptions},
+        vfs::Vfs,
+        error::ResourceError,
+        ResourceState,
+    },
+    engine::{
+        worker_pool::WorkerPool,
+        eviction::EvictionPolicy,
     },
+    scene::node::Node,
     utils::log::Log,
 };
 use std::ops::{Deref, DerefMut};
@@ -43,7 +57,7 @@ impl<T> Default for TimedEntry<T> where T: Default {
     fn default() -> Self {
         Self {
             value: Default::default(),
-            time_to_live: ResourceManager::MAX_RESOURCE_TTL,
+            time_to_live: EvictionPolicy::DEFAULT_TIME_TO_LIVE,
         }
     }
 }
@@ -71,6 +85,208 @@ impl<T> Visit for TimedEntry<T> where T: Default + Visit {
 pub type SharedTexture = Arc<Mutex<Texture>>;
 pub type SharedModel = Arc<Mutex<Model>>;
 pub type SharedSoundBuffer = Arc<Mutex<SoundBuffer>>;
+pub type SharedVfs = Arc<RwLock<Vfs>>;
+
+/// Callback which will be called on main thread (in engine update) once model is loaded
+/// or failed to load.
+pub type ModelLoadCallback = Box<dyn FnOnce(Result<SharedModel, String>) + Send>;
+
+struct ModelRequestState {
+    state: ResourceState,
+    model: Option<SharedModel>,
+}
+
+/// Handle to a model which is requested to load asynchronously. It is cheap to clone, every
+/// clone shares same state which will be changed by resource manager once loading is done.
+#[derive(Clone)]
+pub struct ModelRequest {
+    path: PathBuf,
+    state: Arc<Mutex<ModelRequestState>>,
+}
+
+impl ModelRequest {
+    pub(in crate::engine) fn new(path: PathBuf, model: Option<SharedModel>) -> Self {
+        Self {
+            path,
+            state: Arc::new(Mutex::new(ModelRequestState {
+                state: if model.is_some() { ResourceState::Ok } else { ResourceState::Pending },
+                model,
+            })),
+        }
+    }
+
+    pub(in crate::engine) fn finish(&self, result: Result<SharedModel, String>) {
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok(model) => {
+                state.state = ResourceState::Ok;
+                state.model = Some(model);
+            }
+            Err(reason) => {
+                state.state = ResourceState::LoadError(reason);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    pub fn state(&self) -> ResourceState {
+        self.state.lock().unwrap().state.clone()
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.state.lock().unwrap().state == ResourceState::Pending
+    }
+
+    /// Returns model if it is loaded, None if it is still loading or failed to load.
+    pub fn model(&self) -> Option<SharedModel> {
+        self.state.lock().unwrap().model.clone()
+    }
+
+    /// Returns reason of failure if model failed to load.
+    pub fn error(&self) -> Option<String> {
+        match self.state.lock().unwrap().state {
+            ResourceState::LoadError(ref reason) => Some(reason.clone()),
+            _ => None,
+        }
+    }
+}
+
+struct SoundBufferRequestState {
+    state: ResourceState,
+    sound_buffer: Option<SharedSoundBuffer>,
+}
+
+/// Handle to a sound buffer which is requested to load asynchronously, it works just like
+/// [ModelRequest](ModelRequest).
+#[derive(Clone)]
+pub struct SoundBufferRequest {
+    path: PathBuf,
+    state: Arc<Mutex<SoundBufferRequestState>>,
+}
+
+impl SoundBufferRequest {
+    pub(in crate::engine) fn new(path: PathBuf, sound_buffer: Option<SharedSoundBuffer>) -> Self {
+        Self {
+            path,
+            state: Arc::new(Mutex::new(SoundBufferRequestState {
+                state: if sound_buffer.is_some() { ResourceState::Ok } else { ResourceState::Pending },
+                sound_buffer,
+            })),
+        }
+    }
+
+    pub(in crate::engine) fn finish(&self, result: Result<SharedSoundBuffer, String>) {
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok(sound_buffer) => {
+                state.state = ResourceState::Ok;
+                state.sound_buffer = Some(sound_buffer);
+            }
+            Err(reason) => {
+                state.state = ResourceState::LoadError(reason);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    pub fn state(&self) -> ResourceState {
+        self.state.lock().unwrap().state.clone()
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.state.lock().unwrap().state == ResourceState::Pending
+    }
+
+    /// Returns sound buffer if it is loaded, None if it is still loading or failed to load.
+    pub fn sound_buffer(&self) -> Option<SharedSoundBuffer> {
+        self.state.lock().unwrap().sound_buffer.clone()
+    }
+
+    /// Returns reason of failure if sound buffer failed to load.
+    pub fn error(&self) -> Option<String> {
+        match self.state.lock().unwrap().state {
+            ResourceState::LoadError(ref reason) => Some(reason.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ResourceKind {
+    Texture,
+    Model,
+    SoundBuffer,
+}
+
+/// Memory usage of a single resource, see [memory_report](ResourceManager::memory_report).
+#[derive(Clone, Debug)]
+pub struct ResourceMemoryInfo {
+    pub kind: ResourceKind,
+    pub path: PathBuf,
+    /// Size of CPU-side data of resource in bytes, GPU copies are reported by renderer.
+    pub bytes: usize,
+    /// Amount of references to resource, except reference of resource manager.
+    pub reference_count: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryReport {
+    pub resources: Vec<ResourceMemoryInfo>,
+}
+
+impl MemoryReport {
+    /// Returns total size in bytes of every resource of given kind.
+    pub fn total_bytes(&self, kind: ResourceKind) -> usize {
+        self.resources
+            .iter()
+            .filter(|resource| resource.kind == kind)
+            .map(|resource| resource.bytes)
+            .sum()
+    }
+}
+
+/// Events of resource manager, they're useful to track progress of loading.
+#[derive(Clone, Debug)]
+pub enum ResourceEvent {
+    Loaded {
+        kind: ResourceKind,
+        path: PathBuf,
+    },
+    Failed {
+        kind: ResourceKind,
+        path: PathBuf,
+        reason: String,
+    },
+}
+
+/// Result of model loading in worker thread - model and textures it requested.
+type ModelLoadResult = Result<(Model, Vec<SharedTexture>), String>;
+
+struct PendingModel {
+    request: ModelRequest,
+    options: ModelImportOptions,
+    receiver: Receiver<ModelLoadResult>,
+    callbacks: Vec<ModelLoadCallback>,
+    /// Model which will be replaced in-place by loaded one, this is how models are hot
+    /// reloaded.
+    reload: Option<SharedModel>,
+}
+
+/// Result of sound buffer loading in worker thread - sound buffer and path of its file on disk.
+type SoundBufferLoadResult = Result<(SharedSoundBuffer, Option<PathBuf>), String>;
+
+struct PendingSoundBuffer {
+    request: SoundBufferRequest,
+    receiver: Receiver<SoundBufferLoadResult>,
+}
 
 pub struct ResourceManager {
     textures: Vec<TimedEntry<SharedTexture>>,
@@ -79,135 +295,559 @@ pub struct ResourceManager {
     /// Path to textures, extensively used for resource files which stores path in weird
     /// format (either relative or absolute) which is obviously not good for engine.
     textures_path: PathBuf,
+    /// Every resource is read through this file system.
+    vfs: SharedVfs,
+    /// Workers that are shared by every async request.
+    worker_pool: WorkerPool,
+    pending_models: Vec<PendingModel>,
+    pending_sound_buffers: Vec<PendingSoundBuffer>,
+    hot_reload: bool,
+    hot_reload_interval: f32,
+    hot_reload_timer: f32,
+    /// Last known modification times of source files of resources.
+    modification_times: HashMap<PathBuf, SystemTime>,
+    /// Sound buffers store path of file on disk, this maps such paths to paths in VFS.
+    sound_buffer_paths: HashMap<PathBuf, PathBuf>,
+    reloaded_models: Vec<SharedModel>,
+    /// Sender is cloned to every worker, so events of async requests are delivered
+    /// to the same queue.
+    event_sender: Sender<ResourceEvent>,
+    event_receiver: Receiver<ResourceEvent>,
+    texture_policy: EvictionPolicy,
+    model_policy: EvictionPolicy,
+    sound_buffer_policy: EvictionPolicy,
 }
 
 impl ResourceManager {
-    /// Lifetime of orphaned resource in seconds (with only one strong ref which is resource manager itself)
-    pub const MAX_RESOURCE_TTL: f32 = 20.0;
+    pub(in crate) fn new() -> ResourceManager {
+        Self::with_worker_pool(WorkerPool::default())
+    }
 
-    pub(in crate::engine) fn new() -> ResourceManager {
+    fn with_worker_pool(worker_pool: WorkerPool) -> ResourceManager {
+        let (event_sender, event_receiver) = mpsc::channel();
         Self {
+            event_sender,
+            event_receiver,
+            vfs: Arc::new(RwLock::new(Vfs::default())),
             textures: Vec::new(),
             models: Vec::new(),
             sound_buffers: Vec::new(),
             textures_path: PathBuf::from("data/textures/"),
+            worker_pool,
+            pending_models: Vec::new(),
+            pending_sound_buffers: Vec::new(),
+            hot_reload: false,
+            hot_reload_interval: 1.0,
+            hot_reload_timer: 0.0,
+            modification_times: HashMap::new(),
+            sound_buffer_paths: HashMap::new(),
+            reloaded_models: Vec::new(),
+            texture_policy: Default::default(),
+            model_policy: Default::default(),
+            sound_buffer_policy: Default::default(),
         }
     }
 
-    /// Experimental async texture loader. Always returns valid texture object which could still
-    /// be not loaded, you should check is_loaded flag to ensure.
+    /// Async texture loader. Always returns valid texture object which could still be not
+    /// loaded, you should check state of texture to ensure. Result of loading is also reported
+    /// by [poll_event](ResourceManager::poll_event). Options are taken from sidecar file of
+    /// texture if it has one.
     ///
     /// It extensively used in model loader to speed up loading.
     pub fn request_texture_async<P: AsRef<Path>>(&mut self, path: P, kind: TextureKind) -> SharedTexture {
-        if let Some(texture) = self.find_texture(path.as_ref()) {
+        self.request_texture_async_internal(path.as_ref(), kind, None)
+    }
+
+    /// Same as [request_texture_async](ResourceManager::request_texture_async), but given
+    /// options override options from sidecar file.
+    pub fn request_texture_async_with_options<P: AsRef<Path>>(&mut self, path: P, kind: TextureKind, options: TextureImportOptions) -> SharedTexture {
+        self.request_texture_async_internal(path.as_ref(), kind, Some(options))
+    }
+
+    /// Applies options of request to texture that is already loaded (or loading).
+    fn apply_requested_options(texture: &SharedTexture, options: Option<TextureImportOptions>) {
+        if let Some(options) = options {
+            texture.lock().unwrap().set_options(options);
+        }
+    }
+
+    fn request_texture_async_internal(&mut self, path: &Path, kind: TextureKind, options: Option<TextureImportOptions>) -> SharedTexture {
+        if let Some(texture) = self.find_texture(path) {
+            Self::apply_requested_options(&texture, options);
             return texture;
         }
 
-        let texture = Arc::new(Mutex::new(Texture::default()));
+        let path = PathBuf::from(path);
+        // Path is set right away, so texture can be found by path while it is loading.
+        let texture = Arc::new(Mutex::new(Texture {
+            path: path.clone(),
+            kind,
+            ..Default::default()
+        }));
         self.textures.push(TimedEntry {
             value: texture.clone(),
-            time_to_live: Self::MAX_RESOURCE_TTL,
+            time_to_live: self.texture_policy.refresh_value(),
         });
         let result = texture.clone();
 
-        let path = PathBuf::from(path.as_ref());
-        std::thread::spawn(move || {
-            if let Ok(mut texture) = texture.lock() {
-                let time = time::Instant::now();
-                match Texture::load_from_file(&path, kind) {
-                    Ok(raw_texture) => {
-                        *texture = raw_texture;
-                        Log::writeln(format!("Texture {:?} is loaded in {:?}!", path, time.elapsed()));
-                    }
-                    Err(e) => {
-                        Log::writeln(format!("Unable to load texture {:?}! Reason {}", path, e));
-                    }
+        let vfs = self.vfs.clone();
+        let event_sender = self.event_sender.clone();
+        self.worker_pool.execute(move || {
+            let time = time::Instant::now();
+            // Texture is locked only to put loaded data in it, so main thread won't
+            // be blocked while texture is loading. VFS is locked only to read files.
+            let file = TextureFile::read(&vfs.read().unwrap(), &path, options);
+            let event = match file.and_then(|file| file.decode(&path, kind)) {
+                Ok(raw_texture) => {
+                    *texture.lock().unwrap() = raw_texture;
+                    Log::writeln(format!("Texture {:?} is loaded in {:?}!", path, time.elapsed()));
+                    ResourceEvent::Loaded { kind: ResourceKind::Texture, path }
                 }
-            }
+                Err(e) => {
+                    let reason = e.to_string();
+                    Log::writeln(format!("Unable to load texture {:?}! Reason {}", path, reason));
+                    texture.lock().unwrap().state = ResourceState::LoadError(reason.clone());
+                    ResourceEvent::Failed { kind: ResourceKind::Texture, path, reason }
+                }
+            };
+            // Resource manager could be destroyed already.
+            let _ = event_sender.send(event);
         });
 
         result
     }
 
-    pub fn request_texture<P: AsRef<Path>>(&mut self, path: P, kind: TextureKind) -> Option<SharedTexture> {
-        if let Some(texture) = self.find_texture(path.as_ref()) {
-            return Some(texture);
+    /// Reports result of synchronous request to event queue and to log.
+    fn report<T>(&self, kind: ResourceKind, path: &Path, result: &Result<T, ResourceError>) {
+        let event = match result {
+            Ok(_) => {
+                Log::writeln(format!("{:?} {} is loaded!", kind, path.display()));
+                ResourceEvent::Loaded { kind, path: path.to_owned() }
+            }
+            Err(e) => {
+                Log::writeln(format!("Unable to load {:?} from {}! Reason {}", kind, path.display(), e));
+                ResourceEvent::Failed { kind, path: path.to_owned(), reason: e.to_string() }
+            }
+        };
+        // Receiver is owned by self, so send never fails.
+        let _ = self.event_sender.send(event);
+    }
+
+    /// Loads texture synchronously. Options are taken from sidecar file of texture if it
+    /// has one.
+    pub fn request_texture<P: AsRef<Path>>(&mut self, path: P, kind: TextureKind) -> Result<SharedTexture, ResourceError> {
+        self.request_texture_internal(path.as_ref(), kind, None)
+    }
+
+    /// Same as [request_texture](ResourceManager::request_texture), but given options
+    /// override options from sidecar file. If texture is already loaded, its options
+    /// will be replaced.
+    pub fn request_texture_with_options<P: AsRef<Path>>(&mut self, path: P, kind: TextureKind, options: TextureImportOptions) -> Result<SharedTexture, ResourceError> {
+        self.request_texture_internal(path.as_ref(), kind, Some(options))
+    }
+
+    fn request_texture_internal(&mut self, path: &Path, kind: TextureKind, options: Option<TextureImportOptions>) -> Result<SharedTexture, ResourceError> {
+        if let Some(texture) = self.find_texture(path) {
+            Self::apply_requested_options(&texture, options);
+            return Ok(texture);
         }
 
-        match Texture::load_from_file(path.as_ref(), kind) {
+        let texture = Texture::load(&self.vfs.read().unwrap(), path, kind, options);
+        let result = match texture {
             Ok(texture) => {
                 let shared_texture = Arc::new(Mutex::new(texture));
                 self.textures.push(TimedEntry {
                     value: shared_texture.clone(),
-                    time_to_live: Self::MAX_RESOURCE_TTL,
+                    time_to_live: self.texture_policy.refresh_value(),
                 });
-                Log::writeln(format!("Texture {} is loaded!", path.as_ref().display()));
-                Some(shared_texture)
-            }
-            Err(e) => {
-                Log::writeln(format!("Unable to load texture {}! Reason {}", path.as_ref().display(), e));
-                None
+                Ok(shared_texture)
             }
+            Err(e) => Err(ResourceError::from(e)),
+        };
+        self.report(ResourceKind::Texture, path, &result);
+        result
+    }
+
+    /// Registers texture created at runtime under given virtual path, so it can be found
+    /// by [request_texture](ResourceManager::request_texture) just like texture loaded from
+    /// file. If some texture is already registered under this path, its content is replaced,
+    /// so every user of old texture will see new data.
+    pub fn register_texture<P: AsRef<Path>>(&mut self, path: P, mut texture: Texture) -> SharedTexture {
+        texture.path = path.as_ref().to_path_buf();
+        texture.state = ResourceState::Ok;
+
+        if let Some(existing) = self.find_texture(path.as_ref()) {
+            *existing.lock().unwrap() = texture;
+            return existing;
         }
+
+        let shared_texture = Arc::new(Mutex::new(texture));
+        self.textures.push(TimedEntry {
+            value: shared_texture.clone(),
+            time_to_live: self.texture_policy.refresh_value(),
+        });
+        shared_texture
     }
 
-    pub fn request_model<P: AsRef<Path>>(&mut self, path: P) -> Option<SharedModel> {
-        if let Some(model) = self.find_model(path.as_ref()) {
-            return Some(model);
+    /// Loads model with default import options.
+    pub fn request_model<P: AsRef<Path>>(&mut self, path: P) -> Result<SharedModel, ResourceError> {
+        self.request_model_with_options(path, Default::default())
+    }
+
+    /// Loads model with given import options. Models are cached per set of options, so same
+    /// file requested with different options gives different models.
+    pub fn request_model_with_options<P: AsRef<Path>>(&mut self, path: P, options: ModelImportOptions) -> Result<SharedModel, ResourceError> {
+        if let Some(model) = self.find_model_with_options(path.as_ref(), &options) {
+            return Ok(model);
         }
 
-        match Model::load(path.as_ref(), self) {
-            Ok(model) => {
-                let model = Arc::new(Mutex::new(model));
-                model.lock().unwrap().self_weak_ref = Some(Arc::downgrade(&model));
-                self.models.push(TimedEntry {
-                    value: model.clone(),
-                    time_to_live: Self::MAX_RESOURCE_TTL,
-                });
-                Log::writeln(format!("Model {} is loaded!", path.as_ref().display()));
-                Some(model)
+        let result = Model::load(path.as_ref(), self, &options).map(|model| {
+            let model = Arc::new(Mutex::new(model));
+            model.lock().unwrap().self_weak_ref = Some(Arc::downgrade(&model));
+            self.models.push(TimedEntry {
+                value: model.clone(),
+                time_to_live: self.model_policy.refresh_value(),
+            });
+            model
+        });
+        self.report(ResourceKind::Model, path.as_ref(), &result);
+        result
+    }
+
+    /// Requests model to be loaded on worker thread. Returns request immediately, its state
+    /// will be changed in engine update once model is loaded. Textures of model are loaded
+    /// asynchronously too. Requesting already loaded or loading model returns request that
+    /// shares state with first one.
+    pub fn request_model_async<P: AsRef<Path>>(&mut self, path: P) -> ModelRequest {
+        self.request_model_async_internal(path.as_ref(), Default::default(), None)
+    }
+
+    /// Same as [request_model_async](ResourceManager::request_model_async) but with given
+    /// import options, see [request_model_with_options](ResourceManager::request_model_with_options).
+    pub fn request_model_async_with_options<P: AsRef<Path>>(&mut self, path: P, options: ModelImportOptions) -> ModelRequest {
+        self.request_model_async_internal(path.as_ref(), options, None)
+    }
+
+    /// Same as [request_model_async](ResourceManager::request_model_async) but also calls
+    /// given callback once model is loaded or failed to load. Callback is called on thread
+    /// which updates engine. If model is already loaded, callback is called immediately.
+    pub fn request_model_async_with_callback<P, F>(&mut self, path: P, callback: F) -> ModelRequest
+        where P: AsRef<Path>,
+              F: FnOnce(Result<SharedModel, String>) + Send + 'static {
+        self.request_model_async_internal(path.as_ref(), Default::default(), Some(Box::new(callback)))
+    }
+
+    fn request_model_async_internal(&mut self, path: &Path, options: ModelImportOptions, callback: Option<ModelLoadCallback>) -> ModelRequest {
+        if let Some(model) = self.find_model_with_options(path, &options) {
+            if let Some(callback) = callback {
+                callback(Ok(model.clone()));
             }
-            Err(e) => {
-                Log::writeln(format!("Unable to load model from {}! Reason {}", path.as_ref().display(), e));
-                None
+            return ModelRequest::new(path.to_path_buf(), Some(model));
+        }
+
+        if let Some(pending) = self.pending_models.iter_mut().find(|p| p.reload.is_none() && p.request.path == path && p.options == options) {
+            if let Some(callback) = callback {
+                pending.callbacks.push(callback);
             }
+            return pending.request.clone();
         }
+
+        let request = ModelRequest::new(path.to_path_buf(), None);
+        let receiver = self.load_model_on_worker(path, &options);
+
+        self.pending_models.push(PendingModel {
+            request: request.clone(),
+            options,
+            receiver,
+            callbacks: callback.into_iter().collect(),
+            reload: None,
+        });
+
+        request
     }
 
-    pub fn request_sound_buffer<P: AsRef<Path>>(&mut self, path: P, stream: bool) -> Option<SharedSoundBuffer> {
-        if let Some(sound_buffer) = self.find_sound_buffer(path.as_ref()) {
-            return Some(sound_buffer);
+    /// Loads model on worker thread, result is received in
+    /// [update_pending_models](ResourceManager::update_pending_models).
+    fn load_model_on_worker(&self, path: &Path, options: &ModelImportOptions) -> Receiver<ModelLoadResult> {
+        let (sender, receiver) = mpsc::channel();
+
+        // Model is loaded by separate resource manager with same settings and same VFS. It
+        // knows every texture of this manager, so they're reused instead of being decoded
+        // again, only new textures are merged with textures of this manager once model is
+        // loaded.
+        let mut loader = Self::with_worker_pool(self.worker_pool.clone());
+        loader.textures = self.textures
+            .iter()
+            .map(|entry| TimedEntry {
+                value: entry.value.clone(),
+                time_to_live: entry.time_to_live,
+            })
+            .collect();
+        let known_texture_count = loader.textures.len();
+        loader.vfs = self.vfs.clone();
+        loader.event_sender = self.event_sender.clone();
+        loader.textures_path = self.textures_path.clone();
+        let model_path = path.to_path_buf();
+        let model_options = options.clone();
+        self.worker_pool.execute(move || {
+            let time = time::Instant::now();
+            let result = match Model::load(&model_path, &mut loader, &model_options) {
+                Ok(model) => {
+                    Log::writeln(format!("Model {:?} is loaded in {:?}!", model_path, time.elapsed()));
+                    let textures = loader.textures.drain(known_texture_count..).map(|entry| entry.value).collect();
+                    Ok((model, textures))
+                }
+                Err(e) => Err(format!("{}", e)),
+            };
+            // Receiver can be dropped if resource manager was destroyed, result is not needed then.
+            let _ = sender.send(result);
+        });
+
+        receiver
+    }
+
+    /// Replaces new textures of model loaded by other resource manager with textures of this
+    /// manager if they share same path (such textures could be requested while model was
+    /// loading), rest of textures are registered in this manager.
+    /// Every node that can use texture is checked: surfaces of meshes (diffuse, normal and
+    /// specular textures), sprites and particle systems.
+    fn merge_model_textures(&mut self, model: &mut Model, textures: Vec<SharedTexture>) {
+        for texture in textures {
+            let path = texture.lock().unwrap().path.clone();
+            match self.find_texture(&path) {
+                Some(existing) => {
+                    let is_merged = |current: Option<SharedTexture>| current.map_or(false, |t| Arc::ptr_eq(&t, &texture));
+                    for node in model.get_scene_mut().graph.linear_iter_mut() {
+                        match node {
+                            Node::Mesh(mesh) => {
+                                for surface in mesh.surfaces_mut() {
+                                    if is_merged(surface.get_diffuse_texture()) {
+                                        surface.set_diffuse_texture(existing.clone());
+                                    }
+                                    if is_merged(surface.get_normal_texture()) {
+                                        surface.set_normal_texture(existing.clone());
+                                    }
+                                    if is_merged(surface.get_specular_texture()) {
+                                        surface.set_specular_texture(existing.clone());
+                                    }
+                                }
+                            }
+                            Node::Sprite(sprite) => {
+                                if is_merged(sprite.texture()) {
+                                    sprite.set_texture(existing.clone());
+                                }
+                            }
+                            Node::ParticleSystem(particle_system) => {
+                                if is_merged(particle_system.texture()) {
+                                    particle_system.set_texture(existing.clone());
+                                }
+                            }
+                            _ => ()
+                        }
+                    }
+                }
+                None => {
+                    self.textures.push(TimedEntry {
+                        value: texture,
+                        time_to_live: self.texture_policy.refresh_value(),
+                    });
+                }
+            }
         }
+    }
 
-        match DataSource::from_file(path.as_ref()) {
-            Ok(source) => {
-                let buffer = if stream {
-                    SoundBuffer::new_streaming(source)
-                } else {
-                    SoundBuffer::new_generic(source)
-                };
-                match buffer {
-                    Ok(sound_buffer) => {
-                        self.sound_buffers.push(TimedEntry {
-                            value: sound_buffer.clone(),
-                            time_to_live: Self::MAX_RESOURCE_TTL,
-                        });
-                        Log::writeln(format!("Sound buffer {} is loaded!", path.as_ref().display()));
-                        Some(sound_buffer)
+    fn update_pending_models(&mut self) {
+        let mut i = 0;
+        while i < self.pending_models.len() {
+            let result = match self.pending_models[i].receiver.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => {
+                    i += 1;
+                    continue;
+                }
+                Err(TryRecvError::Disconnected) => Err("Worker thread has terminated".to_owned()),
+            };
+
+            let pending = self.pending_models.remove(i);
+
+            if let Some(model) = pending.reload {
+                match result {
+                    Ok((mut new_model, textures)) => {
+                        self.merge_model_textures(&mut new_model, textures);
+                        new_model.self_weak_ref = Some(Arc::downgrade(&model));
+                        *model.lock().unwrap() = new_model;
+                        self.reloaded_models.push(model);
+                        Log::writeln(format!("Model {:?} was changed and reloaded!", pending.request.path));
                     }
-                    Err(_) => {
-                        Log::writeln(format!("Unable to load sound buffer from {}!", path.as_ref().display()));
-                        None
+                    Err(reason) => {
+                        Log::writeln(format!("Unable to reload {:?} model! Reason: {}", pending.request.path, reason));
                     }
                 }
+                continue;
             }
-            Err(e) => {
-                Log::writeln(format!("Invalid data source: {:?}", e));
-                None
+
+            let result = match result {
+                Ok((mut model, textures)) => {
+                    self.merge_model_textures(&mut model, textures);
+                    let model = Arc::new(Mutex::new(model));
+                    model.lock().unwrap().self_weak_ref = Some(Arc::downgrade(&model));
+                    self.models.push(TimedEntry {
+                        value: model.clone(),
+                        time_to_live: self.model_policy.refresh_value(),
+                    });
+                    let _ = self.event_sender.send(ResourceEvent::Loaded {
+                        kind: ResourceKind::Model,
+                        path: pending.request.path.clone(),
+                    });
+                    Ok(model)
+                }
+                Err(reason) => {
+                    Log::writeln(format!("Unable to load model from {:?}! Reason {}", pending.request.path, reason));
+                    let _ = self.event_sender.send(ResourceEvent::Failed {
+                        kind: ResourceKind::Model,
+                        path: pending.request.path.clone(),
+                        reason: reason.clone(),
+                    });
+                    Err(reason)
+                }
+            };
+            pending.request.finish(result.clone());
+            for callback in pending.callbacks {
+                callback(result.clone());
             }
         }
     }
 
+    /// Returns true if there is at least one model that is still loading.
+    #[inline]
+    pub fn has_pending_models(&self) -> bool {
+        !self.pending_models.is_empty()
+    }
+
+    pub fn request_sound_buffer<P: AsRef<Path>>(&mut self, path: P, stream: bool) -> Result<SharedSoundBuffer, ResourceError> {
+        if let Some(sound_buffer) = self.find_sound_buffer(path.as_ref()) {
+            return Ok(sound_buffer);
+        }
+
+        let result = Self::load_sound_buffer(&self.vfs, path.as_ref(), stream).map(|(sound_buffer, real_path)| {
+            self.register_sound_buffer(path.as_ref(), &sound_buffer, real_path);
+            sound_buffer
+        });
+        self.report(ResourceKind::SoundBuffer, path.as_ref(), &result);
+        result
+    }
+
+    /// Requests sound buffer to be decoded on worker thread. Returns request immediately, its
+    /// state will be changed in engine update once sound buffer is loaded. Requesting already
+    /// loaded or loading sound buffer returns request that shares state with first one.
+    pub fn request_sound_buffer_async<P: AsRef<Path>>(&mut self, path: P, stream: bool) -> SoundBufferRequest {
+        let path = path.as_ref();
+        if let Some(sound_buffer) = self.find_sound_buffer(path) {
+            return SoundBufferRequest::new(path.to_path_buf(), Some(sound_buffer));
+        }
+
+        if let Some(pending) = self.pending_sound_buffers.iter().find(|p| p.request.path == path) {
+            return pending.request.clone();
+        }
+
+        let request = SoundBufferRequest::new(path.to_path_buf(), None);
+        let (sender, receiver) = mpsc::channel();
+        let vfs = self.vfs.clone();
+        let sound_buffer_path = path.to_path_buf();
+        self.worker_pool.execute(move || {
+            let result = Self::load_sound_buffer(&vfs, &sound_buffer_path, stream).map_err(|e| e.to_string());
+            // Receiver can be dropped if resource manager was destroyed, result is not needed then.
+            let _ = sender.send(result);
+        });
+
+        self.pending_sound_buffers.push(PendingSoundBuffer {
+            request: request.clone(),
+            receiver,
+        });
+
+        request
+    }
+
+    /// Loads sound buffer, VFS is locked only to find or read file. Returns path of file on
+    /// disk if sound buffer reads file by itself.
+    fn load_sound_buffer(vfs: &SharedVfs, path: &Path, stream: bool) -> Result<(SharedSoundBuffer, Option<PathBuf>), ResourceError> {
+        // Loose files are opened by sound buffer itself, so they can be streamed and
+        // reloaded. Files from archives are read into memory, such buffers cannot be found
+        // by path and won't be reloaded.
+        let (real_path, source) = {
+            let vfs = vfs.read().unwrap();
+            let real_path = vfs.real_path(path);
+            let source = match real_path.as_ref() {
+                Some(real_path) => DataSource::from_file(real_path),
+                None => vfs.read(path).map(|data| DataSource::Memory(Cursor::new(data))),
+            };
+            (real_path, source)
+        };
+        let source = source.map_err(ResourceError::Io)?;
+        let sound_buffer = if stream {
+            SoundBuffer::new_streaming(source)
+        } else {
+            SoundBuffer::new_generic(source)
+        };
+        sound_buffer
+            .map(|sound_buffer| (sound_buffer, real_path))
+            .map_err(|_| ResourceError::InvalidSoundBuffer)
+    }
+
+    fn register_sound_buffer(&mut self, path: &Path, sound_buffer: &SharedSoundBuffer, real_path: Option<PathBuf>) {
+        self.sound_buffers.push(TimedEntry {
+            value: sound_buffer.clone(),
+            time_to_live: self.sound_buffer_policy.refresh_value(),
+        });
+        if let Some(real_path) = real_path {
+            self.sound_buffer_paths.insert(real_path, path.to_owned());
+        }
+    }
+
+    fn update_pending_sound_buffers(&mut self) {
+        let mut i = 0;
+        while i < self.pending_sound_buffers.len() {
+            let result = match self.pending_sound_buffers[i].receiver.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => {
+                    i += 1;
+                    continue;
+                }
+                Err(TryRecvError::Disconnected) => Err("Worker thread has terminated".to_owned()),
+            };
+
+            let pending = self.pending_sound_buffers.remove(i);
+            let path = pending.request.path.clone();
+            let result = match result {
+                Ok((sound_buffer, real_path)) => {
+                    self.register_sound_buffer(&path, &sound_buffer, real_path);
+                    Log::writeln(format!("Sound buffer {:?} is loaded!", path));
+                    let _ = self.event_sender.send(ResourceEvent::Loaded {
+                        kind: ResourceKind::SoundBuffer,
+                        path,
+                    });
+                    Ok(sound_buffer)
+                }
+                Err(reason) => {
+                    Log::writeln(format!("Unable to load sound buffer from {:?}! Reason {}", path, reason));
+                    let _ = self.event_sender.send(ResourceEvent::Failed {
+                        kind: ResourceKind::SoundBuffer,
+                        path,
+                        reason: reason.clone(),
+                    });
+                    Err(reason)
+                }
+            };
+            pending.request.finish(result);
+        }
+    }
+
+    /// Returns next event from queue, or None if queue is empty. Events should be polled
+    /// every frame, otherwise they will pile up.
+    pub fn poll_event(&mut self) -> Option<ResourceEvent> {
+        self.event_receiver.try_recv().ok()
+    }
+
     #[inline]
     pub fn textures(&self) -> &[TimedEntry<SharedTexture>] {
         &self.textures
@@ -227,9 +867,16 @@ impl ResourceManager {
         &self.models
     }
 
+    /// Finds model loaded with default import options.
     pub fn find_model<P: AsRef<Path>>(&self, path: P) -> Option<SharedModel> {
+        self.find_model_with_options(path, &Default::default())
+    }
+
+    /// Finds model loaded with given import options.
+    pub fn find_model_with_options<P: AsRef<Path>>(&self, path: P, options: &ModelImportOptions) -> Option<SharedModel> {
         for model in self.models.iter() {
-            if model.lock().unwrap().path.as_path() == path.as_ref() {
+            let locked = model.lock().unwrap();
+            if locked.path.as_path() == path.as_ref() && locked.options == *options {
                 return Some(model.value.clone());
             }
         }
@@ -242,9 +889,12 @@ impl ResourceManager {
     }
 
     pub fn find_sound_buffer<P: AsRef<Path>>(&self, path: P) -> Option<SharedSoundBuffer> {
+        // Sound buffers store path of file on disk, which differs from virtual path if
+        // directory is mounted somewhere.
+        let real_path = self.vfs.read().unwrap().real_path(path.as_ref());
         for sound_buffer in self.sound_buffers.iter() {
             if let Some(ext_path) = sound_buffer.lock().unwrap().generic().external_data_path() {
-                if ext_path == path.as_ref() {
+                if ext_path == path.as_ref() || real_path.as_ref().map_or(false, |real_path| ext_path == *real_path) {
                     return Some(sound_buffer.value.clone());
                 }
             }
@@ -252,6 +902,13 @@ impl ResourceManager {
         None
     }
 
+    /// Returns virtual file system which is used to read every resource. Mount directories
+    /// and archives to it before requesting resources from them.
+    #[inline]
+    pub fn vfs(&self) -> &SharedVfs {
+        &self.vfs
+    }
+
     #[inline]
     pub fn textures_path(&self) -> &Path {
         self.textures_path.as_path()
@@ -262,113 +919,286 @@ impl ResourceManager {
         self.textures_path = path.as_ref().to_owned();
     }
 
+    fn texture_memory_usage(texture: &SharedTexture) -> usize {
+        texture.lock().unwrap().bytes.len()
+    }
+
+    fn model_memory_usage(model: &SharedModel) -> usize {
+        model.lock().unwrap().memory_usage()
+    }
+
+    fn sound_buffer_memory_usage(buffer: &SharedSoundBuffer) -> usize {
+        buffer.lock().unwrap().generic().samples().len() * std::mem::size_of::<f32>()
+    }
+
     fn update_textures(&mut self, dt: f32) {
-        for texture in self.textures.iter_mut() {
-            texture.time_to_live -= dt;
-            if texture.lock().unwrap().loaded && Arc::strong_count(texture) > 1 {
-                texture.time_to_live = Self::MAX_RESOURCE_TTL;
-            }
-        }
-        self.textures.retain(|texture| {
-            let retain = texture.time_to_live > 0.0;
-            if !retain && texture.lock().unwrap().path.exists() {
-                Log::writeln(format!("Texture resource {:?} destroyed because it not used anymore!", texture.lock().unwrap().path));
-            }
-            retain
-        });
+        let vfs = self.vfs.clone();
+        // Texture that is still loading is never destroyed.
+        self.texture_policy.update_entries(
+            &mut self.textures,
+            dt,
+            |texture| Arc::strong_count(texture) > 1 || *texture.lock().unwrap().state() == ResourceState::Pending,
+            Self::texture_memory_usage,
+            |texture| {
+                let texture = texture.lock().unwrap();
+                if vfs.read().unwrap().exists(&texture.path) {
+                    Log::writeln(format!("Texture resource {:?} destroyed because it not used anymore!", texture.path));
+                }
+            });
     }
 
     fn update_model(&mut self, dt: f32) {
-        for model in self.models.iter_mut() {
-            model.time_to_live -= dt;
-            if Arc::strong_count(model) > 1 {
-                model.time_to_live = Self::MAX_RESOURCE_TTL;
-            }
-        }
-        self.models.retain(|model| {
-            let retain = model.time_to_live > 0.0;
-            if !retain && model.lock().unwrap().path.exists() {
-                Log::writeln(format!("Model resource {:?} destroyed because it not used anymore!", model.lock().unwrap().path.exists()));
-            }
-            retain
-        });
+        let vfs = self.vfs.clone();
+        self.model_policy.update_entries(
+            &mut self.models,
+            dt,
+            |model| Arc::strong_count(model) > 1,
+            Self::model_memory_usage,
+            |model| {
+                let model = model.lock().unwrap();
+                if vfs.read().unwrap().exists(&model.path) {
+                    Log::writeln(format!("Model resource {:?} destroyed because it not used anymore!", model.path));
+                }
+            });
     }
 
     fn update_sound_buffers(&mut self, dt: f32) {
-        for buffer in self.sound_buffers.iter_mut() {
-            buffer.time_to_live -= dt;
-            if Arc::strong_count(buffer) > 1 {
-                buffer.time_to_live = Self::MAX_RESOURCE_TTL;
-            }
-        }
-        self.sound_buffers.retain(|buffer| {
-            let retain = buffer.time_to_live > 0.0;
-            if !retain {
+        let sound_buffer_paths = &mut self.sound_buffer_paths;
+        self.sound_buffer_policy.update_entries(
+            &mut self.sound_buffers,
+            dt,
+            |buffer| Arc::strong_count(buffer) > 1,
+            Self::sound_buffer_memory_usage,
+            |buffer| {
                 if let Some(path) = buffer.lock().unwrap().generic().external_data_path().as_ref() {
+                    sound_buffer_paths.remove(path);
                     Log::writeln(format!("Sound resource {:?} destroyed because it not used anymore!", path));
                 }
+            });
+    }
+
+    /// Sets policy that decides when unused resources of given kind are destroyed. Default
+    /// policy destroys resources that were unused for 20 seconds.
+    pub fn set_eviction_policy(&mut self, kind: ResourceKind, policy: EvictionPolicy) {
+        // Time to live has different meaning for different policies, so every resource
+        // starts from scratch.
+        let time_to_live = policy.refresh_value();
+        match kind {
+            ResourceKind::Texture => {
+                self.textures.iter_mut().for_each(|entry| entry.time_to_live = time_to_live);
+                self.texture_policy = policy;
             }
-            retain
-        });
+            ResourceKind::Model => {
+                self.models.iter_mut().for_each(|entry| entry.time_to_live = time_to_live);
+                self.model_policy = policy;
+            }
+            ResourceKind::SoundBuffer => {
+                self.sound_buffers.iter_mut().for_each(|entry| entry.time_to_live = time_to_live);
+                self.sound_buffer_policy = policy;
+            }
+        }
+    }
+
+    pub fn eviction_policy(&self, kind: ResourceKind) -> EvictionPolicy {
+        match kind {
+            ResourceKind::Texture => self.texture_policy,
+            ResourceKind::Model => self.model_policy,
+            ResourceKind::SoundBuffer => self.sound_buffer_policy,
+        }
+    }
+
+    /// Returns size and reference count of every resource. Reference count does not include
+    /// reference of resource manager itself, so resource with zero references is unused.
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut resources = Vec::new();
+        for texture in self.textures.iter() {
+            resources.push(ResourceMemoryInfo {
+                kind: ResourceKind::Texture,
+                path: texture.lock().unwrap().path.clone(),
+                bytes: Self::texture_memory_usage(texture),
+                reference_count: Arc::strong_count(texture) - 1,
+            });
+        }
+        for model in self.models.iter() {
+            resources.push(ResourceMemoryInfo {
+                kind: ResourceKind::Model,
+                path: model.lock().unwrap().path.clone(),
+                bytes: Self::model_memory_usage(model),
+                reference_count: Arc::strong_count(model) - 1,
+            });
+        }
+        for buffer in self.sound_buffers.iter() {
+            resources.push(ResourceMemoryInfo {
+                kind: ResourceKind::SoundBuffer,
+                path: buffer.lock().unwrap().generic().external_data_path().unwrap_or_default(),
+                bytes: Self::sound_buffer_memory_usage(buffer),
+                reference_count: Arc::strong_count(buffer) - 1,
+            });
+        }
+        MemoryReport { resources }
     }
 
     pub(in crate) fn update(&mut self, dt: f32) {
+        self.update_pending_models();
+        self.update_pending_sound_buffers();
+        self.update_hot_reload(dt);
         self.update_textures(dt);
         self.update_model(dt);
         self.update_sound_buffers(dt);
     }
 
-    fn reload_textures(&mut self) {
-        for old_texture in self.textures.iter() {
-            let mut old_texture = old_texture.lock().unwrap();
-            let new_texture = match Texture::load_from_file(old_texture.path.as_path(), old_texture.kind) {
-                Ok(texture) => texture,
-                Err(e) => {
-                    Log::writeln(format!("Unable to reload {:?} texture! Reason: {}", old_texture.path, e));
-                    continue;
-                }
+    /// Reloads texture in-place, so every user of the texture will get new data. Revision of
+    /// texture is increased so renderer will know that it must upload texture again. Texture
+    /// is locked only to read its parameters and to put new data in it.
+    fn reload_texture(vfs: &SharedVfs, texture: &SharedTexture) -> bool {
+        let (path, kind, options) = {
+            let texture = texture.lock().unwrap();
+            // Options from sidecar file are read again, unless they were overridden.
+            let options = if texture.options_overridden {
+                Some(texture.options)
+            } else {
+                None
             };
-            old_texture.path = Default::default();
-            *old_texture = new_texture;
+            (texture.path.clone(), texture.kind, options)
+        };
+        let file = TextureFile::read(&vfs.read().unwrap(), &path, options);
+        let new_texture = file.and_then(|file| file.decode(&path, kind));
+        let mut old_texture = texture.lock().unwrap();
+        let new_texture = match new_texture {
+            Ok(texture) => texture,
+            Err(e) => {
+                Log::writeln(format!("Unable to reload {:?} texture! Reason: {}", path, e));
+                // Keep old data if there is any.
+                if !old_texture.is_loaded() {
+                    old_texture.state = ResourceState::LoadError(e.to_string());
+                }
+                return false;
+            }
+        };
+        let revision = old_texture.revision.wrapping_add(1);
+        *old_texture = new_texture;
+        old_texture.revision = revision;
+        true
+    }
+
+    /// Same as [reload_texture](ResourceManager::reload_texture), but texture is loaded on
+    /// worker thread. Old data of texture is used until new data is loaded.
+    fn reload_texture_async(&self, texture: &SharedTexture) {
+        let vfs = self.vfs.clone();
+        let texture = texture.clone();
+        self.worker_pool.execute(move || {
+            if Self::reload_texture(&vfs, &texture) {
+                Log::writeln(format!("Texture {:?} was changed and reloaded!", texture.lock().unwrap().path));
+            }
+        });
+    }
+
+    fn reload_textures(&mut self) {
+        for texture in self.textures.iter() {
+            Self::reload_texture(&self.vfs, texture);
         }
     }
 
+    /// Reloads model in-place. Instances of model will still use old surfaces until they'll
+    /// be re-synced with resource, see [take_reloaded_models](ResourceManager::take_reloaded_models).
+    fn reload_model(&mut self, model: &SharedModel) -> bool {
+        let mut old_model = model.lock().unwrap();
+        let options = old_model.options.clone();
+        let mut new_model = match Model::load(old_model.path.as_path(), self, &options) {
+            Ok(new_model) => new_model,
+            Err(e) => {
+                Log::writeln(format!("Unable to reload {:?} model! Reason: {}", old_model.path, e));
+                return false;
+            }
+        };
+        new_model.self_weak_ref = Some(Arc::downgrade(model));
+        old_model.path = Default::default();
+        *old_model = new_model;
+        self.reloaded_models.push(model.clone());
+        true
+    }
+
+    /// Same as [reload_model](ResourceManager::reload_model), but model is loaded on worker
+    /// thread and replaced in [update_pending_models](ResourceManager::update_pending_models).
+    fn reload_model_async(&mut self, model: &SharedModel) {
+        let already_reloading = self.pending_models
+            .iter()
+            .any(|pending| pending.reload.as_ref().map_or(false, |reload| Arc::ptr_eq(reload, model)));
+        if already_reloading {
+            return;
+        }
+
+        let (path, options) = {
+            let model = model.lock().unwrap();
+            (model.path.clone(), model.options.clone())
+        };
+        let receiver = self.load_model_on_worker(&path, &options);
+        self.pending_models.push(PendingModel {
+            request: ModelRequest::new(path, None),
+            options,
+            receiver,
+            callbacks: Vec::new(),
+            reload: Some(model.clone()),
+        });
+    }
+
     fn reload_models(&mut self) {
-        for old_model in self.models().to_vec() {
-            let old_model_arc = old_model.clone();
-            let mut old_model = old_model.lock().unwrap();
-            let mut new_model = match Model::load(old_model.path.as_path(), self) {
-                Ok(new_model) => new_model,
-                Err(e) => {
-                    Log::writeln(format!("Unable to reload {:?} model! Reason: {}", old_model.path, e));
-                    continue;
-                }
-            };
-            new_model.self_weak_ref = Some(Arc::downgrade(&old_model_arc));
-            old_model.path = Default::default();
-            *old_model = new_model;
+        for model in self.models().to_vec() {
+            self.reload_model(&model);
         }
     }
 
-    fn reload_sound_buffers(&mut self) {
-        for old_sound_buffer in self.sound_buffers() {
-            let mut old_sound_buffer = old_sound_buffer.lock().unwrap();
-            if let Some(ext_path) = old_sound_buffer.generic().external_data_path() {
-                if let Ok(data_source) = DataSource::from_file(ext_path.as_path()) {
-                    let new_sound_buffer = match *old_sound_buffer {
-                        SoundBuffer::Generic(_) => SoundBuffer::raw_generic(data_source),
-                        SoundBuffer::Streaming(_) => SoundBuffer::raw_streaming(data_source),
-                    };
-                    let new_sound_buffer = match new_sound_buffer {
-                        Ok(new_sound_buffer) => new_sound_buffer,
-                        Err(_) => {
-                            Log::writeln(format!("Unable to reload {:?} sound buffer!", ext_path));
-                            continue;
-                        }
-                    };
-                    *old_sound_buffer = new_sound_buffer;
+    /// Reloads sound buffer in-place. Sound buffer is locked only to read its parameters and
+    /// to put new data in it.
+    fn reload_sound_buffer(sound_buffer: &SharedSoundBuffer) -> bool {
+        let (ext_path, streaming) = {
+            let sound_buffer = sound_buffer.lock().unwrap();
+            let streaming = match *sound_buffer {
+                SoundBuffer::Generic(_) => false,
+                SoundBuffer::Streaming(_) => true,
+            };
+            (sound_buffer.generic().external_data_path(), streaming)
+        };
+        let ext_path = match ext_path {
+            Some(ext_path) => ext_path,
+            None => return false,
+        };
+        let new_sound_buffer = DataSource::from_file(ext_path.as_path())
+            .ok()
+            .and_then(|data_source| {
+                if streaming {
+                    SoundBuffer::raw_streaming(data_source).ok()
+                } else {
+                    SoundBuffer::raw_generic(data_source).ok()
                 }
+            });
+        match new_sound_buffer {
+            Some(new_sound_buffer) => {
+                *sound_buffer.lock().unwrap() = new_sound_buffer;
+                true
             }
+            None => {
+                Log::writeln(format!("Unable to reload {:?} sound buffer!", ext_path));
+                false
+            }
+        }
+    }
+
+    /// Same as [reload_sound_buffer](ResourceManager::reload_sound_buffer), but sound buffer
+    /// is decoded on worker thread.
+    fn reload_sound_buffer_async(&self, sound_buffer: &SharedSoundBuffer) {
+        let sound_buffer = sound_buffer.clone();
+        self.worker_pool.execute(move || {
+            if Self::reload_sound_buffer(&sound_buffer) {
+                Log::writeln(format!("Sound buffer {:?} was changed and reloaded!",
+                                     sound_buffer.lock().unwrap().generic().external_data_path()));
+            }
+        });
+    }
+
+    fn reload_sound_buffers(&mut self) {
+        for sound_buffer in self.sound_buffers() {
+            Self::reload_sound_buffer(sound_buffer);
         }
     }
 
@@ -377,6 +1207,129 @@ impl ResourceManager {
         self.reload_models();
         self.reload_sound_buffers();
     }
+
+    /// Enables or disables hot reloading. When enabled, resource manager periodically checks
+    /// modification time of source file of every texture, model and sound buffer and reloads
+    /// only changed ones on worker threads. Resources are reloaded in-place, so every `Arc`
+    /// stays valid, old data is used until new data is loaded.
+    #[inline]
+    pub fn set_hot_reload(&mut self, enabled: bool) {
+        self.hot_reload = enabled;
+        if !enabled {
+            self.modification_times.clear();
+        }
+    }
+
+    #[inline]
+    pub fn is_hot_reload_enabled(&self) -> bool {
+        self.hot_reload
+    }
+
+    /// Sets interval in seconds between checks of modification times, default is one second.
+    #[inline]
+    pub fn set_hot_reload_interval(&mut self, interval: f32) {
+        self.hot_reload_interval = interval.max(0.0);
+    }
+
+    #[inline]
+    pub fn hot_reload_interval(&self) -> f32 {
+        self.hot_reload_interval
+    }
+
+    /// Returns models that were reloaded since last call. Engine uses it to re-sync
+    /// surfaces of model instances in every scene with new data of resource.
+    pub fn take_reloaded_models(&mut self) -> Vec<SharedModel> {
+        std::mem::replace(&mut self.reloaded_models, Vec::new())
+    }
+
+    /// Remembers modification time of given file and returns true if file was changed
+    /// since previous check. First check of a file always returns false.
+    fn is_file_modified(times: &mut HashMap<PathBuf, SystemTime>, path: &Path, modified: Option<SystemTime>) -> bool {
+        let modified = match modified {
+            Some(modified) => modified,
+            // Resource has no file (procedural) or file was removed - nothing to reload.
+            None => return false,
+        };
+        match times.insert(path.to_path_buf(), modified) {
+            Some(previous) => previous != modified,
+            None => false,
+        }
+    }
+
+    fn update_hot_reload(&mut self, dt: f32) {
+        if !self.hot_reload {
+            return;
+        }
+
+        self.hot_reload_timer -= dt;
+        if self.hot_reload_timer > 0.0 {
+            return;
+        }
+        self.hot_reload_timer = self.hot_reload_interval;
+
+        let mut times = std::mem::replace(&mut self.modification_times, HashMap::new());
+        let mut checked = HashMap::new();
+        let vfs = self.vfs.clone();
+
+        for texture in self.textures.iter() {
+            let (path, failed) = {
+                let texture = texture.lock().unwrap();
+                // Texture that is still loading will be checked later.
+                if *texture.state() == ResourceState::Pending {
+                    continue;
+                }
+                (texture.path.clone(), !texture.is_loaded())
+            };
+            // Changed sidecar file with options is reloaded as well.
+            let options_path = TextureImportOptions::sidecar_path(&path);
+            let modified = vfs.read().unwrap().modified(&path);
+            let options_modified = vfs.read().unwrap().modified(&options_path);
+            // Texture that failed to load is retried once its file is seen for the first time,
+            // file could be missing or broken when texture was requested.
+            let retry = failed && modified.is_some() && !times.contains_key(&path);
+            let changed = Self::is_file_modified(&mut times, &path, modified);
+            let options_changed = Self::is_file_modified(&mut times, &options_path, options_modified);
+            if changed || options_changed || retry {
+                self.reload_texture_async(texture);
+            }
+            for path in [path, options_path].iter() {
+                if let Some(time) = times.remove(path) {
+                    checked.insert(path.clone(), time);
+                }
+            }
+        }
+
+        for model in self.models().to_vec() {
+            let path = model.lock().unwrap().path.clone();
+            let modified = vfs.read().unwrap().modified(&path);
+            if Self::is_file_modified(&mut times, &path, modified) {
+                self.reload_model_async(&model);
+            }
+            if let Some(time) = times.remove(&path) {
+                checked.insert(path, time);
+            }
+        }
+
+        for sound_buffer in self.sound_buffers.iter() {
+            let path = match sound_buffer.lock().unwrap().generic().external_data_path() {
+                Some(path) => path,
+                None => continue,
+            };
+            // Sound buffers store path of file on disk, buffers that were not requested
+            // through this manager (loaded from save for example) use it as path in VFS.
+            let virtual_path = self.sound_buffer_paths.get(&path).cloned().unwrap_or_else(|| path.clone());
+            let modified = vfs.read().unwrap().modified(&virtual_path);
+            if Self::is_file_modified(&mut times, &path, modified) {
+                self.reload_sound_buffer_async(sound_buffer);
+            }
+            if let Some(time) = times.remove(&path) {
+                checked.insert(path, time);
+            }
+        }
+
+        // Times of destroyed resources are dropped.
+        self.modification_times = checked;
+    }
 }
 
 impl Visit for ResourceManager {
@@ -389,4 +1342,359 @@ impl Visit for ResourceManager {
 
         visitor.leave_region()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex, RwLock, mpsc},
+        time::{Instant, Duration, SystemTime},
+        collections::HashMap,
+        thread,
+        io,
+    };
+    use crate::{
+        resource::{
+            texture::{Texture, TextureKind},
+            model::{Model, ModelImportOptions},
+            vfs::{Vfs, MountSource},
+            fbx::{
+                conversion::FbxImportOptions,
+                error::FbxError,
+            },
+            error::ResourceError,
+            ResourceState,
+        },
+        animation::channel::KeyFrameReductionSettings,
+        renderer::surface::{Surface, SurfaceSharedData},
+        scene::{
+            node::Node,
+            mesh::Mesh,
+            sprite::SpriteBuilder,
+            base::BaseBuilder,
+        },
+        engine::{
+            worker_pool::WorkerPool,
+            resource_manager::{ResourceManager, SharedTexture, ResourceEvent, ResourceKind, TimedEntry},
+        },
+    };
+
+    fn make_texture(path: &str) -> SharedTexture {
+        let mut texture = Texture::new(1, 1, TextureKind::RGBA8);
+        texture.path = PathBuf::from(path);
+        Arc::new(Mutex::new(texture))
+    }
+
+    /// Files are shared with every clone of source, so they can be changed after source
+    /// was mounted.
+    #[derive(Clone, Default)]
+    struct MemorySource {
+        files: Arc<Mutex<HashMap<PathBuf, (Vec<u8>, SystemTime)>>>,
+    }
+
+    impl MemorySource {
+        fn set_file(&self, path: &str, data: Vec<u8>, modified: SystemTime) {
+            self.files.lock().unwrap().insert(PathBuf::from(path), (data, modified));
+        }
+    }
+
+    impl MountSource for MemorySource {
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.files.lock().unwrap()
+                .get(path)
+                .map(|(data, _)| data.clone())
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        }
+
+        fn exists(&self, path: &Path) -> bool {
+            self.files.lock().unwrap().contains_key(path)
+        }
+
+        fn modified(&self, path: &Path) -> Option<SystemTime> {
+            self.files.lock().unwrap().get(path).map(|(_, modified)| *modified)
+        }
+    }
+
+    fn make_resource_manager(source: &MemorySource) -> ResourceManager {
+        let mut vfs = Vfs::new();
+        vfs.mount("", Box::new(source.clone()));
+        let mut resource_manager = ResourceManager::with_worker_pool(WorkerPool::new(1));
+        resource_manager.vfs = Arc::new(RwLock::new(vfs));
+        resource_manager
+    }
+
+    fn make_png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        image::DynamicImage::new_rgba8(width, height)
+            .write_to(&mut data, image::ImageOutputFormat::PNG)
+            .unwrap();
+        data
+    }
+
+    fn wait<F: FnMut() -> bool>(mut condition: F) {
+        let time = Instant::now();
+        while !condition() {
+            assert!(time.elapsed() < Duration::from_secs(10), "Waiting for too long");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn wait_pending_models(resource_manager: &mut ResourceManager) {
+        let time = Instant::now();
+        while resource_manager.has_pending_models() {
+            assert!(time.elapsed() < Duration::from_secs(10), "Model is loading for too long");
+            thread::sleep(Duration::from_millis(1));
+            resource_manager.update_pending_models();
+        }
+    }
+
+    #[test]
+    fn merge_model_textures() {
+        let mut resource_manager = ResourceManager::with_worker_pool(WorkerPool::new(1));
+        let diffuse = resource_manager.register_texture("diffuse.png", Texture::new(1, 1, TextureKind::RGBA8));
+        let specular = resource_manager.register_texture("specular.png", Texture::new(1, 1, TextureKind::RGBA8));
+
+        // Textures loaded by other resource manager, only normal map is new.
+        let loaded_diffuse = make_texture("diffuse.png");
+        let loaded_normal = make_texture("normal.png");
+        let loaded_specular = make_texture("specular.png");
+
+        let mut model = Model::default();
+        let mut surface = Surface::new(Arc::new(Mutex::new(SurfaceSharedData::make_cube())));
+        surface.set_diffuse_texture(loaded_diffuse.clone());
+        surface.set_normal_texture(loaded_normal.clone());
+        surface.set_specular_texture(loaded_specular.clone());
+        let mut mesh = Mesh::default();
+        mesh.add_surface(surface);
+        let mesh = model.get_scene_mut().graph.add_node(Node::Mesh(mesh));
+        let sprite = model.get_scene_mut().graph.add_node(Node::Sprite(SpriteBuilder::new(BaseBuilder::new())
+            .with_texture(loaded_diffuse.clone())
+            .build()));
+
+        resource_manager.merge_model_textures(&mut model, vec![loaded_diffuse, loaded_normal.clone(), loaded_specular]);
+
+        let graph = &model.get_scene().graph;
+        if let Node::Mesh(mesh) = graph.get(mesh) {
+            let surface = &mesh.surfaces()[0];
+            assert!(Arc::ptr_eq(&surface.get_diffuse_texture().unwrap(), &diffuse));
+            assert!(Arc::ptr_eq(&surface.get_normal_texture().unwrap(), &loaded_normal));
+            assert!(Arc::ptr_eq(&surface.get_specular_texture().unwrap(), &specular));
+        } else {
+            unreachable!();
+        }
+        if let Node::Sprite(sprite) = graph.get(sprite) {
+            assert!(Arc::ptr_eq(&sprite.texture().unwrap(), &diffuse));
+        } else {
+            unreachable!();
+        }
+        assert!(Arc::ptr_eq(&resource_manager.find_texture("normal.png").unwrap(), &loaded_normal));
+    }
+
+    #[test]
+    fn async_request_of_missing_model() {
+        let mut resource_manager = ResourceManager::with_worker_pool(WorkerPool::new(1));
+        let (sender, receiver) = mpsc::channel();
+        let request = resource_manager.request_model_async_with_callback("missing.fbx", move |result| {
+            sender.send(result.is_err()).unwrap();
+        });
+        // Same request must share state with first one.
+        let same_request = resource_manager.request_model_async("missing.fbx");
+        assert!(request.is_loading());
+
+        wait_pending_models(&mut resource_manager);
+
+        assert!(request.model().is_none());
+        assert!(request.error().is_some());
+        assert!(same_request.error().is_some());
+        assert_eq!(receiver.try_recv(), Ok(true));
+        assert!(resource_manager.find_model("missing.fbx").is_none());
+    }
+
+    #[test]
+    fn texture_search_paths() {
+        let source = MemorySource::default();
+        let time = SystemTime::UNIX_EPOCH;
+        source.set_file("data/textures/wall.png", Vec::new(), time);
+        source.set_file("textures/hd/wall_normal.png", Vec::new(), time);
+        source.set_file("textures/sd/wall_normal.png", Vec::new(), time);
+        source.set_file("textures/sd/floor.png", Vec::new(), time);
+        let resource_manager = make_resource_manager(&source);
+
+        // No search paths means textures path of resource manager.
+        let options = ModelImportOptions::default();
+        assert_eq!(options.find_texture(&resource_manager, "wall.png".as_ref()), Some(PathBuf::from("data/textures/wall.png")));
+        assert_eq!(options.find_texture(&resource_manager, "floor.png".as_ref()), None);
+
+        // Paths are checked in order, missing texture falls back to first path.
+        let options = ModelImportOptions::default()
+            .with_texture_search_path("textures/hd")
+            .with_texture_search_path("textures/sd");
+        assert_eq!(options.find_texture(&resource_manager, "wall_normal.png".as_ref()), Some(PathBuf::from("textures/hd/wall_normal.png")));
+        assert_eq!(options.texture_path(&resource_manager, "floor.png".as_ref()), PathBuf::from("textures/sd/floor.png"));
+        assert_eq!(options.find_texture(&resource_manager, "wall.png".as_ref()), None);
+        assert_eq!(options.texture_path(&resource_manager, "wall.png".as_ref()), PathBuf::from("textures/hd/wall.png"));
+    }
+
+    #[test]
+    fn find_model_by_options() {
+        let mut resource_manager = ResourceManager::with_worker_pool(WorkerPool::new(1));
+        let scaled = ModelImportOptions::default().with_scale(2.0);
+        for options in [ModelImportOptions::default(), scaled.clone()].iter() {
+            let mut model = Model::default();
+            model.path = PathBuf::from("model.fbx");
+            model.options = options.clone();
+            resource_manager.models.push(TimedEntry {
+                value: Arc::new(Mutex::new(model)),
+                time_to_live: 1.0,
+            });
+        }
+
+        let default_model = resource_manager.find_model("model.fbx").unwrap();
+        let scaled_model = resource_manager.find_model_with_options("model.fbx", &scaled).unwrap();
+        assert!(!Arc::ptr_eq(&default_model, &scaled_model));
+        assert_eq!(scaled_model.lock().unwrap().import_options(), &scaled);
+        // Request with same options reuses loaded model.
+        let requested = resource_manager.request_model_with_options("model.fbx", scaled.clone()).unwrap();
+        assert!(Arc::ptr_eq(&requested, &scaled_model));
+
+        let merged = ModelImportOptions::default().with_merge_meshes(true);
+        assert!(resource_manager.find_model_with_options("model.fbx", &merged).is_none());
+        assert!(resource_manager.find_model("other.fbx").is_none());
+
+        // Models imported with other key frame reduction settings or FBX import options
+        // are not reused.
+        let reduced = ModelImportOptions::default().with_key_frame_reduction(Some(KeyFrameReductionSettings::default()));
+        assert!(resource_manager.find_model_with_options("model.fbx", &reduced).is_none());
+        let unconverted = ModelImportOptions::default().with_fbx_import_options(FbxImportOptions::no_conversion());
+        assert!(resource_manager.find_model_with_options("model.fbx", &unconverted).is_none());
+    }
+
+    #[test]
+    fn strict_import() {
+        // Light of unknown type falls back to point light with a warning.
+        let text = "FBXHeaderExtension: {\n\tFBXVersion: 7400\n}\nObjects: {\n\tNodeAttribute: 7, \"NodeAttribute::Lamp\", \"Light\" {\n\t\tProperties70: {\n\t\t\tP: \"LightType\", \"enum\", \"\", \"\",9\n\t\t}\n\t}\n}\nConnections: {\n}\n";
+        let source = MemorySource::default();
+        source.set_file("lamp.fbx", text.as_bytes().to_vec(), SystemTime::UNIX_EPOCH);
+        let mut resource_manager = make_resource_manager(&source);
+
+        let model = resource_manager.request_model("lamp.fbx").unwrap();
+        assert_eq!(model.lock().unwrap().import_warnings().len(), 1);
+
+        match resource_manager.request_model_with_options("lamp.fbx", ModelImportOptions::default().with_strict(true)) {
+            Err(ResourceError::Fbx(FbxError::Warnings(warnings))) => {
+                assert_eq!(warnings.len(), 1);
+                assert_eq!(warnings[0].object.as_ref().map(|object| object.name.as_str()), Some("Lamp"));
+            }
+            _ => panic!("Expected warnings of strict import"),
+        }
+    }
+
+    #[test]
+    fn file_modification() {
+        let mut times = HashMap::new();
+        let path = Path::new("a.png");
+        let time = SystemTime::UNIX_EPOCH;
+        // First check only remembers time.
+        assert!(!ResourceManager::is_file_modified(&mut times, path, Some(time)));
+        assert!(!ResourceManager::is_file_modified(&mut times, path, Some(time)));
+        assert!(ResourceManager::is_file_modified(&mut times, path, Some(time + Duration::from_secs(1))));
+        // Removed file cannot be reloaded.
+        assert!(!ResourceManager::is_file_modified(&mut times, path, None));
+    }
+
+    #[test]
+    fn hot_reload_of_texture() {
+        let source = MemorySource::default();
+        let mut time = SystemTime::UNIX_EPOCH;
+        source.set_file("texture.png", b"broken".to_vec(), time);
+
+        let mut resource_manager = make_resource_manager(&source);
+        resource_manager.set_hot_reload(true);
+        resource_manager.set_hot_reload_interval(0.0);
+
+        let texture = resource_manager.request_texture_async("texture.png", TextureKind::RGBA8);
+        wait(|| *texture.lock().unwrap().state() != ResourceState::Pending);
+        assert!(!texture.lock().unwrap().is_loaded());
+
+        // Failed texture must be loaded once file is fixed.
+        time += Duration::from_secs(1);
+        source.set_file("texture.png", make_png(1, 1), time);
+        resource_manager.update_hot_reload(1.0);
+        wait(|| texture.lock().unwrap().is_loaded());
+        let revision = texture.lock().unwrap().revision;
+
+        // Unchanged texture is not reloaded.
+        resource_manager.update_hot_reload(1.0);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(texture.lock().unwrap().revision, revision);
+
+        time += Duration::from_secs(1);
+        source.set_file("texture.png", make_png(2, 2), time);
+        resource_manager.update_hot_reload(1.0);
+        wait(|| texture.lock().unwrap().width == 2);
+        assert_ne!(texture.lock().unwrap().revision, revision);
+        assert!(texture.lock().unwrap().is_loaded());
+    }
+
+    fn is_loaded_event(event: Option<ResourceEvent>, expected_kind: ResourceKind, expected_path: &str) -> bool {
+        match event {
+            Some(ResourceEvent::Loaded { kind, path }) => kind == expected_kind && path == Path::new(expected_path),
+            _ => false,
+        }
+    }
+
+    fn is_failed_event(event: Option<ResourceEvent>, expected_kind: ResourceKind, expected_path: &str) -> bool {
+        match event {
+            Some(ResourceEvent::Failed { kind, path, .. }) => kind == expected_kind && path == Path::new(expected_path),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn events_of_synchronous_requests() {
+        let source = MemorySource::default();
+        source.set_file("texture.png", make_png(1, 1), SystemTime::UNIX_EPOCH);
+        let mut resource_manager = make_resource_manager(&source);
+
+        assert!(resource_manager.request_texture("texture.png", TextureKind::RGBA8).is_ok());
+        assert!(is_loaded_event(resource_manager.poll_event(), ResourceKind::Texture, "texture.png"));
+
+        assert!(resource_manager.request_texture("missing.png", TextureKind::RGBA8).is_err());
+        assert!(is_failed_event(resource_manager.poll_event(), ResourceKind::Texture, "missing.png"));
+
+        assert!(resource_manager.request_model("missing.fbx").is_err());
+        assert!(is_failed_event(resource_manager.poll_event(), ResourceKind::Model, "missing.fbx"));
+
+        assert!(resource_manager.request_sound_buffer("missing.wav", false).is_err());
+        assert!(is_failed_event(resource_manager.poll_event(), ResourceKind::SoundBuffer, "missing.wav"));
+
+        assert!(resource_manager.poll_event().is_none());
+    }
+
+    #[test]
+    fn events_of_asynchronous_requests() {
+        let source = MemorySource::default();
+        source.set_file("texture.png", make_png(1, 1), SystemTime::UNIX_EPOCH);
+        let mut resource_manager = make_resource_manager(&source);
+
+        let texture = resource_manager.request_texture_async("texture.png", TextureKind::RGBA8);
+        wait(|| *texture.lock().unwrap().state() != ResourceState::Pending);
+        assert!(texture.lock().unwrap().is_loaded());
+        assert!(is_loaded_event(resource_manager.poll_event(), ResourceKind::Texture, "texture.png"));
+
+        let texture = resource_manager.request_texture_async("missing.png", TextureKind::RGBA8);
+        wait(|| *texture.lock().unwrap().state() != ResourceState::Pending);
+        match texture.lock().unwrap().state() {
+            ResourceState::LoadError(_) => (),
+            state => panic!("Unexpected state {:?}", state),
+        }
+        assert!(is_failed_event(resource_manager.poll_event(), ResourceKind::Texture, "missing.png"));
+
+        resource_manager.request_model_async("missing.fbx");
+        wait_pending_models(&mut resource_manager);
+        assert!(is_failed_event(resource_manager.poll_event(), ResourceKind::Model, "missing.fbx"));
+
+        assert!(resource_manager.poll_event().is_none());
+    }
+}
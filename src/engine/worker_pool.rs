@@ -0,0 +1,74 @@
+//! Fixed-size pool of worker threads that is shared by every async request of resource
+//! manager, so loading of many resources at once will not spawn a thread per resource.
+
+use std::{
+    sync::{
+        Arc,
+        Mutex,
+        mpsc::{self, Sender, Receiver},
+    },
+    thread,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Cheap to clone handle to a pool, every clone submits jobs to the same set of threads.
+/// Threads are stopped when last handle is dropped and every queued job is done.
+#[derive(Clone)]
+pub struct WorkerPool {
+    sender: Arc<Mutex<Sender<Job>>>,
+    thread_count: usize,
+}
+
+impl WorkerPool {
+    pub const DEFAULT_THREAD_COUNT: usize = 4;
+
+    pub fn new(thread_count: usize) -> Self {
+        let thread_count = thread_count.max(1);
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..thread_count {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("rg3d-worker-{}", i))
+                .spawn(move || Self::worker_loop(receiver))
+                .expect("Unable to spawn worker thread!");
+        }
+        Self {
+            sender: Arc::new(Mutex::new(sender)),
+            thread_count,
+        }
+    }
+
+    fn worker_loop(receiver: Arc<Mutex<Receiver<Job>>>) {
+        loop {
+            // Lock is released right after job is received, so other workers can take next job.
+            let job = match receiver.lock() {
+                Ok(receiver) => receiver.recv(),
+                Err(_) => return,
+            };
+            match job {
+                Ok(job) => job(),
+                // Every sender is dropped - pool is destroyed.
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// Puts given job in queue, it will be executed by first free worker.
+    pub fn execute<F>(&self, job: F) where F: FnOnce() + Send + 'static {
+        // Send can fail only if every worker is dead, nothing can be done in this case.
+        let _ = self.sender.lock().unwrap().send(Box::new(job));
+    }
+
+    #[inline]
+    pub fn thread_count(&self) -> usize {
+        self.thread_count
+    }
+}
+
+impl Default for WorkerPool {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_THREAD_COUNT)
+    }
+}
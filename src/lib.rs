@@ -2,7 +2,10 @@
 //!
 //! Features:
 //! - Scene graph with pivot, camera, mesh, light, particle system, sprite nodes.
-//! - FBX Loader - both ASCII and binary. Note: Only 7100 - 7400 versions are supported!
+//! - FBX Loader - both ASCII and binary, versions 7100 - 7700 are supported. Textures embedded in binary FBX are loaded too.
+//!     - Axis system and units of file are converted to engine's (Y up, meters), conversion can be overridden or disabled
+//!     - Cameras, vertex colors and second set of texture coordinates are imported
+//!     - Errors and warnings point to object, node path and byte offset in file, strict mode turns warnings into errors
 //! - Advanced node-based UI with these widgets:
 //!     - Border
 //!     - Button
@@ -21,10 +24,26 @@
 //! - Skinning
 //! - Animation blending - allows you to blend your animations as you want to, i.e. idle animation can be blended with walk.
 //! - Animation retargetting - allows you to remap animation from one model to another.
+//! - Key frame reduction of imported animations with per-channel tolerance.
+//! - Animation blend spaces and inverse kinematics (two bone, look at, FABRIK).
+//! - Ragdolls built from skeletons with blending between animation and physics.
+//! - Property animation tracks - light color, sprite size and color, camera FOV, visibility.
+//! - Blend shapes (morph targets) imported from FBX, animatable by property tracks.
 //! - Automatic resource management
 //!     - Texture
 //!     - Models
 //!     - Sound buffers
+//!     - Asynchronous loading of textures and models on shared worker pool
+//!     - Hot reloading of changed resources
+//!     - Virtual file system with mount points for directories and packed archives
+//!     - DDS and KTX textures with block compression (BC1 - BC7) and mip chains
+//!     - Per-texture filtering, wrapping, anisotropy and sRGB options, optionally in sidecar files
+//!     - Procedural textures created at runtime with partial re-upload of changed regions
+//!     - Configurable eviction policies (time to live, memory budget, pinned) and memory reports
+//!     - Preload manifests with aggregate loading progress of a group of resources
+//!     - Compiled binary models that are loaded instead of slow FBX import when up-to-date
+//!     - Per-model import options: texture search paths, normal and specular map naming, tangents, scale, mesh merging, animation-only import
+//!     - Vertex welding with position, normal and UV tolerance, meshes are optimized for vertex cache and overdraw
 //! - Deferred shading
 //!     - Point light
 //!     - Spot light
@@ -44,7 +63,10 @@ extern crate glutin;
 extern crate lexical;
 extern crate byteorder;
 extern crate inflate;
+extern crate deflate;
 extern crate rand;
+extern crate serde;
+extern crate ron;
 #[macro_use]
 extern crate lazy_static;
 
@@ -6,7 +6,12 @@ use std::{
     marker::PhantomData,
 };
 use crate::{
-    resource::texture::TextureKind,
+    resource::texture::{
+        TextureKind,
+        TextureMinificationFilter,
+        TextureMagnificationFilter,
+        TextureWrapMode,
+    },
     renderer::{
         framework::{
             gl::types::GLuint,
@@ -59,6 +64,22 @@ pub enum PixelKind {
     RGB8,
     RG8,
     R8,
+    RGB32F,
+    RGB16F,
+    BC1,
+    BC2,
+    BC3,
+    BC4,
+    BC5,
+    BC6H,
+    BC6HSigned,
+    BC7,
+    SRGB8,
+    SRGBA8,
+    BC1Srgb,
+    BC2Srgb,
+    BC3Srgb,
+    BC7Srgb,
 }
 
 impl From<TextureKind> for PixelKind {
@@ -67,32 +88,138 @@ impl From<TextureKind> for PixelKind {
             TextureKind::R8 => PixelKind::R8,
             TextureKind::RGB8 => PixelKind::RGB8,
             TextureKind::RGBA8 => PixelKind::RGBA8,
+            TextureKind::BC1 => PixelKind::BC1,
+            TextureKind::BC2 => PixelKind::BC2,
+            TextureKind::BC3 => PixelKind::BC3,
+            TextureKind::BC4 => PixelKind::BC4,
+            TextureKind::BC5 => PixelKind::BC5,
+            TextureKind::BC6H => PixelKind::BC6H,
+            TextureKind::BC6HSigned => PixelKind::BC6HSigned,
+            TextureKind::BC7 => PixelKind::BC7,
         }
     }
 }
 
+// S3TC is an extension, so its formats are not in core profile bindings.
+const COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = 0x83F1;
+const COMPRESSED_RGBA_S3TC_DXT3_EXT: u32 = 0x83F2;
+const COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = 0x83F3;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT: u32 = 0x8C4D;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT: u32 = 0x8C4E;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT: u32 = 0x8C4F;
+
 pub struct GpuTexture {
     texture: GLuint,
     kind: GpuTextureKind,
+    pixel_kind: PixelKind,
     // Force compiler to not implement Send and Sync, because OpenGL is not thread-safe.
     thread_mark: PhantomData<*const u8>,
 }
 
 impl PixelKind {
+    /// Size of a pixel in bytes, zero for compressed kinds.
     fn size_bytes(self) -> usize {
         match self {
-            PixelKind::RGBA8 | PixelKind::D24S8 | PixelKind::D32 | PixelKind::F32 => 4,
-            PixelKind::RGB8 => 3,
+            PixelKind::RGBA8 | PixelKind::SRGBA8 | PixelKind::D24S8 | PixelKind::D32 | PixelKind::F32 => 4,
+            PixelKind::RGB8 | PixelKind::SRGB8 => 3,
             PixelKind::RG8 => 2,
             PixelKind::R8 => 1,
+            PixelKind::RGB32F => 12,
+            PixelKind::RGB16F => 6,
+            _ => 0,
+        }
+    }
+
+    /// Size of 4x4 block in bytes for compressed kinds.
+    fn block_size(self) -> Option<usize> {
+        match self {
+            PixelKind::BC1 | PixelKind::BC1Srgb | PixelKind::BC4 => Some(8),
+            PixelKind::BC2 | PixelKind::BC2Srgb | PixelKind::BC3 | PixelKind::BC3Srgb | PixelKind::BC5 |
+            PixelKind::BC6H | PixelKind::BC6HSigned | PixelKind::BC7 | PixelKind::BC7Srgb => Some(16),
+            _ => None,
+        }
+    }
+
+    pub fn is_compressed(self) -> bool {
+        self.block_size().is_some()
+    }
+
+    /// Returns size in bytes of image of given size.
+    fn image_size(self, width: usize, height: usize) -> usize {
+        match self.block_size() {
+            Some(block_size) => ((width + 3) / 4) * ((height + 3) / 4) * block_size,
+            None => width * height * self.size_bytes(),
         }
     }
 
     fn unpack_alignment(self) -> i32 {
         match self {
-            PixelKind::RGBA8 | PixelKind::RGB8 | PixelKind::D24S8 | PixelKind::D32 | PixelKind::F32 => 4,
-            PixelKind::RG8 => 2,
-            PixelKind::R8 => 1
+            PixelKind::RGBA8 | PixelKind::RGB8 | PixelKind::SRGBA8 | PixelKind::SRGB8 |
+            PixelKind::D24S8 | PixelKind::D32 | PixelKind::F32 | PixelKind::RGB32F => 4,
+            PixelKind::RG8 | PixelKind::RGB16F => 2,
+            _ => 1
+        }
+    }
+
+    /// Checks if current context can create textures of this kind. Only S3TC and BPTC can be
+    /// missing on GL 3.3, RGTC is a part of core profile.
+    pub fn is_supported(self, state: &State) -> bool {
+        match self {
+            PixelKind::BC1 | PixelKind::BC2 | PixelKind::BC3 => {
+                state.is_extension_supported("GL_EXT_texture_compression_s3tc")
+            }
+            PixelKind::BC1Srgb | PixelKind::BC2Srgb | PixelKind::BC3Srgb => {
+                state.is_extension_supported("GL_EXT_texture_compression_s3tc")
+                    && (state.is_extension_supported("GL_EXT_texture_sRGB")
+                    || state.is_extension_supported("GL_EXT_texture_compression_s3tc_srgb"))
+            }
+            PixelKind::BC6H | PixelKind::BC6HSigned | PixelKind::BC7 | PixelKind::BC7Srgb => {
+                state.is_extension_supported("GL_ARB_texture_compression_bptc")
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns sRGB variant of pixel kind, kinds that have no such variant are returned as is.
+    pub fn to_srgb(self) -> Self {
+        match self {
+            PixelKind::RGB8 => PixelKind::SRGB8,
+            PixelKind::RGBA8 => PixelKind::SRGBA8,
+            PixelKind::BC1 => PixelKind::BC1Srgb,
+            PixelKind::BC2 => PixelKind::BC2Srgb,
+            PixelKind::BC3 => PixelKind::BC3Srgb,
+            PixelKind::BC7 => PixelKind::BC7Srgb,
+            _ => self,
+        }
+    }
+
+    /// Returns (type, format, internal format) of pixel kind, type and format are not used
+    /// by compressed kinds.
+    fn gl_formats(self) -> (u32, u32, u32) {
+        match self {
+            PixelKind::F32 => (gl::FLOAT, gl::RED, gl::R32F),
+            PixelKind::D32 => (gl::FLOAT, gl::DEPTH_COMPONENT, gl::DEPTH_COMPONENT),
+            PixelKind::D24S8 => (gl::UNSIGNED_INT_24_8, gl::DEPTH_STENCIL, gl::DEPTH24_STENCIL8),
+            PixelKind::RGBA8 => (gl::UNSIGNED_BYTE, gl::RGBA, gl::RGBA8),
+            PixelKind::RGB8 => (gl::UNSIGNED_BYTE, gl::RGB, gl::RGB8),
+            PixelKind::RG8 => (gl::UNSIGNED_BYTE, gl::RG, gl::RG8),
+            PixelKind::R8 => (gl::UNSIGNED_BYTE, gl::RED, gl::R8),
+            PixelKind::RGB32F => (gl::FLOAT, gl::RGB, gl::RGB32F),
+            PixelKind::RGB16F => (gl::HALF_FLOAT, gl::RGB, gl::RGB16F),
+            PixelKind::BC1 => (0, 0, COMPRESSED_RGBA_S3TC_DXT1_EXT),
+            PixelKind::BC2 => (0, 0, COMPRESSED_RGBA_S3TC_DXT3_EXT),
+            PixelKind::BC3 => (0, 0, COMPRESSED_RGBA_S3TC_DXT5_EXT),
+            PixelKind::BC4 => (0, 0, gl::COMPRESSED_RED_RGTC1),
+            PixelKind::BC5 => (0, 0, gl::COMPRESSED_RG_RGTC2),
+            PixelKind::BC6H => (0, 0, gl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT),
+            PixelKind::BC6HSigned => (0, 0, gl::COMPRESSED_RGB_BPTC_SIGNED_FLOAT),
+            PixelKind::BC7 => (0, 0, gl::COMPRESSED_RGBA_BPTC_UNORM),
+            PixelKind::SRGB8 => (gl::UNSIGNED_BYTE, gl::RGB, gl::SRGB8),
+            PixelKind::SRGBA8 => (gl::UNSIGNED_BYTE, gl::RGBA, gl::SRGB8_ALPHA8),
+            PixelKind::BC1Srgb => (0, 0, COMPRESSED_SRGB_ALPHA_S3TC_DXT1_EXT),
+            PixelKind::BC2Srgb => (0, 0, COMPRESSED_SRGB_ALPHA_S3TC_DXT3_EXT),
+            PixelKind::BC3Srgb => (0, 0, COMPRESSED_SRGB_ALPHA_S3TC_DXT5_EXT),
+            PixelKind::BC7Srgb => (0, 0, gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM),
         }
     }
 }
@@ -112,9 +239,19 @@ impl MagnificationFilter {
     }
 }
 
+impl From<TextureMagnificationFilter> for MagnificationFilter {
+    fn from(filter: TextureMagnificationFilter) -> Self {
+        match filter {
+            TextureMagnificationFilter::Nearest => MagnificationFilter::Nearest,
+            TextureMagnificationFilter::Linear => MagnificationFilter::Linear,
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum MininificationFilter {
     Nearest,
+    NearestMip,
     Linear,
     LinearMip,
 }
@@ -123,15 +260,28 @@ impl MininificationFilter {
     pub fn into_gl_value(self) -> i32 {
         (match self {
             MininificationFilter::Nearest => gl::NEAREST,
+            MininificationFilter::NearestMip => gl::NEAREST_MIPMAP_NEAREST,
             MininificationFilter::Linear => gl::LINEAR,
             MininificationFilter::LinearMip => gl::LINEAR_MIPMAP_LINEAR,
         }) as i32
     }
 }
 
+impl From<TextureMinificationFilter> for MininificationFilter {
+    fn from(filter: TextureMinificationFilter) -> Self {
+        match filter {
+            TextureMinificationFilter::Nearest => MininificationFilter::Nearest,
+            TextureMinificationFilter::NearestMip => MininificationFilter::NearestMip,
+            TextureMinificationFilter::Linear => MininificationFilter::Linear,
+            TextureMinificationFilter::LinearMip => MininificationFilter::LinearMip,
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum WrapMode {
     Repeat,
+    MirroredRepeat,
     ClampToEdge,
     ClampToBorder,
 }
@@ -140,12 +290,24 @@ impl WrapMode {
     pub fn into_gl_value(self) -> i32 {
         (match self {
             WrapMode::Repeat => gl::REPEAT,
+            WrapMode::MirroredRepeat => gl::MIRRORED_REPEAT,
             WrapMode::ClampToEdge => gl::CLAMP_TO_EDGE,
             WrapMode::ClampToBorder => gl::CLAMP_TO_BORDER,
         }) as i32
     }
 }
 
+impl From<TextureWrapMode> for WrapMode {
+    fn from(wrap: TextureWrapMode) -> Self {
+        match wrap {
+            TextureWrapMode::Repeat => WrapMode::Repeat,
+            TextureWrapMode::MirroredRepeat => WrapMode::MirroredRepeat,
+            TextureWrapMode::ClampToEdge => WrapMode::ClampToEdge,
+            TextureWrapMode::ClampToBorder => WrapMode::ClampToBorder,
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum Coordinate {
     S,
@@ -198,6 +360,16 @@ impl<'a> TextureBinding<'a> {
         self
     }
 
+    /// Sets level of anisotropic filtering, it is clamped to maximum supported level.
+    pub fn set_anisotropy(self, level: f32) -> Self {
+        unsafe {
+            let mut max = 0.0;
+            gl::GetFloatv(gl::MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max);
+            gl::TexParameterf(self.texture.kind.to_texture_target(), gl::TEXTURE_MAX_ANISOTROPY_EXT, level.max(1.0).min(max.max(1.0)));
+        }
+        self
+    }
+
     pub fn set_minification_filter(self, min_filter: MininificationFilter) -> Self {
         unsafe {
             gl::TexParameteri(self.texture.kind.to_texture_target(), gl::TEXTURE_MIN_FILTER, min_filter.into_gl_value());
@@ -250,11 +422,30 @@ impl GpuTexture {
                kind: GpuTextureKind,
                pixel_kind: PixelKind,
                data: Option<&[u8]>) -> Result<Self, RendererError> {
+        Self::new_with_mips(state, kind, pixel_kind, 1, data)
+    }
+
+    /// Creates new GPU texture with given amount of mip levels, `data` must contain every
+    /// level one after another starting from the largest one. Mip chains and compressed
+    /// pixel kinds are supported only by Rectangle textures.
+    pub fn new_with_mips(state: &mut State,
+                         kind: GpuTextureKind,
+                         pixel_kind: PixelKind,
+                         mip_count: usize,
+                         data: Option<&[u8]>) -> Result<Self, RendererError> {
+        let mip_count = mip_count.max(1);
         let bytes_per_pixel = pixel_kind.size_bytes();
 
         let desired_byte_count = match kind {
+            GpuTextureKind::Rectangle { width, height } => {
+                (0..mip_count)
+                    .map(|level| pixel_kind.image_size((width >> level).max(1), (height >> level).max(1)))
+                    .sum()
+            }
+            _ if mip_count > 1 || pixel_kind.is_compressed() => {
+                return Err(RendererError::InvalidTextureData);
+            }
             GpuTextureKind::Line { length } => length * bytes_per_pixel,
-            GpuTextureKind::Rectangle { width, height } => width * height * bytes_per_pixel,
             GpuTextureKind::Cube { width, height } => 6 * width * height * bytes_per_pixel,
             GpuTextureKind::Volume { width, height, depth } => {
                 width * height * depth * bytes_per_pixel
@@ -265,6 +456,9 @@ impl GpuTexture {
             if data.len() != desired_byte_count {
                 return Err(RendererError::InvalidTextureData);
             }
+        } else if pixel_kind.is_compressed() {
+            // Compressed texture can't be render target.
+            return Err(RendererError::InvalidTextureData);
         }
 
         let target = kind.to_texture_target();
@@ -275,15 +469,7 @@ impl GpuTexture {
 
             state.set_texture(0, target, texture);
 
-            let (type_, format, internal_format) = match pixel_kind {
-                PixelKind::F32 => (gl::FLOAT, gl::RED, gl::R32F),
-                PixelKind::D32 => (gl::FLOAT, gl::DEPTH_COMPONENT, gl::DEPTH_COMPONENT),
-                PixelKind::D24S8 => (gl::UNSIGNED_INT_24_8, gl::DEPTH_STENCIL, gl::DEPTH24_STENCIL8),
-                PixelKind::RGBA8 => (gl::UNSIGNED_BYTE, gl::RGBA, gl::RGBA8),
-                PixelKind::RGB8 => (gl::UNSIGNED_BYTE, gl::RGB, gl::RGB8),
-                PixelKind::RG8 => (gl::UNSIGNED_BYTE, gl::RG, gl::RG8),
-                PixelKind::R8 => (gl::UNSIGNED_BYTE, gl::RED, gl::R8),
-            };
+            let (type_, format, internal_format) = pixel_kind.gl_formats();
 
             gl::PixelStorei(gl::UNPACK_ALIGNMENT, pixel_kind.unpack_alignment());
 
@@ -298,9 +484,30 @@ impl GpuTexture {
                                    length as i32, 0, format, type_, pixels);
                 }
                 GpuTextureKind::Rectangle { width, height } => {
-                    gl::TexImage2D(gl::TEXTURE_2D, 0, internal_format as i32,
-                                   width as i32, height as i32, 0,
-                                   format, type_, pixels);
+                    let mut offset = 0;
+                    for level in 0..mip_count {
+                        let level_width = (width >> level).max(1);
+                        let level_height = (height >> level).max(1);
+                        let size = pixel_kind.image_size(level_width, level_height);
+
+                        let level_pixels = match data {
+                            None => std::ptr::null(),
+                            Some(data) => data[offset..offset + size].as_ptr() as *const c_void,
+                        };
+
+                        if pixel_kind.is_compressed() {
+                            gl::CompressedTexImage2D(gl::TEXTURE_2D, level as i32, internal_format,
+                                                     level_width as i32, level_height as i32, 0,
+                                                     size as i32, level_pixels);
+                        } else {
+                            gl::TexImage2D(gl::TEXTURE_2D, level as i32, internal_format as i32,
+                                           level_width as i32, level_height as i32, 0,
+                                           format, type_, level_pixels);
+                        }
+
+                        offset += size;
+                    }
+                    gl::TexParameteri(target, gl::TEXTURE_MAX_LEVEL, mip_count as i32 - 1);
                 }
                 GpuTextureKind::Cube { width, height } => {
                     for face in 0..6 {
@@ -336,6 +543,7 @@ impl GpuTexture {
             Ok(Self {
                 texture,
                 kind,
+                pixel_kind,
                 thread_mark: PhantomData,
             })
         }
@@ -354,6 +562,44 @@ impl GpuTexture {
         self.kind
     }
 
+    pub fn pixel_kind(&self) -> PixelKind {
+        self.pixel_kind
+    }
+
+    /// Replaces pixels of given region of first mip level, `data` must contain rows of region
+    /// one after another. Supported only by uncompressed Rectangle textures.
+    pub fn set_region(&mut self,
+                      state: &mut State,
+                      x: usize,
+                      y: usize,
+                      width: usize,
+                      height: usize,
+                      data: &[u8]) -> Result<(), RendererError> {
+        let (texture_width, texture_height) = match self.kind {
+            GpuTextureKind::Rectangle { width, height } => (width, height),
+            _ => return Err(RendererError::InvalidTextureData),
+        };
+        if self.pixel_kind.is_compressed()
+            || x + width > texture_width
+            || y + height > texture_height
+            || data.len() != self.pixel_kind.image_size(width, height) {
+            return Err(RendererError::InvalidTextureData);
+        }
+
+        unsafe {
+            state.set_texture(0, gl::TEXTURE_2D, self.texture);
+
+            let (type_, format, _) = self.pixel_kind.gl_formats();
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, self.pixel_kind.unpack_alignment());
+            gl::TexSubImage2D(gl::TEXTURE_2D, 0, x as i32, y as i32, width as i32, height as i32,
+                              format, type_, data.as_ptr() as *const c_void);
+
+            state.set_texture(0, gl::TEXTURE_2D, 0);
+        }
+
+        Ok(())
+    }
+
     pub fn id(&self) -> u32 {
         self.texture
     }
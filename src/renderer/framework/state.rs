@@ -1,3 +1,7 @@
+use std::{
+    collections::HashSet,
+    ffi::CStr,
+};
 use crate::{
     renderer::{
         framework::{
@@ -46,6 +50,9 @@ pub struct State {
 
     stencil_func: StencilFunc,
     stencil_op: StencilOp,
+
+    /// Names of extensions supported by current context.
+    extensions: HashSet<String>,
 }
 
 #[derive(Copy, Clone)]
@@ -162,9 +169,29 @@ impl State {
             texture_units: [Default::default(); 32],
             stencil_func: Default::default(),
             stencil_op: Default::default(),
+            extensions: Self::query_extensions(),
         }
     }
 
+    fn query_extensions() -> HashSet<String> {
+        let mut extensions = HashSet::new();
+        unsafe {
+            let mut count = 0;
+            gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+            for i in 0..count.max(0) as GLuint {
+                let name = gl::GetStringi(gl::EXTENSIONS, i);
+                if !name.is_null() {
+                    extensions.insert(CStr::from_ptr(name as *const _).to_string_lossy().into_owned());
+                }
+            }
+        }
+        extensions
+    }
+
+    pub fn is_extension_supported(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
     pub fn set_framebuffer(&mut self, framebuffer: GLuint) {
         if self.framebuffer != framebuffer {
             self.framebuffer = framebuffer;
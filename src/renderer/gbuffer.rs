@@ -31,6 +31,7 @@ use crate::{
         RenderPassStatistics,
         TextureCache,
         GeometryCache,
+        MAX_BLEND_SHAPES,
     },
     scene::{
         node::Node,
@@ -56,6 +57,11 @@ struct GBufferShader {
     bone_matrices: UniformLocation,
     diffuse_texture: UniformLocation,
     normal_texture: UniformLocation,
+    specular_texture: UniformLocation,
+    blend_shapes_texture: UniformLocation,
+    blend_shapes_count: UniformLocation,
+    blend_shapes_vertex_count: UniformLocation,
+    blend_shapes_weights: UniformLocation,
 }
 
 impl GBufferShader {
@@ -70,6 +76,11 @@ impl GBufferShader {
             bone_matrices: program.uniform_location("boneMatrices")?,
             diffuse_texture: program.uniform_location("diffuseTexture")?,
             normal_texture: program.uniform_location("normalTexture")?,
+            specular_texture: program.uniform_location("specularTexture")?,
+            blend_shapes_texture: program.uniform_location("blendShapesTexture")?,
+            blend_shapes_count: program.uniform_location("blendShapesCount")?,
+            blend_shapes_vertex_count: program.uniform_location("blendShapesVertexCount")?,
+            blend_shapes_weights: program.uniform_location("blendShapesWeights")?,
             program,
         })
     }
@@ -223,11 +234,30 @@ impl GBuffer {
                     normal_dummy.clone()
                 };
 
+                // Surfaces without specular map take specular intensity from diffuse texture.
+                let specular_texture = surface.get_specular_texture()
+                    .and_then(|texture| texture_cache.get(state, texture))
+                    .unwrap_or_else(|| diffuse_texture.clone());
+
+                let data = surface.get_data();
+                let data = data.lock().unwrap();
+
+                let blend_shapes_texture = geom_cache.blend_shapes(state, &data);
+                // Blend shapes are not applied if their texture can't be created.
+                let blend_shapes_count = if blend_shapes_texture.is_some() {
+                    data.blend_shapes().len()
+                        .min(mesh.blend_shape_weights().len())
+                        .min(MAX_BLEND_SHAPES)
+                } else {
+                    0
+                };
+                let blend_shapes_texture = blend_shapes_texture.unwrap_or_else(|| white_dummy.clone());
+
                 statistics.add_draw_call(
                     self.framebuffer.draw(
                         state,
                         viewport,
-                        geom_cache.get(&surface.get_data().lock().unwrap()),
+                        geom_cache.get(&data),
                         &mut self.shader.program,
                         DrawParameters {
                             cull_face: CullFace::Back,
@@ -259,7 +289,18 @@ impl GBuffer {
                                             bone_node.base().inv_bind_pose_transform());
                                 }
                                 &self.bone_matrices
-                            }))
+                            })),
+                            (self.shader.blend_shapes_texture, UniformValue::Sampler {
+                                index: 2,
+                                texture: blend_shapes_texture,
+                            }),
+                            (self.shader.specular_texture, UniformValue::Sampler {
+                                index: 3,
+                                texture: specular_texture,
+                            }),
+                            (self.shader.blend_shapes_count, UniformValue::Integer(blend_shapes_count as i32)),
+                            (self.shader.blend_shapes_vertex_count, UniformValue::Integer(data.get_vertices().len() as i32)),
+                            (self.shader.blend_shapes_weights, UniformValue::FloatArray(&mesh.blend_shape_weights()[..blend_shapes_count]))
                         ],
                     ));
             }
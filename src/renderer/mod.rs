@@ -27,11 +27,19 @@ use std::{
         Mutex,
     },
     time,
-    collections::HashMap,
+    collections::{
+        HashMap,
+        hash_map::Entry,
+    },
     cell::RefCell,
+    path::PathBuf,
 };
 use crate::{
-    resource::texture::Texture,
+    resource::{
+        texture::{Texture, TextureKind, TextureMinificationFilter, TextureRegion},
+        block_compression,
+        ResourceState,
+    },
     renderer::{
         ui_renderer::UiRenderer,
         surface::SurfaceSharedData,
@@ -47,8 +55,7 @@ use crate::{
                 GpuTexture,
                 GpuTextureKind,
                 PixelKind,
-                MininificationFilter,
-                MagnificationFilter,
+                Coordinate,
             },
             geometry_buffer::{
                 GeometryBuffer,
@@ -88,7 +95,10 @@ use crate::{
     },
     utils::log::Log,
     gui::draw::DrawingContext,
-    engine::resource_manager::TimedEntry,
+    engine::{
+        resource_manager::TimedEntry,
+        eviction::EvictionPolicy,
+    },
 };
 
 #[derive(Copy, Clone)]
@@ -256,14 +266,67 @@ pub struct Renderer {
     geometry_cache: GeometryCache,
 }
 
+/// Video memory used by a single texture.
+#[derive(Clone, Debug)]
+pub struct GpuTextureMemoryInfo {
+    pub path: PathBuf,
+    /// Approximate size in bytes, including mip levels.
+    pub bytes: usize,
+}
+
+/// Video memory used by renderer caches, see [memory_report](Renderer::memory_report).
+/// Render targets and internal textures of renderer are not included.
+#[derive(Clone, Debug, Default)]
+pub struct GpuMemoryReport {
+    pub textures: Vec<GpuTextureMemoryInfo>,
+    pub texture_bytes: usize,
+    /// Size of vertex and index buffers, along with blend shape textures.
+    pub geometry_bytes: usize,
+    pub geometry_buffer_count: usize,
+}
+
+/// Maximum amount of blend shapes of a surface that can be applied at once, must match size
+/// of blendShapesWeights array in vertex shaders.
+pub(in crate) const MAX_BLEND_SHAPES: usize = 32;
+
+/// Width of blend shapes texture. Offsets of blend shapes are stored in a texture row by row,
+/// each vertex of each blend shape takes two texels - position offset and normal offset.
+const BLEND_SHAPES_TEXTURE_WIDTH: usize = 1024;
+
+struct GeometryCacheEntry {
+    buffer: GeometryBuffer<surface::Vertex>,
+    /// Revision of surface data at the moment of upload.
+    revision: u32,
+    unique_id: usize,
+    /// Size of vertex and index buffers in bytes.
+    size: usize,
+}
+
+struct BlendShapesCacheEntry {
+    texture: Rc<RefCell<GpuTexture>>,
+    /// Revision of surface data at the moment of upload.
+    revision: u32,
+    unique_id: usize,
+    size: usize,
+}
+
 #[derive(Default)]
 pub struct GeometryCache {
-    map: HashMap<usize, TimedEntry<GeometryBuffer<surface::Vertex>>>
+    map: HashMap<usize, TimedEntry<GeometryCacheEntry>>,
+    blend_shapes: HashMap<usize, TimedEntry<BlendShapesCacheEntry>>,
+    policy: EvictionPolicy,
 }
 
 impl GeometryCache {
     fn get(&mut self, data: &SurfaceSharedData) -> &mut GeometryBuffer<surface::Vertex> {
         let key = (data as *const _) as usize;
+        let time_to_live = self.policy.refresh_value();
+
+        // Surface data was changed or it is another surface data at the address of destroyed
+        // one - drop outdated buffer, it'll be created again.
+        if self.map.get(&key).map_or(false, |entry| entry.revision != data.revision || entry.unique_id != data.unique_id) {
+            self.map.remove(&key);
+        }
 
         let geometry_buffer = self.map.entry(key).or_insert_with(|| {
             let mut triangles = Vec::with_capacity(data.indices.len() / 3);
@@ -280,74 +343,249 @@ impl GeometryCache {
                     AttributeDefinition { kind: AttributeKind::Float3, normalized: false },
                     AttributeDefinition { kind: AttributeKind::Float4, normalized: false },
                     AttributeDefinition { kind: AttributeKind::Float4, normalized: false },
-                    AttributeDefinition { kind: AttributeKind::UnsignedByte4, normalized: false }])
+                    AttributeDefinition { kind: AttributeKind::UnsignedByte4, normalized: false },
+                    AttributeDefinition { kind: AttributeKind::Float2, normalized: false },
+                    AttributeDefinition { kind: AttributeKind::UnsignedByte4, normalized: true }])
                 .unwrap()
                 .set_vertices(data.vertices.as_slice())
                 .set_triangles(&triangles);
 
-            TimedEntry { value: geometry_buffer, time_to_live: 20.0 }
+            let size = data.vertices.len() * std::mem::size_of::<surface::Vertex>()
+                + triangles.len() * std::mem::size_of::<TriangleDefinition>();
+
+            TimedEntry {
+                value: GeometryCacheEntry {
+                    buffer: geometry_buffer,
+                    revision: data.revision,
+                    unique_id: data.unique_id,
+                    size,
+                },
+                time_to_live,
+            }
         });
 
-        geometry_buffer.time_to_live = 20.0;
-        geometry_buffer
+        geometry_buffer.time_to_live = time_to_live;
+        &mut geometry_buffer.value.buffer
     }
 
-    fn update(&mut self, dt: f32) {
-        for entry in self.map.values_mut() {
-            entry.time_to_live -= dt;
+    /// Returns texture with offsets of every blend shape of surface, or None if surface
+    /// has no blend shapes.
+    fn blend_shapes(&mut self, state: &mut State, data: &SurfaceSharedData) -> Option<Rc<RefCell<GpuTexture>>> {
+        if data.blend_shapes.is_empty() {
+            return None;
         }
-        self.map.retain(|_, v| {
-            v.time_to_live > 0.0
-        });
+
+        let key = (data as *const _) as usize;
+        let time_to_live = self.policy.refresh_value();
+
+        if self.blend_shapes.get(&key).map_or(false, |entry| entry.revision != data.revision || entry.unique_id != data.unique_id) {
+            self.blend_shapes.remove(&key);
+        }
+
+        if !self.blend_shapes.contains_key(&key) {
+            let texel_count = 2 * data.vertices.len() * data.blend_shapes.len();
+            let height = (texel_count + BLEND_SHAPES_TEXTURE_WIDTH - 1) / BLEND_SHAPES_TEXTURE_WIDTH;
+
+            let mut bytes = Vec::with_capacity(BLEND_SHAPES_TEXTURE_WIDTH * height * 12);
+            for blend_shape in data.blend_shapes.iter() {
+                for (position, normal) in blend_shape.position_offsets.iter().zip(blend_shape.normal_offsets.iter()) {
+                    for v in &[position.x, position.y, position.z, normal.x, normal.y, normal.z] {
+                        bytes.extend_from_slice(&v.to_ne_bytes());
+                    }
+                }
+            }
+            // Pad last row.
+            bytes.resize(BLEND_SHAPES_TEXTURE_WIDTH * height * 12, 0);
+
+            let kind = GpuTextureKind::Rectangle {
+                width: BLEND_SHAPES_TEXTURE_WIDTH,
+                height,
+            };
+            let texture = match GpuTexture::new(state, kind, PixelKind::RGB32F, Some(bytes.as_slice())) {
+                Ok(texture) => texture,
+                Err(e) => {
+                    // Surface will be rendered without blend shapes.
+                    Log::writeln(format!("Unable to create blend shapes texture: {:?}", e));
+                    return None;
+                }
+            };
+
+            self.blend_shapes.insert(key, TimedEntry {
+                value: BlendShapesCacheEntry {
+                    texture: Rc::new(RefCell::new(texture)),
+                    revision: data.revision,
+                    unique_id: data.unique_id,
+                    size: bytes.len(),
+                },
+                time_to_live,
+            });
+        }
+
+        let texture = self.blend_shapes.get_mut(&key)?;
+        texture.time_to_live = time_to_live;
+        Some(texture.value.texture.clone())
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.policy.update_cache(&mut self.map, dt, |entry| entry.size);
+        self.policy.update_cache(&mut self.blend_shapes, dt, |entry| entry.size);
     }
 }
 
+struct TextureCacheEntry {
+    gpu_texture: Rc<RefCell<GpuTexture>>,
+    /// Revision of texture at the moment of upload.
+    revision: u32,
+    unique_id: usize,
+    /// Mips of texture were generated by driver, they must be generated again when
+    /// some region of texture is changed.
+    generated_mips: bool,
+    /// Approximate size of texture in video memory in bytes.
+    size: usize,
+    path: PathBuf,
+}
+
 #[derive(Default)]
 pub struct TextureCache {
-    map: HashMap<usize, TimedEntry<Rc<RefCell<GpuTexture>>>>
+    map: HashMap<usize, TimedEntry<TextureCacheEntry>>,
+    policy: EvictionPolicy,
 }
 
 impl TextureCache {
+    fn upload(state: &mut State, texture: &Texture, time_to_live: f32) -> Result<TimedEntry<TextureCacheEntry>, String> {
+        let kind = GpuTextureKind::Rectangle {
+            width: texture.width as usize,
+            height: texture.height as usize,
+        };
+        let options = texture.options;
+        let mut pixel_kind = PixelKind::from(texture.kind);
+        let decompressed;
+        let mut bytes = texture.bytes.as_slice();
+        // Driver can't sample this format - decompress every level on CPU, it is slow
+        // and needs more memory, but texture will look as intended.
+        if !pixel_kind.is_supported(state) || (options.srgb && !pixel_kind.to_srgb().is_supported(state)) {
+            Log::writeln(format!("Compressed texture {:?} is not supported by driver, it will be decompressed!", texture.path));
+            decompressed = block_compression::decompress_mip_chain(
+                texture.kind, texture.width, texture.height, texture.mip_count, bytes)
+                .ok_or_else(|| "Unable to decompress mip levels".to_owned())?;
+            bytes = decompressed.as_slice();
+            pixel_kind = match texture.kind {
+                TextureKind::BC6H | TextureKind::BC6HSigned => PixelKind::RGB16F,
+                _ => PixelKind::RGBA8,
+            };
+        }
+        if options.srgb {
+            pixel_kind = pixel_kind.to_srgb();
+        }
+        let mut gpu_texture = GpuTexture::new_with_mips(
+            state,
+            kind,
+            pixel_kind,
+            texture.mip_count as usize,
+            Some(bytes))
+            .map_err(|e| format!("{:?}", e))?;
+        let mut binding = gpu_texture.bind_mut(state, 0);
+        // Compressed textures can't have mips generated by driver.
+        let generate_mips = options.generate_mips && texture.mip_count == 1 && !pixel_kind.is_compressed();
+        if generate_mips {
+            binding = binding.generate_mip_maps();
+        }
+        // Sampling of mip levels of texture that has none will give black texture.
+        let mut minification_filter = options.minification_filter;
+        if !generate_mips && texture.mip_count == 1 {
+            minification_filter = match minification_filter {
+                TextureMinificationFilter::NearestMip => TextureMinificationFilter::Nearest,
+                TextureMinificationFilter::LinearMip => TextureMinificationFilter::Linear,
+                other => other,
+            };
+        }
+        binding.set_minification_filter(minification_filter.into())
+            .set_magnification_filter(options.magnification_filter.into())
+            .set_wrap(Coordinate::S, options.wrap_s.into())
+            .set_wrap(Coordinate::T, options.wrap_t.into())
+            .set_anisotropy(options.anisotropy);
+        let mut size = bytes.len();
+        if generate_mips {
+            // Full mip chain takes one third of size of main level.
+            size = size * 4 / 3;
+        }
+        Ok(TimedEntry {
+            value: TextureCacheEntry {
+                gpu_texture: Rc::new(RefCell::new(gpu_texture)),
+                revision: texture.revision,
+                unique_id: texture.unique_id,
+                generated_mips: generate_mips,
+                size,
+                path: texture.path.clone(),
+            },
+            time_to_live,
+        })
+    }
+
+    /// Uploads changed region of texture to existing GPU texture.
+    fn upload_region(state: &mut State, entry: &TextureCacheEntry, texture: &Texture, region: TextureRegion) {
+        let bytes_per_pixel = texture.kind.bytes_per_pixel();
+        let row_size = texture.width as usize * bytes_per_pixel;
+        let region_row_size = region.width as usize * bytes_per_pixel;
+        let mut data = Vec::with_capacity(region_row_size * region.height as usize);
+        for y in region.y as usize..(region.y + region.height) as usize {
+            let begin = y * row_size + region.x as usize * bytes_per_pixel;
+            data.extend_from_slice(&texture.bytes[begin..begin + region_row_size]);
+        }
+
+        let mut gpu_texture = entry.gpu_texture.borrow_mut();
+        if let Err(e) = gpu_texture.set_region(state, region.x as usize, region.y as usize,
+                                               region.width as usize, region.height as usize, &data) {
+            Log::writeln(format!("Unable to update region {:?} of texture {:?}: {:?}", region, texture.path, e));
+            return;
+        }
+        if entry.generated_mips {
+            gpu_texture.bind_mut(state, 0).generate_mip_maps();
+        }
+    }
+
     fn get(&mut self, state: &mut State, texture: Arc<Mutex<Texture>>) -> Option<Rc<RefCell<GpuTexture>>> {
-        if texture.lock().unwrap().loaded {
-            let key = (&*texture as *const _) as usize;
-            let gpu_texture = self.map.entry(key).or_insert_with(move || {
-                let texture = texture.lock().unwrap();
-                let kind = GpuTextureKind::Rectangle {
-                    width: texture.width as usize,
-                    height: texture.height as usize,
-                };
-                let mut gpu_texture = GpuTexture::new(
-                    state,
-                    kind,
-                    PixelKind::from(texture.kind),
-                    Some(texture.bytes.as_slice()))
-                    .unwrap();
-                gpu_texture.bind_mut(state, 0)
-                    .generate_mip_maps()
-                    .set_minification_filter(MininificationFilter::LinearMip)
-                    .set_magnification_filter(MagnificationFilter::Linear)
-                    .set_max_anisotropy();
-                TimedEntry {
-                    value: Rc::new(RefCell::new(gpu_texture)),
-                    time_to_live: 20.0,
-                }
-            });
-            // Texture won't be destroyed while it used.
-            gpu_texture.time_to_live = 20.0;
-            Some(gpu_texture.value.clone())
-        } else {
-            None
+        let mut texture = texture.lock().unwrap();
+        if !texture.is_loaded() {
+            return None;
         }
+
+        let key = (&*texture as *const _) as usize;
+        // Texture was reloaded or it is another texture at the address of destroyed one - drop
+        // outdated GPU texture, it'll be uploaded again.
+        if self.map.get(&key).map_or(false, |entry| entry.revision != texture.revision || entry.unique_id != texture.unique_id) {
+            self.map.remove(&key);
+        }
+
+        // Whole texture is uploaded when GPU texture is created, so changed region matters
+        // only for existing GPU texture.
+        let dirty_region = texture.dirty_region.take();
+        let entry = match self.map.entry(key) {
+            Entry::Occupied(entry) => {
+                let entry = entry.into_mut();
+                if let Some(region) = dirty_region {
+                    Self::upload_region(state, entry, &texture, region);
+                }
+                entry
+            }
+            Entry::Vacant(entry) => match Self::upload(state, &texture, self.policy.refresh_value()) {
+                Ok(uploaded) => entry.insert(uploaded),
+                Err(reason) => {
+                    Log::writeln(format!("Unable to upload texture {:?}! Reason: {}", texture.path, reason));
+                    // Texture is skipped until it is reloaded, so upload won't be tried every frame.
+                    texture.state = ResourceState::LoadError(reason);
+                    return None;
+                }
+            },
+        };
+
+        // Texture won't be destroyed while it used.
+        entry.time_to_live = self.policy.refresh_value();
+        Some(entry.gpu_texture.clone())
     }
 
     fn update(&mut self, dt: f32) {
-        for entry in self.map.values_mut() {
-            entry.time_to_live -= dt;
-        }
-        self.map.retain(|_, v| {
-            v.time_to_live > 0.0
-        });
+        self.policy.update_cache(&mut self.map, dt, |entry| entry.size);
     }
 }
 
@@ -426,6 +664,34 @@ impl Renderer {
         self.quality_settings
     }
 
+    /// Sets policy that decides when GPU copies of textures are destroyed. GPU texture is
+    /// uploaded again when texture is used after destruction.
+    pub fn set_texture_cache_policy(&mut self, policy: EvictionPolicy) {
+        self.texture_cache.policy = policy;
+    }
+
+    /// Sets policy that decides when vertex and index buffers of surfaces are destroyed.
+    pub fn set_geometry_cache_policy(&mut self, policy: EvictionPolicy) {
+        self.geometry_cache.policy = policy;
+    }
+
+    pub fn memory_report(&self) -> GpuMemoryReport {
+        let textures = self.texture_cache.map
+            .values()
+            .map(|entry| GpuTextureMemoryInfo {
+                path: entry.path.clone(),
+                bytes: entry.size,
+            })
+            .collect::<Vec<_>>();
+        GpuMemoryReport {
+            texture_bytes: textures.iter().map(|texture| texture.bytes).sum(),
+            textures,
+            geometry_bytes: self.geometry_cache.map.values().map(|entry| entry.size).sum::<usize>()
+                + self.geometry_cache.blend_shapes.values().map(|entry| entry.size).sum::<usize>(),
+            geometry_buffer_count: self.geometry_cache.map.len(),
+        }
+    }
+
     pub(in crate) fn render(&mut self,
                             scenes: &SceneContainer,
                             drawing_context: &DrawingContext,
@@ -11,10 +11,12 @@ in vec3 normal;
 in vec2 texCoord;
 in vec3 tangent;
 in vec3 binormal;
+in vec2 secondTexCoord;
+in vec4 color;
 
 void main()
 {
-    outColor = texture2D(diffuseTexture, texCoord);
+    outColor = color * texture2D(diffuseTexture, texCoord);
     if (outColor.a < 0.5) discard;
     outColor.a = 1;
     vec4 n = normalize(texture2D(normalTexture, texCoord) * 2.0 - 1.0);
@@ -6,25 +6,52 @@ layout(location = 2) in vec3 vertexNormal;
 layout(location = 3) in vec4 vertexTangent;
 layout(location = 4) in vec4 boneWeights;
 layout(location = 5) in vec4 boneIndices;
+layout(location = 6) in vec2 vertexSecondTexCoord;
+layout(location = 7) in vec4 vertexColor;
 
 uniform mat4 worldMatrix;
 uniform mat4 worldViewProjection;
 uniform bool useSkeletalAnimation;
 uniform mat4 boneMatrices[60];
+uniform sampler2D blendShapesTexture;
+uniform int blendShapesCount;
+uniform int blendShapesVertexCount;
+uniform float blendShapesWeights[32];
 
 out vec3 normal;
 out vec2 texCoord;
 out vec3 tangent;
 out vec3 binormal;
+out vec2 secondTexCoord;
+out vec4 color;
+
+vec3 fetchBlendShapeOffset(int index)
+{
+    int width = textureSize(blendShapesTexture, 0).x;
+    return texelFetch(blendShapesTexture, ivec2(index % width, index / width), 0).xyz;
+}
 
 void main()
 {
+    vec3 position = vertexPosition;
+    vec3 blendedNormal = vertexNormal;
+    for (int i = 0; i < blendShapesCount; ++i)
+    {
+        float weight = blendShapesWeights[i];
+        if (weight != 0.0)
+        {
+            int texel = 2 * (i * blendShapesVertexCount + gl_VertexID);
+            position += weight * fetchBlendShapeOffset(texel);
+            blendedNormal += weight * fetchBlendShapeOffset(texel + 1);
+        }
+    }
+
     vec4 localPosition = vec4(0);
     vec3 localNormal = vec3(0);
     vec3 localTangent = vec3(0);
     if (useSkeletalAnimation)
     {
-        vec4 vertex = vec4(vertexPosition, 1.0);
+        vec4 vertex = vec4(position, 1.0);
 
         int i0 = int(boneIndices.x);
         int i1 = int(boneIndices.y);
@@ -36,10 +63,10 @@ void main()
         localPosition += boneMatrices[i2] * vertex * boneWeights.z;
         localPosition += boneMatrices[i3] * vertex * boneWeights.w;
 
-        localNormal += mat3(boneMatrices[i0]) * vertexNormal * boneWeights.x;
-        localNormal += mat3(boneMatrices[i1]) * vertexNormal * boneWeights.y;
-        localNormal += mat3(boneMatrices[i2]) * vertexNormal * boneWeights.z;
-        localNormal += mat3(boneMatrices[i3]) * vertexNormal * boneWeights.w;
+        localNormal += mat3(boneMatrices[i0]) * blendedNormal * boneWeights.x;
+        localNormal += mat3(boneMatrices[i1]) * blendedNormal * boneWeights.y;
+        localNormal += mat3(boneMatrices[i2]) * blendedNormal * boneWeights.z;
+        localNormal += mat3(boneMatrices[i3]) * blendedNormal * boneWeights.w;
 
         localTangent += mat3(boneMatrices[i0]) * vertexTangent.xyz * boneWeights.x;
         localTangent += mat3(boneMatrices[i1]) * vertexTangent.xyz * boneWeights.y;
@@ -48,8 +75,8 @@ void main()
     }
     else
     {
-        localPosition = vec4(vertexPosition, 1.0);
-        localNormal = vertexNormal;
+        localPosition = vec4(position, 1.0);
+        localNormal = blendedNormal;
         localTangent = vertexTangent.xyz;
     }
     gl_Position = worldViewProjection * localPosition;
@@ -57,4 +84,6 @@ void main()
     tangent = normalize(mat3(worldMatrix) * localTangent);
     binormal = normalize(vertexTangent.w * cross(tangent, normal));
     texCoord = vertexTexCoord;
+    secondTexCoord = vertexSecondTexCoord;
+    color = vertexColor;
 }
\ No newline at end of file
@@ -8,16 +8,37 @@ layout(location = 5) in vec4 boneIndices;
 uniform mat4 worldViewProjection;
 uniform bool useSkeletalAnimation;
 uniform mat4 boneMatrices[60];
+uniform sampler2D blendShapesTexture;
+uniform int blendShapesCount;
+uniform int blendShapesVertexCount;
+uniform float blendShapesWeights[32];
 
 out vec2 texCoord;
 
+vec3 fetchBlendShapeOffset(int index)
+{
+    int width = textureSize(blendShapesTexture, 0).x;
+    return texelFetch(blendShapesTexture, ivec2(index % width, index / width), 0).xyz;
+}
+
 void main()
 {
+    vec3 position = vertexPosition;
+    for (int i = 0; i < blendShapesCount; ++i)
+    {
+        float weight = blendShapesWeights[i];
+        if (weight != 0.0)
+        {
+            int texel = 2 * (i * blendShapesVertexCount + gl_VertexID);
+            position += weight * fetchBlendShapeOffset(texel);
+        }
+    }
+
     vec4 localPosition = vec4(0);
 
     if (useSkeletalAnimation)
     {
-        vec4 vertex = vec4(vertexPosition, 1.0);
+        vec4 vertex = vec4(position, 1.0);
 
         localPosition += boneMatrices[int(boneIndices.x)] * vertex * boneWeights.x;
         localPosition += boneMatrices[int(boneIndices.y)] * vertex * boneWeights.y;
@@ -26,7 +47,7 @@ void main()
     }
     else
     {
-        localPosition = vec4(vertexPosition, 1.0);
+        localPosition = vec4(position, 1.0);
     }
 
     gl_Position = worldViewProjection * localPosition;
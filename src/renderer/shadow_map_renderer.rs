@@ -47,6 +47,7 @@ use crate::{
         TextureCache,
         GeometryCache,
         RenderPassStatistics,
+        MAX_BLEND_SHAPES,
         error::RendererError,
     }
 };
@@ -57,6 +58,10 @@ struct SpotShadowMapShader {
     world_view_projection_matrix: UniformLocation,
     use_skeletal_animation: UniformLocation,
     diffuse_texture: UniformLocation,
+    blend_shapes_texture: UniformLocation,
+    blend_shapes_count: UniformLocation,
+    blend_shapes_vertex_count: UniformLocation,
+    blend_shapes_weights: UniformLocation,
 }
 
 impl SpotShadowMapShader {
@@ -69,6 +74,10 @@ impl SpotShadowMapShader {
             world_view_projection_matrix: program.uniform_location("worldViewProjection")?,
             use_skeletal_animation: program.uniform_location("useSkeletalAnimation")?,
             diffuse_texture: program.uniform_location("diffuseTexture")?,
+            blend_shapes_texture: program.uniform_location("blendShapesTexture")?,
+            blend_shapes_count: program.uniform_location("blendShapesCount")?,
+            blend_shapes_vertex_count: program.uniform_location("blendShapesVertexCount")?,
+            blend_shapes_weights: program.uniform_location("blendShapesWeights")?,
 
             program,
         })
@@ -163,10 +172,24 @@ impl SpotShadowMapRenderer {
                         white_dummy.clone()
                     };
 
+                    let data = surface.get_data();
+                    let data = data.lock().unwrap();
+
+                    let blend_shapes_texture = geom_map.blend_shapes(state, &data);
+                    // Blend shapes are not applied if their texture can't be created.
+                    let blend_shapes_count = if blend_shapes_texture.is_some() {
+                        data.blend_shapes().len()
+                            .min(mesh.blend_shape_weights().len())
+                            .min(MAX_BLEND_SHAPES)
+                    } else {
+                        0
+                    };
+                    let blend_shapes_texture = blend_shapes_texture.unwrap_or_else(|| white_dummy.clone());
+
                     statistics.add_draw_call(self.framebuffer.draw(
                         state,
                         viewport,
-                        geom_map.get(&surface.get_data().lock().unwrap()),
+                        geom_map.get(&data),
                         &mut self.shader.program,
                         DrawParameters {
                             cull_face: CullFace::Back,
@@ -195,7 +218,14 @@ impl SpotShadowMapRenderer {
                             (self.shader.diffuse_texture, UniformValue::Sampler {
                                 index: 0,
                                 texture: diffuse_texture,
-                            })
+                            }),
+                            (self.shader.blend_shapes_texture, UniformValue::Sampler {
+                                index: 1,
+                                texture: blend_shapes_texture,
+                            }),
+                            (self.shader.blend_shapes_count, UniformValue::Integer(blend_shapes_count as i32)),
+                            (self.shader.blend_shapes_vertex_count, UniformValue::Integer(data.get_vertices().len() as i32)),
+                            (self.shader.blend_shapes_weights, UniformValue::FloatArray(&mesh.blend_shape_weights()[..blend_shapes_count]))
                         ],
                     ));
                 }
@@ -214,6 +244,10 @@ struct PointShadowMapShader {
     use_skeletal_animation: UniformLocation,
     diffuse_texture: UniformLocation,
     light_position: UniformLocation,
+    blend_shapes_texture: UniformLocation,
+    blend_shapes_count: UniformLocation,
+    blend_shapes_vertex_count: UniformLocation,
+    blend_shapes_weights: UniformLocation,
 }
 
 impl PointShadowMapShader {
@@ -228,6 +262,10 @@ impl PointShadowMapShader {
             use_skeletal_animation: program.uniform_location("useSkeletalAnimation")?,
             diffuse_texture: program.uniform_location("diffuseTexture")?,
             light_position: program.uniform_location("lightPosition")?,
+            blend_shapes_texture: program.uniform_location("blendShapesTexture")?,
+            blend_shapes_count: program.uniform_location("blendShapesCount")?,
+            blend_shapes_vertex_count: program.uniform_location("blendShapesVertexCount")?,
+            blend_shapes_weights: program.uniform_location("blendShapesWeights")?,
             program,
         })
     }
@@ -387,10 +425,24 @@ impl PointShadowMapRenderer {
                             white_dummy.clone()
                         };
 
+                        let data = surface.get_data();
+                        let data = data.lock().unwrap();
+
+                        let blend_shapes_texture = geom_cache.blend_shapes(state, &data);
+                        // Blend shapes are not applied if their texture can't be created.
+                        let blend_shapes_count = if blend_shapes_texture.is_some() {
+                            data.blend_shapes().len()
+                                .min(mesh.blend_shape_weights().len())
+                                .min(MAX_BLEND_SHAPES)
+                        } else {
+                            0
+                        };
+                        let blend_shapes_texture = blend_shapes_texture.unwrap_or_else(|| white_dummy.clone());
+
                         statistics.add_draw_call(self.framebuffer.draw(
                             state,
                             viewport,
-                            geom_cache.get(&surface.get_data().lock().unwrap()),
+                            geom_cache.get(&data),
                             &mut self.shader.program,
                             DrawParameters {
                                 cull_face: CullFace::Back,
@@ -421,7 +473,14 @@ impl PointShadowMapRenderer {
                                 (self.shader.diffuse_texture, UniformValue::Sampler {
                                     index: 0,
                                     texture: diffuse_texture,
-                                })
+                                }),
+                                (self.shader.blend_shapes_texture, UniformValue::Sampler {
+                                    index: 1,
+                                    texture: blend_shapes_texture,
+                                }),
+                                (self.shader.blend_shapes_count, UniformValue::Integer(blend_shapes_count as i32)),
+                                (self.shader.blend_shapes_vertex_count, UniformValue::Integer(data.get_vertices().len() as i32)),
+                                (self.shader.blend_shapes_weights, UniformValue::FloatArray(&mesh.blend_shape_weights()[..blend_shapes_count]))
                             ],
                         ));
                     }
@@ -4,7 +4,9 @@ use crate::{
             vec2::Vec2,
             vec3::Vec3,
             vec4::Vec4,
+            mat4::Mat4,
         },
+        color::Color,
         pool::{
             Handle,
             ErasedHandle,
@@ -12,12 +14,20 @@ use crate::{
     },
     scene::node::Node,
     resource::texture::Texture,
+    utils::{
+        mesh_optimizer,
+        weld::{VertexWelder, WeldOptions},
+    },
 };
 use std::sync::{
     Mutex,
     Arc,
+    atomic::{AtomicUsize, Ordering},
 };
 
+/// Vertex of a surface. Fields are passed to shaders as attributes in order of declaration,
+/// starting from location 0: position, texture coordinates, normal, tangent, bone weights,
+/// bone indices, second texture coordinates (location 6) and color (location 7).
 #[derive(Copy, Clone, Debug)]
 #[repr(C)] // OpenGL expects this structure packed as in C
 pub struct Vertex {
@@ -27,13 +37,47 @@ pub struct Vertex {
     pub tangent: Vec4,
     pub bone_weights: [f32; 4],
     pub bone_indices: [u8; 4],
+    /// Second set of texture coordinates, for example for light maps or detail textures.
+    pub second_tex_coord: Vec2,
+    /// Color of vertex, it is multiplied with diffuse color of surface. White by default.
+    pub color: Color,
+}
+
+/// Blend shape (morph target) stores offsets of vertices of a surface for some deformed
+/// state of the surface, for example for a smile of a character. Final vertex position is
+/// `position + sum(weight[i] * position_offset[i])`, where weights are stored in a `Mesh`.
+#[derive(Clone, Debug, Default)]
+pub struct BlendShape {
+    pub name: String,
+    /// Offset of each vertex of surface, count of offsets must be equal to vertex count.
+    pub position_offsets: Vec<Vec3>,
+    /// Offset of normal of each vertex of surface, count of offsets must be equal to vertex count.
+    pub normal_offsets: Vec<Vec3>,
 }
 
 pub struct SurfaceSharedData {
     pub(in crate) vertices: Vec<Vertex>,
     pub(in crate) indices: Vec<u32>,
+    pub(in crate) blend_shapes: Vec<BlendShape>,
+    /// Lookup table of [insert_vertex](SurfaceSharedData::insert_vertex).
+    pub(in crate) welder: VertexWelder,
+    /// Increased on every change of vertices, indices or blend shapes made through methods,
+    /// so renderer knows when to rebuild its GPU buffers.
+    pub(in crate) revision: u32,
+    /// Different for every instance, renderer caches buffers by address of surface data
+    /// and this id tells new data apart from destroyed data at the same address.
+    pub(in crate) unique_id: usize,
 }
 
+fn next_unique_id() -> usize {
+    static NEXT_UNIQUE_ID: AtomicUsize = AtomicUsize::new(0);
+    NEXT_UNIQUE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Size of post-transform vertex cache meshes are optimized for, it is close to cache
+/// size of most GPUs.
+const VERTEX_CACHE_SIZE: usize = 16;
+
 impl Default for SurfaceSharedData {
     fn default() -> Self {
         Self::new()
@@ -45,34 +89,108 @@ impl SurfaceSharedData {
         Self {
             vertices: Vec::new(),
             indices: Vec::new(),
+            blend_shapes: Vec::new(),
+            welder: Default::default(),
+            revision: 0,
+            unique_id: next_unique_id(),
+        }
+    }
+
+    #[inline]
+    fn mark_modified(&mut self) {
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    /// Adds new blend shape. Offsets of blend shape must be defined for every vertex, otherwise
+    /// blend shape is not added and error is returned.
+    pub fn add_blend_shape(&mut self, blend_shape: BlendShape) -> Result<(), String> {
+        if blend_shape.position_offsets.len() != self.vertices.len() || blend_shape.normal_offsets.len() != self.vertices.len() {
+            return Err(format!("Blend shape {} has {} position and {} normal offsets, but surface has {} vertices",
+                               blend_shape.name,
+                               blend_shape.position_offsets.len(),
+                               blend_shape.normal_offsets.len(),
+                               self.vertices.len()));
         }
+        self.blend_shapes.push(blend_shape);
+        self.mark_modified();
+        Ok(())
+    }
+
+    #[inline]
+    pub fn blend_shapes(&self) -> &[BlendShape] {
+        &self.blend_shapes
+    }
+
+    /// Returns approximate size of surface data in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.vertices.len() * std::mem::size_of::<Vertex>()
+            + self.indices.len() * std::mem::size_of::<u32>()
+            + self.blend_shapes
+            .iter()
+            .map(|blend_shape| (blend_shape.position_offsets.len() + blend_shape.normal_offsets.len()) * std::mem::size_of::<Vec3>())
+            .sum::<usize>()
     }
 
     #[inline]
     pub fn add_vertex(&mut self, vertex: Vertex) {
         self.vertices.push(vertex);
+        self.mark_modified();
     }
 
     /// Inserts vertex or its index. Performs optimizing insertion with checking if such
-    /// vertex already exists.
+    /// vertex already exists, vertices are welded using [weld options](SurfaceSharedData::set_weld_options).
     /// Returns true if inserted vertex was unique.
     #[inline]
     pub fn insert_vertex(&mut self, vertex: Vertex) -> bool {
-        // Reverse search is much faster because it is most likely that we'll find identic
-        // vertex at the end of the array.
-        let mut is_unique = false;
-        self.indices.push(match self.vertices.iter().rposition(|v| {
-            v.position == vertex.position && v.normal == vertex.normal && v.tex_coord == vertex.tex_coord
-        }) {
-            Some(existing_index) => existing_index as u32, // Already have such vertex
-            None => { // No such vertex, add it
-                is_unique = true;
-                let index = self.vertices.len() as u32;
-                self.vertices.push(vertex);
-                index
-            }
-        });
-        is_unique
+        self.mark_modified();
+        self.welder.insert(&mut self.vertices, &mut self.indices, vertex)
+    }
+
+    /// Same as [insert_vertex](SurfaceSharedData::insert_vertex), but welds vertex only with
+    /// existing vertices whose indices pass given filter. It is useful when vertices carry
+    /// data that is stored outside of surface, for example skin weights.
+    #[inline]
+    pub fn insert_vertex_filtered<F: Fn(u32) -> bool>(&mut self, vertex: Vertex, filter: F) -> bool {
+        self.mark_modified();
+        self.welder.insert_filtered(&mut self.vertices, &mut self.indices, vertex, filter)
+    }
+
+    /// Sets tolerances of welding of [insert_vertex](SurfaceSharedData::insert_vertex),
+    /// exact match of vertices is required by default.
+    pub fn set_weld_options(&mut self, options: WeldOptions) {
+        self.welder.set_options(options);
+    }
+
+    #[inline]
+    pub fn weld_options(&self) -> WeldOptions {
+        self.welder.options()
+    }
+
+    /// Frees memory of lookup table of welding, it is rebuilt on next insertion of vertex.
+    /// Call it when surface data is built.
+    pub fn clear_weld_cache(&mut self) {
+        self.welder.clear();
+    }
+
+    /// Reorders triangles and vertices for faster rendering - for better use of vertex
+    /// cache and less overdraw. Returns new order of vertices, i-th vertex of optimized
+    /// data was `order[i]`-th vertex before optimization, it can be used to reorder data
+    /// that is stored per vertex elsewhere. Blend shapes are reordered too.
+    pub fn optimize(&mut self) -> Vec<u32> {
+        let clusters = mesh_optimizer::optimize_vertex_cache(&mut self.indices, self.vertices.len(), VERTEX_CACHE_SIZE);
+        let vertices = &self.vertices;
+        mesh_optimizer::optimize_overdraw(&mut self.indices, |i| vertices[i].position, &clusters);
+        let order = mesh_optimizer::optimize_vertex_fetch(&mut self.indices, self.vertices.len());
+
+        self.vertices = order.iter().map(|i| self.vertices[*i as usize]).collect();
+        for blend_shape in self.blend_shapes.iter_mut() {
+            blend_shape.position_offsets = order.iter().map(|i| blend_shape.position_offsets[*i as usize]).collect();
+            blend_shape.normal_offsets = order.iter().map(|i| blend_shape.normal_offsets[*i as usize]).collect();
+        }
+        self.welder.clear();
+        self.mark_modified();
+
+        order
     }
 
     #[inline]
@@ -82,6 +200,9 @@ impl SurfaceSharedData {
 
     #[inline]
     pub fn get_vertices_mut(&mut self) -> &mut [Vertex] {
+        // Vertices can be moved, so lookup table of welding is not valid anymore.
+        self.welder.clear();
+        self.mark_modified();
         &mut self.vertices
     }
 
@@ -90,7 +211,29 @@ impl SurfaceSharedData {
         self.indices.as_slice()
     }
 
+    /// Appends vertices and indices of other surface data transformed by given matrix.
+    /// Blend shapes of other surface data are ignored.
+    pub fn append(&mut self, other: &SurfaceSharedData, transform: Mat4) {
+        let base_index = self.vertices.len() as u32;
+        for vertex in other.vertices.iter() {
+            let normal = transform.transform_vector_normal(vertex.normal);
+            let tangent = transform.transform_vector_normal(Vec3::new(vertex.tangent.x, vertex.tangent.y, vertex.tangent.z));
+            self.vertices.push(Vertex {
+                position: transform.transform_vector(vertex.position),
+                normal: normal.normalized().unwrap_or(vertex.normal),
+                tangent: match tangent.normalized() {
+                    Some(tangent) => Vec4 { x: tangent.x, y: tangent.y, z: tangent.z, w: vertex.tangent.w },
+                    None => vertex.tangent,
+                },
+                ..*vertex
+            });
+        }
+        self.indices.extend(other.indices.iter().map(|index| base_index + index));
+        self.mark_modified();
+    }
+
     pub fn calculate_tangents(&mut self) {
+        self.mark_modified();
         let mut tan1 = vec![Vec3::ZERO; self.vertices.len()];
         let mut tan2 = vec![Vec3::ZERO; self.vertices.len()];
 
@@ -173,6 +316,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 1.0, y: 0.0, z: 0.0 },
@@ -181,6 +326,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 1.0, y: 1.0, z: 0.0 },
@@ -189,6 +336,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.0, y: 1.0, z: 0.0 },
@@ -197,6 +346,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             }
         ];
 
@@ -219,6 +370,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
@@ -227,6 +380,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
@@ -235,6 +390,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
@@ -243,6 +400,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             }
         ];
 
@@ -262,10 +421,13 @@ impl SurfaceSharedData {
             tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
             bone_weights: [0.0, 0.0, 0.0, 0.0],
             bone_indices: Default::default(),
+            second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+            color: Color::WHITE,
         });
     }
 
     pub fn calculate_normals(&mut self) {
+        self.mark_modified();
         for m in (0..self.indices.len()).step_by(3) {
             let ia = self.indices[m] as usize;
             let ib = self.indices[m + 1] as usize;
@@ -375,6 +537,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: -0.5, y: 0.5, z: 0.5 },
@@ -383,6 +547,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: 0.5, z: 0.5 },
@@ -391,6 +557,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: -0.5, z: 0.5 },
@@ -399,6 +567,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
 
             // Back
@@ -409,6 +579,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: -0.5, y: 0.5, z: -0.5 },
@@ -417,6 +589,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: 0.5, z: -0.5 },
@@ -425,6 +599,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: -0.5, z: -0.5 },
@@ -433,6 +609,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
 
             // Left
@@ -443,6 +621,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: -0.5, y: 0.5, z: -0.5 },
@@ -451,6 +631,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: -0.5, y: 0.5, z: 0.5 },
@@ -459,6 +641,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: -0.5, y: -0.5, z: 0.5 },
@@ -467,6 +651,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
 
             // Right
@@ -477,6 +663,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: 0.5, z: -0.5 },
@@ -485,6 +673,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: 0.5, z: 0.5 },
@@ -493,6 +683,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: -0.5, z: 0.5 },
@@ -501,6 +693,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
 
             // Top
@@ -511,6 +705,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: -0.5, y: 0.5, z: -0.5 },
@@ -519,6 +715,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: 0.5, z: -0.5 },
@@ -527,6 +725,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: 0.5, z: 0.5 },
@@ -535,6 +735,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
 
             // Bottom
@@ -545,6 +747,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: -0.5, y: -0.5, z: -0.5 },
@@ -553,6 +757,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: -0.5, z: -0.5 },
@@ -561,6 +767,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
             Vertex {
                 position: Vec3 { x: 0.5, y: -0.5, z: 0.5 },
@@ -569,6 +777,8 @@ impl SurfaceSharedData {
                 tangent: Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 },
                 bone_weights: [0.0, 0.0, 0.0, 0.0],
                 bone_indices: [0, 0, 0, 0],
+                second_tex_coord: Vec2 { x: 0.0, y: 0.0 },
+                color: Color::WHITE,
             },
         ];
 
@@ -661,6 +871,8 @@ pub struct Surface {
     data: Arc<Mutex<SurfaceSharedData>>,
     diffuse_texture: Option<Arc<Mutex<Texture>>>,
     normal_texture: Option<Arc<Mutex<Texture>>>,
+    /// Specular intensity is taken from red channel of texture.
+    specular_texture: Option<Arc<Mutex<Texture>>>,
     /// Temporal array for FBX conversion needs, it holds skinning data (weight + bone handle)
     /// and will be used to fill actual bone indices and weight in vertices that will be
     /// sent to GPU. The idea is very simple: GPU needs to know only indices of matrices of
@@ -683,6 +895,7 @@ impl Clone for Surface {
             data: Arc::clone(&self.data),
             diffuse_texture: self.diffuse_texture.clone(),
             normal_texture: self.normal_texture.clone(),
+            specular_texture: self.specular_texture.clone(),
             bones: self.bones.clone(),
             vertex_weights: Vec::new(),
         }
@@ -696,6 +909,7 @@ impl Surface {
             data,
             diffuse_texture: None,
             normal_texture: None,
+            specular_texture: None,
             bones: Vec::new(),
             vertex_weights: Vec::new(),
         }
@@ -716,6 +930,11 @@ impl Surface {
         self.normal_texture.clone()
     }
 
+    #[inline]
+    pub fn get_specular_texture(&self) -> Option<Arc<Mutex<Texture>>> {
+        self.specular_texture.clone()
+    }
+
     #[inline]
     pub fn set_diffuse_texture(&mut self, tex: Arc<Mutex<Texture>>) {
         self.diffuse_texture = Some(tex);
@@ -725,4 +944,9 @@ impl Surface {
     pub fn set_normal_texture(&mut self, tex: Arc<Mutex<Texture>>) {
         self.normal_texture = Some(tex);
     }
+
+    #[inline]
+    pub fn set_specular_texture(&mut self, tex: Arc<Mutex<Texture>>) {
+        self.specular_texture = Some(tex);
+    }
 }
\ No newline at end of file
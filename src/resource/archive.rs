@@ -0,0 +1,364 @@
+//! Packed resource archive.
+//!
+//! Archive is a single file that contains many files, it is used to ship game data without
+//! thousands of loose files. Layout of archive (all numbers are little-endian):
+//!
+//! ```text
+//! magic         - 8 bytes "RG3DPAK\0"
+//! version       - u32
+//! index offset  - u64
+//! file data     - data of every file one after another
+//! index         - u32 entry count, then for each entry:
+//!                     path length u32, path bytes (utf8, '/' separated),
+//!                     offset u64, stored size u64, size u64, compression u8
+//! ```
+//!
+//! Every file can be stored as is or compressed with zlib, packer keeps compressed data only
+//! if it is smaller than original. Index is read once when archive is opened, files are read
+//! on demand.
+
+use std::{
+    io::{self, Read, Write, Seek, SeekFrom, BufWriter},
+    fs::{self, File},
+    path::{Path, PathBuf},
+    collections::HashMap,
+    time::SystemTime,
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use crate::resource::vfs::{MountSource, normalize_path};
+
+const MAGIC: &[u8; 8] = b"RG3DPAK\0";
+const VERSION: u32 = 1;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Compression {
+    None,
+    Zlib,
+}
+
+impl Compression {
+    pub fn id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Zlib => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, String> {
+        match id {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Zlib),
+            _ => Err(format!("Invalid compression id {}", id))
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArchiveEntry {
+    offset: u64,
+    stored_size: u64,
+    size: u64,
+    compression: Compression,
+}
+
+impl ArchiveEntry {
+    /// Size of file after decompression.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Size of file inside archive.
+    #[inline]
+    pub fn stored_size(&self) -> u64 {
+        self.stored_size
+    }
+
+    #[inline]
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+}
+
+/// Converts path to a key of archive index - normalized path with '/' separators.
+fn make_key(path: &Path) -> String {
+    normalize_path(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn invalid_data<E: ToString>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+/// Opened archive, can be mounted to VFS.
+pub struct Archive {
+    path: PathBuf,
+    index: HashMap<String, ArchiveEntry>,
+}
+
+impl Archive {
+    /// Opens archive and reads its index.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = File::open(path.as_ref())?;
+        // Sizes from index are checked against size of file, so corrupted archive won't make
+        // us allocate huge buffers.
+        let file_size = file.metadata()?.len();
+
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if magic != *MAGIC {
+            return Err(invalid_data(format!("{:?} is not an archive", path.as_ref())));
+        }
+
+        let version = file.read_u32::<LittleEndian>()?;
+        if version != VERSION {
+            return Err(invalid_data(format!("Unsupported archive version {}", version)));
+        }
+
+        let index_offset = file.read_u64::<LittleEndian>()?;
+        file.seek(SeekFrom::Start(index_offset))?;
+
+        let count = file.read_u32::<LittleEndian>()?;
+        // Every entry takes at least 29 bytes (path length, offset, sizes and compression).
+        let max_count = file_size.saturating_sub(index_offset) / 29;
+        let mut index = HashMap::with_capacity(u64::from(count).min(max_count) as usize);
+        for _ in 0..count {
+            let path_len = u64::from(file.read_u32::<LittleEndian>()?);
+            let remaining = file_size.saturating_sub(file.seek(SeekFrom::Current(0))?);
+            if path_len > remaining {
+                return Err(invalid_data(format!("Invalid length {} of path in archive index", path_len)));
+            }
+            let mut path_bytes = vec![0; path_len as usize];
+            file.read_exact(&mut path_bytes)?;
+            let entry_path = String::from_utf8(path_bytes).map_err(invalid_data)?;
+            let entry = ArchiveEntry {
+                offset: file.read_u64::<LittleEndian>()?,
+                stored_size: file.read_u64::<LittleEndian>()?,
+                size: file.read_u64::<LittleEndian>()?,
+                compression: Compression::from_id(file.read_u8()?).map_err(invalid_data)?,
+            };
+            if entry.offset.checked_add(entry.stored_size).map_or(true, |end| end > file_size) {
+                return Err(invalid_data(format!("File {} is out of bounds of archive", entry_path)));
+            }
+            index.insert(entry_path, entry);
+        }
+
+        Ok(Self {
+            path: path.as_ref().to_owned(),
+            index,
+        })
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    pub fn entry<P: AsRef<Path>>(&self, path: P) -> Option<&ArchiveEntry> {
+        self.index.get(&make_key(path.as_ref()))
+    }
+
+    /// Returns iterator over paths of every file in archive.
+    pub fn files(&self) -> impl Iterator<Item=&str> {
+        self.index.keys().map(|key| key.as_str())
+    }
+
+    /// Reads and decompresses file from archive.
+    pub fn read_file<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<u8>> {
+        let entry = self.entry(path.as_ref())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{:?} is not in archive {:?}", path.as_ref(), self.path)))?;
+
+        // File is opened for every read, so archive can be read from many threads at once.
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut data = vec![0; entry.stored_size as usize];
+        file.read_exact(&mut data)?;
+
+        match entry.compression {
+            Compression::None => Ok(data),
+            Compression::Zlib => {
+                let data = inflate::inflate_bytes_zlib(&data).map_err(invalid_data)?;
+                if data.len() as u64 != entry.size {
+                    return Err(invalid_data(format!("Corrupted file {:?} in archive {:?}", path.as_ref(), self.path)));
+                }
+                Ok(data)
+            }
+        }
+    }
+}
+
+impl MountSource for Archive {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.read_file(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.entry(path).is_some()
+    }
+
+    /// Files in archive have modification time of archive itself.
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        if self.exists(path) {
+            fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// Writes archives. Files are written to disk as soon as they're added, index is written
+/// by [finish](ArchiveBuilder::finish).
+pub struct ArchiveBuilder {
+    writer: BufWriter<File>,
+    index: Vec<(String, ArchiveEntry)>,
+    offset: u64,
+    compression: Compression,
+}
+
+impl ArchiveBuilder {
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_u32::<LittleEndian>(VERSION)?;
+        // Placeholder for index offset, it will be written when archive is finished.
+        writer.write_u64::<LittleEndian>(0)?;
+        Ok(Self {
+            writer,
+            index: Vec::new(),
+            offset: (MAGIC.len() + 4 + 8) as u64,
+            compression: Compression::Zlib,
+        })
+    }
+
+    /// Sets compression for files that will be added after this call.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Adds file with given virtual path and content to archive.
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P, data: &[u8]) -> io::Result<()> {
+        let compressed = match self.compression {
+            Compression::None => None,
+            Compression::Zlib => {
+                let compressed = deflate::deflate_bytes_zlib(data);
+                // Compression is useless for some files (already compressed images, sounds).
+                if compressed.len() < data.len() {
+                    Some(compressed)
+                } else {
+                    None
+                }
+            }
+        };
+
+        let (stored, compression) = match compressed.as_ref() {
+            Some(compressed) => (compressed.as_slice(), Compression::Zlib),
+            None => (data, Compression::None),
+        };
+
+        self.writer.write_all(stored)?;
+        self.index.push((make_key(path.as_ref()), ArchiveEntry {
+            offset: self.offset,
+            stored_size: stored.len() as u64,
+            size: data.len() as u64,
+            compression,
+        }));
+        self.offset += stored.len() as u64;
+
+        Ok(())
+    }
+
+    /// Adds every file from given directory (recursively). Paths inside archive will be
+    /// relative to given directory.
+    pub fn add_directory<P: AsRef<Path>>(&mut self, directory: P) -> io::Result<usize> {
+        let mut count = 0;
+        let mut stack = vec![directory.as_ref().to_owned()];
+        while let Some(current) = stack.pop() {
+            let mut entries = fs::read_dir(&current)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<io::Result<Vec<_>>>()?;
+            // Sort to make archives reproducible.
+            entries.sort();
+            for path in entries {
+                if path.is_dir() {
+                    stack.push(path);
+                } else {
+                    let relative = path.strip_prefix(directory.as_ref()).map_err(invalid_data)?;
+                    self.add_file(relative, &fs::read(&path)?)?;
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Writes index and flushes archive to disk.
+    pub fn finish(mut self) -> io::Result<()> {
+        let index_offset = self.offset;
+        self.writer.write_u32::<LittleEndian>(self.index.len() as u32)?;
+        for (path, entry) in self.index.iter() {
+            self.writer.write_u32::<LittleEndian>(path.len() as u32)?;
+            self.writer.write_all(path.as_bytes())?;
+            self.writer.write_u64::<LittleEndian>(entry.offset)?;
+            self.writer.write_u64::<LittleEndian>(entry.stored_size)?;
+            self.writer.write_u64::<LittleEndian>(entry.size)?;
+            self.writer.write_u8(entry.compression.id())?;
+        }
+        self.writer.seek(SeekFrom::Start(MAGIC.len() as u64 + 4))?;
+        self.writer.write_u64::<LittleEndian>(index_offset)?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::resource::{
+        archive::{ArchiveBuilder, Archive, Compression},
+        vfs::Vfs,
+    };
+
+    #[test]
+    fn archive_round_trip() {
+        let path = std::env::temp_dir().join("rg3d_archive_round_trip.pak");
+        let text = "Some text that is repeated. ".repeat(100).into_bytes();
+        let binary = (0..=255u8).collect::<Vec<_>>();
+
+        let mut builder = ArchiveBuilder::new(&path).unwrap();
+        builder.add_file("textures/./text.txt", &text).unwrap();
+        builder.add_file("binary.bin", &binary).unwrap();
+        builder.finish().unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+        assert_eq!(archive.entry("textures/text.txt").unwrap().compression(), Compression::Zlib);
+        assert_eq!(archive.read_file("textures/text.txt").unwrap(), text);
+        assert_eq!(archive.read_file("binary.bin").unwrap(), binary);
+
+        let mut vfs = Vfs::new();
+        vfs.mount_archive("data", &path).unwrap();
+        assert!(vfs.exists("data/textures/../binary.bin"));
+        assert!(!vfs.exists("binary.bin"));
+        assert_eq!(vfs.read("./data/textures/text.txt").unwrap(), text);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupted_index() {
+        let path = std::env::temp_dir().join("rg3d_archive_corrupted_index.pak");
+        let mut data = b"RG3DPAK\0".to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        // Index is right after header.
+        data.extend_from_slice(&20u64.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        // Path length that is much larger than file.
+        data.extend_from_slice(&u32::max_value().to_le_bytes());
+        std::fs::write(&path, &data).unwrap();
+
+        assert!(Archive::open(&path).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,758 @@
+//! CPU decompressor of block compressed (BC1 - BC7) textures.
+//!
+//! It is used as a fallback on drivers that do not support some of compressed formats, so
+//! it is written to be correct rather than fast. Every format is decoded to RGBA8, except
+//! BC6H which is decoded to RGB16F (three half floats per pixel). BC4 is decoded to red
+//! channel, BC5 - to red and green channels.
+
+use crate::resource::texture::TextureKind;
+
+/// Reads bits of a block starting from least significant bit of first byte.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+        }
+    }
+
+    fn read(&mut self, count: usize) -> u32 {
+        let mut value = 0;
+        for i in 0..count {
+            let bit = (self.data[self.position >> 3] >> (self.position & 7)) & 1;
+            value |= u32::from(bit) << i;
+            self.position += 1;
+        }
+        value
+    }
+}
+
+type Rgba8Block = [[u8; 4]; 16];
+
+fn expand_565(color: u16) -> [u8; 3] {
+    let r = ((color >> 11) & 31) as u8;
+    let g = ((color >> 5) & 63) as u8;
+    let b = (color & 31) as u8;
+    [(r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2)]
+}
+
+/// Decodes color part of BC1, BC2 and BC3 blocks. Only BC1 can use three color mode
+/// with transparent black.
+fn decode_color_block(block: &[u8], allow_transparency: bool, out: &mut Rgba8Block) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let e0 = expand_565(c0);
+    let e1 = expand_565(c1);
+
+    let mut palette = [[0u8; 4]; 4];
+    palette[0] = [e0[0], e0[1], e0[2], 255];
+    palette[1] = [e1[0], e1[1], e1[2], 255];
+    if c0 > c1 || !allow_transparency {
+        for i in 0..3 {
+            palette[2][i] = ((2 * u32::from(e0[i]) + u32::from(e1[i])) / 3) as u8;
+            palette[3][i] = ((u32::from(e0[i]) + 2 * u32::from(e1[i])) / 3) as u8;
+        }
+        palette[2][3] = 255;
+        palette[3][3] = 255;
+    } else {
+        for i in 0..3 {
+            palette[2][i] = ((u32::from(e0[i]) + u32::from(e1[i])) / 2) as u8;
+        }
+        palette[2][3] = 255;
+        palette[3] = [0, 0, 0, 0];
+    }
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (i, pixel) in out.iter_mut().enumerate() {
+        *pixel = palette[((indices >> (2 * i)) & 3) as usize];
+    }
+}
+
+/// Decodes single channel block which is used for alpha of BC3 and for channels of BC4 and BC5.
+fn decode_channel_block(block: &[u8]) -> [u8; 16] {
+    let a0 = u32::from(block[0]);
+    let a1 = u32::from(block[1]);
+
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * a0 + i as u32 * a1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * a0 + i as u32 * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    let mut indices = 0u64;
+    for (i, byte) in block[2..8].iter().enumerate() {
+        indices |= u64::from(*byte) << (8 * i);
+    }
+
+    let mut result = [0; 16];
+    for (i, value) in result.iter_mut().enumerate() {
+        *value = palette[((indices >> (3 * i)) & 7) as usize];
+    }
+    result
+}
+
+fn decode_bc1_block(block: &[u8], out: &mut Rgba8Block) {
+    decode_color_block(block, true, out);
+}
+
+fn decode_bc2_block(block: &[u8], out: &mut Rgba8Block) {
+    decode_color_block(&block[8..16], false, out);
+    for (i, pixel) in out.iter_mut().enumerate() {
+        let alpha = (block[i / 2] >> (4 * (i % 2))) & 15;
+        pixel[3] = alpha * 17;
+    }
+}
+
+fn decode_bc3_block(block: &[u8], out: &mut Rgba8Block) {
+    decode_color_block(&block[8..16], false, out);
+    let alpha = decode_channel_block(&block[0..8]);
+    for (pixel, alpha) in out.iter_mut().zip(alpha.iter()) {
+        pixel[3] = *alpha;
+    }
+}
+
+fn decode_bc4_block(block: &[u8], out: &mut Rgba8Block) {
+    let red = decode_channel_block(&block[0..8]);
+    for (pixel, red) in out.iter_mut().zip(red.iter()) {
+        *pixel = [*red, 0, 0, 255];
+    }
+}
+
+fn decode_bc5_block(block: &[u8], out: &mut Rgba8Block) {
+    let red = decode_channel_block(&block[0..8]);
+    let green = decode_channel_block(&block[8..16]);
+    for (i, pixel) in out.iter_mut().enumerate() {
+        *pixel = [red[i], green[i], 0, 255];
+    }
+}
+
+/// Subset of every pixel for partitions with two subsets, bit i is subset of pixel i.
+/// First 32 partitions are shared with BC6H.
+const PARTITIONS_2: [u16; 64] = [
+    0xCCCC, 0x8888, 0xEEEE, 0xECC8, 0xC880, 0xFEEC, 0xFEC8, 0xEC80,
+    0xC800, 0xFFEC, 0xFE80, 0xE800, 0xFFE8, 0xFF00, 0xFFF0, 0xF000,
+    0xF710, 0x008E, 0x7100, 0x08CE, 0x008C, 0x7310, 0x3100, 0x8CCE,
+    0x088C, 0x3110, 0x6666, 0x366C, 0x17E8, 0x0FF0, 0x718E, 0x399C,
+    0xAAAA, 0xF0F0, 0x5A5A, 0x33CC, 0x3C3C, 0x55AA, 0x9696, 0xA55A,
+    0x73CE, 0x13C8, 0x324C, 0x3BDC, 0x6996, 0xC33C, 0x9966, 0x0660,
+    0x0272, 0x04E4, 0x4E40, 0x2720, 0xC936, 0x936C, 0x39C6, 0x639C,
+    0x9336, 0x9CC6, 0x817E, 0xE718, 0xCCF0, 0x0FCC, 0x7744, 0xEE22,
+];
+
+/// Subset of every pixel for partitions with three subsets, two bits per pixel.
+const PARTITIONS_3: [u32; 64] = [
+    0xAA685050, 0x6A5A5040, 0x5A5A4200, 0x5450A0A8, 0xA5A50000, 0xA0A05050, 0x5555A0A0, 0x5A5A5050,
+    0xAA550000, 0xAA555500, 0xAAAA5500, 0x90909090, 0x94949494, 0xA4A4A4A4, 0xA9A59450, 0x2A0A4250,
+    0xA5945040, 0x0A425054, 0xA5A5A500, 0x55A0A0A0, 0xA8A85454, 0x6A6A4040, 0xA4A45000, 0x1A1A0500,
+    0x0050A4A4, 0xAAA59090, 0x14696914, 0x69691400, 0xA08585A0, 0xAA821414, 0x50A4A450, 0x6A5A0200,
+    0xA9A58000, 0x5090A0A8, 0xA8A09050, 0x24242424, 0x00AA5500, 0x24924924, 0x24499224, 0x50A50A50,
+    0x500AA550, 0xAAAA4444, 0x66660000, 0xA5A0A5A0, 0x50A050A0, 0x69286928, 0x44AAAA44, 0x66666600,
+    0xAA444444, 0x54A854A8, 0x95809580, 0x96969600, 0xA85454A8, 0x80959580, 0xAA141414, 0x96960000,
+    0xAAAA1414, 0xA05050A0, 0xA0A5A5A0, 0x96000000, 0x40804080, 0xA9A8A9A8, 0xAAAAAA44, 0x2A4A5254,
+];
+
+/// Anchor pixel of second subset for partitions with two subsets.
+const ANCHORS_2: [u8; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15,
+    15, 15, 15, 15, 15, 15, 15, 15,
+    15, 2, 8, 2, 2, 8, 8, 15,
+    2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15,
+    2, 8, 2, 2, 2, 15, 15, 6,
+    6, 2, 6, 8, 15, 15, 2, 2,
+    15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+/// Anchor pixel of second subset for partitions with three subsets.
+const ANCHORS_3_SECOND: [u8; 64] = [
+    3, 3, 15, 15, 8, 3, 15, 15,
+    8, 8, 6, 6, 6, 5, 3, 3,
+    3, 3, 8, 15, 3, 3, 6, 10,
+    5, 8, 8, 6, 8, 5, 15, 15,
+    8, 15, 3, 5, 6, 10, 8, 15,
+    15, 3, 15, 5, 15, 15, 15, 15,
+    3, 15, 5, 5, 5, 8, 5, 10,
+    5, 10, 8, 13, 15, 12, 3, 3,
+];
+
+/// Anchor pixel of third subset for partitions with three subsets.
+const ANCHORS_3_THIRD: [u8; 64] = [
+    15, 8, 8, 3, 15, 15, 3, 8,
+    15, 15, 15, 15, 15, 15, 15, 8,
+    15, 8, 15, 3, 15, 8, 15, 8,
+    3, 15, 6, 10, 15, 15, 10, 8,
+    15, 3, 15, 10, 10, 8, 9, 10,
+    6, 15, 8, 15, 3, 6, 6, 8,
+    15, 3, 15, 15, 15, 15, 15, 15,
+    15, 15, 15, 15, 3, 15, 15, 8,
+];
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn weight(index_bits: usize, index: u32) -> u32 {
+    match index_bits {
+        2 => WEIGHTS_2[index as usize],
+        3 => WEIGHTS_3[index as usize],
+        _ => WEIGHTS_4[index as usize],
+    }
+}
+
+fn subset_of(subsets: usize, partition: usize, pixel: usize) -> usize {
+    match subsets {
+        2 => ((PARTITIONS_2[partition] >> pixel) & 1) as usize,
+        3 => ((PARTITIONS_3[partition] >> (2 * pixel)) & 3) as usize,
+        _ => 0,
+    }
+}
+
+fn is_anchor(subsets: usize, partition: usize, pixel: usize) -> bool {
+    pixel == 0 || match subsets {
+        2 => pixel == ANCHORS_2[partition] as usize,
+        3 => pixel == ANCHORS_3_SECOND[partition] as usize || pixel == ANCHORS_3_THIRD[partition] as usize,
+        _ => false,
+    }
+}
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: usize,
+    rotation_bits: usize,
+    index_selection_bits: usize,
+    color_bits: usize,
+    alpha_bits: usize,
+    /// Unique p-bit per endpoint.
+    endpoint_pbits: bool,
+    /// P-bit shared by both endpoints of a subset.
+    shared_pbits: bool,
+    index_bits: usize,
+    secondary_index_bits: usize,
+}
+
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, index_selection_bits: 0, color_bits: 4, alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 6, alpha_bits: 0, endpoint_pbits: false, shared_pbits: true, index_bits: 3, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 0, endpoint_pbits: false, shared_pbits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 2, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 1, color_bits: 5, alpha_bits: 6, endpoint_pbits: false, shared_pbits: false, index_bits: 2, secondary_index_bits: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, index_selection_bits: 0, color_bits: 7, alpha_bits: 8, endpoint_pbits: false, shared_pbits: false, index_bits: 2, secondary_index_bits: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, index_selection_bits: 0, color_bits: 7, alpha_bits: 7, endpoint_pbits: true, shared_pbits: false, index_bits: 4, secondary_index_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, index_selection_bits: 0, color_bits: 5, alpha_bits: 5, endpoint_pbits: true, shared_pbits: false, index_bits: 2, secondary_index_bits: 0 },
+];
+
+fn expand_bits(value: u32, precision: usize) -> u32 {
+    let value = value << (8 - precision);
+    value | (value >> precision)
+}
+
+fn interpolate_u8(e0: u32, e1: u32, weight: u32) -> u8 {
+    (((64 - weight) * e0 + weight * e1 + 32) >> 6) as u8
+}
+
+fn decode_bc7_block(block: &[u8], out: &mut Rgba8Block) {
+    let mut reader = BitReader::new(block);
+
+    let mut mode_index = 0;
+    while mode_index < 8 && reader.read(1) == 0 {
+        mode_index += 1;
+    }
+    if mode_index == 8 {
+        // Reserved mode.
+        *out = [[0; 4]; 16];
+        return;
+    }
+    let mode = &BC7_MODES[mode_index];
+
+    let partition = reader.read(mode.partition_bits) as usize;
+    let rotation = reader.read(mode.rotation_bits);
+    let index_selection = reader.read(mode.index_selection_bits);
+
+    let endpoint_count = 2 * mode.subsets;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..3 {
+        for endpoint in endpoints.iter_mut().take(endpoint_count) {
+            endpoint[channel] = reader.read(mode.color_bits);
+        }
+    }
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        endpoint[3] = reader.read(mode.alpha_bits);
+    }
+
+    let mut color_precision = mode.color_bits;
+    let mut alpha_precision = mode.alpha_bits;
+    if mode.endpoint_pbits || mode.shared_pbits {
+        let mut pbits = [0; 6];
+        if mode.endpoint_pbits {
+            for pbit in pbits.iter_mut().take(endpoint_count) {
+                *pbit = reader.read(1);
+            }
+        } else {
+            for subset in 0..mode.subsets {
+                let pbit = reader.read(1);
+                pbits[2 * subset] = pbit;
+                pbits[2 * subset + 1] = pbit;
+            }
+        }
+        for (endpoint, pbit) in endpoints.iter_mut().zip(pbits.iter()).take(endpoint_count) {
+            for value in endpoint.iter_mut() {
+                *value = (*value << 1) | *pbit;
+            }
+        }
+        color_precision += 1;
+        if alpha_precision > 0 {
+            alpha_precision += 1;
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        for value in endpoint.iter_mut().take(3) {
+            *value = expand_bits(*value, color_precision);
+        }
+        endpoint[3] = if alpha_precision > 0 {
+            expand_bits(endpoint[3], alpha_precision)
+        } else {
+            255
+        };
+    }
+
+    let mut indices = [0u32; 16];
+    for (pixel, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(mode.subsets, partition, pixel);
+        *index = reader.read(mode.index_bits - anchor as usize);
+    }
+    let mut secondary_indices = [0u32; 16];
+    if mode.secondary_index_bits > 0 {
+        for (pixel, index) in secondary_indices.iter_mut().enumerate() {
+            *index = reader.read(mode.secondary_index_bits - (pixel == 0) as usize);
+        }
+    }
+
+    for (pixel, color) in out.iter_mut().enumerate() {
+        let subset = subset_of(mode.subsets, partition, pixel);
+        let e0 = endpoints[2 * subset];
+        let e1 = endpoints[2 * subset + 1];
+
+        let (color_weight, alpha_weight) = if mode.secondary_index_bits == 0 {
+            let w = weight(mode.index_bits, indices[pixel]);
+            (w, w)
+        } else if index_selection == 0 {
+            (weight(mode.index_bits, indices[pixel]), weight(mode.secondary_index_bits, secondary_indices[pixel]))
+        } else {
+            (weight(mode.secondary_index_bits, secondary_indices[pixel]), weight(mode.index_bits, indices[pixel]))
+        };
+
+        for channel in 0..3 {
+            color[channel] = interpolate_u8(e0[channel], e1[channel], color_weight);
+        }
+        color[3] = interpolate_u8(e0[3], e1[3], alpha_weight);
+
+        match rotation {
+            1 => color.swap(0, 3),
+            2 => color.swap(1, 3),
+            3 => color.swap(2, 3),
+            _ => (),
+        }
+    }
+}
+
+/// Fields of BC6H block.
+#[derive(Copy, Clone)]
+enum Field {
+    RW,
+    RX,
+    RY,
+    RZ,
+    GW,
+    GX,
+    GY,
+    GZ,
+    BW,
+    BX,
+    BY,
+    BZ,
+    /// Partition.
+    D,
+}
+
+/// Describes `count` bits of a field starting from `first_bit`, stored in block in
+/// ascending order.
+type Bits = (Field, u32, usize);
+
+struct Bc6hMode {
+    regions: usize,
+    transformed: bool,
+    endpoint_bits: u32,
+    delta_bits: [u32; 3],
+    layout: &'static [Bits],
+}
+
+use self::Field::*;
+
+const BC6H_MODE_1: &[Bits] = &[
+    (GY, 4, 1), (BY, 4, 1), (BZ, 4, 1), (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5), (GZ, 4, 1),
+    (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5),
+    (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5)
+];
+
+const BC6H_MODE_2: &[Bits] = &[
+    (GY, 5, 1), (GZ, 4, 1), (GZ, 5, 1), (RW, 0, 7), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 7),
+    (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 7), (BZ, 3, 1), (BZ, 5, 1), (BZ, 4, 1), (RX, 0, 6),
+    (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6), (D, 0, 5)
+];
+
+const BC6H_MODE_3: &[Bits] = &[
+    (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5), (RW, 10, 1), (GY, 0, 4), (GX, 0, 4), (GW, 10, 1),
+    (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1),
+    (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5)
+];
+
+const BC6H_MODE_4: &[Bits] = &[
+    (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5),
+    (GW, 10, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 4), (BZ, 0, 1),
+    (BZ, 2, 1), (RZ, 0, 4), (GY, 4, 1), (BZ, 3, 1), (D, 0, 5)
+];
+
+const BC6H_MODE_5: &[Bits] = &[
+    (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (BY, 4, 1), (GY, 0, 4), (GX, 0, 4),
+    (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BW, 10, 1), (BY, 0, 4), (RY, 0, 4), (BZ, 1, 1),
+    (BZ, 2, 1), (RZ, 0, 4), (BZ, 4, 1), (BZ, 3, 1), (D, 0, 5)
+];
+
+const BC6H_MODE_6: &[Bits] = &[
+    (RW, 0, 9), (BY, 4, 1), (GW, 0, 9), (GY, 4, 1), (BW, 0, 9), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1),
+    (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5),
+    (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5)
+];
+
+const BC6H_MODE_7: &[Bits] = &[
+    (RW, 0, 8), (GZ, 4, 1), (BY, 4, 1), (GW, 0, 8), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 8), (BZ, 3, 1),
+    (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+    (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6), (D, 0, 5)
+];
+
+const BC6H_MODE_8: &[Bits] = &[
+    (RW, 0, 8), (BZ, 0, 1), (BY, 4, 1), (GW, 0, 8), (GY, 5, 1), (GY, 4, 1), (BW, 0, 8), (GZ, 5, 1),
+    (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+    (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5)
+];
+
+const BC6H_MODE_9: &[Bits] = &[
+    (RW, 0, 8), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 8), (BY, 5, 1), (GY, 4, 1), (BW, 0, 8), (BZ, 5, 1),
+    (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 6),
+    (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5)
+];
+
+const BC6H_MODE_10: &[Bits] = &[
+    (RW, 0, 6), (GZ, 4, 1), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 6), (GY, 5, 1), (BY, 5, 1),
+    (BZ, 2, 1), (GY, 4, 1), (BW, 0, 6), (GZ, 5, 1), (BZ, 3, 1), (BZ, 5, 1), (BZ, 4, 1), (RX, 0, 6),
+    (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6), (D, 0, 5)
+];
+
+const BC6H_MODE_11: &[Bits] = &[
+    (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 10), (GX, 0, 10), (BX, 0, 10)
+];
+
+const BC6H_MODE_12: &[Bits] = &[
+    (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 9), (RW, 10, 1), (GX, 0, 9), (GW, 10, 1), (BX, 0, 9),
+    (BW, 10, 1)
+];
+
+// High bits of endpoints in last two modes are stored in reversed order.
+const BC6H_MODE_13: &[Bits] = &[
+    (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 8), (RW, 11, 1), (RW, 10, 1), (GX, 0, 8), (GW, 11, 1),
+    (GW, 10, 1), (BX, 0, 8), (BW, 11, 1), (BW, 10, 1)
+];
+
+const BC6H_MODE_14: &[Bits] = &[
+    (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 15, 1), (RW, 14, 1), (RW, 13, 1), (RW, 12, 1),
+    (RW, 11, 1), (RW, 10, 1), (GX, 0, 4), (GW, 15, 1), (GW, 14, 1), (GW, 13, 1), (GW, 12, 1), (GW, 11, 1),
+    (GW, 10, 1), (BX, 0, 4), (BW, 15, 1), (BW, 14, 1), (BW, 13, 1), (BW, 12, 1), (BW, 11, 1), (BW, 10, 1)
+];
+
+fn bc6h_mode(mode_bits: u32) -> Option<Bc6hMode> {
+    let (regions, transformed, endpoint_bits, delta_bits, layout) = match mode_bits {
+        0x00 => (2, true, 10, [5, 5, 5], BC6H_MODE_1),
+        0x01 => (2, true, 7, [6, 6, 6], BC6H_MODE_2),
+        0x02 => (2, true, 11, [5, 4, 4], BC6H_MODE_3),
+        0x06 => (2, true, 11, [4, 5, 4], BC6H_MODE_4),
+        0x0A => (2, true, 11, [4, 4, 5], BC6H_MODE_5),
+        0x0E => (2, true, 9, [5, 5, 5], BC6H_MODE_6),
+        0x12 => (2, true, 8, [6, 5, 5], BC6H_MODE_7),
+        0x16 => (2, true, 8, [5, 6, 5], BC6H_MODE_8),
+        0x1A => (2, true, 8, [5, 5, 6], BC6H_MODE_9),
+        0x1E => (2, false, 6, [6, 6, 6], BC6H_MODE_10),
+        0x03 => (1, false, 10, [10, 10, 10], BC6H_MODE_11),
+        0x07 => (1, true, 11, [9, 9, 9], BC6H_MODE_12),
+        0x0B => (1, true, 12, [8, 8, 8], BC6H_MODE_13),
+        0x0F => (1, true, 16, [4, 4, 4], BC6H_MODE_14),
+        _ => return None,
+    };
+    Some(Bc6hMode {
+        regions,
+        transformed,
+        endpoint_bits,
+        delta_bits,
+        layout,
+    })
+}
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    (value << shift) >> shift
+}
+
+fn bc6h_unquantize(value: i32, bits: u32, signed: bool) -> i32 {
+    if signed {
+        if bits >= 16 {
+            return value;
+        }
+        let (negative, magnitude) = if value < 0 { (true, -value) } else { (false, value) };
+        let unquantized = if magnitude == 0 {
+            0
+        } else if magnitude >= (1 << (bits - 1)) - 1 {
+            0x7FFF
+        } else {
+            ((magnitude << 15) + 0x4000) >> (bits - 1)
+        };
+        if negative { -unquantized } else { unquantized }
+    } else if bits >= 15 {
+        value
+    } else if value == 0 {
+        0
+    } else if value == (1 << bits) - 1 {
+        0xFFFF
+    } else {
+        ((value << 16) + 0x8000) >> bits
+    }
+}
+
+/// Converts interpolated value to bits of half float.
+fn bc6h_finish_unquantize(value: i32, signed: bool) -> u16 {
+    if signed {
+        if value < 0 {
+            ((((-value) * 31) >> 5) as u16) | 0x8000
+        } else {
+            ((value * 31) >> 5) as u16
+        }
+    } else {
+        ((value * 31) >> 6) as u16
+    }
+}
+
+type Rgb16fBlock = [[u16; 3]; 16];
+
+fn decode_bc6h_block(block: &[u8], signed: bool, out: &mut Rgb16fBlock) {
+    let mut reader = BitReader::new(block);
+
+    let mut mode_bits = reader.read(2);
+    if mode_bits > 1 {
+        mode_bits |= reader.read(3) << 2;
+    }
+    let mode = match bc6h_mode(mode_bits) {
+        Some(mode) => mode,
+        None => {
+            // Reserved mode.
+            *out = [[0; 3]; 16];
+            return;
+        }
+    };
+
+    let mut fields = [0i32; 13];
+    for (field, first_bit, count) in mode.layout.iter() {
+        fields[*field as usize] |= (reader.read(*count) << *first_bit) as i32;
+    }
+
+    // Endpoints in order w, x, y, z for every channel.
+    let channels = [[RW, RX, RY, RZ], [GW, GX, GY, GZ], [BW, BX, BY, BZ]];
+    let endpoint_count = 2 * mode.regions;
+    let mut endpoints = [[0i32; 3]; 4];
+    for (channel, channel_fields) in channels.iter().enumerate() {
+        let w = fields[channel_fields[0] as usize];
+        let w = if signed { sign_extend(w, mode.endpoint_bits) } else { w };
+        endpoints[0][channel] = w;
+        for (endpoint, field) in channel_fields.iter().enumerate().take(endpoint_count).skip(1) {
+            let value = fields[*field as usize];
+            endpoints[endpoint][channel] = if mode.transformed {
+                let delta = sign_extend(value, mode.delta_bits[channel]);
+                let value = (w + delta) & ((1 << mode.endpoint_bits) - 1);
+                if signed { sign_extend(value, mode.endpoint_bits) } else { value }
+            } else if signed {
+                sign_extend(value, mode.endpoint_bits)
+            } else {
+                value
+            };
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(endpoint_count) {
+        for value in endpoint.iter_mut() {
+            *value = bc6h_unquantize(*value, mode.endpoint_bits, signed);
+        }
+    }
+
+    let partition = fields[D as usize] as usize;
+    let index_bits = if mode.regions == 2 { 3 } else { 4 };
+    for (pixel, color) in out.iter_mut().enumerate() {
+        let region = subset_of(mode.regions, partition, pixel);
+        let anchor = is_anchor(mode.regions, partition, pixel);
+        let index = reader.read(index_bits - anchor as usize);
+        let w = weight(index_bits, index) as i32;
+        let e0 = endpoints[2 * region];
+        let e1 = endpoints[2 * region + 1];
+        for channel in 0..3 {
+            let value = (e0[channel] * (64 - w) + e1[channel] * w + 32) >> 6;
+            color[channel] = bc6h_finish_unquantize(value, signed);
+        }
+    }
+}
+
+/// Returns size in bytes of a single mip level of texture of given kind.
+pub fn level_size(kind: TextureKind, width: u32, height: u32) -> usize {
+    let width = width.max(1) as usize;
+    let height = height.max(1) as usize;
+    match kind.block_size() {
+        Some(block_size) => ((width + 3) / 4) * ((height + 3) / 4) * block_size,
+        None => width * height * kind.bytes_per_pixel(),
+    }
+}
+
+/// Same as [level_size](level_size), but returns None if size does not fit in `usize`. Use
+/// it for sizes that are taken from untrusted data, such as headers of files.
+pub fn checked_level_size(kind: TextureKind, width: u32, height: u32) -> Option<usize> {
+    let width = width.max(1) as usize;
+    let height = height.max(1) as usize;
+    match kind.block_size() {
+        Some(block_size) => ((width + 3) / 4).checked_mul((height + 3) / 4)?.checked_mul(block_size),
+        None => width.checked_mul(height)?.checked_mul(kind.bytes_per_pixel()),
+    }
+}
+
+/// Decompresses single mip level. Returns None if kind is not compressed or there is
+/// not enough data.
+pub fn decompress_level(kind: TextureKind, width: u32, height: u32, data: &[u8]) -> Option<Vec<u8>> {
+    let block_size = kind.block_size()?;
+    if data.len() < level_size(kind, width, height) {
+        return None;
+    }
+
+    let width = width.max(1) as usize;
+    let height = height.max(1) as usize;
+    let blocks_x = (width + 3) / 4;
+    let blocks_y = (height + 3) / 4;
+
+    match kind {
+        TextureKind::BC6H | TextureKind::BC6HSigned => {
+            let signed = kind == TextureKind::BC6HSigned;
+            let mut result = vec![0u8; width * height * 6];
+            let mut pixels = [[0u16; 3]; 16];
+            for by in 0..blocks_y {
+                for bx in 0..blocks_x {
+                    let offset = (by * blocks_x + bx) * block_size;
+                    decode_bc6h_block(&data[offset..offset + block_size], signed, &mut pixels);
+                    for (i, pixel) in pixels.iter().enumerate() {
+                        let x = bx * 4 + i % 4;
+                        let y = by * 4 + i / 4;
+                        if x < width && y < height {
+                            let dest = (y * width + x) * 6;
+                            for (channel, value) in pixel.iter().enumerate() {
+                                result[dest + 2 * channel..dest + 2 * channel + 2].copy_from_slice(&value.to_ne_bytes());
+                            }
+                        }
+                    }
+                }
+            }
+            Some(result)
+        }
+        _ => {
+            let decode: fn(&[u8], &mut Rgba8Block) = match kind {
+                TextureKind::BC1 => decode_bc1_block,
+                TextureKind::BC2 => decode_bc2_block,
+                TextureKind::BC3 => decode_bc3_block,
+                TextureKind::BC4 => decode_bc4_block,
+                TextureKind::BC5 => decode_bc5_block,
+                _ => decode_bc7_block,
+            };
+            let mut result = vec![0u8; width * height * 4];
+            let mut pixels = [[0u8; 4]; 16];
+            for by in 0..blocks_y {
+                for bx in 0..blocks_x {
+                    let offset = (by * blocks_x + bx) * block_size;
+                    decode(&data[offset..offset + block_size], &mut pixels);
+                    for (i, pixel) in pixels.iter().enumerate() {
+                        let x = bx * 4 + i % 4;
+                        let y = by * 4 + i / 4;
+                        if x < width && y < height {
+                            let dest = (y * width + x) * 4;
+                            result[dest..dest + 4].copy_from_slice(pixel);
+                        }
+                    }
+                }
+            }
+            Some(result)
+        }
+    }
+}
+
+/// Decompresses whole mip chain, levels are stored one after another just like in source data.
+pub fn decompress_mip_chain(kind: TextureKind, width: u32, height: u32, mip_count: u32, data: &[u8]) -> Option<Vec<u8>> {
+    let mut result = Vec::new();
+    let mut offset = 0;
+    for level in 0..mip_count {
+        let level_width = (width >> level).max(1);
+        let level_height = (height >> level).max(1);
+        let size = level_size(kind, level_width, level_height);
+        let level_data = data.get(offset..offset + size)?;
+        result.extend(decompress_level(kind, level_width, level_height, level_data)?);
+        offset += size;
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::resource::{
+        block_compression::decompress_level,
+        texture::TextureKind,
+    };
+
+    #[test]
+    fn decode_bc1_and_bc7_blocks() {
+        // Pure red and pure blue endpoints, first row uses every index.
+        let bc1 = [0x00, 0xF8, 0x1F, 0x00, 0b1110_0100, 0, 0, 0];
+        let pixels = decompress_level(TextureKind::BC1, 4, 4, &bc1).unwrap();
+        assert_eq!(&pixels[0..4], &[255, 0, 0, 255]);
+        assert_eq!(&pixels[4..8], &[0, 0, 255, 255]);
+        assert_eq!(&pixels[8..12], &[170, 0, 85, 255]);
+        assert_eq!(&pixels[12..16], &[85, 0, 170, 255]);
+        assert_eq!(&pixels[16..20], &[255, 0, 0, 255]);
+
+        // Mode 6 block where every endpoint and p-bit is one - opaque white.
+        let mut bc7 = [0u8; 16];
+        bc7[0] = 0xC0;
+        for byte in bc7[1..8].iter_mut() {
+            *byte = 0xFF;
+        }
+        bc7[8] = 0x01;
+        let pixels = decompress_level(TextureKind::BC7, 4, 4, &bc7).unwrap();
+        assert!(pixels.iter().all(|c| *c == 255));
+
+        // Size that is not multiple of block size.
+        let pixels = decompress_level(TextureKind::BC1, 2, 3, &bc1).unwrap();
+        assert_eq!(pixels.len(), 2 * 3 * 4);
+    }
+}
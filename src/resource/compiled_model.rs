@@ -0,0 +1,993 @@
+//! Compiled model format.
+//!
+//! Import of a model from FBX means parsing of the file, triangulation of polygons and
+//! calculation of tangents, which is slow for big models. Compiled model stores result of
+//! import - graph, vertex and index buffers, skinning data and animations - in a compact
+//! binary layout, which is read almost as fast as disk allows. Compiled model is produced
+//! by [compile](compile) and is loaded by resource manager instead of source file when it
+//! is newer than the source, see [cache_path](cache_path).
+//!
+//! Layout is little-endian: header (magic, version and [import settings](ImportSettings)), table
+//! of surface data, nodes in depth-first order with index of parent, animations. Surface data and nodes are referenced
+//! by index in their tables. Textures are stored by path, except textures that have no file
+//! (for example textures embedded in FBX) - their pixels are stored in compiled model.
+//!
+//! Compiled model is the result of import with default [import options](crate::resource::model::ModelImportOptions),
+//! models requested with other options are always imported from source. Compiled model is
+//! also ignored when default import options differ from ones stored in header, for example
+//! when it was compiled by older version of engine.
+
+use std::{
+    path::{Path, PathBuf},
+    io::{self, Write, Read, Cursor},
+    sync::{Arc, Mutex},
+    collections::HashMap,
+};
+use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
+use crate::{
+    core::{
+        math::{
+            vec2::Vec2,
+            vec3::Vec3,
+            vec4::Vec4,
+            quat::Quat,
+            mat4::Mat4,
+        },
+        color::Color,
+        pool::Handle,
+    },
+    scene::{
+        Scene,
+        node::Node,
+        base::{AsBase, BaseBuilder},
+        transform::TransformBuilder,
+        mesh::Mesh,
+        camera::CameraBuilder,
+        light::{LightKind, LightBuilder, SpotLight, PointLight},
+    },
+    renderer::surface::{Surface, SurfaceSharedData, Vertex, BlendShape},
+    animation::{
+        Animation,
+        AnimationSignal,
+        Track,
+        channel::{TrackChannel, KeyFrameReductionSettings},
+        property::{PropertyTrack, PropertyKeyFrame, Property, PropertyValue},
+    },
+    resource::{
+        texture::{Texture, TextureKind},
+        model::{Model, ModelImportOptions},
+        vfs::Vfs,
+        error::ResourceError,
+        fbx::conversion::{FbxImportOptions, FbxAxisSystem, FbxAxis},
+    },
+    engine::resource_manager::ResourceManager,
+};
+
+const MAGIC: &[u8; 8] = b"RG3DMODL";
+/// Version of layout, compiled models of other versions are ignored.
+const VERSION: u32 = 7;
+/// Index of parent of nodes that are attached to root of graph, also used for handles
+/// that point to nothing.
+const NO_INDEX: u32 = u32::max_value();
+
+const NODE_BASE: u8 = 0;
+const NODE_LIGHT: u8 = 1;
+const NODE_MESH: u8 = 2;
+const NODE_CAMERA: u8 = 3;
+
+/// Import options which affect conversion of content of a model, they're stored in header.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ImportSettings {
+    pub fbx_import_options: FbxImportOptions,
+    pub key_frame_reduction: Option<KeyFrameReductionSettings>,
+}
+
+impl ImportSettings {
+    /// Returns settings of given import options.
+    pub fn of(options: &ModelImportOptions) -> Self {
+        Self {
+            fbx_import_options: options.fbx_import_options,
+            key_frame_reduction: options.key_frame_reduction,
+        }
+    }
+}
+
+/// Returns path of compiled model for given source file - source path with `.rgm` appended,
+/// for example `data/models/tree.fbx.rgm`.
+pub fn cache_path<P: AsRef<Path>>(source: P) -> PathBuf {
+    let mut path = source.as_ref().as_os_str().to_owned();
+    path.push(".rgm");
+    PathBuf::from(path)
+}
+
+/// Returns true if given file system has compiled model of given source and it is not older
+/// than the source. Compiled model without source is always up-to-date.
+pub fn is_cache_up_to_date<P: AsRef<Path>>(vfs: &Vfs, source: P) -> bool {
+    match vfs.modified(cache_path(source.as_ref())) {
+        Some(cache_time) => vfs.modified(source.as_ref()).map_or(true, |source_time| cache_time >= source_time),
+        None => false,
+    }
+}
+
+fn invalid_data<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn write_string<W: Write>(writer: &mut W, string: &str) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(string.len() as u32)?;
+    writer.write_all(string.as_bytes())
+}
+
+fn write_vec2<W: Write>(writer: &mut W, v: Vec2) -> io::Result<()> {
+    writer.write_f32::<LittleEndian>(v.x)?;
+    writer.write_f32::<LittleEndian>(v.y)
+}
+
+fn write_vec3<W: Write>(writer: &mut W, v: Vec3) -> io::Result<()> {
+    writer.write_f32::<LittleEndian>(v.x)?;
+    writer.write_f32::<LittleEndian>(v.y)?;
+    writer.write_f32::<LittleEndian>(v.z)
+}
+
+fn write_vec4<W: Write>(writer: &mut W, v: Vec4) -> io::Result<()> {
+    writer.write_f32::<LittleEndian>(v.x)?;
+    writer.write_f32::<LittleEndian>(v.y)?;
+    writer.write_f32::<LittleEndian>(v.z)?;
+    writer.write_f32::<LittleEndian>(v.w)
+}
+
+fn write_quat<W: Write>(writer: &mut W, q: Quat) -> io::Result<()> {
+    writer.write_f32::<LittleEndian>(q.x)?;
+    writer.write_f32::<LittleEndian>(q.y)?;
+    writer.write_f32::<LittleEndian>(q.z)?;
+    writer.write_f32::<LittleEndian>(q.w)
+}
+
+fn write_color<W: Write>(writer: &mut W, color: Color) -> io::Result<()> {
+    writer.write_all(&[color.r, color.g, color.b, color.a])
+}
+
+fn write_axis<W: Write>(writer: &mut W, axis: FbxAxis) -> io::Result<()> {
+    writer.write_u8(axis.index() as u8)?;
+    writer.write_u8((axis.sign() < 0.0) as u8)
+}
+
+fn write_settings<W: Write>(writer: &mut W, settings: &ImportSettings) -> io::Result<()> {
+    let options = &settings.fbx_import_options;
+    writer.write_u8(options.convert_axes as u8)?;
+    writer.write_u8(options.convert_units as u8)?;
+    match options.axis_system {
+        Some(axis_system) => {
+            writer.write_u8(1)?;
+            write_axis(writer, axis_system.up)?;
+            write_axis(writer, axis_system.front)?;
+            write_axis(writer, axis_system.coord)?;
+        }
+        None => writer.write_u8(0)?,
+    }
+    match settings.key_frame_reduction {
+        Some(reduction) => {
+            writer.write_u8(1)?;
+            writer.write_f32::<LittleEndian>(reduction.position_tolerance)?;
+            writer.write_f32::<LittleEndian>(reduction.scale_tolerance)?;
+            writer.write_f32::<LittleEndian>(reduction.rotation_tolerance)
+        }
+        None => writer.write_u8(0),
+    }
+}
+
+const TEXTURE_NONE: u8 = 0;
+const TEXTURE_FILE: u8 = 1;
+const TEXTURE_EMBEDDED: u8 = 2;
+
+fn write_texture<W: Write>(writer: &mut W, vfs: &Vfs, texture: Option<Arc<Mutex<Texture>>>) -> io::Result<()> {
+    match texture {
+        Some(texture) => {
+            let texture = texture.lock().unwrap();
+            // Texture that is loaded but has no file was created from memory, its pixels
+            // can't be loaded later so they're stored in place.
+            let embedded = texture.is_loaded() && !vfs.exists(&texture.path);
+            writer.write_u8(if embedded { TEXTURE_EMBEDDED } else { TEXTURE_FILE })?;
+            write_string(writer, &texture.path.to_string_lossy())?;
+            writer.write_u32::<LittleEndian>(texture.kind.id())?;
+            if embedded {
+                writer.write_u32::<LittleEndian>(texture.width)?;
+                writer.write_u32::<LittleEndian>(texture.height)?;
+                writer.write_u32::<LittleEndian>(texture.mip_count)?;
+                writer.write_u32::<LittleEndian>(texture.bytes.len() as u32)?;
+                writer.write_all(&texture.bytes)?;
+            }
+            Ok(())
+        }
+        None => writer.write_u8(TEXTURE_NONE),
+    }
+}
+
+fn write_surface_data<W: Write>(writer: &mut W, data: &SurfaceSharedData) -> io::Result<()> {
+    writer.write_u32::<LittleEndian>(data.vertices.len() as u32)?;
+    for vertex in data.vertices.iter() {
+        write_vec3(writer, vertex.position)?;
+        write_vec2(writer, vertex.tex_coord)?;
+        write_vec3(writer, vertex.normal)?;
+        write_vec4(writer, vertex.tangent)?;
+        for weight in vertex.bone_weights.iter() {
+            writer.write_f32::<LittleEndian>(*weight)?;
+        }
+        writer.write_all(&vertex.bone_indices)?;
+        write_vec2(writer, vertex.second_tex_coord)?;
+        write_color(writer, vertex.color)?;
+    }
+
+    writer.write_u32::<LittleEndian>(data.indices.len() as u32)?;
+    for index in data.indices.iter() {
+        writer.write_u32::<LittleEndian>(*index)?;
+    }
+
+    writer.write_u32::<LittleEndian>(data.blend_shapes.len() as u32)?;
+    for blend_shape in data.blend_shapes.iter() {
+        write_string(writer, &blend_shape.name)?;
+        writer.write_u32::<LittleEndian>(blend_shape.position_offsets.len() as u32)?;
+        for (position, normal) in blend_shape.position_offsets.iter().zip(blend_shape.normal_offsets.iter()) {
+            write_vec3(writer, *position)?;
+            write_vec3(writer, *normal)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_property_value<W: Write>(writer: &mut W, value: &PropertyValue) -> io::Result<()> {
+    match value {
+        PropertyValue::Float(value) => {
+            writer.write_u8(0)?;
+            writer.write_f32::<LittleEndian>(*value)
+        }
+        PropertyValue::Vector(value) => {
+            writer.write_u8(1)?;
+            write_vec3(writer, *value)
+        }
+        PropertyValue::Color(value) => {
+            writer.write_u8(2)?;
+            write_color(writer, *value)
+        }
+        PropertyValue::Bool(value) => {
+            writer.write_u8(3)?;
+            writer.write_u8(*value as u8)
+        }
+    }
+}
+
+fn write_animation<W: Write>(writer: &mut W, animation: &Animation, node_index: &dyn Fn(Handle<Node>) -> u32) -> io::Result<()> {
+    write_string(writer, animation.name())?;
+    writer.write_u8(animation.is_loop() as u8)?;
+    writer.write_u8(animation.is_enabled() as u8)?;
+    writer.write_f32::<LittleEndian>(animation.get_speed())?;
+
+    writer.write_u32::<LittleEndian>(animation.get_tracks().len() as u32)?;
+    for track in animation.get_tracks() {
+        writer.write_u32::<LittleEndian>(node_index(track.get_node()))?;
+        writer.write_u8(track.is_enabled() as u8)?;
+        for channel in &[track.position_channel(), track.scale_channel()] {
+            writer.write_u32::<LittleEndian>(channel.keys().len() as u32)?;
+            for key in channel.keys() {
+                writer.write_f32::<LittleEndian>(key.time)?;
+                write_vec3(writer, key.value)?;
+            }
+        }
+        writer.write_u32::<LittleEndian>(track.rotation_channel().keys().len() as u32)?;
+        for key in track.rotation_channel().keys() {
+            writer.write_f32::<LittleEndian>(key.time)?;
+            write_quat(writer, key.value)?;
+        }
+    }
+
+    writer.write_u32::<LittleEndian>(animation.get_property_tracks().len() as u32)?;
+    for track in animation.get_property_tracks() {
+        writer.write_u32::<LittleEndian>(node_index(track.node()))?;
+        writer.write_u8(track.is_enabled() as u8)?;
+        writer.write_u32::<LittleEndian>(track.property().id())?;
+        match track.property() {
+            Property::BlendShapeWeight(index) => writer.write_u32::<LittleEndian>(*index as u32)?,
+            Property::Custom(name) => write_string(writer, name)?,
+            _ => (),
+        }
+        writer.write_u32::<LittleEndian>(track.key_frames().len() as u32)?;
+        for key_frame in track.key_frames() {
+            writer.write_f32::<LittleEndian>(key_frame.time)?;
+            write_property_value(writer, &key_frame.value)?;
+        }
+    }
+
+    writer.write_u32::<LittleEndian>(animation.signals().len() as u32)?;
+    for signal in animation.signals() {
+        writer.write_u64::<LittleEndian>(signal.id())?;
+        write_string(writer, signal.name())?;
+        writer.write_f32::<LittleEndian>(signal.time())?;
+        writer.write_u8(signal.is_enabled() as u8)?;
+    }
+
+    Ok(())
+}
+
+/// Writes scene of a model in compiled format. Only nodes that can be produced by model
+/// import (base nodes, lights, cameras and meshes) are supported. Virtual file system is used
+/// to find out which textures have no file and must be stored in compiled model. Settings
+/// must be the ones model was imported with.
+pub fn write<W: Write>(writer: &mut W, scene: &Scene, vfs: &Vfs, settings: &ImportSettings) -> io::Result<()> {
+    let graph = &scene.graph;
+
+    // Parents must be written before their children, so nodes are written in depth-first order.
+    let mut nodes = Vec::new();
+    let mut stack = graph.get(graph.get_root()).base().children().to_vec();
+    stack.reverse();
+    while let Some(handle) = stack.pop() {
+        nodes.push(handle);
+        stack.extend(graph.get(handle).base().children().iter().rev());
+    }
+    let indices = nodes.iter()
+        .enumerate()
+        .map(|(i, handle)| (*handle, i as u32))
+        .collect::<HashMap<_, _>>();
+    let node_index = |handle: Handle<Node>| indices.get(&handle).copied().unwrap_or(NO_INDEX);
+
+    // Surface data can be shared between surfaces, it is written once.
+    let mut surface_data: Vec<Arc<Mutex<SurfaceSharedData>>> = Vec::new();
+    for handle in nodes.iter() {
+        if let Node::Mesh(mesh) = graph.get(*handle) {
+            for surface in mesh.surfaces() {
+                let data = surface.get_data();
+                if !surface_data.iter().any(|d| Arc::ptr_eq(d, &data)) {
+                    surface_data.push(data);
+                }
+            }
+        }
+    }
+
+    writer.write_all(MAGIC)?;
+    writer.write_u32::<LittleEndian>(VERSION)?;
+    write_settings(writer, settings)?;
+
+    writer.write_u32::<LittleEndian>(surface_data.len() as u32)?;
+    for data in surface_data.iter() {
+        write_surface_data(writer, &data.lock().unwrap())?;
+    }
+
+    writer.write_u32::<LittleEndian>(nodes.len() as u32)?;
+    for handle in nodes.iter() {
+        let node = graph.get(*handle);
+        let kind = match node {
+            Node::Base(_) => NODE_BASE,
+            Node::Light(_) => NODE_LIGHT,
+            Node::Mesh(_) => NODE_MESH,
+            Node::Camera(_) => NODE_CAMERA,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                           format!("Node {} can't be stored in compiled model", node.base().name()))),
+        };
+        writer.write_u8(kind)?;
+
+        let base = node.base();
+        write_string(writer, base.name())?;
+        writer.write_u32::<LittleEndian>(node_index(base.parent()))?;
+        writer.write_u8(base.visibility() as u8)?;
+        let transform = base.local_transform();
+        write_vec3(writer, transform.position())?;
+        write_quat(writer, transform.rotation())?;
+        write_vec3(writer, transform.scale())?;
+        write_quat(writer, transform.pre_rotation())?;
+        write_quat(writer, transform.post_rotation())?;
+        write_vec3(writer, transform.rotation_offset())?;
+        write_vec3(writer, transform.rotation_pivot())?;
+        write_vec3(writer, transform.scaling_offset())?;
+        write_vec3(writer, transform.scaling_pivot())?;
+        for value in base.inv_bind_pose_transform().f.iter() {
+            writer.write_f32::<LittleEndian>(*value)?;
+        }
+
+        match node {
+            Node::Light(light) => {
+                writer.write_u32::<LittleEndian>(light.get_kind().id())?;
+                match light.get_kind() {
+                    LightKind::Spot(spot) => {
+                        writer.write_f32::<LittleEndian>(spot.distance())?;
+                        writer.write_f32::<LittleEndian>(spot.hotspot_cone_angle())?;
+                        writer.write_f32::<LittleEndian>(spot.falloff_angle_delta())?;
+                    }
+                    LightKind::Point(point) => {
+                        writer.write_f32::<LittleEndian>(point.get_radius())?;
+                    }
+                }
+                write_color(writer, light.get_color())?;
+                writer.write_u8(light.is_cast_shadows() as u8)?;
+            }
+            Node::Camera(camera) => {
+                writer.write_f32::<LittleEndian>(camera.fov())?;
+                writer.write_f32::<LittleEndian>(camera.z_near())?;
+                writer.write_f32::<LittleEndian>(camera.z_far())?;
+                writer.write_u8(camera.is_enabled() as u8)?;
+            }
+            Node::Mesh(mesh) => {
+                writer.write_u32::<LittleEndian>(mesh.blend_shape_weights().len() as u32)?;
+                for weight in mesh.blend_shape_weights() {
+                    writer.write_f32::<LittleEndian>(*weight)?;
+                }
+                writer.write_u32::<LittleEndian>(mesh.surfaces().len() as u32)?;
+                for surface in mesh.surfaces() {
+                    let data = surface.get_data();
+                    let data_index = surface_data.iter().position(|d| Arc::ptr_eq(d, &data)).unwrap_or_default();
+                    writer.write_u32::<LittleEndian>(data_index as u32)?;
+                    write_texture(writer, vfs, surface.get_diffuse_texture())?;
+                    write_texture(writer, vfs, surface.get_normal_texture())?;
+                    write_texture(writer, vfs, surface.get_specular_texture())?;
+                    writer.write_u32::<LittleEndian>(surface.bones.len() as u32)?;
+                    for bone in surface.bones.iter() {
+                        writer.write_u32::<LittleEndian>(node_index(*bone))?;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    writer.write_u32::<LittleEndian>(scene.animations.iter().count() as u32)?;
+    for animation in scene.animations.iter() {
+        write_animation(writer, animation, &node_index)?;
+    }
+
+    Ok(())
+}
+
+/// Reader of compiled model from memory.
+struct Reader<'a> {
+    cursor: Cursor<&'a [u8]>,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads count of elements. Every element takes at least one byte, so count is checked
+    /// against remaining data to not allocate huge buffers for corrupted files.
+    fn count(&mut self) -> io::Result<usize> {
+        let count = self.cursor.read_u32::<LittleEndian>()? as usize;
+        let remaining = self.cursor.get_ref().len() - self.cursor.position() as usize;
+        if count > remaining {
+            return Err(invalid_data("Compiled model is truncated"));
+        }
+        Ok(count)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        self.cursor.read_u8()
+    }
+
+    fn bool(&mut self) -> io::Result<bool> {
+        Ok(self.cursor.read_u8()? != 0)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.cursor.read_u32::<LittleEndian>()
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        self.cursor.read_f32::<LittleEndian>()
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let mut bytes = vec![0; self.count()?];
+        self.cursor.read_exact(&mut bytes)?;
+        String::from_utf8(bytes).map_err(|_| invalid_data("Compiled model has invalid string"))
+    }
+
+    fn vec2(&mut self) -> io::Result<Vec2> {
+        Ok(Vec2::new(self.f32()?, self.f32()?))
+    }
+
+    fn vec3(&mut self) -> io::Result<Vec3> {
+        Ok(Vec3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn vec4(&mut self) -> io::Result<Vec4> {
+        Ok(Vec4 { x: self.f32()?, y: self.f32()?, z: self.f32()?, w: self.f32()? })
+    }
+
+    fn quat(&mut self) -> io::Result<Quat> {
+        Ok(Quat { x: self.f32()?, y: self.f32()?, z: self.f32()?, w: self.f32()? })
+    }
+
+    fn color(&mut self) -> io::Result<Color> {
+        let mut rgba = [0; 4];
+        self.cursor.read_exact(&mut rgba)?;
+        Ok(Color::from_rgba(rgba[0], rgba[1], rgba[2], rgba[3]))
+    }
+
+    fn axis(&mut self) -> io::Result<FbxAxis> {
+        let index = self.u8()?;
+        let sign = if self.bool()? { -1 } else { 1 };
+        FbxAxis::new(i32::from(index), sign).map_err(invalid_data)
+    }
+
+    fn settings(&mut self) -> io::Result<ImportSettings> {
+        let convert_axes = self.bool()?;
+        let convert_units = self.bool()?;
+        let axis_system = if self.bool()? {
+            Some(FbxAxisSystem { up: self.axis()?, front: self.axis()?, coord: self.axis()? })
+        } else {
+            None
+        };
+        let key_frame_reduction = if self.bool()? {
+            Some(KeyFrameReductionSettings {
+                position_tolerance: self.f32()?,
+                scale_tolerance: self.f32()?,
+                rotation_tolerance: self.f32()?,
+            })
+        } else {
+            None
+        };
+        Ok(ImportSettings {
+            fbx_import_options: FbxImportOptions { convert_axes, convert_units, axis_system },
+            key_frame_reduction,
+        })
+    }
+
+    fn surface_data(&mut self) -> io::Result<SurfaceSharedData> {
+        let vertex_count = self.count()?;
+        let mut vertices = Vec::with_capacity(vertex_count);
+        for _ in 0..vertex_count {
+            let position = self.vec3()?;
+            let tex_coord = self.vec2()?;
+            let normal = self.vec3()?;
+            let tangent = self.vec4()?;
+            let mut bone_weights = [0.0; 4];
+            for weight in bone_weights.iter_mut() {
+                *weight = self.f32()?;
+            }
+            let mut bone_indices = [0; 4];
+            self.cursor.read_exact(&mut bone_indices)?;
+            let second_tex_coord = self.vec2()?;
+            let color = self.color()?;
+            vertices.push(Vertex { position, tex_coord, normal, tangent, bone_weights, bone_indices, second_tex_coord, color });
+        }
+
+        let index_count = self.count()?;
+        let mut indices = Vec::with_capacity(index_count);
+        for _ in 0..index_count {
+            let index = self.u32()?;
+            if index as usize >= vertices.len() {
+                return Err(invalid_data("Compiled model has index out of bounds"));
+            }
+            indices.push(index);
+        }
+
+        let mut blend_shapes = Vec::new();
+        for _ in 0..self.count()? {
+            let name = self.string()?;
+            let count = self.count()?;
+            let mut blend_shape = BlendShape {
+                name,
+                position_offsets: Vec::with_capacity(count),
+                normal_offsets: Vec::with_capacity(count),
+            };
+            for _ in 0..count {
+                blend_shape.position_offsets.push(self.vec3()?);
+                blend_shape.normal_offsets.push(self.vec3()?);
+            }
+            blend_shapes.push(blend_shape);
+        }
+
+        let mut data = SurfaceSharedData::new();
+        data.vertices = vertices;
+        data.indices = indices;
+        data.blend_shapes = blend_shapes;
+        Ok(data)
+    }
+
+    fn texture(&mut self, resource_manager: &mut ResourceManager) -> io::Result<Option<Arc<Mutex<Texture>>>> {
+        let tag = self.u8()?;
+        if tag == TEXTURE_NONE {
+            return Ok(None);
+        }
+        let path = PathBuf::from(self.string()?);
+        let kind = TextureKind::new(self.u32()?).map_err(invalid_data)?;
+        match tag {
+            TEXTURE_FILE => Ok(Some(resource_manager.request_texture_async(path, kind))),
+            TEXTURE_EMBEDDED => {
+                let width = self.u32()?;
+                let height = self.u32()?;
+                let mip_count = self.u32()?;
+                let size = self.count()?;
+                let mut bytes = vec![0; size];
+                self.cursor.read_exact(&mut bytes)?;
+                // Embedded texture could be already registered by other model.
+                if let Some(texture) = resource_manager.find_texture(&path) {
+                    return Ok(Some(texture));
+                }
+                let texture = Texture::from_mip_chain(width, height, kind, mip_count, bytes);
+                Ok(Some(resource_manager.register_texture(path, texture)))
+            }
+            _ => Err(invalid_data(format!("Compiled model has invalid texture tag {}", tag))),
+        }
+    }
+
+    fn property_value(&mut self) -> io::Result<PropertyValue> {
+        match self.u8()? {
+            0 => Ok(PropertyValue::Float(self.f32()?)),
+            1 => Ok(PropertyValue::Vector(self.vec3()?)),
+            2 => Ok(PropertyValue::Color(self.color()?)),
+            3 => Ok(PropertyValue::Bool(self.bool()?)),
+            id => Err(invalid_data(format!("Compiled model has invalid property value {}", id))),
+        }
+    }
+
+    fn animation(&mut self, handles: &[Handle<Node>]) -> io::Result<Animation> {
+        let node = |index: u32| handles.get(index as usize).copied().unwrap_or(Handle::NONE);
+
+        let mut animation = Animation::default();
+        animation.set_name(&self.string()?);
+        animation.set_loop(self.bool()?);
+        animation.set_enabled(self.bool()?);
+        animation.set_speed(self.f32()?);
+
+        for _ in 0..self.count()? {
+            let node = node(self.u32()?);
+            let enabled = self.bool()?;
+            let mut position = TrackChannel::default();
+            for _ in 0..self.count()? {
+                position.add_key(self.f32()?, self.vec3()?);
+            }
+            let mut scale = TrackChannel::default();
+            for _ in 0..self.count()? {
+                scale.add_key(self.f32()?, self.vec3()?);
+            }
+            let mut rotation = TrackChannel::default();
+            for _ in 0..self.count()? {
+                rotation.add_key(self.f32()?, self.quat()?);
+            }
+            let mut track = Track::from_channels(node, position, scale, rotation);
+            track.enable(enabled);
+            animation.add_track(track);
+        }
+
+        for _ in 0..self.count()? {
+            let node = node(self.u32()?);
+            let enabled = self.bool()?;
+            let property = match Property::from_id(self.u32()?).map_err(invalid_data)? {
+                Property::BlendShapeWeight(_) => Property::BlendShapeWeight(self.u32()? as usize),
+                Property::Custom(_) => Property::Custom(self.string()?),
+                property => property,
+            };
+            let mut track = PropertyTrack::new(node, property);
+            track.enable(enabled);
+            for _ in 0..self.count()? {
+                track.add_key_frame(PropertyKeyFrame::new(self.f32()?, self.property_value()?));
+            }
+            animation.add_property_track(track);
+        }
+
+        for _ in 0..self.count()? {
+            let id = self.cursor.read_u64::<LittleEndian>()?;
+            let name = self.string()?;
+            let time = self.f32()?;
+            // Signal is either numeric or named.
+            let mut signal = if name.is_empty() {
+                AnimationSignal::new(id, time)
+            } else {
+                AnimationSignal::named(&name, time)
+            };
+            signal.set_enabled(self.bool()?);
+            animation.add_signal(signal);
+        }
+
+        Ok(animation)
+    }
+}
+
+/// Reads scene of compiled model from memory. Textures of surfaces are requested from resource
+/// manager asynchronously. Compiled model which was imported with other [settings](ImportSettings::of)
+/// than given import options is rejected.
+pub(in crate) fn read(data: &[u8], resource_manager: &mut ResourceManager, options: &ModelImportOptions) -> io::Result<Scene> {
+    if data.len() < MAGIC.len() + 4 || &data[0..MAGIC.len()] != MAGIC {
+        return Err(invalid_data("Not a compiled model"));
+    }
+    let mut reader = Reader { cursor: Cursor::new(data) };
+    reader.cursor.set_position(MAGIC.len() as u64);
+    let version = reader.u32()?;
+    if version != VERSION {
+        return Err(invalid_data(format!("Unsupported version {} of compiled model", version)));
+    }
+    let settings = reader.settings()?;
+    if settings != ImportSettings::of(options) {
+        return Err(invalid_data("Compiled model was imported with other settings"));
+    }
+
+    let mut surface_data = Vec::new();
+    for _ in 0..reader.count()? {
+        surface_data.push(Arc::new(Mutex::new(reader.surface_data()?)));
+    }
+
+    let mut scene = Scene::new();
+    let node_count = reader.count()?;
+    let mut handles = Vec::with_capacity(node_count);
+    // Bones can be stored after meshes they affect, so they're resolved when every node is read.
+    let mut bones = Vec::new();
+    for i in 0..node_count {
+        let kind = reader.u8()?;
+        let name = reader.string()?;
+        let parent = reader.u32()?;
+        let visibility = reader.bool()?;
+        let transform = TransformBuilder::new()
+            .with_local_position(reader.vec3()?)
+            .with_local_rotation(reader.quat()?)
+            .with_local_scale(reader.vec3()?)
+            .with_pre_rotation(reader.quat()?)
+            .with_post_rotation(reader.quat()?)
+            .with_rotation_offset(reader.vec3()?)
+            .with_rotation_pivot(reader.vec3()?)
+            .with_scaling_offset(reader.vec3()?)
+            .with_scaling_pivot(reader.vec3()?)
+            .build();
+        let mut inv_bind_pose_transform = Mat4::IDENTITY;
+        for value in inv_bind_pose_transform.f.iter_mut() {
+            *value = reader.f32()?;
+        }
+        let base_builder = BaseBuilder::new()
+            .with_name(&name)
+            .with_visibility(visibility)
+            .with_local_transform(transform);
+
+        let mut node = match kind {
+            NODE_BASE => Node::Base(base_builder.build()),
+            NODE_LIGHT => {
+                let kind = match LightKind::new(reader.u32()?).map_err(invalid_data)? {
+                    LightKind::Spot(_) => LightKind::Spot(SpotLight::new(reader.f32()?, reader.f32()?, reader.f32()?)),
+                    LightKind::Point(_) => LightKind::Point(PointLight::new(reader.f32()?)),
+                };
+                Node::Light(LightBuilder::new(kind, base_builder)
+                    .with_color(reader.color()?)
+                    .cast_shadows(reader.bool()?)
+                    .build())
+            }
+            NODE_MESH => {
+                let mut mesh = Mesh::default();
+                *mesh.base_mut() = base_builder.build();
+                for i in 0..reader.count()? {
+                    mesh.set_blend_shape_weight(i, reader.f32()?);
+                }
+                for surface_index in 0..reader.count()? {
+                    let data = surface_data.get(reader.u32()? as usize)
+                        .ok_or_else(|| invalid_data("Compiled model has invalid surface data index"))?;
+                    let mut surface = Surface::new(data.clone());
+                    if let Some(texture) = reader.texture(resource_manager)? {
+                        surface.set_diffuse_texture(texture);
+                    }
+                    if let Some(texture) = reader.texture(resource_manager)? {
+                        surface.set_normal_texture(texture);
+                    }
+                    if let Some(texture) = reader.texture(resource_manager)? {
+                        surface.set_specular_texture(texture);
+                    }
+                    let mut surface_bones = Vec::new();
+                    for _ in 0..reader.count()? {
+                        surface_bones.push(reader.u32()?);
+                    }
+                    bones.push((i, surface_index, surface_bones));
+                    mesh.add_surface(surface);
+                }
+                Node::Mesh(mesh)
+            }
+            NODE_CAMERA => {
+                Node::Camera(CameraBuilder::new(base_builder)
+                    .with_fov(reader.f32()?)
+                    .with_z_near(reader.f32()?)
+                    .with_z_far(reader.f32()?)
+                    .enabled(reader.bool()?)
+                    .build())
+            }
+            _ => return Err(invalid_data(format!("Compiled model has invalid node kind {}", kind))),
+        };
+        node.base_mut().inv_bind_pose_transform = inv_bind_pose_transform;
+
+        let handle = scene.graph.add_node(node);
+        if parent != NO_INDEX {
+            let parent = handles.get(parent as usize)
+                .copied()
+                .ok_or_else(|| invalid_data("Compiled model has node before its parent"))?;
+            scene.graph.link_nodes(handle, parent);
+        }
+        handles.push(handle);
+    }
+
+    for (node_index, surface_index, surface_bones) in bones {
+        let bones = surface_bones.iter()
+            .map(|bone| handles.get(*bone as usize).copied()
+                .ok_or_else(|| invalid_data("Compiled model has invalid bone index")))
+            .collect::<io::Result<Vec<_>>>()?;
+        if let Node::Mesh(mesh) = scene.graph.get_mut(handles[node_index]) {
+            mesh.surfaces_mut()[surface_index].bones = bones;
+        }
+    }
+
+    for _ in 0..reader.count()? {
+        let animation = reader.animation(&handles)?;
+        scene.animations.add(animation);
+    }
+
+    scene.graph.update_transforms();
+
+    Ok(scene)
+}
+
+/// Imports model from source file (any format supported by resource manager) and writes it in
+/// compiled format to `dest` in virtual file system of resource manager. Compiled model written
+/// to [cache_path](cache_path) of source will be used by resource manager instead of source file
+/// while it is up-to-date and default import options are the same.
+pub fn compile<P: AsRef<Path>, Q: AsRef<Path>>(resource_manager: &mut ResourceManager, source: P, dest: Q) -> Result<(), ResourceError> {
+    let model = Model::import(source.as_ref(), resource_manager, &Default::default())?;
+    let mut data = Vec::new();
+    let vfs = resource_manager.vfs().clone();
+    let vfs = vfs.read().unwrap();
+    write(&mut data, model.get_scene(), &vfs, &ImportSettings::of(model.import_options()))?;
+    vfs.write(dest.as_ref(), &data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+    use crate::{
+        core::{
+            math::{vec2::Vec2, vec3::Vec3, quat::Quat},
+            color::Color,
+        },
+        scene::{
+            Scene,
+            node::Node,
+            base::{AsBase, BaseBuilder},
+            mesh::Mesh,
+        },
+        renderer::surface::{Surface, SurfaceSharedData, BlendShape},
+        animation::{
+            Animation,
+            AnimationSignal,
+            Track,
+            channel::{TrackChannel, KeyFrameReductionSettings},
+            property::{PropertyTrack, PropertyKeyFrame, Property, PropertyValue},
+        },
+        resource::{
+            compiled_model::{self, ImportSettings},
+            fbx::conversion::FbxImportOptions,
+            model::ModelImportOptions,
+            vfs::Vfs,
+        },
+        engine::resource_manager::ResourceManager,
+    };
+
+    /// Creates scene with skinned mesh that has blend shape, its bone is stored after the mesh.
+    fn make_scene() -> Scene {
+        let mut scene = Scene::new();
+
+        let mut data = SurfaceSharedData::make_cube();
+        data.vertices[0].second_tex_coord = Vec2::new(0.25, 0.75);
+        data.vertices[0].color = Color::from_rgba(10, 20, 30, 40);
+        data.vertices[1].bone_weights = [1.0, 0.0, 0.0, 0.0];
+        let vertex_count = data.vertices.len();
+        data.add_blend_shape(BlendShape {
+            name: "Smile".to_owned(),
+            position_offsets: vec![Vec3::new(0.0, 0.1, 0.0); vertex_count],
+            normal_offsets: vec![Vec3::new(0.0, 0.0, 0.0); vertex_count],
+        }).unwrap();
+
+        let root = scene.graph.add_node(Node::Base(BaseBuilder::new().with_name("Root").build()));
+        let mut mesh = Mesh::default();
+        *mesh.base_mut() = BaseBuilder::new().with_name("Body").build();
+        mesh.add_surface(Surface::new(Arc::new(Mutex::new(data))));
+        mesh.set_blend_shape_weight(0, 0.5);
+        let body = scene.graph.add_node(Node::Mesh(mesh));
+        scene.graph.link_nodes(body, root);
+        let bone = scene.graph.add_node(Node::Base(BaseBuilder::new().with_name("Bone").build()));
+        scene.graph.link_nodes(bone, root);
+        if let Node::Mesh(mesh) = scene.graph.get_mut(body) {
+            mesh.surfaces_mut()[0].bones.push(bone);
+        }
+
+        let mut position = TrackChannel::default();
+        position.add_key(0.0, Vec3::new(0.0, 0.0, 0.0));
+        position.add_key(1.0, Vec3::new(1.0, 2.0, 3.0));
+        let mut scale = TrackChannel::default();
+        scale.add_key(0.0, Vec3::new(1.0, 1.0, 1.0));
+        let mut rotation = TrackChannel::default();
+        rotation.add_key(0.5, Quat::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 1.0));
+        let mut animation = Animation::default();
+        animation.set_name("Walk").set_loop(false).set_speed(2.0);
+        animation.add_track(Track::from_channels(bone, position, scale, rotation));
+        let mut weight = PropertyTrack::new(body, Property::BlendShapeWeight(0));
+        weight.add_key_frame(PropertyKeyFrame::new(0.0, PropertyValue::Float(0.0)));
+        weight.add_key_frame(PropertyKeyFrame::new(1.0, PropertyValue::Float(1.0)));
+        animation.add_property_track(weight);
+        animation.add_signal(AnimationSignal::named("Step", 0.5));
+        scene.animations.add(animation);
+
+        scene
+    }
+
+    fn compile_scene(settings: &ImportSettings) -> Vec<u8> {
+        let mut data = Vec::new();
+        compiled_model::write(&mut data, &make_scene(), &Vfs::new(), settings).unwrap();
+        data
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut resource_manager = ResourceManager::new();
+        let options = ModelImportOptions::default();
+        let data = compile_scene(&ImportSettings::of(&options));
+        let scene = compiled_model::read(&data, &mut resource_manager, &options).unwrap();
+        let graph = &scene.graph;
+
+        let root = graph.find_by_name_from_root("Root");
+        let body = graph.find_by_name_from_root("Body");
+        let bone = graph.find_by_name_from_root("Bone");
+        assert!(root.is_some() && body.is_some() && bone.is_some());
+        assert_eq!(graph.get(body).base().parent(), root);
+        assert_eq!(graph.get(bone).base().parent(), root);
+
+        let mesh = match graph.get(body) {
+            Node::Mesh(mesh) => mesh,
+            _ => panic!("Body must be a mesh"),
+        };
+        assert_eq!(mesh.blend_shape_weights(), &[0.5]);
+        assert_eq!(mesh.surfaces().len(), 1);
+        let surface = &mesh.surfaces()[0];
+        assert_eq!(surface.bones, vec![bone]);
+        let data = surface.get_data();
+        let data = data.lock().unwrap();
+        let source = SurfaceSharedData::make_cube();
+        assert_eq!(data.vertices.len(), source.vertices.len());
+        assert_eq!(data.indices, source.indices);
+        for (vertex, source) in data.vertices.iter().zip(source.vertices.iter()) {
+            assert_eq!(vertex.position, source.position);
+            assert_eq!(vertex.normal, source.normal);
+        }
+        assert_eq!(data.vertices[0].second_tex_coord, Vec2::new(0.25, 0.75));
+        assert_eq!(data.vertices[0].color, Color::from_rgba(10, 20, 30, 40));
+        assert_eq!(data.vertices[1].bone_weights, [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(data.blend_shapes().len(), 1);
+        assert_eq!(data.blend_shapes()[0].name, "Smile");
+        assert_eq!(data.blend_shapes()[0].position_offsets[3], Vec3::new(0.0, 0.1, 0.0));
+
+        let animation = scene.animations.iter().next().unwrap();
+        assert_eq!(animation.name(), "Walk");
+        assert!(!animation.is_loop());
+        assert_eq!(animation.get_speed(), 2.0);
+        let track = &animation.get_tracks()[0];
+        assert_eq!(track.get_node(), bone);
+        assert_eq!(track.position_channel().keys().len(), 2);
+        assert_eq!(track.position_channel().keys()[1].value, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(track.rotation_channel().keys()[0].time, 0.5);
+        let property_track = &animation.get_property_tracks()[0];
+        assert_eq!(property_track.node(), body);
+        assert_eq!(property_track.property(), &Property::BlendShapeWeight(0));
+        assert_eq!(property_track.key_frames()[1].value, PropertyValue::Float(1.0));
+        assert_eq!(animation.signals()[0].name(), "Step");
+    }
+
+    #[test]
+    fn truncated_input() {
+        let mut resource_manager = ResourceManager::new();
+        let options = ModelImportOptions::default();
+        let data = compile_scene(&ImportSettings::of(&options));
+        for length in 0..data.len() {
+            assert!(compiled_model::read(&data[..length], &mut resource_manager, &options).is_err());
+        }
+    }
+
+    #[test]
+    fn other_import_settings_are_rejected() {
+        let mut resource_manager = ResourceManager::new();
+        let data = compile_scene(&ImportSettings {
+            fbx_import_options: FbxImportOptions::no_conversion(),
+            key_frame_reduction: None,
+        });
+        assert!(compiled_model::read(&data, &mut resource_manager, &Default::default()).is_err());
+        let options = ModelImportOptions::default().with_fbx_import_options(FbxImportOptions::no_conversion());
+        assert!(compiled_model::read(&data, &mut resource_manager, &options).is_ok());
+        let options = options.with_key_frame_reduction(Some(KeyFrameReductionSettings::default()));
+        assert!(compiled_model::read(&data, &mut resource_manager, &options).is_err());
+    }
+}
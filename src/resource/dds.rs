@@ -0,0 +1,349 @@
+//! Reader of DirectDraw Surface (DDS) textures.
+//!
+//! Only 2D textures are supported. Block compressed formats (DXT1-5, BC4-BC7, including
+//! formats from DX10 extended header) are read as is, along with every mip level stored
+//! in file. Uncompressed formats are read as R8, RGB8 or RGBA8, BGR(A) data is swizzled.
+
+use byteorder::{ByteOrder, LittleEndian};
+use image::ImageError;
+use crate::resource::{
+    texture::{Texture, TextureKind},
+    block_compression,
+};
+
+const MAGIC: &[u8; 4] = b"DDS ";
+/// Magic and header.
+const HEADER_SIZE: usize = 4 + 124;
+const DX10_HEADER_SIZE: usize = 20;
+
+const FLAG_MIPMAP_COUNT: u32 = 0x20000;
+
+const PIXEL_FORMAT_ALPHA_PIXELS: u32 = 0x1;
+const PIXEL_FORMAT_ALPHA: u32 = 0x2;
+const PIXEL_FORMAT_FOURCC: u32 = 0x4;
+const PIXEL_FORMAT_RGB: u32 = 0x40;
+const PIXEL_FORMAT_LUMINANCE: u32 = 0x20000;
+
+const CAPS2_CUBEMAP: u32 = 0x200;
+const CAPS2_VOLUME: u32 = 0x20_0000;
+
+const DIMENSION_TEXTURE_2D: u32 = 3;
+
+fn four_cc(code: &[u8; 4]) -> u32 {
+    LittleEndian::read_u32(code)
+}
+
+fn format_error<S: Into<String>>(message: S) -> ImageError {
+    ImageError::FormatError(message.into())
+}
+
+fn unsupported<S: Into<String>>(message: S) -> ImageError {
+    ImageError::UnsupportedError(message.into())
+}
+
+/// Layout of pixels in file.
+#[derive(Copy, Clone, PartialEq)]
+enum Layout {
+    AsIs(TextureKind),
+    Bgr,
+    Bgra,
+    /// BGRA without alpha, alpha must be set to 255.
+    Bgrx,
+    /// RGBA without alpha, alpha must be set to 255.
+    Rgbx,
+}
+
+impl Layout {
+    fn kind(self) -> TextureKind {
+        match self {
+            Layout::AsIs(kind) => kind,
+            Layout::Bgr => TextureKind::RGB8,
+            Layout::Bgra | Layout::Bgrx | Layout::Rgbx => TextureKind::RGBA8,
+        }
+    }
+}
+
+/// Returns layout of pixels of given DXGI format and whether format stores colors in sRGB space.
+fn dxgi_layout(format: u32) -> Result<(Layout, bool), ImageError> {
+    let layout = match format {
+        28 | 29 => Layout::AsIs(TextureKind::RGBA8),
+        61 => Layout::AsIs(TextureKind::R8),
+        70 | 71 | 72 => Layout::AsIs(TextureKind::BC1),
+        73 | 74 | 75 => Layout::AsIs(TextureKind::BC2),
+        76 | 77 | 78 => Layout::AsIs(TextureKind::BC3),
+        79 | 80 => Layout::AsIs(TextureKind::BC4),
+        82 | 83 => Layout::AsIs(TextureKind::BC5),
+        87 | 91 => Layout::Bgra,
+        88 | 93 => Layout::Bgrx,
+        94 | 95 => Layout::AsIs(TextureKind::BC6H),
+        96 => Layout::AsIs(TextureKind::BC6HSigned),
+        97 | 98 | 99 => Layout::AsIs(TextureKind::BC7),
+        _ => return Err(unsupported(format!("Unsupported DXGI format {} of DDS texture", format))),
+    };
+    Ok((layout, matches!(format, 29 | 72 | 75 | 78 | 91 | 93 | 99)))
+}
+
+fn legacy_layout(data: &[u8]) -> Result<Layout, ImageError> {
+    let flags = LittleEndian::read_u32(&data[80..]);
+    let bit_count = LittleEndian::read_u32(&data[88..]);
+    let red_mask = LittleEndian::read_u32(&data[92..]);
+    let alpha_mask = LittleEndian::read_u32(&data[104..]);
+
+    if flags & PIXEL_FORMAT_FOURCC != 0 {
+        let code = LittleEndian::read_u32(&data[84..]);
+        return if code == four_cc(b"DXT1") {
+            Ok(Layout::AsIs(TextureKind::BC1))
+        } else if code == four_cc(b"DXT2") || code == four_cc(b"DXT3") {
+            Ok(Layout::AsIs(TextureKind::BC2))
+        } else if code == four_cc(b"DXT4") || code == four_cc(b"DXT5") {
+            Ok(Layout::AsIs(TextureKind::BC3))
+        } else if code == four_cc(b"ATI1") || code == four_cc(b"BC4U") {
+            Ok(Layout::AsIs(TextureKind::BC4))
+        } else if code == four_cc(b"ATI2") || code == four_cc(b"BC5U") {
+            Ok(Layout::AsIs(TextureKind::BC5))
+        } else {
+            Err(unsupported(format!("Unsupported FourCC {:?} of DDS texture", &data[84..88])))
+        };
+    }
+
+    let has_alpha = flags & PIXEL_FORMAT_ALPHA_PIXELS != 0 && alpha_mask != 0;
+    if flags & PIXEL_FORMAT_RGB != 0 {
+        match (bit_count, red_mask) {
+            (32, 0xFF) => Ok(if has_alpha { Layout::AsIs(TextureKind::RGBA8) } else { Layout::Rgbx }),
+            (32, 0xFF_0000) => Ok(if has_alpha { Layout::Bgra } else { Layout::Bgrx }),
+            (24, 0xFF) => Ok(Layout::AsIs(TextureKind::RGB8)),
+            (24, 0xFF_0000) => Ok(Layout::Bgr),
+            _ => Err(unsupported(format!("Unsupported {}-bit RGB layout of DDS texture", bit_count))),
+        }
+    } else if flags & (PIXEL_FORMAT_LUMINANCE | PIXEL_FORMAT_ALPHA) != 0 && bit_count == 8 {
+        Ok(Layout::AsIs(TextureKind::R8))
+    } else {
+        Err(unsupported("Unsupported pixel format of DDS texture"))
+    }
+}
+
+/// Converts pixels of given layout to layout of texture kind.
+fn convert(layout: Layout, bytes: &mut [u8]) {
+    match layout {
+        Layout::AsIs(_) => (),
+        Layout::Bgr => {
+            for pixel in bytes.chunks_exact_mut(3) {
+                pixel.swap(0, 2);
+            }
+        }
+        Layout::Bgra | Layout::Bgrx => {
+            for pixel in bytes.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+                if layout == Layout::Bgrx {
+                    pixel[3] = 255;
+                }
+            }
+        }
+        Layout::Rgbx => {
+            for pixel in bytes.chunks_exact_mut(4) {
+                pixel[3] = 255;
+            }
+        }
+    }
+}
+
+/// Reads DDS texture from memory.
+pub(in crate) fn read(data: &[u8]) -> Result<Texture, ImageError> {
+    if data.len() < HEADER_SIZE || &data[0..4] != MAGIC {
+        return Err(format_error("Not a DDS texture"));
+    }
+
+    let flags = LittleEndian::read_u32(&data[8..]);
+    let height = LittleEndian::read_u32(&data[12..]);
+    let width = LittleEndian::read_u32(&data[16..]);
+    let stored_mip_count = LittleEndian::read_u32(&data[28..]);
+    let caps2 = LittleEndian::read_u32(&data[112..]);
+
+    if caps2 & CAPS2_CUBEMAP != 0 {
+        return Err(unsupported("Cube map DDS textures are not supported"));
+    }
+    if caps2 & CAPS2_VOLUME != 0 {
+        return Err(unsupported("Volume DDS textures are not supported"));
+    }
+    if width == 0 || height == 0 {
+        return Err(format_error("DDS texture has zero size"));
+    }
+
+    let pixel_format_flags = LittleEndian::read_u32(&data[80..]);
+    let is_dx10 = pixel_format_flags & PIXEL_FORMAT_FOURCC != 0
+        && LittleEndian::read_u32(&data[84..]) == four_cc(b"DX10");
+    // Legacy header has no way to tell that colors are in sRGB space.
+    let ((layout, srgb), data_offset) = if is_dx10 {
+        if data.len() < HEADER_SIZE + DX10_HEADER_SIZE {
+            return Err(format_error("DDS texture has truncated DX10 header"));
+        }
+        let dimension = LittleEndian::read_u32(&data[HEADER_SIZE + 4..]);
+        let array_size = LittleEndian::read_u32(&data[HEADER_SIZE + 12..]);
+        if dimension != DIMENSION_TEXTURE_2D || array_size > 1 {
+            return Err(unsupported("Only 2D DDS textures are supported"));
+        }
+        (dxgi_layout(LittleEndian::read_u32(&data[HEADER_SIZE..]))?, HEADER_SIZE + DX10_HEADER_SIZE)
+    } else {
+        ((legacy_layout(data)?, false), HEADER_SIZE)
+    };
+
+    let kind = layout.kind();
+
+    // Some exporters write more levels than texture can have.
+    let max_mip_count = 32 - width.max(height).leading_zeros();
+    let mip_count = if flags & FLAG_MIPMAP_COUNT != 0 && stored_mip_count > 0 {
+        stored_mip_count.min(max_mip_count)
+    } else {
+        1
+    };
+
+    // Size is taken from header, so it must not overflow.
+    let end = (0..mip_count)
+        .try_fold(data_offset, |end, level| {
+            end.checked_add(block_compression::checked_level_size(kind, width >> level, height >> level)?)
+        })
+        .ok_or_else(|| format_error("DDS texture is too large"))?;
+    let mut bytes = data.get(data_offset..end)
+        .ok_or_else(|| format_error("DDS texture has not enough data for every mip level"))?
+        .to_vec();
+    convert(layout, &mut bytes);
+
+    let mut texture = Texture::from_mip_chain(width, height, kind, mip_count, bytes);
+    texture.options.srgb = srgb;
+    Ok(texture)
+}
+
+#[cfg(test)]
+mod test {
+    use byteorder::{ByteOrder, LittleEndian};
+    use crate::resource::{
+        dds::{read, FLAG_MIPMAP_COUNT, PIXEL_FORMAT_ALPHA_PIXELS, PIXEL_FORMAT_FOURCC, PIXEL_FORMAT_RGB},
+        texture::TextureKind,
+    };
+
+    /// Pixel format of header: flags, FourCC, bit count, red mask and alpha mask.
+    struct PixelFormat {
+        flags: u32,
+        four_cc: [u8; 4],
+        bit_count: u32,
+        red_mask: u32,
+        alpha_mask: u32,
+    }
+
+    fn make_header(width: u32, height: u32, mip_count: u32, format: PixelFormat) -> Vec<u8> {
+        let mut data = vec![0; 128];
+        data[0..4].copy_from_slice(b"DDS ");
+        LittleEndian::write_u32(&mut data[4..], 124);
+        LittleEndian::write_u32(&mut data[8..], if mip_count > 0 { FLAG_MIPMAP_COUNT } else { 0 });
+        LittleEndian::write_u32(&mut data[12..], height);
+        LittleEndian::write_u32(&mut data[16..], width);
+        LittleEndian::write_u32(&mut data[28..], mip_count);
+        LittleEndian::write_u32(&mut data[76..], 32);
+        LittleEndian::write_u32(&mut data[80..], format.flags);
+        data[84..88].copy_from_slice(&format.four_cc);
+        LittleEndian::write_u32(&mut data[88..], format.bit_count);
+        LittleEndian::write_u32(&mut data[92..], format.red_mask);
+        LittleEndian::write_u32(&mut data[104..], format.alpha_mask);
+        data
+    }
+
+    fn rgb(bit_count: u32, red_mask: u32, alpha_mask: u32) -> PixelFormat {
+        let alpha_flag = if alpha_mask != 0 { PIXEL_FORMAT_ALPHA_PIXELS } else { 0 };
+        PixelFormat { flags: PIXEL_FORMAT_RGB | alpha_flag, four_cc: [0; 4], bit_count, red_mask, alpha_mask }
+    }
+
+    fn four_cc(code: &[u8; 4]) -> PixelFormat {
+        PixelFormat { flags: PIXEL_FORMAT_FOURCC, four_cc: *code, bit_count: 0, red_mask: 0, alpha_mask: 0 }
+    }
+
+    #[test]
+    fn header() {
+        let mut data = make_header(4, 4, 0, four_cc(b"DXT5"));
+        data.extend_from_slice(&[7; 16]);
+        let texture = read(&data).unwrap();
+        assert_eq!((texture.width, texture.height), (4, 4));
+        assert_eq!(texture.kind, TextureKind::BC3);
+        assert_eq!(texture.mip_count, 1);
+        assert_eq!(texture.bytes, vec![7; 16]);
+
+        assert!(read(b"PNG not a DDS").is_err());
+        let mut data = make_header(4, 4, 0, four_cc(b"XYZW"));
+        data.extend_from_slice(&[0; 16]);
+        assert!(read(&data).is_err());
+    }
+
+    #[test]
+    fn dx10_header() {
+        let mut data = make_header(4, 8, 0, four_cc(b"DX10"));
+        let mut dx10 = [0; 20];
+        // DXGI_FORMAT_BC1_UNORM, 2D texture, one element in array.
+        LittleEndian::write_u32(&mut dx10[0..], 71);
+        LittleEndian::write_u32(&mut dx10[4..], 3);
+        LittleEndian::write_u32(&mut dx10[12..], 1);
+        data.extend_from_slice(&dx10);
+        data.extend_from_slice(&[1; 16]);
+        let texture = read(&data).unwrap();
+        assert_eq!(texture.kind, TextureKind::BC1);
+        assert_eq!(texture.bytes, vec![1; 16]);
+        assert!(!texture.options.srgb);
+
+        // DXGI_FORMAT_BC1_UNORM_SRGB.
+        LittleEndian::write_u32(&mut data[128..], 72);
+        let texture = read(&data).unwrap();
+        assert_eq!(texture.kind, TextureKind::BC1);
+        assert!(texture.options.srgb);
+
+        // Header of DX10 is cut.
+        let data = &data[..128 + 10];
+        assert!(read(data).is_err());
+    }
+
+    #[test]
+    fn bgr_swizzle() {
+        let mut data = make_header(2, 1, 0, rgb(24, 0xFF_0000, 0));
+        data.extend_from_slice(&[1, 2, 3, 4, 5, 6]);
+        let texture = read(&data).unwrap();
+        assert_eq!(texture.kind, TextureKind::RGB8);
+        assert_eq!(texture.bytes, vec![3, 2, 1, 6, 5, 4]);
+
+        let mut data = make_header(1, 1, 0, rgb(32, 0xFF_0000, 0xFF00_0000));
+        data.extend_from_slice(&[1, 2, 3, 4]);
+        let texture = read(&data).unwrap();
+        assert_eq!(texture.kind, TextureKind::RGBA8);
+        assert_eq!(texture.bytes, vec![3, 2, 1, 4]);
+
+        // Unused alpha of BGRX becomes opaque.
+        let mut data = make_header(1, 1, 0, rgb(32, 0xFF_0000, 0));
+        data.extend_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(read(&data).unwrap().bytes, vec![3, 2, 1, 255]);
+    }
+
+    #[test]
+    fn truncated_data() {
+        assert!(read(&make_header(4, 4, 0, four_cc(b"DXT1"))[..100]).is_err());
+
+        let mut data = make_header(2, 2, 0, rgb(32, 0xFF, 0xFF00_0000));
+        data.extend_from_slice(&[0; 15]);
+        assert!(read(&data).is_err());
+
+        // Second mip level is missing.
+        let mut data = make_header(2, 2, 2, rgb(32, 0xFF, 0xFF00_0000));
+        data.extend_from_slice(&[0; 16]);
+        assert!(read(&data).is_err());
+
+        // Size of texture from header does not fit in memory.
+        let mut data = make_header(u32::max_value(), u32::max_value(), 32, rgb(32, 0xFF, 0xFF00_0000));
+        data.extend_from_slice(&[0; 16]);
+        assert!(read(&data).is_err());
+    }
+
+    #[test]
+    fn mip_count_clamping() {
+        // 4x4 texture can have only 3 levels: 4x4, 2x2 and 1x1.
+        let mut data = make_header(4, 4, 10, rgb(32, 0xFF, 0xFF00_0000));
+        data.extend_from_slice(&[0; (16 + 4 + 1) * 4]);
+        let texture = read(&data).unwrap();
+        assert_eq!(texture.mip_count, 3);
+        assert_eq!(texture.bytes.len(), (16 + 4 + 1) * 4);
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt::Formatter;
+use crate::resource::fbx::error::FbxError;
+
+#[derive(Debug)]
+pub enum ResourceError {
+    Io(std::io::Error),
+    Image(image::ImageError),
+    Fbx(FbxError),
+    /// Data source is not a valid sound file.
+    InvalidSoundBuffer,
+}
+
+impl std::fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            ResourceError::Io(io) => write!(f, "Io error: {}", io),
+            ResourceError::Image(image) => write!(f, "Image error: {}", image),
+            ResourceError::Fbx(fbx) => write!(f, "FBX error: {}", fbx),
+            ResourceError::InvalidSoundBuffer => write!(f, "Invalid sound buffer"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ResourceError {
+    fn from(err: std::io::Error) -> Self {
+        ResourceError::Io(err)
+    }
+}
+
+impl From<image::ImageError> for ResourceError {
+    fn from(err: image::ImageError) -> Self {
+        ResourceError::Image(err)
+    }
+}
+
+impl From<FbxError> for ResourceError {
+    fn from(err: FbxError) -> Self {
+        ResourceError::Fbx(err)
+    }
+}
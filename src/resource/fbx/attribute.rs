@@ -7,6 +7,8 @@ pub enum FbxAttribute {
     Long(i64),
     Bool(bool),
     String(String), // ASCII Fbx always have every attribute in string form
+    /// Binary blob, for example content of embedded media.
+    Raw(Vec<u8>),
 }
 
 impl std::fmt::Display for FbxAttribute {
@@ -18,6 +20,7 @@ impl std::fmt::Display for FbxAttribute {
             FbxAttribute::Long(long) => write!(f, "{}", long),
             FbxAttribute::Bool(boolean) => write!(f, "{}", boolean),
             FbxAttribute::String(string) => write!(f, "{}", string),
+            FbxAttribute::Raw(data) => write!(f, "<{} bytes>", data.len()),
         }
     }
 }
@@ -36,6 +39,7 @@ impl FbxAttribute {
                     Err(_) => Err(format!("Unable to convert string {} to i32", val))
                 }
             }
+            FbxAttribute::Raw(_) => Err("Unable to convert raw data to i32".to_owned()),
         }
     }
 
@@ -52,6 +56,7 @@ impl FbxAttribute {
                     Err(_) => Err(format!("Unable to convert string {} to i64", val))
                 }
             }
+            FbxAttribute::Raw(_) => Err("Unable to convert raw data to i64".to_owned()),
         }
     }
 
@@ -68,6 +73,7 @@ impl FbxAttribute {
                     Err(_) => Err(format!("Unable to convert string {} to f64", val))
                 }
             }
+            FbxAttribute::Raw(_) => Err("Unable to convert raw data to f64".to_owned()),
         }
     }
 
@@ -84,6 +90,7 @@ impl FbxAttribute {
                     Err(_) => Err(format!("Unable to convert string {} to f32", val))
                 }
             }
+            FbxAttribute::Raw(_) => Err("Unable to convert raw data to f32".to_owned()),
         }
     }
 
@@ -95,6 +102,14 @@ impl FbxAttribute {
             FbxAttribute::Long(val) => val.to_string(),
             FbxAttribute::Bool(val) => val.to_string(),
             FbxAttribute::String(val) => val.clone(),
+            FbxAttribute::Raw(_) => String::new(),
+        }
+    }
+
+    pub fn as_raw(&self) -> Option<&[u8]> {
+        match self {
+            FbxAttribute::Raw(data) => Some(data),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,118 @@
+use crate::{
+    resource::fbx::{
+        FbxNode,
+        FbxComponent,
+        find_and_borrow_node,
+        find_node,
+    },
+    core::{
+        math::vec3::Vec3,
+        pool::{
+            Handle,
+            Pool,
+        },
+    },
+};
+
+/// BlendShape deformer, it is attached to a geometry and holds a set of channels.
+pub struct FbxBlendShape {
+    pub(in crate::resource::fbx) channels: Vec<Handle<FbxComponent>>,
+}
+
+impl FbxBlendShape {
+    pub(in crate::resource::fbx) fn read(_blend_shape_node_handle: Handle<FbxNode>, _nodes: &Pool<FbxNode>) -> Result<Self, String> {
+        Ok(FbxBlendShape {
+            channels: Vec::new()
+        })
+    }
+}
+
+/// BlendShapeChannel sub deformer, holds shapes and default weight of a blend shape. Weight
+/// can be animated by DeformPercent animation curve node.
+pub struct FbxBlendShapeChannel {
+    pub(in crate::resource::fbx) name: String,
+    /// Weight in 0..100 range.
+    pub(in crate::resource::fbx) deform_percent: f32,
+    pub(in crate::resource::fbx) shapes: Vec<Handle<FbxComponent>>,
+    pub(in crate::resource::fbx) animation_curve_node: Handle<FbxComponent>,
+}
+
+/// Removes class prefix (ASCII format) or suffix (binary format) from object name.
+fn clean_name(name: &str, class: &str) -> String {
+    let prefix = format!("{}::", class);
+    if name.starts_with(&prefix) {
+        name[prefix.len()..].to_owned()
+    } else if let Some(pos) = name.find('\u{0}') {
+        name[..pos].to_owned()
+    } else {
+        name.to_owned()
+    }
+}
+
+impl FbxBlendShapeChannel {
+    pub(in crate::resource::fbx) fn read(channel_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<Self, String> {
+        let channel_node = nodes.borrow(channel_node_handle);
+        let name = clean_name(&channel_node.get_attrib(1)?.as_string(), "SubDeformer");
+
+        let deform_percent = if let Ok(deform_percent_node) = find_and_borrow_node(nodes, channel_node_handle, "DeformPercent") {
+            deform_percent_node.get_attrib(0)?.as_f64()? as f32
+        } else {
+            0.0
+        };
+
+        Ok(FbxBlendShapeChannel {
+            name,
+            deform_percent,
+            shapes: Vec::new(),
+            animation_curve_node: Handle::NONE,
+        })
+    }
+}
+
+/// Shape geometry, stores offsets of control points of deformed geometry. Offsets are sparse,
+/// `indices` holds indices of control points that are affected by shape.
+pub struct FbxShape {
+    pub(in crate::resource::fbx) indices: Vec<i32>,
+    pub(in crate::resource::fbx) vertices: Vec<Vec3>,
+    pub(in crate::resource::fbx) normals: Vec<Vec3>,
+}
+
+impl FbxShape {
+    pub(in crate::resource::fbx) fn read(shape_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<Self, String> {
+        let indices_node_handle = find_node(nodes, shape_node_handle, "Indexes")?;
+        let indices_array_node = find_and_borrow_node(nodes, indices_node_handle, "a")?;
+        let mut indices = Vec::with_capacity(indices_array_node.attrib_count());
+        for i in 0..indices_array_node.attrib_count() {
+            indices.push(indices_array_node.get_attrib(i)?.as_i32()?);
+        }
+
+        let vertices_node_handle = find_node(nodes, shape_node_handle, "Vertices")?;
+        let vertices_array_node = find_and_borrow_node(nodes, vertices_node_handle, "a")?;
+        let vertex_count = vertices_array_node.attrib_count() / 3;
+        if vertex_count != indices.len() {
+            return Err(String::from("FBX: Shape vertex count does not match index count!"));
+        }
+        let mut vertices = Vec::with_capacity(vertex_count);
+        for i in 0..vertex_count {
+            vertices.push(vertices_array_node.get_vec3_at(i * 3)?);
+        }
+
+        // Normals are optional.
+        let mut normals = Vec::new();
+        if let Ok(normals_node_handle) = find_node(nodes, shape_node_handle, "Normals") {
+            let normals_array_node = find_and_borrow_node(nodes, normals_node_handle, "a")?;
+            let normal_count = normals_array_node.attrib_count() / 3;
+            if normal_count == indices.len() {
+                for i in 0..normal_count {
+                    normals.push(normals_array_node.get_vec3_at(i * 3)?);
+                }
+            }
+        }
+
+        Ok(FbxShape {
+            indices,
+            vertices,
+            normals,
+        })
+    }
+}
@@ -0,0 +1,438 @@
+//! Conversion of axis system and units of FBX file to engine's convention.
+//!
+//! Every FBX file stores its axis system and size of unit in `GlobalSettings`. Engine uses
+//! right-handed coordinate system where Y is up, Z is front and X is side axis, and one unit
+//! is one meter. Conversion is a change of basis by signed permutation of axes and uniform
+//! scale, so it can be applied to every vector, rotation and matrix of file separately.
+
+use crate::{
+    core::{
+        math::{
+            vec3::Vec3,
+            quat::Quat,
+            mat4::Mat4,
+        },
+        pool::{Handle, Pool},
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    resource::fbx::{
+        FbxNode,
+        find_and_borrow_node,
+    },
+};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FbxAxis {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl FbxAxis {
+    /// Creates axis from index (0 - X, 1 - Y, 2 - Z) and sign as they stored in FBX.
+    pub fn new(index: i32, sign: i32) -> Result<Self, String> {
+        match (index, sign >= 0) {
+            (0, true) => Ok(FbxAxis::PositiveX),
+            (0, false) => Ok(FbxAxis::NegativeX),
+            (1, true) => Ok(FbxAxis::PositiveY),
+            (1, false) => Ok(FbxAxis::NegativeY),
+            (2, true) => Ok(FbxAxis::PositiveZ),
+            (2, false) => Ok(FbxAxis::NegativeZ),
+            _ => Err(format!("Invalid FBX axis index {}", index)),
+        }
+    }
+
+    pub fn index(self) -> usize {
+        match self {
+            FbxAxis::PositiveX | FbxAxis::NegativeX => 0,
+            FbxAxis::PositiveY | FbxAxis::NegativeY => 1,
+            FbxAxis::PositiveZ | FbxAxis::NegativeZ => 2,
+        }
+    }
+
+    pub fn sign(self) -> f32 {
+        match self {
+            FbxAxis::PositiveX | FbxAxis::PositiveY | FbxAxis::PositiveZ => 1.0,
+            FbxAxis::NegativeX | FbxAxis::NegativeY | FbxAxis::NegativeZ => -1.0,
+        }
+    }
+
+    fn vector(self) -> Vec3 {
+        let mut v = [0.0; 3];
+        v[self.index()] = self.sign();
+        Vec3::new(v[0], v[1], v[2])
+    }
+}
+
+impl Visit for FbxAxis {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut index = self.index() as i32;
+        index.visit("Index", visitor)?;
+        let mut sign = self.sign() as i32;
+        sign.visit("Sign", visitor)?;
+        if visitor.is_reading() {
+            *self = FbxAxis::new(index, sign)?;
+        }
+
+        visitor.leave_region()
+    }
+}
+
+/// Directions of up, front and side (`coord`) axes of a file.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FbxAxisSystem {
+    pub up: FbxAxis,
+    pub front: FbxAxis,
+    pub coord: FbxAxis,
+}
+
+impl Default for FbxAxisSystem {
+    fn default() -> Self {
+        Self::Y_UP
+    }
+}
+
+impl FbxAxisSystem {
+    /// Axis system of engine, also used by Maya. Files with this axis system are not rotated.
+    pub const Y_UP: Self = Self {
+        up: FbxAxis::PositiveY,
+        front: FbxAxis::PositiveZ,
+        coord: FbxAxis::PositiveX,
+    };
+
+    /// Axis system of 3ds Max and Blender.
+    pub const Z_UP: Self = Self {
+        up: FbxAxis::PositiveZ,
+        front: FbxAxis::NegativeY,
+        coord: FbxAxis::PositiveX,
+    };
+
+    /// Returns true if every axis is along different direction.
+    pub fn is_valid(&self) -> bool {
+        self.up.index() != self.front.index() &&
+            self.up.index() != self.coord.index() &&
+            self.front.index() != self.coord.index()
+    }
+}
+
+impl Visit for FbxAxisSystem {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.up.visit("Up", visitor)?;
+        self.front.visit("Front", visitor)?;
+        self.coord.visit("Coord", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Options of conversion of FBX content, see [fbx_import_options](crate::resource::model::ModelImportOptions::fbx_import_options).
+/// Size of unit of file can't be overridden, disable `convert_units` and set
+/// [scale](crate::resource::model::ModelImportOptions::scale) of import options instead.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FbxImportOptions {
+    /// Whether content of file should be rotated (or mirrored) to axis system of engine.
+    pub convert_axes: bool,
+    /// Whether content of file should be scaled so one unit is one meter.
+    pub convert_units: bool,
+    /// Axis system that is used instead of axis system from file, useful for files
+    /// written by exporters that store wrong settings.
+    pub axis_system: Option<FbxAxisSystem>,
+}
+
+/// Axes and units of every file are converted by default, see [no_conversion](FbxImportOptions::no_conversion)
+/// to keep content of files as is.
+impl Default for FbxImportOptions {
+    fn default() -> Self {
+        Self::full_conversion()
+    }
+}
+
+impl FbxImportOptions {
+    /// Options that keep content of file as is, like it was before FBX loader learned
+    /// to read global settings.
+    pub fn no_conversion() -> Self {
+        Self {
+            convert_axes: false,
+            convert_units: false,
+            axis_system: None,
+        }
+    }
+
+    /// Options that convert axes and units of every file to engine's convention.
+    pub fn full_conversion() -> Self {
+        Self {
+            convert_axes: true,
+            convert_units: true,
+            axis_system: None,
+        }
+    }
+
+    pub fn with_convert_axes(mut self, convert_axes: bool) -> Self {
+        self.convert_axes = convert_axes;
+        self
+    }
+
+    pub fn with_convert_units(mut self, convert_units: bool) -> Self {
+        self.convert_units = convert_units;
+        self
+    }
+
+    pub fn with_axis_system(mut self, axis_system: FbxAxisSystem) -> Self {
+        self.axis_system = Some(axis_system);
+        self
+    }
+}
+
+impl Visit for FbxImportOptions {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.convert_axes.visit("ConvertAxes", visitor)?;
+        self.convert_units.visit("ConvertUnits", visitor)?;
+        self.axis_system.visit("AxisSystem", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Content of `GlobalSettings` node that is used by conversion.
+pub(in crate::resource::fbx) struct FbxGlobalSettings {
+    pub(in crate::resource::fbx) axis_system: FbxAxisSystem,
+    /// Size of unit in centimeters.
+    pub(in crate::resource::fbx) unit_scale_factor: f32,
+}
+
+impl Default for FbxGlobalSettings {
+    fn default() -> Self {
+        Self {
+            axis_system: FbxAxisSystem::Y_UP,
+            unit_scale_factor: 1.0,
+        }
+    }
+}
+
+impl FbxGlobalSettings {
+    /// Reads global settings of file, files without `GlobalSettings` get default settings
+    /// of FBX SDK.
+    pub(in crate::resource::fbx) fn read(nodes: &Pool<FbxNode>, root: Handle<FbxNode>) -> Result<Self, String> {
+        let mut settings = Self::default();
+
+        let global_settings = match find_and_borrow_node(nodes, root, "GlobalSettings") {
+            Ok(global_settings) => global_settings,
+            Err(_) => return Ok(settings),
+        };
+        let props = match global_settings.children.iter()
+            .map(|handle| nodes.borrow(*handle))
+            .find(|node| node.name == "Properties70") {
+            Some(props) => props,
+            None => return Ok(settings),
+        };
+
+        let mut up = (1, 1);
+        let mut front = (2, 1);
+        let mut coord = (0, 1);
+        for prop_handle in props.children.iter() {
+            let prop = nodes.borrow(*prop_handle);
+            match prop.get_attrib(0)?.as_string().as_str() {
+                "UpAxis" => up.0 = prop.get_attrib(4)?.as_i32()?,
+                "UpAxisSign" => up.1 = prop.get_attrib(4)?.as_i32()?,
+                "FrontAxis" => front.0 = prop.get_attrib(4)?.as_i32()?,
+                "FrontAxisSign" => front.1 = prop.get_attrib(4)?.as_i32()?,
+                "CoordAxis" => coord.0 = prop.get_attrib(4)?.as_i32()?,
+                "CoordAxisSign" => coord.1 = prop.get_attrib(4)?.as_i32()?,
+                "UnitScaleFactor" => settings.unit_scale_factor = prop.get_attrib(4)?.as_f64()? as f32,
+                _ => ()
+            }
+        }
+
+        settings.axis_system = FbxAxisSystem {
+            up: FbxAxis::new(up.0, up.1)?,
+            front: FbxAxis::new(front.0, front.1)?,
+            coord: FbxAxis::new(coord.0, coord.1)?,
+        };
+        if !settings.axis_system.is_valid() {
+            return Err(format!("FBX: Invalid axis system {:?}", settings.axis_system));
+        }
+        if settings.unit_scale_factor <= 0.0 {
+            return Err(format!("FBX: Invalid unit scale factor {}", settings.unit_scale_factor));
+        }
+
+        Ok(settings)
+    }
+}
+
+fn component(v: Vec3, index: usize) -> f32 {
+    match index {
+        0 => v.x,
+        1 => v.y,
+        _ => v.z,
+    }
+}
+
+/// Creates rotation that transforms X, Y and Z axes to given orthonormal vectors.
+fn quat_from_basis(x: Vec3, y: Vec3, z: Vec3) -> Quat {
+    let trace = x.x + y.y + z.z;
+    if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        Quat { x: (y.z - z.y) / s, y: (z.x - x.z) / s, z: (x.y - y.x) / s, w: 0.25 * s }
+    } else if x.x > y.y && x.x > z.z {
+        let s = (1.0 + x.x - y.y - z.z).sqrt() * 2.0;
+        Quat { x: 0.25 * s, y: (y.x + x.y) / s, z: (z.x + x.z) / s, w: (y.z - z.y) / s }
+    } else if y.y > z.z {
+        let s = (1.0 + y.y - x.x - z.z).sqrt() * 2.0;
+        Quat { x: (y.x + x.y) / s, y: 0.25 * s, z: (z.y + y.z) / s, w: (z.x - x.z) / s }
+    } else {
+        let s = (1.0 + z.z - x.x - y.y).sqrt() * 2.0;
+        Quat { x: (z.x + x.z) / s, y: (z.y + y.z) / s, z: 0.25 * s, w: (x.y - y.x) / s }
+    }
+}
+
+/// Change of basis from file to engine.
+pub(in crate::resource::fbx) struct FbxConversion {
+    /// Axis of file for X, Y and Z axes of engine.
+    axes: [FbxAxis; 3],
+    scale: f32,
+}
+
+impl FbxConversion {
+    /// Creates conversion for given settings of file, `scale` is additional uniform scale
+    /// of content.
+    pub(in crate::resource::fbx) fn new(settings: &FbxGlobalSettings, options: &FbxImportOptions, scale: f32) -> Self {
+        let axis_system = options.axis_system.unwrap_or(settings.axis_system);
+        let axes = if options.convert_axes && axis_system.is_valid() {
+            [axis_system.coord, axis_system.up, axis_system.front]
+        } else {
+            [FbxAxis::PositiveX, FbxAxis::PositiveY, FbxAxis::PositiveZ]
+        };
+        let scale = if options.convert_units {
+            // Unit scale factor is size of unit in centimeters.
+            scale * settings.unit_scale_factor / 100.0
+        } else {
+            scale
+        };
+        Self {
+            axes,
+            scale,
+        }
+    }
+
+    /// Returns true if conversion mirrors content, winding of triangles must be reversed
+    /// and handedness of tangent space must be flipped then.
+    pub(in crate::resource::fbx) fn is_mirroring(&self) -> bool {
+        let [x, y, z] = self.axes;
+        x.vector().cross(&y.vector()).dot(&z.vector()) < 0.0
+    }
+
+    /// Converts direction, for example normal or tangent.
+    pub(in crate::resource::fbx) fn direction(&self, v: Vec3) -> Vec3 {
+        let [x, y, z] = self.axes;
+        Vec3::new(
+            x.sign() * component(v, x.index()),
+            y.sign() * component(v, y.index()),
+            z.sign() * component(v, z.index()))
+    }
+
+    /// Converts position or offset.
+    pub(in crate::resource::fbx) fn position(&self, v: Vec3) -> Vec3 {
+        self.direction(v).scale(self.scale)
+    }
+
+    /// Converts distance, for example radius of light.
+    pub(in crate::resource::fbx) fn length(&self, length: f32) -> f32 {
+        length * self.scale
+    }
+
+    /// Converts scale along axes, scale has no sign because permutation of axes keeps
+    /// axis-aligned scale axis-aligned.
+    pub(in crate::resource::fbx) fn scale(&self, v: Vec3) -> Vec3 {
+        let [x, y, z] = self.axes;
+        Vec3::new(component(v, x.index()), component(v, y.index()), component(v, z.index()))
+    }
+
+    pub(in crate::resource::fbx) fn rotation(&self, q: Quat) -> Quat {
+        // Axis of rotation is converted as direction. Mirroring is a rotation combined with
+        // inversion, and inversion flips axis of rotation once more.
+        let axis = self.direction(Vec3::new(q.x, q.y, q.z));
+        let axis = if self.is_mirroring() { axis.scale(-1.0) } else { axis };
+        Quat { x: axis.x, y: axis.y, z: axis.z, w: q.w }
+    }
+
+    /// Returns post rotation of camera node. FBX camera looks along its +X axis with +Y up,
+    /// while camera of engine looks along +Z, so camera is turned by extra rotation which
+    /// is appended to post rotation of node.
+    pub(in crate::resource::fbx) fn camera_post_rotation(&self, post_rotation: Quat) -> Quat {
+        let look = self.direction(Vec3::new(1.0, 0.0, 0.0));
+        let up = self.direction(Vec3::new(0.0, 1.0, 0.0));
+        let fix = quat_from_basis(up.cross(&look), up, look);
+        // Post rotation is inverted by transform, so inverse of fix is prepended.
+        let inv_fix = Quat { x: -fix.x, y: -fix.y, z: -fix.z, w: fix.w };
+        inv_fix * post_rotation
+    }
+
+    /// Converts transform matrix, for example inverse bind pose.
+    pub(in crate::resource::fbx) fn matrix(&self, m: Mat4) -> Mat4 {
+        // Matrix of change of basis and its inverse (transposed signed permutation with
+        // inverse scale), columns are stored one after another.
+        let mut basis = Mat4 { f: [0.0; 16] };
+        let mut inv_basis = Mat4 { f: [0.0; 16] };
+        for (row, axis) in self.axes.iter().enumerate() {
+            let column = axis.index();
+            basis.f[column * 4 + row] = axis.sign() * self.scale;
+            inv_basis.f[row * 4 + column] = axis.sign() / self.scale;
+        }
+        basis.f[15] = 1.0;
+        inv_basis.f[15] = 1.0;
+        basis * m * inv_basis
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::math::vec3::Vec3,
+        resource::fbx::conversion::{
+            FbxAxis,
+            FbxAxisSystem,
+            FbxConversion,
+            FbxGlobalSettings,
+            FbxImportOptions,
+        },
+    };
+
+    #[test]
+    fn z_up_conversion() {
+        let settings = FbxGlobalSettings {
+            axis_system: FbxAxisSystem::Z_UP,
+            unit_scale_factor: 100.0,
+        };
+        let conversion = FbxConversion::new(&settings, &Default::default(), 1.0);
+        assert!(!conversion.is_mirroring());
+        // Up axis of file becomes Y, front axis (-Y) becomes Z.
+        let up = conversion.position(Vec3::new(0.0, 0.0, 2.0));
+        assert_eq!((up.x, up.y, up.z), (0.0, 2.0, 0.0));
+        let front = conversion.position(Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!((front.x, front.y, front.z), (0.0, 0.0, 1.0));
+
+        // Centimeters are converted to meters, axes are kept as is.
+        let settings = FbxGlobalSettings::default();
+        let conversion = FbxConversion::new(&settings, &FbxImportOptions::default().with_convert_axes(false), 1.0);
+        assert_eq!(conversion.length(250.0), 2.5);
+
+        let left_handed = FbxGlobalSettings {
+            axis_system: FbxAxisSystem {
+                coord: FbxAxis::NegativeX,
+                ..FbxAxisSystem::Y_UP
+            },
+            unit_scale_factor: 1.0,
+        };
+        assert!(FbxConversion::new(&left_handed, &Default::default(), 1.0).is_mirroring());
+        assert!(!FbxConversion::new(&left_handed, &FbxImportOptions::no_conversion(), 1.0).is_mirroring());
+    }
+}
@@ -1,5 +1,45 @@
 use std::fmt::Formatter;
 
+/// Location of an object (model, geometry, material, etc.) in FBX file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FbxObjectInfo {
+    /// Unique id of object in file, 0 if node is not an object.
+    pub id: i64,
+    /// Name of object without class prefix, empty if object has no name.
+    pub name: String,
+    /// Path of node of object from root of file, for example `Objects/Geometry`.
+    pub node_path: String,
+    /// Offset of node in bytes - offset of node record in binary files or offset of line
+    /// in ASCII files.
+    pub offset: u64,
+}
+
+impl std::fmt::Display for FbxObjectInfo {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "{} {:?} (id {}) at offset {}", self.node_path, self.name, self.id, self.offset)
+    }
+}
+
+/// Non-fatal problem of import, model is still loaded but could look different from what
+/// author expects. Warnings are turned into errors in strict mode, see
+/// [ModelImportOptions::strict](crate::resource::model::ModelImportOptions::strict).
+#[derive(Clone, Debug, PartialEq)]
+pub struct FbxWarning {
+    /// Object that caused the warning, None if warning is about whole file.
+    pub object: Option<FbxObjectInfo>,
+    pub message: String,
+}
+
+impl std::fmt::Display for FbxWarning {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self.object.as_ref() {
+            Some(object) => write!(f, "{} - {}", object, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub enum FbxError {
     Io(std::io::Error),
     UnknownAttributeType(u8),
@@ -13,6 +53,13 @@ pub enum FbxError {
     IndexOutOfBounds,
     UnableToFindBone,
     UnableToRemapModelToNode,
+    /// Error that has occurred while reading or converting particular object.
+    Object {
+        object: FbxObjectInfo,
+        error: Box<FbxError>,
+    },
+    /// Import has produced warnings in strict mode.
+    Warnings(Vec<FbxWarning>),
 }
 
 impl std::fmt::Display for FbxError {
@@ -30,6 +77,14 @@ impl std::fmt::Display for FbxError {
             FbxError::IndexOutOfBounds => write!(f, "Index out of bounds."),
             FbxError::UnableToFindBone => write!(f, "Unable to find bone."),
             FbxError::UnableToRemapModelToNode => write!(f, "Unable to remap model to node."),
+            FbxError::Object { object, error } => write!(f, "{} - {}", object, error),
+            FbxError::Warnings(warnings) => {
+                write!(f, "Import has {} warning(s) in strict mode:", warnings.len())?;
+                for warning in warnings {
+                    write!(f, "\n\t{}", warning)?;
+                }
+                Ok(())
+            }
         }
     }
 }
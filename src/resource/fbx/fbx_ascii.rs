@@ -28,6 +28,7 @@ pub fn read_ascii<R>(reader: &mut R, buf_len: u64) -> Result<Fbx, FbxError>
         children: Vec::new(),
         parent: Handle::NONE,
         attribs: Vec::new(),
+        offset: 0,
     });
     let mut parent_handle: Handle<FbxNode> = root_handle;
     let mut node_handle: Handle<FbxNode> = Handle::NONE;
@@ -39,6 +40,7 @@ pub fn read_ascii<R>(reader: &mut R, buf_len: u64) -> Result<Fbx, FbxError>
     while reader.seek(SeekFrom::Current(0))? < buf_len {
         // Read line, trim spaces (but leave spaces in quotes)
         buffer.clear();
+        let line_offset = reader.seek(SeekFrom::Current(0))?;
 
         let mut read_all = false;
         while reader.seek(SeekFrom::Current(0))? < buf_len {
@@ -73,6 +75,7 @@ pub fn read_ascii<R>(reader: &mut R, buf_len: u64) -> Result<Fbx, FbxError>
                     attribs: Vec::new(),
                     parent: parent_handle,
                     children: Vec::new(),
+                    offset: line_offset,
                 };
                 node_handle = nodes.spawn(node);
                 name.clear();
@@ -117,5 +120,7 @@ pub fn read_ascii<R>(reader: &mut R, buf_len: u64) -> Result<Fbx, FbxError>
         component_pool: Pool::new(),
         components: Vec::new(),
         index_to_component: HashMap::new(),
+        component_nodes: HashMap::new(),
+        warnings: Default::default(),
     })
 }
@@ -4,6 +4,8 @@ use crate::{
         Fbx,
         attribute::FbxAttribute,
         error::FbxError,
+        MIN_VERSION,
+        MAX_VERSION,
     },
     core::pool::{
         Handle,
@@ -17,8 +19,6 @@ use std::{
         Seek,
         SeekFrom,
     },
-    path::Path,
-    fs::File,
 };
 use byteorder::{
     LittleEndian,
@@ -78,20 +78,34 @@ fn read_string<R>(file: &mut R) -> Result<FbxAttribute, FbxError> where R: Read
     Ok(FbxAttribute::String(string))
 }
 
+/// Files of version 7500 and later store offsets and counts of node records as 64-bit numbers.
+const FIRST_64_BIT_VERSION: i32 = 7500;
+
+/// Reads offset or count of node record, its size depends on version of file.
+fn read_record_value<R>(file: &mut R, is_64_bit: bool) -> Result<u64, FbxError>
+    where R: Read {
+    if is_64_bit {
+        Ok(file.read_u64::<LittleEndian>()?)
+    } else {
+        Ok(u64::from(file.read_u32::<LittleEndian>()?))
+    }
+}
+
 /// Read binary FBX DOM using this specification:
 /// https://code.blender.org/2013/08/fbx-binary-file-format-specification/
 /// In case of success returns Ok(valid_handle), in case if no more nodes
 /// are present returns Ok(none_handle), in case of error returns some FbxError.
-fn read_binary_node<R>(file: &mut R, pool: &mut Pool<FbxNode>) -> Result<Handle<FbxNode>, FbxError>
+fn read_binary_node<R>(file: &mut R, pool: &mut Pool<FbxNode>, is_64_bit: bool) -> Result<Handle<FbxNode>, FbxError>
     where R: Read + Seek {
-    let end_offset = u64::from(file.read_u32::<LittleEndian>()?);
+    let offset = file.seek(SeekFrom::Current(0))?;
+    let end_offset = read_record_value(file, is_64_bit)?;
     if end_offset == 0 {
         // Footer found. We're done.
         return Ok(Handle::NONE);
     }
 
-    let num_attrib = file.read_u32::<LittleEndian>()? as usize;
-    let _attrib_list_len = file.read_u32::<LittleEndian>()?;
+    let num_attrib = read_record_value(file, is_64_bit)? as usize;
+    let _attrib_list_len = read_record_value(file, is_64_bit)?;
 
     // Read name.
     let name_len = file.read_u8()? as usize;
@@ -101,6 +115,7 @@ fn read_binary_node<R>(file: &mut R, pool: &mut Pool<FbxNode>) -> Result<Handle<
 
     let mut node = FbxNode::default();
     node.name = String::from_utf8(raw_name)?;
+    node.offset = offset;
     let node_handle = pool.spawn(node);
 
     // Read attributes.
@@ -114,6 +129,7 @@ fn read_binary_node<R>(file: &mut R, pool: &mut Pool<FbxNode>) -> Result<Handle<
             b'f' | b'd' | b'l' | b'i' | b'b' => {
                 let mut a = FbxNode::default();
                 a.name = String::from("a");
+                a.offset = file.seek(SeekFrom::Current(0))?;
                 a.attribs = read_array(type_code, file)?;
                 a.parent = node_handle;
                 let a_handle = pool.spawn(a);
@@ -122,18 +138,28 @@ fn read_binary_node<R>(file: &mut R, pool: &mut Pool<FbxNode>) -> Result<Handle<
             }
             b'S' => pool.borrow_mut(node_handle).attribs.push(read_string(file)?),
             b'R' => {
-                // Ignore Raw data
-                let length = i64::from(file.read_u32::<LittleEndian>()?);
-                file.seek(SeekFrom::Current(length))?;
+                // Raw data is content of embedded media, for example textures. Length is checked
+                // against end of record to not allocate huge buffers for corrupted files.
+                let length = file.read_u32::<LittleEndian>()?;
+                if file.seek(SeekFrom::Current(0))? + u64::from(length) > end_offset {
+                    let node = pool.borrow(node_handle);
+                    return Err(FbxError::Custom(format!("Raw data of {} node at offset {} exceeds its record", node.name, node.offset)));
+                }
+                let mut data = vec![0; length as usize];
+                file.read_exact(&mut data)?;
+                pool.borrow_mut(node_handle).attribs.push(FbxAttribute::Raw(data));
             }
             _ => ()
         }
     }
 
     if file.seek(SeekFrom::Current(0))? < end_offset {
-        let null_record_position = end_offset - 13;
+        // Null record is a record header filled with zeros.
+        let null_record_size = if is_64_bit { 25 } else { 13 };
+        let null_record_position = end_offset.checked_sub(null_record_size)
+            .ok_or(FbxError::InvalidNullRecord)?;
         while file.seek(SeekFrom::Current(0))? < null_record_position {
-            let child_handle = read_binary_node(file, pool)?;
+            let child_handle = read_binary_node(file, pool, is_64_bit)?;
             if child_handle.is_none() {
                 return Ok(child_handle);
             }
@@ -142,8 +168,9 @@ fn read_binary_node<R>(file: &mut R, pool: &mut Pool<FbxNode>) -> Result<Handle<
         }
 
         // Check if we have a null-record
-        let mut null_record = [0; 13];
-        file.read_exact(&mut null_record)?;
+        let mut null_record = [0; 25];
+        let null_record = &mut null_record[..null_record_size as usize];
+        file.read_exact(null_record)?;
         if !null_record.iter().all(|i| *i == 0) {
             return Err(FbxError::InvalidNullRecord);
         }
@@ -152,12 +179,9 @@ fn read_binary_node<R>(file: &mut R, pool: &mut Pool<FbxNode>) -> Result<Handle<
     Ok(node_handle)
 }
 
-pub fn is_binary<P: AsRef<Path>>(path: P) -> Result<bool, FbxError> {
-    let mut file = File::open(path)?;
-    let mut magic = [0; 18];
-    file.read_exact(&mut magic)?;
+pub fn is_binary(data: &[u8]) -> bool {
     let fbx_magic = b"Kaydara FBX Binary";
-    Ok(magic == *fbx_magic)
+    data.starts_with(fbx_magic)
 }
 
 pub fn read_binary<R>(file: &mut R) -> Result<Fbx, FbxError>
@@ -171,9 +195,10 @@ pub fn read_binary<R>(file: &mut R) -> Result<Fbx, FbxError>
 
     // Verify version.
     let version = file.read_u32::<LittleEndian>()? as i32;
-    if version < 7100 || version > 7400 {
+    if version < MIN_VERSION || version > MAX_VERSION {
         return Err(FbxError::UnsupportedVersion(version));
     }
+    let is_64_bit = version >= FIRST_64_BIT_VERSION;
 
     let mut nodes = Pool::new();
     let mut root = FbxNode::default();
@@ -183,7 +208,7 @@ pub fn read_binary<R>(file: &mut R) -> Result<Fbx, FbxError>
     // FBX document can have multiple root nodes, so we must read the file
     // until the end.
     while file.seek(SeekFrom::Current(0))? < total_length {
-        let root_child = read_binary_node(file, &mut nodes)?;
+        let root_child = read_binary_node(file, &mut nodes, is_64_bit)?;
         if root_child.is_none() {
             break;
         }
@@ -197,5 +222,133 @@ pub fn read_binary<R>(file: &mut R) -> Result<Fbx, FbxError>
         index_to_component: Default::default(),
         component_pool: Pool::new(),
         components: Vec::new(),
+        component_nodes: Default::default(),
+        warnings: Default::default(),
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use crate::resource::fbx::{
+        fbx_binary::read_binary,
+        texture::FbxVideo,
+        find_node,
+        error::FbxError,
+    };
+
+    struct Node {
+        name: &'static str,
+        attribs: Vec<Vec<u8>>,
+        children: Vec<Node>,
+    }
+
+    fn string(value: &str) -> Vec<u8> {
+        let mut data = vec![b'S'];
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(value.as_bytes());
+        data
+    }
+
+    fn long(value: i64) -> Vec<u8> {
+        let mut data = vec![b'L'];
+        data.extend_from_slice(&value.to_le_bytes());
+        data
+    }
+
+    fn raw(value: &[u8]) -> Vec<u8> {
+        let mut data = vec![b'R'];
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(value);
+        data
+    }
+
+    fn write_record_value(data: &mut Vec<u8>, position: usize, value: u64, is_64_bit: bool) {
+        if is_64_bit {
+            data[position..position + 8].copy_from_slice(&value.to_le_bytes());
+        } else {
+            data[position..position + 4].copy_from_slice(&(value as u32).to_le_bytes());
+        }
+    }
+
+    fn write_node(data: &mut Vec<u8>, node: &Node, is_64_bit: bool) {
+        let value_size = if is_64_bit { 8 } else { 4 };
+        let start = data.len();
+        data.resize(start + 3 * value_size, 0);
+        data.push(node.name.len() as u8);
+        data.extend_from_slice(node.name.as_bytes());
+        let attribs_start = data.len();
+        for attrib in node.attribs.iter() {
+            data.extend_from_slice(attrib);
+        }
+        let attribs_len = data.len() - attribs_start;
+        if !node.children.is_empty() {
+            for child in node.children.iter() {
+                write_node(data, child, is_64_bit);
+            }
+            // Null record.
+            data.resize(data.len() + 3 * value_size + 1, 0);
+        }
+        let end = data.len() as u64;
+        write_record_value(data, start, end, is_64_bit);
+        write_record_value(data, start + value_size, node.attribs.len() as u64, is_64_bit);
+        write_record_value(data, start + 2 * value_size, attribs_len as u64, is_64_bit);
+    }
+
+    fn write_file(version: u32, nodes: &[Node]) -> Vec<u8> {
+        let is_64_bit = version >= 7500;
+        let mut data = b"Kaydara FBX Binary  \0\x1a\0".to_vec();
+        data.extend_from_slice(&version.to_le_bytes());
+        for node in nodes {
+            write_node(&mut data, node, is_64_bit);
+        }
+        // Footer starts with null record.
+        data.resize(data.len() + if is_64_bit { 25 } else { 13 }, 0);
+        data
+    }
+
+    fn make_objects(content: Vec<u8>) -> Node {
+        Node {
+            name: "Objects",
+            attribs: Vec::new(),
+            children: vec![Node {
+                name: "Video",
+                attribs: vec![long(42), string("Video::diffuse.png"), string("Clip")],
+                children: vec![
+                    Node { name: "RelativeFilename", attribs: vec![string("diffuse.png")], children: Vec::new() },
+                    Node { name: "Content", attribs: vec![content], children: Vec::new() },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn embedded_media() {
+        for version in &[7400, 7500] {
+            let data = write_file(*version, &[make_objects(raw(&[1, 2, 3, 4]))]);
+            let fbx = read_binary(&mut Cursor::new(data.as_slice())).unwrap();
+            let video_handle = find_node(&fbx.nodes, fbx.root, "Video").unwrap();
+            let video_node = fbx.nodes.borrow(video_handle);
+            assert_eq!(video_node.attribs[0].as_i64().unwrap(), 42);
+            assert_eq!(video_node.attribs[1].as_string(), "Video::diffuse.png");
+            assert_eq!(video_node.children.len(), 2);
+            // Record of Objects node follows header and version, its header is
+            // three record values, length and name.
+            let value_size = if *version >= 7500 { 8 } else { 4 };
+            assert_eq!(video_node.offset, 27 + 3 * value_size + 1 + "Objects".len() as u64);
+
+            let video = FbxVideo::read(video_handle, &fbx.nodes).unwrap();
+            assert_eq!(video.content(), &[1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn raw_data_is_bounded_by_record() {
+        let mut content = raw(&[1, 2, 3, 4]);
+        content[1..5].copy_from_slice(&u32::max_value().to_le_bytes());
+        let data = write_file(7500, &[make_objects(content)]);
+        match read_binary(&mut Cursor::new(data.as_slice())) {
+            Err(FbxError::Custom(message)) => assert!(message.contains("Content")),
+            _ => panic!("Raw data outside of record must be rejected"),
+        }
+    }
+}
@@ -4,6 +4,7 @@ use crate::{
             vec3::Vec3,
             vec2::Vec2
         },
+        color::Color,
         pool::{Pool, Handle},
     },
     resource::fbx::{
@@ -16,9 +17,9 @@ use crate::{
         string_to_reference,
         string_to_mapping,
         error::FbxError,
+        blend_shape::{FbxBlendShapeChannel, FbxShape},
     },
     renderer::surface::{VertexWeightSet, VertexWeight},
-    utils::log::Log
 };
 
 pub struct FbxGeometry {
@@ -26,23 +27,37 @@ pub struct FbxGeometry {
     pub indices: Vec<i32>,
     pub normals: FbxContainer<Vec3>,
     pub uvs: FbxContainer<Vec2>,
+    /// Texture coordinates of second UV layer, empty if geometry has only one layer.
+    pub second_uvs: FbxContainer<Vec2>,
+    pub colors: FbxContainer<Color>,
     pub materials: FbxContainer<i32>,
     pub tangents: FbxContainer<Vec3>,
     pub binormals: FbxContainer<Vec3>,
     pub(in crate::resource::fbx) deformers: Vec<Handle<FbxComponent>>,
+    pub(in crate::resource::fbx) blend_shapes: Vec<Handle<FbxComponent>>,
 }
 
 impl FbxGeometry {
     pub(in crate::resource::fbx) fn read(geom_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<FbxGeometry, String> {
+        let uv_layers = Self::find_layer_elements(geom_node_handle, nodes, "LayerElementUV");
         Ok(FbxGeometry {
             vertices: Self::read_vertices(geom_node_handle, nodes)?,
             indices: Self::read_indices(geom_node_handle, nodes)?,
             normals: Self::read_normals(geom_node_handle, nodes)?,
-            uvs: Self::read_uvs(geom_node_handle, nodes)?,
+            uvs: match uv_layers.get(0) {
+                Some(layer) => Self::read_uvs(*layer, nodes)?,
+                None => Default::default(),
+            },
+            second_uvs: match uv_layers.get(1) {
+                Some(layer) => Self::read_uvs(*layer, nodes)?,
+                None => Default::default(),
+            },
+            colors: Self::read_colors(geom_node_handle, nodes)?,
             materials: Self::read_materials(geom_node_handle, nodes)?,
             tangents: FbxContainer::default(),
             binormals: FbxContainer::default(),
             deformers: Vec::new(),
+            blend_shapes: Vec::new(),
         })
     }
 
@@ -96,34 +111,84 @@ impl FbxGeometry {
         }
     }
 
-    fn read_uvs(geom_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<FbxContainer<Vec2>, String> {
-        if let Ok(layer_element_uv_node_handle) = find_node(nodes, geom_node_handle, "LayerElementUV") {
-            let map_type_node = find_and_borrow_node(nodes, layer_element_uv_node_handle, "MappingInformationType")?;
+    /// Returns handles of every layer element with given name, sorted by index of layer.
+    fn find_layer_elements(geom_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>, name: &str) -> Vec<Handle<FbxNode>> {
+        let mut layers = Vec::new();
+        for child_handle in nodes.borrow(geom_node_handle).children.iter() {
+            let child = nodes.borrow(*child_handle);
+            if child.name == name {
+                layers.push((child.get_attrib(0).and_then(|index| index.as_i32()).unwrap_or_default(), *child_handle));
+            }
+        }
+        layers.sort_by_key(|(index, _)| *index);
+        layers.into_iter().map(|(_, handle)| handle).collect()
+    }
+
+    fn read_uvs(layer_element_uv_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<FbxContainer<Vec2>, String> {
+        let map_type_node = find_and_borrow_node(nodes, layer_element_uv_node_handle, "MappingInformationType")?;
+        let mapping = string_to_mapping(&map_type_node.get_attrib(0)?.as_string());
+
+        let ref_type_node = find_and_borrow_node(nodes, layer_element_uv_node_handle, "ReferenceInformationType")?;
+        let reference = string_to_reference(&ref_type_node.get_attrib(0)?.as_string());
+
+        let uvs_node_handle = find_node(nodes, layer_element_uv_node_handle, "UV")?;
+        let uvs_array_node = find_and_borrow_node(nodes, uvs_node_handle, "a")?;
+        let count = uvs_array_node.attrib_count() / 2;
+        let mut uvs = Vec::with_capacity(count);
+        for i in 0..count {
+            let uv = uvs_array_node.get_vec2_at(i * 2)?;
+            uvs.push(Vec2 { x: uv.x, y: -uv.y }); // Hack FIXME
+        }
+
+        let mut index = Vec::new();
+        if reference == FbxReference::IndexToDirect {
+            let uv_index_node = find_node(nodes, layer_element_uv_node_handle, "UVIndex")?;
+            let uv_index_array_node = find_and_borrow_node(nodes, uv_index_node, "a")?;
+            for i in 0..uv_index_array_node.attrib_count() {
+                index.push(uv_index_array_node.get_attrib(i)?.as_i32()?);
+            }
+        }
+
+        Ok(FbxContainer {
+            elements: uvs,
+            index,
+            mapping,
+            reference,
+        })
+    }
+
+    fn read_colors(geom_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<FbxContainer<Color>, String> {
+        if let Ok(layer_element_color_node_handle) = find_node(nodes, geom_node_handle, "LayerElementColor") {
+            let map_type_node = find_and_borrow_node(nodes, layer_element_color_node_handle, "MappingInformationType")?;
             let mapping = string_to_mapping(&map_type_node.get_attrib(0)?.as_string());
 
-            let ref_type_node = find_and_borrow_node(nodes, layer_element_uv_node_handle, "ReferenceInformationType")?;
+            let ref_type_node = find_and_borrow_node(nodes, layer_element_color_node_handle, "ReferenceInformationType")?;
             let reference = string_to_reference(&ref_type_node.get_attrib(0)?.as_string());
 
-            let uvs_node_handle = find_node(nodes, layer_element_uv_node_handle, "UV")?;
-            let uvs_array_node = find_and_borrow_node(nodes, uvs_node_handle, "a")?;
-            let count = uvs_array_node.attrib_count() / 2;
-            let mut uvs = Vec::with_capacity(count);
+            // Colors are stored as RGBA in [0; 1] range.
+            let colors_node_handle = find_node(nodes, layer_element_color_node_handle, "Colors")?;
+            let colors_array_node = find_and_borrow_node(nodes, colors_node_handle, "a")?;
+            let count = colors_array_node.attrib_count() / 4;
+            let mut colors = Vec::with_capacity(count);
             for i in 0..count {
-                let uv = uvs_array_node.get_vec2_at(i * 2)?;
-                uvs.push(Vec2 { x: uv.x, y: -uv.y }); // Hack FIXME
+                let mut rgba = [0; 4];
+                for (k, component) in rgba.iter_mut().enumerate() {
+                    *component = (colors_array_node.get_attrib(i * 4 + k)?.as_f64()?.max(0.0).min(1.0) * 255.0) as u8;
+                }
+                colors.push(Color::from_rgba(rgba[0], rgba[1], rgba[2], rgba[3]));
             }
 
             let mut index = Vec::new();
             if reference == FbxReference::IndexToDirect {
-                let uv_index_node = find_node(nodes, layer_element_uv_node_handle, "UVIndex")?;
-                let uv_index_array_node = find_and_borrow_node(nodes, uv_index_node, "a")?;
-                for i in 0..uv_index_array_node.attrib_count() {
-                    index.push(uv_index_array_node.get_attrib(i)?.as_i32()?);
+                let color_index_node = find_node(nodes, layer_element_color_node_handle, "ColorIndex")?;
+                let color_index_array_node = find_and_borrow_node(nodes, color_index_node, "a")?;
+                for i in 0..color_index_array_node.attrib_count() {
+                    index.push(color_index_array_node.get_attrib(i)?.as_i32()?);
                 }
             }
 
             Ok(FbxContainer {
-                elements: uvs,
+                elements: colors,
                 index,
                 mapping,
                 reference,
@@ -160,8 +225,11 @@ impl FbxGeometry {
         }
     }
 
-    pub(in crate::resource::fbx) fn get_skin_data(&self, components: &Pool<FbxComponent>) -> Result<Vec<VertexWeightSet>, FbxError> {
+    /// Returns weights of every vertex, weights of bones after first four bones of a vertex
+    /// are discarded and reported to given warnings.
+    pub(in crate::resource::fbx) fn get_skin_data(&self, components: &Pool<FbxComponent>, warnings: &mut Vec<String>) -> Result<Vec<VertexWeightSet>, FbxError> {
         let mut out = vec![VertexWeightSet::default(); self.vertices.len()];
+        let mut discarded = 0;
         for deformer_handle in self.deformers.iter() {
             for sub_deformer_handle in components.borrow(*deformer_handle)
                 .as_deformer()?.sub_deformers.iter() {
@@ -175,11 +243,30 @@ impl FbxGeometry {
                         effector: sub_deformer.model.into(),
                     }) {
                         // TODO: Maybe gather all weights, but then re-normalize them to 4-bones?
-                        Log::writeln("FBX: More than 4 bones per vertex?! Excess will be discarded".to_owned());
+                        discarded += 1;
                     }
                 }
             }
         }
+        if discarded > 0 {
+            warnings.push(format!("{} vertex weights are discarded, because vertices have more than 4 bones", discarded));
+        }
         Ok(out)
     }
-}
\ No newline at end of file
+
+    /// Returns every blend shape channel of geometry together with its first shape, in-between
+    /// shapes are ignored. Index of channel in returned array is the index of blend shape in mesh.
+    pub(in crate::resource::fbx) fn get_blend_shape_channels<'a>(&self, components: &'a Pool<FbxComponent>)
+                                                                 -> Result<Vec<(&'a FbxBlendShapeChannel, &'a FbxShape)>, FbxError> {
+        let mut out = Vec::new();
+        for blend_shape_handle in self.blend_shapes.iter() {
+            for channel_handle in components.borrow(*blend_shape_handle).as_blend_shape()?.channels.iter() {
+                let channel = components.borrow(*channel_handle).as_blend_shape_channel()?;
+                if let Some(shape_handle) = channel.shapes.first() {
+                    out.push((channel, components.borrow(*shape_handle).as_shape()?));
+                }
+            }
+        }
+        Ok(out)
+    }
+}
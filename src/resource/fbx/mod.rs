@@ -3,50 +3,67 @@ mod fbx_binary;
 mod texture;
 mod attribute;
 mod geometry;
+mod blend_shape;
 pub mod error;
+pub mod conversion;
 
 use std::{
     path::Path,
-    fs::File,
-    io::{Read, Cursor},
+    io::Cursor,
     collections::{HashMap, HashSet},
     time::Instant,
     sync::{Arc, Mutex},
+    cell::RefCell,
 };
 use crate::{
     resource::{
-        texture::TextureKind,
+        texture::{Texture, TextureKind},
+        model::{ModelImportOptions, TangentPolicy},
         fbx::{
-            texture::FbxTexture,
+            texture::{FbxTexture, FbxVideo},
             attribute::FbxAttribute,
-            error::FbxError,
+            error::{FbxError, FbxObjectInfo, FbxWarning},
+            conversion::{FbxGlobalSettings, FbxConversion},
         },
         fbx::geometry::FbxGeometry,
+        fbx::blend_shape::{
+            FbxBlendShape,
+            FbxBlendShapeChannel,
+            FbxShape,
+        },
     },
     animation::{
         AnimationContainer,
         Track,
         KeyFrame,
         Animation,
+        property::{
+            Property,
+            PropertyTrack,
+            PropertyKeyFrame,
+            PropertyValue,
+        },
     },
     scene::{
         graph::Graph,
         Scene,
         node::Node,
         mesh::Mesh,
+        camera::{Camera, CameraBuilder},
         light::{
             Light,
             LightKind,
             PointLight,
             SpotLight,
         },
-        base::{Base, AsBase},
+        base::{Base, AsBase, BaseBuilder},
     },
-    engine::resource_manager::ResourceManager,
+    engine::resource_manager::{ResourceManager, SharedTexture},
     renderer::{
         surface::{
             SurfaceSharedData, Surface,
             Vertex, VertexWeightSet,
+            BlendShape,
         }
     },
     core::{
@@ -67,6 +84,11 @@ use crate::{
 // https://help.autodesk.com/view/FBX/2016/ENU/?guid=__cpp_ref_class_fbx_anim_curve_html
 const FBX_TIME_UNIT: f64 = 1.0 / 46_186_158_000.0;
 
+/// Oldest supported version of FBX format.
+const MIN_VERSION: i32 = 7100;
+/// Newest supported version of FBX format, it is written by current exporters.
+const MAX_VERSION: i32 = 7700;
+
 struct FbxTimeValuePair {
     time: f32,
     value: f32,
@@ -127,13 +149,17 @@ impl FbxSubDeformer {
 }
 
 struct FbxMaterial {
-    diffuse_texture: Handle<FbxComponent>
+    diffuse_texture: Handle<FbxComponent>,
+    normal_texture: Handle<FbxComponent>,
+    specular_texture: Handle<FbxComponent>,
 }
 
 impl FbxMaterial {
     fn read(_material_node_handle: Handle<FbxNode>) -> Result<FbxMaterial, String> {
         Ok(FbxMaterial {
-            diffuse_texture: Handle::NONE
+            diffuse_texture: Handle::NONE,
+            normal_texture: Handle::NONE,
+            specular_texture: Handle::NONE,
         })
     }
 }
@@ -155,7 +181,7 @@ struct FbxAnimationCurve {
 }
 
 impl FbxAnimationCurve {
-    pub fn read(curve_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<Self, String> {
+    pub fn read(curve_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>, warnings: &mut Vec<String>) -> Result<Self, String> {
         let key_time_handle = find_node(nodes, curve_handle, "KeyTime")?;
         let key_time_array = find_and_borrow_node(nodes, key_time_handle, "a")?;
 
@@ -177,13 +203,16 @@ impl FbxAnimationCurve {
             });
         }
 
+        if curve.keys.is_empty() {
+            warnings.push("Animation curve has no keys, it is evaluated as zero".to_owned());
+        }
+
         Ok(curve)
     }
 
     fn eval(&self, time: f32) -> f32 {
+        // Curves without keys are reported when they're read.
         if self.keys.is_empty() {
-            Log::writeln("FBX: Trying to evaluate curve with no keys!".to_owned());
-
             return 0.0;
         }
 
@@ -222,6 +251,7 @@ enum FbxAnimationCurveNodeType {
     Translation,
     Rotation,
     Scale,
+    DeformPercent,
 }
 
 struct FbxAnimationCurveNode {
@@ -237,6 +267,7 @@ impl FbxAnimationCurveNode {
                 "T" | "AnimCurveNode::T" => { FbxAnimationCurveNodeType::Translation }
                 "R" | "AnimCurveNode::R" => { FbxAnimationCurveNodeType::Rotation }
                 "S" | "AnimCurveNode::S" => { FbxAnimationCurveNodeType::Scale }
+                "DeformPercent" | "AnimCurveNode::DeformPercent" => { FbxAnimationCurveNodeType::DeformPercent }
                 _ => { FbxAnimationCurveNodeType::Unknown }
             },
             curves: Vec::new(),
@@ -291,6 +322,8 @@ struct FbxNode {
     attribs: Vec<FbxAttribute>,
     parent: Handle<FbxNode>,
     children: Vec<Handle<FbxNode>>,
+    /// Offset of node record (binary) or line (ASCII) in file, used in diagnostics.
+    offset: u64,
 }
 
 impl Default for FbxNode {
@@ -300,6 +333,7 @@ impl Default for FbxNode {
             attribs: Vec::new(),
             parent: Default::default(),
             children: Vec::new(),
+            offset: 0,
         }
     }
 }
@@ -323,7 +357,7 @@ impl FbxNode {
     fn get_attrib(&self, n: usize) -> Result<&FbxAttribute, String> {
         match self.attribs.get(n) {
             Some(attrib) => Ok(attrib),
-            None => Err(format!("Unable to get {} attribute because index out of bounds.", n))
+            None => Err(format!("Unable to get {} attribute of {} node at offset {} because index out of bounds.", n, self.name, self.offset))
         }
     }
 
@@ -339,6 +373,32 @@ pub struct FbxContainer<T> {
     reference: FbxReference,
 }
 
+impl<T: Copy> FbxContainer<T> {
+    /// Returns element for a vertex of polygon, `index` is index of control point and
+    /// `relative_index` is index of vertex in polygon vertex list. Returns None if
+    /// container is empty or has unsupported mapping.
+    fn get(&self, index: usize, relative_index: usize) -> Result<Option<T>, FbxError> {
+        let element_index = match self.mapping {
+            FbxMapping::ByPolygonVertex => relative_index,
+            FbxMapping::ByVertex => index,
+            FbxMapping::AllSame => 0,
+            _ => return Ok(None),
+        };
+        let element_index = match self.reference {
+            FbxReference::Direct => element_index,
+            FbxReference::IndexToDirect => *self.index
+                .get(element_index)
+                .ok_or(FbxError::IndexOutOfBounds)? as usize,
+            FbxReference::Unknown => return Ok(None),
+        };
+        self.elements
+            .get(element_index)
+            .copied()
+            .map(Some)
+            .ok_or(FbxError::IndexOutOfBounds)
+    }
+}
+
 impl<T> Default for FbxContainer<T> {
     fn default() -> Self {
         Self {
@@ -367,7 +427,7 @@ struct FbxLight {
 }
 
 impl FbxLight {
-    pub fn read(light_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<Self, String> {
+    pub fn read(light_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>, warnings: &mut Vec<String>) -> Result<Self, String> {
         let mut light = Self {
             actual_type: FbxLightType::Point,
             color: Color::WHITE,
@@ -402,7 +462,7 @@ impl FbxLight {
                         3 => FbxLightType::Area,
                         4 => FbxLightType::Volume,
                         _ => {
-                            Log::writeln(format!("FBX: Unknown light type {}, fallback to Point!", type_code));
+                            warnings.push(format!("Unknown light type {}, fallback to Point", type_code));
                             FbxLightType::Point
                         }
                     };
@@ -415,6 +475,93 @@ impl FbxLight {
     }
 }
 
+/// How field of view of camera is defined.
+#[derive(Copy, Clone, PartialEq)]
+enum FbxApertureMode {
+    HorizontalAndVertical = 0,
+    Horizontal = 1,
+    Vertical = 2,
+    FocalLength = 3,
+}
+
+struct FbxCamera {
+    aperture_mode: FbxApertureMode,
+    /// Field of view in degrees.
+    field_of_view: f32,
+    field_of_view_y: f32,
+    aspect_width: f32,
+    aspect_height: f32,
+    /// Focal length in millimeters.
+    focal_length: f32,
+    /// Height of film in inches.
+    film_height: f32,
+    near_plane: f32,
+    far_plane: f32,
+}
+
+impl FbxCamera {
+    pub fn read(camera_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>, warnings: &mut Vec<String>) -> Result<Self, String> {
+        let mut camera = Self {
+            aperture_mode: FbxApertureMode::Vertical,
+            field_of_view: 40.0,
+            field_of_view_y: 40.0,
+            aspect_width: 320.0,
+            aspect_height: 200.0,
+            focal_length: 34.89,
+            film_height: 0.612,
+            near_plane: 10.0,
+            far_plane: 4000.0,
+        };
+
+        let props = find_and_borrow_node(nodes, camera_node_handle, "Properties70")?;
+        for prop_handle in props.children.iter() {
+            let prop = nodes.borrow(*prop_handle);
+            match prop.get_attrib(0)?.as_string().as_str() {
+                "ApertureMode" => {
+                    let mode = prop.get_attrib(4)?.as_i32()?;
+                    camera.aperture_mode = match mode {
+                        0 => FbxApertureMode::HorizontalAndVertical,
+                        1 => FbxApertureMode::Horizontal,
+                        2 => FbxApertureMode::Vertical,
+                        3 => FbxApertureMode::FocalLength,
+                        _ => {
+                            warnings.push(format!("Unknown aperture mode {}, fallback to Vertical", mode));
+                            FbxApertureMode::Vertical
+                        }
+                    };
+                }
+                "FieldOfView" => camera.field_of_view = prop.get_attrib(4)?.as_f32()?,
+                "FieldOfViewY" => camera.field_of_view_y = prop.get_attrib(4)?.as_f32()?,
+                "AspectWidth" => camera.aspect_width = prop.get_attrib(4)?.as_f32()?,
+                "AspectHeight" => camera.aspect_height = prop.get_attrib(4)?.as_f32()?,
+                "FocalLength" => camera.focal_length = prop.get_attrib(4)?.as_f32()?,
+                "FilmHeight" => camera.film_height = prop.get_attrib(4)?.as_f32()?,
+                "NearPlane" => camera.near_plane = prop.get_attrib(4)?.as_f32()?,
+                "FarPlane" => camera.far_plane = prop.get_attrib(4)?.as_f32()?,
+                _ => ()
+            }
+        }
+
+        Ok(camera)
+    }
+
+    /// Returns vertical field of view in radians.
+    fn vertical_fov(&self) -> f32 {
+        match self.aperture_mode {
+            FbxApertureMode::HorizontalAndVertical => self.field_of_view_y.to_radians(),
+            FbxApertureMode::Horizontal => {
+                let aspect = if self.aspect_height > 0.0 { self.aspect_width / self.aspect_height } else { 1.0 };
+                2.0 * ((self.field_of_view.to_radians() * 0.5).tan() / aspect).atan()
+            }
+            FbxApertureMode::Vertical => self.field_of_view.to_radians(),
+            FbxApertureMode::FocalLength => {
+                // Film height is in inches, focal length is in millimeters.
+                2.0 * (self.film_height * 25.4 * 0.5 / self.focal_length.max(std::f32::EPSILON)).atan()
+            }
+        }
+    }
+}
+
 struct FbxModel {
     name: String,
     pre_rotation: Vec3,
@@ -439,6 +586,8 @@ struct FbxModel {
     children: Vec<Handle<FbxComponent>>,
     /// Handle to light component
     light: Handle<FbxComponent>,
+    /// Handle to camera component
+    camera: Handle<FbxComponent>,
 }
 
 impl FbxModel {
@@ -475,6 +624,7 @@ impl FbxModel {
             animation_curve_nodes: Vec::new(),
             children: Vec::new(),
             light: Handle::NONE,
+            camera: Handle::NONE,
         };
 
         let properties70_node_handle = find_node(nodes, model_node_handle, "Properties70")?;
@@ -506,12 +656,17 @@ enum FbxComponent {
     Deformer(FbxDeformer),
     SubDeformer(FbxSubDeformer),
     Texture(FbxTexture),
+    Video(FbxVideo),
     Light(FbxLight),
+    Camera(FbxCamera),
     Model(Box<FbxModel>),
     Material(FbxMaterial),
     AnimationCurveNode(FbxAnimationCurveNode),
     AnimationCurve(FbxAnimationCurve),
     Geometry(Box<FbxGeometry>),
+    BlendShape(FbxBlendShape),
+    BlendShapeChannel(FbxBlendShapeChannel),
+    Shape(Box<FbxShape>),
 }
 
 macro_rules! define_as {
@@ -530,9 +685,14 @@ impl FbxComponent {
     define_as!(self, as_deformer, FbxDeformer, Deformer);
     define_as!(self, as_sub_deformer, FbxSubDeformer, SubDeformer);
     define_as!(self, as_texture, FbxTexture, Texture);
+    define_as!(self, as_video, FbxVideo, Video);
     define_as!(self, as_light, FbxLight, Light);
+    define_as!(self, as_camera, FbxCamera, Camera);
     define_as!(self, as_material, FbxMaterial, Material);
     define_as!(self, as_geometry, FbxGeometry, Geometry);
+    define_as!(self, as_blend_shape, FbxBlendShape, BlendShape);
+    define_as!(self, as_blend_shape_channel, FbxBlendShapeChannel, BlendShapeChannel);
+    define_as!(self, as_shape, FbxShape, Shape);
 }
 
 pub struct Fbx {
@@ -546,44 +706,56 @@ pub struct Fbx {
     index_to_component: HashMap<i64, Handle<FbxComponent>>,
     /// Actual list of created components
     components: Vec<Handle<FbxComponent>>,
+    /// Node of every component, it is used to describe component in diagnostics.
+    component_nodes: HashMap<Handle<FbxComponent>, Handle<FbxNode>>,
+    /// Non-fatal problems found during preparation and conversion.
+    warnings: RefCell<Vec<FbxWarning>>,
 }
 
-/// Searches node by specified name and returns its handle if found
-fn find_node(pool: &Pool<FbxNode>, root: Handle<FbxNode>, name: &str) -> Result<Handle<FbxNode>, String> {
-    let node = pool.borrow(root);
-
-    if node.name == name {
-        return Ok(root);
-    }
-
-    for child_handle in node.children.iter() {
-        if let Ok(result) = find_node(pool, *child_handle, name) {
-            return Ok(result);
+/// Returns path of node from root of file, for example `Objects/Model/Properties70`.
+fn node_path(pool: &Pool<FbxNode>, handle: Handle<FbxNode>) -> String {
+    let mut names = Vec::new();
+    let mut handle = handle;
+    while handle.is_some() {
+        let node = pool.borrow(handle);
+        // Root node is artificial, it is not a part of path.
+        if node.parent.is_some() {
+            names.push(node.name.as_str());
         }
+        handle = node.parent;
     }
-
-    Err(format!("FBX DOM: Unable to find {} node", name))
+    names.reverse();
+    names.join("/")
 }
 
-/// Searches node by specified name and borrows a reference to it
-fn find_and_borrow_node<'a>(pool: &'a Pool<FbxNode>, root: Handle<FbxNode>, name: &str) -> Result<&'a FbxNode, String> {
+fn search_node(pool: &Pool<FbxNode>, root: Handle<FbxNode>, name: &str) -> Option<Handle<FbxNode>> {
     let node = pool.borrow(root);
 
     if node.name == name {
-        return Ok(node);
+        return Some(root);
     }
 
-    for child_handle in node.children.iter() {
-        if let Ok(result) = find_and_borrow_node(pool, *child_handle, name) {
-            return Ok(result);
-        }
-    }
+    node.children
+        .iter()
+        .find_map(|child_handle| search_node(pool, *child_handle, name))
+}
+
+/// Searches node by specified name and returns its handle if found
+fn find_node(pool: &Pool<FbxNode>, root: Handle<FbxNode>, name: &str) -> Result<Handle<FbxNode>, String> {
+    search_node(pool, root, name).ok_or_else(|| {
+        let root_node = pool.borrow(root);
+        format!("FBX DOM: Unable to find {} node in {} node at offset {}", name, root_node.name, root_node.offset)
+    })
+}
 
-    Err(format!("FBX DOM: Unable to find {} node", name))
+/// Searches node by specified name and borrows a reference to it
+fn find_and_borrow_node<'a>(pool: &'a Pool<FbxNode>, root: Handle<FbxNode>, name: &str) -> Result<&'a FbxNode, String> {
+    find_node(pool, root, name).map(|handle| pool.borrow(handle))
 }
 
-/// Links child component with parent component so parent will know about child
-fn link_child_with_parent_component(parent: &mut FbxComponent, child: &mut FbxComponent, child_handle: Handle<FbxComponent>) {
+/// Links child component with parent component so parent will know about child. Property is
+/// name of property of parent child is connected to, empty for object-object connections.
+fn link_child_with_parent_component(parent: &mut FbxComponent, child: &mut FbxComponent, child_handle: Handle<FbxComponent>, property: &str) {
     match parent {
         // Link model with other components
         FbxComponent::Model(model) => {
@@ -592,6 +764,7 @@ fn link_child_with_parent_component(parent: &mut FbxComponent, child: &mut FbxCo
                 FbxComponent::Material(_) => model.materials.push(child_handle),
                 FbxComponent::AnimationCurveNode(_) => model.animation_curve_nodes.push(child_handle),
                 FbxComponent::Light(_) => model.light = child_handle,
+                FbxComponent::Camera(_) => model.camera = child_handle,
                 FbxComponent::Model(_) => model.children.push(child_handle),
                 _ => ()
             }
@@ -599,7 +772,17 @@ fn link_child_with_parent_component(parent: &mut FbxComponent, child: &mut FbxCo
         // Link material with textures
         FbxComponent::Material(material) => {
             if let FbxComponent::Texture(_) = child {
-                material.diffuse_texture = child_handle;
+                match property {
+                    "NormalMap" | "Bump" => material.normal_texture = child_handle,
+                    "SpecularColor" | "SpecularFactor" => material.specular_texture = child_handle,
+                    _ => material.diffuse_texture = child_handle,
+                }
+            }
+        }
+        // Link texture with embedded media
+        FbxComponent::Texture(texture) => {
+            if let FbxComponent::Video(_) = child {
+                texture.video = child_handle;
             }
         }
         // Link animation curve node with animation curve
@@ -614,10 +797,26 @@ fn link_child_with_parent_component(parent: &mut FbxComponent, child: &mut FbxCo
                 deformer.sub_deformers.push(child_handle);
             }
         }
-        // Link geometry with deformers
+        // Link geometry with deformers and blend shapes
         FbxComponent::Geometry(geometry) => {
-            if let FbxComponent::Deformer(_) = child {
-                geometry.deformers.push(child_handle);
+            match child {
+                FbxComponent::Deformer(_) => geometry.deformers.push(child_handle),
+                FbxComponent::BlendShape(_) => geometry.blend_shapes.push(child_handle),
+                _ => ()
+            }
+        }
+        // Link blend shape with channels
+        FbxComponent::BlendShape(blend_shape) => {
+            if let FbxComponent::BlendShapeChannel(_) = child {
+                blend_shape.channels.push(child_handle);
+            }
+        }
+        // Link blend shape channel with shapes and weight animation
+        FbxComponent::BlendShapeChannel(channel) => {
+            match child {
+                FbxComponent::Shape(_) => channel.shapes.push(child_handle),
+                FbxComponent::AnimationCurveNode(_) => channel.animation_curve_node = child_handle,
+                _ => ()
             }
         }
         // Link sub-deformer with model
@@ -720,17 +919,18 @@ fn prepare_next_face(geom: &FbxGeometry,
     vertex_per_face
 }
 
+/// Converts polygon vertex of geometry, returns index of surface the vertex belongs to
+/// and the vertex itself.
 fn convert_vertex(geom: &FbxGeometry,
-                  mesh: &mut Mesh,
                   geometric_transform: &Mat4,
+                  conversion: &FbxConversion,
                   material_index: usize,
                   index: usize,
-                  relative_index: usize,
-                  skin_data: &[VertexWeightSet]) -> Result<(), FbxError> {
-    let position = geometric_transform.transform_vector(*geom.vertices.get(index)
-        .ok_or(FbxError::IndexOutOfBounds)?);
+                  relative_index: usize) -> Result<(usize, Vertex), FbxError> {
+    let position = conversion.position(geometric_transform.transform_vector(*geom.vertices.get(index)
+        .ok_or(FbxError::IndexOutOfBounds)?));
 
-    let normal = geometric_transform.transform_vector_normal(match geom.normals.mapping {
+    let normal = conversion.direction(geometric_transform.transform_vector_normal(match geom.normals.mapping {
         FbxMapping::ByPolygonVertex => *geom.normals
             .elements
             .get(relative_index)
@@ -740,16 +940,16 @@ fn convert_vertex(geom: &FbxGeometry,
             .get(index)
             .ok_or(FbxError::IndexOutOfBounds)?,
         _ => Vec3 { x: 0.0, y: 1.0, z: 0.0 }
-    });
+    }));
 
-    let tangent = geometric_transform.transform_vector_normal(match geom.tangents.mapping {
+    let tangent = conversion.direction(geometric_transform.transform_vector_normal(match geom.tangents.mapping {
         FbxMapping::ByPolygonVertex => *geom.tangents
             .elements
             .get(relative_index)
             .ok_or(FbxError::IndexOutOfBounds)?,
         FbxMapping::ByVertex => *geom.tangents.elements.get(index).ok_or(FbxError::IndexOutOfBounds)?,
         _ => Vec3 { x: 0.0, y: 1.0, z: 0.0 }
-    });
+    }));
 
     let uv = match geom.uvs.mapping {
         FbxMapping::ByPolygonVertex => {
@@ -774,6 +974,14 @@ fn convert_vertex(geom: &FbxGeometry,
         _ => Vec2 { x: 0.0, y: 0.0 }
     };
 
+    let second_tex_coord = geom.second_uvs
+        .get(index, relative_index)?
+        .unwrap_or(Vec2 { x: 0.0, y: 0.0 });
+
+    let color = geom.colors
+        .get(index, relative_index)?
+        .unwrap_or(Color::WHITE);
+
     let material = match geom.materials.mapping {
         FbxMapping::AllSame => *geom.materials
             .elements
@@ -786,30 +994,245 @@ fn convert_vertex(geom: &FbxGeometry,
         _ => 0
     };
 
-    let surface = mesh.surfaces_mut()
-        .get_mut(material)
-        .unwrap();
-
-    let is_unique_vertex = surface.get_data().lock().unwrap().insert_vertex(Vertex {
+    Ok((material, Vertex {
         position,
         normal,
         tex_coord: uv,
-        tangent: Vec4 { x: tangent.x, y: tangent.y, z: tangent.z, w: 1.0 },
+        // Mirroring flips handedness of tangent space.
+        tangent: Vec4 { x: tangent.x, y: tangent.y, z: tangent.z, w: if conversion.is_mirroring() { -1.0 } else { 1.0 } },
         // We can't get correct values for bone weights and indices because
         // not all nodes are converted yet at this stage. Actual calculation
         // will be performed later on after converting all nodes.
         bone_weights: [0.0, 0.0, 0.0, 0.0],
         bone_indices: [0, 0, 0, 0],
-    });
+        second_tex_coord,
+        color,
+    }))
+}
 
-    if is_unique_vertex && !skin_data.is_empty() {
-        surface.vertex_weights.push(*skin_data.get(index).ok_or(FbxError::IndexOutOfBounds)?);
+/// Converts faces of geometry into triangles of surfaces of mesh. Returns index of control
+/// point of each vertex of each surface, it is used to convert blend shapes. If
+/// `keep_control_points` is set, vertices of different control points are never welded,
+/// even if they are equal - control points may have different skin weights or blend shape
+/// offsets.
+fn convert_faces(geom: &FbxGeometry,
+                 mesh: &mut Mesh,
+                 geometric_transform: &Mat4,
+                 conversion: &FbxConversion,
+                 skin_data: &[VertexWeightSet],
+                 keep_control_points: bool) -> Result<Vec<Vec<usize>>, FbxError> {
+    let mut temp_vertices: Vec<Vec3> = Vec::new();
+    let mut triangles = Vec::new();
+    let mut relative_triangles = Vec::new();
+
+    // Mirrored triangles must have reversed winding to keep facing outside.
+    let winding = if conversion.is_mirroring() { [2, 1, 0] } else { [0, 1, 2] };
+
+    let mut control_points = vec![Vec::new(); mesh.surfaces().len()];
+    let mut material_index = 0;
+    let mut n = 0;
+    while n < geom.indices.len() {
+        let origin = n;
+        n += prepare_next_face(geom, n, &mut temp_vertices, &mut triangles, &mut relative_triangles);
+        for i in 0..triangles.len() {
+            let triangle = &triangles[i];
+            let relative_triangle = &relative_triangles[i];
+            for k in winding.iter() {
+                let index = triangle[*k];
+                let relative_index = origin + relative_triangle[*k];
+                let (surface_index, vertex) = convert_vertex(geom, geometric_transform, conversion, material_index, index, relative_index)?;
+                let surface = mesh.surfaces_mut()
+                    .get_mut(surface_index)
+                    .ok_or(FbxError::IndexOutOfBounds)?;
+                let surface_control_points = &mut control_points[surface_index];
+                let is_unique_vertex = if keep_control_points {
+                    surface.get_data()
+                        .lock()
+                        .unwrap()
+                        .insert_vertex_filtered(vertex, |i| surface_control_points.get(i as usize) == Some(&index))
+                } else {
+                    surface.get_data().lock().unwrap().insert_vertex(vertex)
+                };
+                // Remember control point of each unique vertex, this is needed to map control
+                // points to vertices of surfaces.
+                if is_unique_vertex {
+                    surface_control_points.push(index);
+                    if !skin_data.is_empty() {
+                        surface.vertex_weights.push(*skin_data.get(index).ok_or(FbxError::IndexOutOfBounds)?);
+                    }
+                }
+            }
+        }
+        if geom.materials.mapping == FbxMapping::ByPolygon {
+            material_index += 1;
+        }
     }
 
-    Ok(())
+    Ok(control_points)
+}
+
+/// Converts sparse shape offsets defined per control point to dense per-vertex offsets
+/// of a surface. `control_points` maps each vertex of surface to control point of geometry.
+fn convert_shape(shape: &FbxShape,
+                 name: &str,
+                 geometric_transform: &Mat4,
+                 conversion: &FbxConversion,
+                 control_points: &[usize]) -> BlendShape {
+    let mut lookup = HashMap::new();
+    for (i, index) in shape.indices.iter().enumerate() {
+        lookup.insert(*index as usize, i);
+    }
+
+    let mut blend_shape = BlendShape {
+        name: name.to_owned(),
+        position_offsets: Vec::with_capacity(control_points.len()),
+        normal_offsets: Vec::with_capacity(control_points.len()),
+    };
+
+    for control_point in control_points {
+        if let Some(i) = lookup.get(control_point) {
+            blend_shape.position_offsets.push(conversion.position(geometric_transform.transform_vector_normal(shape.vertices[*i])));
+            blend_shape.normal_offsets.push(match shape.normals.get(*i) {
+                Some(normal) => conversion.direction(geometric_transform.transform_vector_normal(*normal)),
+                None => Vec3::ZERO,
+            });
+        } else {
+            blend_shape.position_offsets.push(Vec3::ZERO);
+            blend_shape.normal_offsets.push(Vec3::ZERO);
+        }
+    }
+
+    blend_shape
 }
 
 impl Fbx {
+    /// Creates component of object node, returns none handle for unsupported objects.
+    fn read_object(&mut self, object_handle: Handle<FbxNode>, warnings: &mut Vec<String>) -> Result<Handle<FbxComponent>, FbxError> {
+        let object = self.nodes.borrow(object_handle);
+        let mut component_handle: Handle<FbxComponent> = Handle::NONE;
+        match object.name.as_str() {
+            "Geometry" => {
+                if object.attrib_count() > 2 && object.get_attrib(2)?.as_string() == "Shape" {
+                    component_handle = self.component_pool.spawn(FbxComponent::Shape(
+                        Box::new(FbxShape::read(object_handle, &self.nodes)?)));
+                } else {
+                    component_handle = self.component_pool.spawn(FbxComponent::Geometry(
+                        Box::new(FbxGeometry::read(object_handle, &self.nodes)?)));
+                }
+            }
+            "Model" => {
+                component_handle = self.component_pool.spawn(FbxComponent::Model(
+                    Box::new(FbxModel::read(object_handle, &self.nodes)?)));
+            }
+            "Material" => {
+                component_handle = self.component_pool.spawn(FbxComponent::Material(
+                    FbxMaterial::read(object_handle)?));
+            }
+            "Texture" => {
+                component_handle = self.component_pool.spawn(FbxComponent::Texture(
+                    FbxTexture::read(object_handle, &self.nodes)?));
+            }
+            "Video" => {
+                component_handle = self.component_pool.spawn(FbxComponent::Video(
+                    FbxVideo::read(object_handle, &self.nodes)?));
+            }
+            "NodeAttribute" => {
+                if object.attrib_count() > 2 {
+                    match object.get_attrib(2)?.as_string().as_str() {
+                        "Light" => {
+                            component_handle = self.component_pool.spawn(FbxComponent::Light(
+                                FbxLight::read(object_handle, &self.nodes, warnings)?));
+                        }
+                        "Camera" => {
+                            component_handle = self.component_pool.spawn(FbxComponent::Camera(
+                                FbxCamera::read(object_handle, &self.nodes, warnings)?));
+                        }
+                        _ => ()
+                    }
+                }
+            }
+            "AnimationCurve" => {
+                component_handle = self.component_pool.spawn(FbxComponent::AnimationCurve(
+                    FbxAnimationCurve::read(object_handle, &self.nodes, warnings)?));
+            }
+            "AnimationCurveNode" => {
+                component_handle = self.component_pool.spawn(FbxComponent::AnimationCurveNode(
+                    FbxAnimationCurveNode::read(object_handle, &self.nodes)?));
+            }
+            "Deformer" => {
+                match object.get_attrib(2)?.as_string().as_str() {
+                    "Cluster" => {
+                        component_handle = self.component_pool.spawn(FbxComponent::SubDeformer(
+                            FbxSubDeformer::read(object_handle, &self.nodes)?));
+                    }
+                    "Skin" => {
+                        component_handle = self.component_pool.spawn(FbxComponent::Deformer(
+                            FbxDeformer::read(object_handle, &self.nodes)?));
+                    }
+                    "BlendShape" => {
+                        component_handle = self.component_pool.spawn(FbxComponent::BlendShape(
+                            FbxBlendShape::read(object_handle, &self.nodes)?));
+                    }
+                    "BlendShapeChannel" => {
+                        component_handle = self.component_pool.spawn(FbxComponent::BlendShapeChannel(
+                            FbxBlendShapeChannel::read(object_handle, &self.nodes)?));
+                    }
+                    _ => ()
+                }
+            }
+            _ => ()
+        }
+        Ok(component_handle)
+    }
+
+    /// Describes object of given node for diagnostics.
+    fn object_info(&self, node_handle: Handle<FbxNode>) -> FbxObjectInfo {
+        let node = self.nodes.borrow(node_handle);
+        let name = if node.attrib_count() > 1 {
+            let name = node.attribs[1].as_string();
+            // ASCII files prefix names with class, like "Model::Cube".
+            match name.find("::") {
+                Some(separator) => name[separator + 2..].to_owned(),
+                None => name,
+            }
+        } else {
+            String::new()
+        };
+        FbxObjectInfo {
+            id: node.get_attrib(0).and_then(|attrib| attrib.as_i64()).unwrap_or_default(),
+            name,
+            node_path: node_path(&self.nodes, node_handle),
+            offset: node.offset,
+        }
+    }
+
+    /// Adds description of object of given node to error, innermost description is kept if
+    /// error already has one.
+    fn object_error(&self, node_handle: Handle<FbxNode>, error: FbxError) -> FbxError {
+        match error {
+            FbxError::Object { .. } => error,
+            _ if node_handle.is_none() => error,
+            _ => FbxError::Object {
+                object: self.object_info(node_handle),
+                error: Box::new(error),
+            }
+        }
+    }
+
+    fn component_node(&self, component_handle: Handle<FbxComponent>) -> Handle<FbxNode> {
+        self.component_nodes.get(&component_handle).copied().unwrap_or(Handle::NONE)
+    }
+
+    /// Records non-fatal problem of object of given node, see [FbxWarning](FbxWarning).
+    fn warn(&self, node_handle: Handle<FbxNode>, message: String) {
+        let warning = FbxWarning {
+            object: if node_handle.is_some() { Some(self.object_info(node_handle)) } else { None },
+            message,
+        };
+        Log::writeln(format!("FBX warning: {}", warning));
+        self.warnings.borrow_mut().push(warning);
+    }
+
     /// Parses FBX DOM and filling internal lists to prepare
     /// for conversion to engine format
     fn prepare(&mut self) -> Result<(), FbxError> {
@@ -817,65 +1240,29 @@ impl Fbx {
         let header_handle = find_node(&self.nodes, self.root, "FBXHeaderExtension")?;
         let version = find_and_borrow_node(&self.nodes, header_handle, "FBXVersion")?;
         let version = version.get_attrib(0)?.as_i32()?;
-        if version < 7100 && version > 7400 {
+        if version < MIN_VERSION || version > MAX_VERSION {
             return Err(FbxError::UnsupportedVersion(version));
         }
 
         // Read objects
-        let objects_node = find_and_borrow_node(&self.nodes, self.root, "Objects")?;
-        for object_handle in objects_node.children.iter() {
-            let object = self.nodes.borrow(*object_handle);
-            let index = object.get_attrib(0)?.as_i64()?;
-            let mut component_handle: Handle<FbxComponent> = Handle::NONE;
-            match object.name.as_str() {
-                "Geometry" => {
-                    component_handle = self.component_pool.spawn(FbxComponent::Geometry(
-                        Box::new(FbxGeometry::read(*object_handle, &self.nodes)?)));
-                }
-                "Model" => {
-                    component_handle = self.component_pool.spawn(FbxComponent::Model(
-                        Box::new(FbxModel::read(*object_handle, &self.nodes)?)));
-                }
-                "Material" => {
-                    component_handle = self.component_pool.spawn(FbxComponent::Material(
-                        FbxMaterial::read(*object_handle)?));
-                }
-                "Texture" => {
-                    component_handle = self.component_pool.spawn(FbxComponent::Texture(
-                        FbxTexture::read(*object_handle, &self.nodes)?));
-                }
-                "NodeAttribute" => {
-                    if object.attrib_count() > 2 && object.get_attrib(2)?.as_string() == "Light" {
-                        component_handle = self.component_pool.spawn(FbxComponent::Light(
-                            FbxLight::read(*object_handle, &self.nodes)?));
-                    }
-                }
-                "AnimationCurve" => {
-                    component_handle = self.component_pool.spawn(FbxComponent::AnimationCurve(
-                        FbxAnimationCurve::read(*object_handle, &self.nodes)?));
-                }
-                "AnimationCurveNode" => {
-                    component_handle = self.component_pool.spawn(FbxComponent::AnimationCurveNode(
-                        FbxAnimationCurveNode::read(*object_handle, &self.nodes)?));
-                }
-                "Deformer" => {
-                    match object.get_attrib(2)?.as_string().as_str() {
-                        "Cluster" => {
-                            component_handle = self.component_pool.spawn(FbxComponent::SubDeformer(
-                                FbxSubDeformer::read(*object_handle, &self.nodes)?));
-                        }
-                        "Skin" => {
-                            component_handle = self.component_pool.spawn(FbxComponent::Deformer(
-                                FbxDeformer::read(*object_handle, &self.nodes)?));
-                        }
-                        _ => ()
-                    }
-                }
-                _ => ()
+        let objects_handle = find_node(&self.nodes, self.root, "Objects")?;
+        let object_handles = self.nodes.borrow(objects_handle).children.clone();
+        for object_handle in object_handles {
+            let index = self.nodes
+                .borrow(object_handle)
+                .get_attrib(0)
+                .and_then(|attrib| attrib.as_i64())
+                .map_err(|e| self.object_error(object_handle, FbxError::Custom(e)))?;
+            let mut warnings = Vec::new();
+            let component_handle = self.read_object(object_handle, &mut warnings)
+                .map_err(|e| self.object_error(object_handle, e))?;
+            for warning in warnings {
+                self.warn(object_handle, warning);
             }
             if !component_handle.is_none() {
                 self.index_to_component.insert(index, component_handle);
                 self.components.push(component_handle);
+                self.component_nodes.insert(component_handle, object_handle);
             }
         }
 
@@ -885,10 +1272,12 @@ impl Fbx {
             let connection = self.nodes.borrow(*connection_handle);
             let child_index = connection.get_attrib(1)?.as_i64()?;
             let parent_index = connection.get_attrib(2)?.as_i64()?;
+            // Object-property connections have name of property as last attribute.
+            let property = connection.get_attrib(3).map(|property| property.as_string()).unwrap_or_default();
             if let Some(parent_handle) = self.index_to_component.get(&parent_index) {
                 if let Some(child_handle) = self.index_to_component.get(&child_index) {
                     let (child, parent) = self.component_pool.borrow_two_mut((*child_handle, *parent_handle));
-                    link_child_with_parent_component(parent, child, *child_handle);
+                    link_child_with_parent_component(parent, child, *child_handle, &property);
                 }
             }
         }
@@ -896,13 +1285,14 @@ impl Fbx {
         Ok(())
     }
 
-    fn convert_light(&self, fbx_light: &FbxLight) -> Light {
+    fn convert_light(&self, fbx_light: &FbxLight, conversion: &FbxConversion) -> Light {
+        let radius = conversion.length(fbx_light.radius);
         let light_kind = match fbx_light.actual_type {
             FbxLightType::Point | FbxLightType::Directional | FbxLightType::Area | FbxLightType::Volume => {
-                LightKind::Point(PointLight::new(fbx_light.radius))
+                LightKind::Point(PointLight::new(radius))
             }
             FbxLightType::Spot => {
-                LightKind::Spot(SpotLight::new(fbx_light.radius, fbx_light.hotspot_cone_angle, fbx_light.falloff_cone_angle_delta))
+                LightKind::Spot(SpotLight::new(radius, fbx_light.hotspot_cone_angle, fbx_light.falloff_cone_angle_delta))
             }
         };
 
@@ -913,17 +1303,92 @@ impl Fbx {
         light
     }
 
+    /// Cameras of models are disabled, otherwise every instance of a model would render
+    /// the scene once more. Game enables the camera it wants to look through.
+    fn convert_camera(&self, fbx_camera: &FbxCamera, conversion: &FbxConversion) -> Camera {
+        CameraBuilder::new(BaseBuilder::new())
+            .with_fov(fbx_camera.vertical_fov())
+            .with_z_near(conversion.length(fbx_camera.near_plane))
+            .with_z_far(conversion.length(fbx_camera.far_plane))
+            .enabled(false)
+            .build()
+    }
+
+    /// Creates texture from media embedded in FBX, texture is registered in resource manager
+    /// by given path. Returns None if texture has no embedded media or it can't be decoded,
+    /// texture should be loaded from file then.
+    fn load_embedded_texture(&self,
+                             texture_handle: Handle<FbxComponent>,
+                             resource_manager: &mut ResourceManager,
+                             path: &Path,
+                             kind: TextureKind) -> Option<SharedTexture> {
+        let texture = self.component_pool.borrow(texture_handle).as_texture().ok()?;
+        if texture.video.is_none() {
+            return None;
+        }
+        let content = self.component_pool.borrow(texture.video).as_video().ok()?.content();
+        if content.is_empty() {
+            return None;
+        }
+        // Same media can be embedded in many models.
+        if let Some(texture) = resource_manager.find_texture(path) {
+            return Some(texture);
+        }
+        match Texture::load_from_memory(content, path, kind) {
+            Ok(texture) => {
+                Log::writeln(format!("Embedded texture {:?} is loaded!", path));
+                Some(resource_manager.register_texture(path, texture))
+            }
+            Err(e) => {
+                self.warn(self.component_node(texture_handle),
+                          format!("Unable to load embedded texture {:?}, it will be loaded from file. Reason: {}", path, e));
+                None
+            }
+        }
+    }
+
+    /// Creates texture from media embedded in FBX for texture of material, texture is registered
+    /// by its file name in texture path of import options. Returns None if material has no such
+    /// texture or texture has no embedded media.
+    fn load_embedded_material_texture(&self,
+                                      texture_handle: Handle<FbxComponent>,
+                                      resource_manager: &mut ResourceManager,
+                                      options: &ModelImportOptions,
+                                      kind: TextureKind) -> Option<SharedTexture> {
+        if texture_handle.is_none() {
+            return None;
+        }
+        let texture = self.component_pool.borrow(texture_handle).as_texture().ok()?;
+        let filename = texture.get_file_path().file_name()?;
+        let path = options.texture_path(resource_manager, filename);
+        self.load_embedded_texture(texture_handle, resource_manager, &path, kind)
+    }
+
     fn create_surfaces(&self,
                        mesh: &mut Mesh,
                        resource_manager: &mut ResourceManager,
-                       model: &FbxModel) -> Result<(), FbxError> {
+                       model: &FbxModel,
+                       options: &ModelImportOptions) -> Result<(), FbxError> {
+        let new_data = || {
+            let mut data = SurfaceSharedData::new();
+            data.set_weld_options(options.weld);
+            Arc::new(Mutex::new(data))
+        };
+
         // Create surfaces per material
         if model.materials.is_empty() {
-            mesh.add_surface(Surface::new(Arc::new(Mutex::new(SurfaceSharedData::new()))));
+            mesh.add_surface(Surface::new(new_data()));
         } else {
             for material_handle in model.materials.iter() {
-                let mut surface = Surface::new(Arc::new(Mutex::new(SurfaceSharedData::new())));
+                let mut surface = Surface::new(new_data());
                 let material = self.component_pool.borrow(*material_handle).as_material()?;
+                // Maps embedded in file are preferred over maps found by name patterns.
+                if let Some(texture) = self.load_embedded_material_texture(material.normal_texture, resource_manager, options, TextureKind::RGB8) {
+                    surface.set_normal_texture(texture);
+                }
+                if let Some(texture) = self.load_embedded_material_texture(material.specular_texture, resource_manager, options, TextureKind::R8) {
+                    surface.set_specular_texture(texture);
+                }
                 if material.diffuse_texture.is_some() {
                     let texture = self.component_pool.borrow(material.diffuse_texture).as_texture()?;
                     let path = texture.get_file_path();
@@ -931,21 +1396,35 @@ impl Fbx {
                         let file_stem = path.file_stem().ok_or(FbxError::InvalidPath)?;
                         let extension = path.extension().ok_or(FbxError::InvalidPath)?;
 
-                        let diffuse_path = resource_manager.textures_path().join(&filename);
-                        // Here we will load *every* texture as RGBA8, this probably is overkill,
-                        // that will lead to higher memory consumption, but this will remove
-                        // problems with transparent textures (like mesh texture, etc.)
-                        surface.set_diffuse_texture(resource_manager.request_texture_async(diffuse_path.as_path(), TextureKind::RGBA8));
-
-                        let mut normal_map_name = file_stem.to_os_string();
-                        normal_map_name.push("_normal.");
-                        normal_map_name.push(extension);
-                        let normal_path = resource_manager.textures_path().join(normal_map_name);
-                        if normal_path.exists() {
-                            // Not sure if alpha channel is useful on normal maps, so will use RGB8 here.
-                            // Potentially it can be used to store some per-pixel material data like
-                            // roughness, shininess, etc. For now this is a TODO.
-                            surface.set_normal_texture(resource_manager.request_texture_async(normal_path.as_path(), TextureKind::RGB8));
+                        let diffuse_path = options.texture_path(resource_manager, filename);
+                        let diffuse_texture = match self.load_embedded_texture(material.diffuse_texture, resource_manager, &diffuse_path, options.diffuse_texture_kind) {
+                            Some(texture) => texture,
+                            None => {
+                                if !resource_manager.vfs().read().unwrap().exists(&diffuse_path) {
+                                    self.warn(self.component_node(material.diffuse_texture),
+                                              format!("Texture {:?} is not found in texture search paths", filename));
+                                }
+                                resource_manager.request_texture_async(diffuse_path.as_path(), options.diffuse_texture_kind)
+                            }
+                        };
+                        surface.set_diffuse_texture(diffuse_texture);
+
+                        if let Some(pattern) = options.normal_map_pattern.as_ref().filter(|_| surface.get_normal_texture().is_none()) {
+                            let normal_map_name = ModelImportOptions::map_file_name(pattern, file_stem, extension);
+                            if let Some(normal_path) = options.find_texture(resource_manager, normal_map_name.as_ref()) {
+                                // Not sure if alpha channel is useful on normal maps, so will use RGB8 here.
+                                // Potentially it can be used to store some per-pixel material data like
+                                // roughness, shininess, etc. For now this is a TODO.
+                                surface.set_normal_texture(resource_manager.request_texture_async(normal_path.as_path(), TextureKind::RGB8));
+                            }
+                        }
+
+                        if let Some(pattern) = options.specular_map_pattern.as_ref().filter(|_| surface.get_specular_texture().is_none()) {
+                            let specular_map_name = ModelImportOptions::map_file_name(pattern, file_stem, extension);
+                            if let Some(specular_path) = options.find_texture(resource_manager, specular_map_name.as_ref()) {
+                                // Only red channel is used as specular intensity.
+                                surface.set_specular_texture(resource_manager.request_texture_async(specular_path.as_path(), TextureKind::R8));
+                            }
                         }
                     }
                 }
@@ -958,42 +1437,51 @@ impl Fbx {
 
     fn convert_mesh(&self,
                     resource_manager: &mut ResourceManager,
-                    model: &FbxModel) -> Result<Mesh, FbxError> {
+                    model: &FbxModel,
+                    conversion: &FbxConversion,
+                    options: &ModelImportOptions) -> Result<Mesh, FbxError> {
         let mut mesh = Mesh::default();
 
         let geometric_transform = Mat4::translate(model.geometric_translation) *
             Mat4::from_quat(quat_from_euler(model.geometric_rotation)) *
             Mat4::scale(model.geometric_scale);
 
-        let mut temp_vertices: Vec<Vec3> = Vec::new();
-        let mut triangles = Vec::new();
-        let mut relative_triangles = Vec::new();
-
         for geom_handle in &model.geoms {
             let geom = self.component_pool.borrow(*geom_handle).as_geometry()?;
-            self.create_surfaces(&mut mesh, resource_manager, model)?;
-
-            let skin_data = geom.get_skin_data(&self.component_pool)?;
-
-            let mut material_index = 0;
-            let mut n = 0;
-            while n < geom.indices.len() {
-                let origin = n;
-                n += prepare_next_face(geom, n, &mut temp_vertices, &mut triangles, &mut relative_triangles);
-                for i in 0..triangles.len() {
-                    let triangle = &triangles[i];
-                    let relative_triangle = &relative_triangles[i];
-                    for (index, relative_index) in triangle.iter().zip(relative_triangle.iter()) {
-                        let relative_index = origin + *relative_index;
-                        convert_vertex(geom, &mut mesh, &geometric_transform, material_index, *index, relative_index, &skin_data)?;
-                    }
+            self.create_surfaces(&mut mesh, resource_manager, model, options)?;
+
+            let geom_node = self.component_node(*geom_handle);
+            let mut warnings = Vec::new();
+            let skin_data = geom.get_skin_data(&self.component_pool, &mut warnings)?;
+            let blend_shape_channels = geom.get_blend_shape_channels(&self.component_pool)?;
+            for (channel, _) in blend_shape_channels.iter() {
+                if channel.shapes.len() > 1 {
+                    warnings.push(format!("Blend shape channel {} has in-between shapes, only first one is used", channel.name));
                 }
-                if geom.materials.mapping == FbxMapping::ByPolygon {
-                    material_index += 1;
+            }
+            for warning in warnings {
+                self.warn(geom_node, warning);
+            }
+
+            let keep_control_points = !skin_data.is_empty() || !blend_shape_channels.is_empty();
+            let control_points = convert_faces(geom, &mut mesh, &geometric_transform, conversion, &skin_data, keep_control_points)?;
+
+            for (i, (channel, shape)) in blend_shape_channels.iter().enumerate() {
+                for (surface, surface_control_points) in mesh.surfaces().iter().zip(control_points.iter()) {
+                    let blend_shape = convert_shape(shape, &channel.name, &geometric_transform, conversion, surface_control_points);
+                    if let Err(e) = surface.get_data().lock().unwrap().add_blend_shape(blend_shape) {
+                        self.warn(geom_node, e);
+                    }
                 }
+                mesh.set_blend_shape_weight(i, channel.deform_percent / 100.0);
             }
 
-            if geom.tangents.mapping == FbxMapping::Unknown {
+            let calculate_tangents = match options.tangents {
+                TangentPolicy::FromFile => geom.tangents.mapping == FbxMapping::Unknown,
+                TangentPolicy::Calculate => true,
+                TangentPolicy::Never => false,
+            };
+            if calculate_tangents {
                 for surface in mesh.surfaces_mut() {
                     surface.get_data()
                         .lock()
@@ -1011,36 +1499,71 @@ impl Fbx {
                      resource_manager: &mut ResourceManager,
                      graph: &mut Graph,
                      animations: &mut AnimationContainer,
-                     animation_handle: Handle<Animation>)
+                     animation_handle: Handle<Animation>,
+                     conversion: &FbxConversion,
+                     options: &ModelImportOptions)
                      -> Result<Handle<Node>, FbxError> {
-        // Create node with correct kind.
+        // Create node with correct kind, only skeleton is needed for animations.
         let mut node =
-            if !model.geoms.is_empty() {
-                Node::Mesh(self.convert_mesh(resource_manager, model)?)
+            if options.animation_only {
+                Node::Base(Base::default())
+            } else if !model.geoms.is_empty() {
+                Node::Mesh(self.convert_mesh(resource_manager, model, conversion, options)?)
             } else if model.light.is_some() {
                 let fbx_light_component = self.component_pool.borrow(model.light);
-                Node::Light(self.convert_light(fbx_light_component.as_light()?))
+                Node::Light(self.convert_light(fbx_light_component.as_light()?, conversion))
+            } else if model.camera.is_some() {
+                let fbx_camera_component = self.component_pool.borrow(model.camera);
+                Node::Camera(self.convert_camera(fbx_camera_component.as_camera()?, conversion))
             } else {
                 Node::Base(Base::default())
             };
 
-        let node_local_rotation = quat_from_euler(model.rotation);
+        let node_local_rotation = conversion.rotation(quat_from_euler(model.rotation));
+        let node_local_translation = conversion.position(model.translation);
+        let node_local_scale = conversion.scale(model.scale);
+        let mut post_rotation = conversion.rotation(quat_from_euler(model.post_rotation));
+        if let Node::Camera(_) = node {
+            post_rotation = conversion.camera_post_rotation(post_rotation);
+        }
         node.base_mut()
             .set_name(model.name.as_str())
             .local_transform_mut()
             .set_rotation(node_local_rotation)
-            .set_scale(model.scale)
-            .set_position(model.translation)
-            .set_post_rotation(quat_from_euler(model.post_rotation))
-            .set_pre_rotation(quat_from_euler(model.pre_rotation))
-            .set_rotation_offset(model.rotation_offset)
-            .set_rotation_pivot(model.rotation_pivot)
-            .set_scaling_offset(model.scaling_offset)
-            .set_scaling_pivot(model.scaling_pivot);
-        node.base_mut().inv_bind_pose_transform = model.inv_bind_transform;
+            .set_scale(node_local_scale)
+            .set_position(node_local_translation)
+            .set_post_rotation(post_rotation)
+            .set_pre_rotation(conversion.rotation(quat_from_euler(model.pre_rotation)))
+            .set_rotation_offset(conversion.position(model.rotation_offset))
+            .set_rotation_pivot(conversion.position(model.rotation_pivot))
+            .set_scaling_offset(conversion.position(model.scaling_offset))
+            .set_scaling_pivot(conversion.position(model.scaling_pivot));
+        node.base_mut().inv_bind_pose_transform = conversion.matrix(model.inv_bind_transform);
 
         let node_handle = graph.add_node(node);
 
+        // Convert blend shape weight animations
+        let geoms = if options.animation_only { &[][..] } else { &model.geoms[..] };
+        for geom_handle in geoms.iter() {
+            let geom = self.component_pool.borrow(*geom_handle).as_geometry()?;
+            for (i, (channel, _)) in geom.get_blend_shape_channels(&self.component_pool)?.iter().enumerate() {
+                if channel.animation_curve_node.is_none() {
+                    continue;
+                }
+                if let FbxComponent::AnimationCurveNode(curve_node) = self.component_pool.borrow(channel.animation_curve_node) {
+                    if let Some(curve_handle) = curve_node.curves.first() {
+                        if let FbxComponent::AnimationCurve(curve) = self.component_pool.borrow(*curve_handle) {
+                            let mut track = PropertyTrack::new(node_handle, Property::BlendShapeWeight(i));
+                            for key in curve.keys.iter() {
+                                track.add_key_frame(PropertyKeyFrame::new(key.time, PropertyValue::Float(key.value / 100.0)));
+                            }
+                            animations.get_mut(animation_handle).add_property_track(track);
+                        }
+                    }
+                }
+            }
+        }
+
         // Convert animations
         if !model.animation_curve_nodes.is_empty() {
             // Find supported curve nodes (translation, rotation, scale)
@@ -1068,22 +1591,22 @@ impl Fbx {
             loop {
                 let translation =
                     if let Some(curve) = lcl_translation {
-                        curve.eval_vec3(&self.component_pool, time)
+                        conversion.position(curve.eval_vec3(&self.component_pool, time))
                     } else {
-                        model.translation
+                        node_local_translation
                     };
 
                 let rotation =
                     if let Some(curve) = lcl_rotation {
-                        quat_from_euler(curve.eval_vec3(&self.component_pool, time))
+                        conversion.rotation(quat_from_euler(curve.eval_vec3(&self.component_pool, time)))
                     } else {
                         node_local_rotation
                     };
 
                 let scale = if let Some(curve) = lcl_scale {
-                    curve.eval_vec3(&self.component_pool, time)
+                    conversion.scale(curve.eval_vec3(&self.component_pool, time))
                 } else {
-                    model.scale
+                    node_local_scale
                 };
 
                 track.add_key_frame(KeyFrame::new(time, translation, scale, rotation));
@@ -1121,9 +1644,14 @@ impl Fbx {
     }
 
     ///
-    /// Converts FBX DOM to native engine representation.
+    /// Converts FBX DOM to native engine representation, conversion is controlled by given
+    /// model import options.
     ///
-    pub fn convert(&self, resource_manager: &mut ResourceManager, scene: &mut Scene) -> Result<Handle<Node>, FbxError> {
+    pub fn convert(&self, resource_manager: &mut ResourceManager, scene: &mut Scene, options: &ModelImportOptions) -> Result<Handle<Node>, FbxError> {
+        let global_settings = FbxGlobalSettings::read(&self.nodes, self.root)?;
+        let conversion = FbxConversion::new(&global_settings, &options.fbx_import_options, options.scale);
+
+        // Pairs of FBX model and node instantiated from it.
         let mut instantiated_nodes = Vec::new();
         let root = scene.graph.add_node(Node::Base(Base::default()));
         let animation_handle = scene.animations.add(Animation::default());
@@ -1131,8 +1659,9 @@ impl Fbx {
         for component_handle in self.components.iter() {
             let component = self.component_pool.borrow(*component_handle);
             if let FbxComponent::Model(model) = component {
-                let node = self.convert_model(model, resource_manager, &mut scene.graph, &mut scene.animations, animation_handle)?;
-                instantiated_nodes.push(node);
+                let node = self.convert_model(model, resource_manager, &mut scene.graph, &mut scene.animations, animation_handle, &conversion, options)
+                    .map_err(|e| self.object_error(self.component_node(*component_handle), e))?;
+                instantiated_nodes.push((*component_handle, node));
                 scene.graph.link_nodes(node, root);
                 fbx_model_to_node_map.insert(*component_handle, node);
             }
@@ -1151,7 +1680,8 @@ impl Fbx {
 
         // Remap handles from fbx model to handles of instantiated nodes
         // on each surface of each mesh.
-        for handle in instantiated_nodes.iter() {
+        for (fbx_model_handle, handle) in instantiated_nodes.iter() {
+            let model_node = self.component_node(*fbx_model_handle);
             let node = scene.graph.get_mut(*handle);
             if let Node::Mesh(mesh) = node {
                 let mut surface_bones = HashSet::new();
@@ -1160,7 +1690,7 @@ impl Fbx {
                         for weight in weight_set.iter_mut() {
                             let fbx_model: Handle<FbxComponent> = weight.effector.into();
                             let bone_handle = fbx_model_to_node_map.get(&fbx_model)
-                                .ok_or(FbxError::UnableToRemapModelToNode)?;
+                                .ok_or_else(|| self.object_error(model_node, FbxError::UnableToRemapModelToNode))?;
                             surface_bones.insert(*bone_handle);
                             weight.effector = (*bone_handle).into();
                         }
@@ -1175,7 +1705,7 @@ impl Fbx {
                     if data.get_vertices().len() == surface.vertex_weights.len() {
                         for (i, vertex) in data.get_vertices_mut().iter_mut().enumerate() {
                             let weight_set = surface.vertex_weights.get_mut(i)
-                                .ok_or(FbxError::IndexOutOfBounds)?;
+                                .ok_or_else(|| self.object_error(model_node, FbxError::IndexOutOfBounds))?;
                             for (k, weight) in weight_set.iter().enumerate() {
                                 vertex.bone_indices[k] = {
                                     let mut index = None;
@@ -1185,7 +1715,7 @@ impl Fbx {
                                             break;
                                         }
                                     }
-                                    index.ok_or(FbxError::UnableToFindBone)? as u8
+                                    index.ok_or_else(|| self.object_error(model_node, FbxError::UnableToFindBone))? as u8
                                 };
                                 vertex.bone_weights[k] = weight.value;
                             }
@@ -1195,6 +1725,28 @@ impl Fbx {
             }
         }
 
+        // Lookup tables of welding are not needed anymore, meshes are built.
+        let now = Instant::now();
+        for (_, handle) in instantiated_nodes.iter() {
+            if let Node::Mesh(mesh) = scene.graph.get_mut(*handle) {
+                for surface in mesh.surfaces_mut() {
+                    let data_rc = surface.get_data();
+                    let mut data = data_rc.lock().unwrap();
+                    if options.optimize_meshes {
+                        let order = data.optimize();
+                        if surface.vertex_weights.len() == order.len() {
+                            surface.vertex_weights = order.iter().map(|i| surface.vertex_weights[*i as usize]).collect();
+                        }
+                    } else {
+                        data.clear_weld_cache();
+                    }
+                }
+            }
+        }
+        if options.optimize_meshes {
+            Log::writeln(format!("\t- Mesh optimization - {} ms", now.elapsed().as_millis()));
+        }
+
         Ok(root)
     }
 
@@ -1213,19 +1765,21 @@ impl Fbx {
     }
 }
 
-pub fn load_to_scene<P: AsRef<Path>>(scene: &mut Scene, resource_manager: &mut ResourceManager, path: P) -> Result<Handle<Node>, FbxError> {
+/// Loads FBX file to given scene, returns root of loaded nodes and non-fatal problems of
+/// file. In strict mode of options warnings are turned into [FbxError::Warnings](FbxError::Warnings).
+pub fn load_to_scene<P: AsRef<Path>>(scene: &mut Scene,
+                                     resource_manager: &mut ResourceManager,
+                                     path: P,
+                                     options: &ModelImportOptions) -> Result<(Handle<Node>, Vec<FbxWarning>), FbxError> {
     let start_time = Instant::now();
 
-    let mut file = File::open(path.as_ref())?;
-
     Log::writeln(format!("Trying to load {:?}", path.as_ref()));
 
     let now = Instant::now();
-    let is_bin = fbx_binary::is_binary(path.as_ref())?;
+    let file_content = resource_manager.vfs().read().unwrap().read(path.as_ref())?;
+    let is_bin = fbx_binary::is_binary(&file_content);
 
-    let buf_len = file.metadata()?.len() as usize;
-    let mut file_content = Vec::with_capacity(buf_len);
-    file.read_to_end(&mut file_content)?;
+    let buf_len = file_content.len();
     let mut reader = Cursor::new(file_content);
 
     let mut fbx = if is_bin {
@@ -1240,10 +1794,275 @@ pub fn load_to_scene<P: AsRef<Path>>(scene: &mut Scene, resource_manager: &mut R
     Log::writeln(format!("\t- DOM Prepare - {} ms", now.elapsed().as_millis()));
 
     let now = Instant::now();
-    let result = fbx.convert(resource_manager, scene);
+    let root = fbx.convert(resource_manager, scene, options)?;
     Log::writeln(format!("\t- Conversion - {} ms", now.elapsed().as_millis()));
 
-    Log::writeln(format!("\t- {:?} loaded in {} ms", path.as_ref(), start_time.elapsed().as_millis()));
+    let warnings = fbx.warnings.into_inner();
+    if options.strict && !warnings.is_empty() {
+        return Err(FbxError::Warnings(warnings));
+    }
+
+    Log::writeln(format!("\t- {:?} loaded in {} ms with {} warning(s)", path.as_ref(), start_time.elapsed().as_millis(), warnings.len()));
 
-    result
-}
\ No newline at end of file
+    Ok((root, warnings))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Cursor,
+        sync::{Arc, Mutex},
+    };
+    use crate::{
+        core::{
+            math::{
+                vec2::Vec2,
+                vec3::Vec3,
+                mat4::Mat4,
+            },
+            color::Color,
+        },
+        renderer::surface::{Surface, SurfaceSharedData},
+        scene::mesh::Mesh,
+        resource::fbx::{
+            fbx_ascii,
+            error::FbxError,
+            geometry::FbxGeometry,
+            blend_shape::FbxShape,
+            conversion::{FbxConversion, FbxGlobalSettings, FbxImportOptions},
+            FbxContainer,
+            FbxMapping,
+            FbxReference,
+            FbxCamera,
+            FbxApertureMode,
+            convert_faces,
+            convert_shape,
+        },
+    };
+
+    const HEADER: &str = "FBXHeaderExtension: {\n\tFBXVersion: 7400\n}\n";
+
+    #[test]
+    fn object_diagnostics() {
+        // Model without Properties70 is invalid.
+        let text = format!("{}Objects: {{\n\tModel: 123, \"Model::Cube\", \"Mesh\" {{\n\t}}\n}}\nConnections: {{\n}}\n", HEADER);
+        let mut fbx = fbx_ascii::read_ascii(&mut Cursor::new(text.as_bytes()), text.len() as u64).unwrap();
+        match fbx.prepare() {
+            Err(FbxError::Object { object, .. }) => {
+                assert_eq!(object.id, 123);
+                assert_eq!(object.name, "Cube");
+                assert_eq!(object.node_path, "Objects/Model");
+                assert_eq!(object.offset, text.find("\tModel").unwrap() as u64);
+            }
+            _ => panic!("Expected error of Cube model"),
+        }
+
+        // Unknown light type is not fatal.
+        let text = format!("{}Objects: {{\n\tNodeAttribute: 7, \"NodeAttribute::Lamp\", \"Light\" {{\n\t\tProperties70: {{\n\t\t\tP: \"LightType\", \"enum\", \"\", \"\",9\n\t\t}}\n\t}}\n}}\nConnections: {{\n}}\n", HEADER);
+        let mut fbx = fbx_ascii::read_ascii(&mut Cursor::new(text.as_bytes()), text.len() as u64).unwrap();
+        fbx.prepare().unwrap();
+        let warnings = fbx.warnings.into_inner();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].object.as_ref().map(|object| object.name.as_str()), Some("Lamp"));
+    }
+
+    /// Two triangles which share an edge, normals are defined per polygon vertex and differ
+    /// between triangles, so shared control points produce two vertices each.
+    fn make_geometry() -> FbxGeometry {
+        let up = Vec3::new(0.0, 0.0, 1.0);
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        FbxGeometry {
+            vertices: vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)],
+            // Last index of polygon is stored as bitwise negation.
+            indices: vec![0, 1, -3, 0, 2, -4],
+            normals: FbxContainer {
+                elements: vec![up, up, up, down, down, down],
+                index: Vec::new(),
+                mapping: FbxMapping::ByPolygonVertex,
+                reference: FbxReference::Direct,
+            },
+            uvs: Default::default(),
+            second_uvs: Default::default(),
+            colors: Default::default(),
+            materials: Default::default(),
+            tangents: Default::default(),
+            binormals: Default::default(),
+            deformers: Vec::new(),
+            blend_shapes: Vec::new(),
+        }
+    }
+
+    fn convert_geometry(geom: &FbxGeometry, keep_control_points: bool) -> (Mesh, Vec<Vec<usize>>) {
+        let mut mesh = Mesh::default();
+        mesh.add_surface(Surface::new(Arc::new(Mutex::new(SurfaceSharedData::new()))));
+        let conversion = FbxConversion::new(&FbxGlobalSettings::default(), &FbxImportOptions::no_conversion(), 1.0);
+        let control_points = convert_faces(geom, &mut mesh, &Mat4::IDENTITY, &conversion, &[], keep_control_points).unwrap();
+        (mesh, control_points)
+    }
+
+    #[test]
+    fn control_points_of_vertices() {
+        let geom = make_geometry();
+        let (mesh, control_points) = convert_geometry(&geom, false);
+        let data = mesh.surfaces()[0].get_data();
+        let data = data.lock().unwrap();
+
+        assert_eq!(control_points.len(), 1);
+        assert_eq!(data.get_vertices().len(), 6);
+        assert_eq!(control_points[0].len(), data.get_vertices().len());
+        for (vertex, control_point) in data.get_vertices().iter().zip(control_points[0].iter()) {
+            let position = geom.vertices[*control_point];
+            assert_eq!((vertex.position.x, vertex.position.y, vertex.position.z), (position.x, position.y, position.z));
+        }
+        // Shared control points are used by two vertices.
+        assert_eq!(control_points[0].iter().filter(|i| **i == 0).count(), 2);
+        assert_eq!(control_points[0].iter().filter(|i| **i == 2).count(), 2);
+    }
+
+    #[test]
+    fn blend_shape_conversion() {
+        let geom = make_geometry();
+        let (mesh, control_points) = convert_geometry(&geom, false);
+        let shape = FbxShape {
+            indices: vec![2, 3],
+            vertices: vec![Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, 2.0)],
+            normals: vec![Vec3::new(1.0, 0.0, 0.0)],
+        };
+        let conversion = FbxConversion::new(&FbxGlobalSettings::default(), &FbxImportOptions::no_conversion(), 2.0);
+        let blend_shape = convert_shape(&shape, "Smile", &Mat4::IDENTITY, &conversion, &control_points[0]);
+
+        assert_eq!(blend_shape.name, "Smile");
+        assert_eq!(blend_shape.position_offsets.len(), 6);
+        for ((offset, normal_offset), control_point) in blend_shape.position_offsets.iter()
+            .zip(blend_shape.normal_offsets.iter())
+            .zip(control_points[0].iter()) {
+            // Offsets are scaled as positions, missing normals are zero.
+            let (expected_offset, expected_normal) = match control_point {
+                2 => (2.0, 1.0),
+                3 => (4.0, 0.0),
+                _ => (0.0, 0.0),
+            };
+            assert_eq!((offset.x, offset.y, offset.z), (0.0, 0.0, expected_offset));
+            assert_eq!((normal_offset.x, normal_offset.y, normal_offset.z), (expected_normal, 0.0, 0.0));
+        }
+
+        let data = mesh.surfaces()[0].get_data();
+        let mut data = data.lock().unwrap();
+        assert!(data.add_blend_shape(blend_shape.clone()).is_ok());
+
+        // Blend shape of other surface can't be added.
+        let mut invalid = blend_shape;
+        invalid.normal_offsets.pop();
+        assert!(data.add_blend_shape(invalid).is_err());
+        assert_eq!(data.blend_shapes().len(), 1);
+    }
+
+    #[test]
+    fn coincident_control_points_with_different_offsets() {
+        // Two triangles touch at coincident control points 1 and 3, only control point 3 is
+        // moved by the shape.
+        let mut geom = make_geometry();
+        geom.vertices = vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0),
+                             Vec3::new(1.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 0.0)];
+        geom.indices = vec![0, 1, -3, 3, 4, -6];
+        geom.normals = Default::default();
+        let shape = FbxShape {
+            indices: vec![3],
+            vertices: vec![Vec3::new(0.0, 0.0, 1.0)],
+            normals: Vec::new(),
+        };
+
+        // Without blend shapes and skin equal vertices are welded.
+        let (mesh, _) = convert_geometry(&geom, false);
+        assert_eq!(mesh.surfaces()[0].get_data().lock().unwrap().get_vertices().len(), 5);
+
+        let (mesh, control_points) = convert_geometry(&geom, true);
+        assert_eq!(mesh.surfaces()[0].get_data().lock().unwrap().get_vertices().len(), 6);
+        let conversion = FbxConversion::new(&FbxGlobalSettings::default(), &FbxImportOptions::no_conversion(), 1.0);
+        let blend_shape = convert_shape(&shape, "Bend", &Mat4::IDENTITY, &conversion, &control_points[0]);
+        for (offset, control_point) in blend_shape.position_offsets.iter().zip(control_points[0].iter()) {
+            let expected_offset = if *control_point == 3 { 1.0 } else { 0.0 };
+            assert_eq!((offset.x, offset.y, offset.z), (0.0, 0.0, expected_offset));
+        }
+    }
+
+    #[test]
+    fn second_tex_coord_and_color() {
+        let mut geom = make_geometry();
+        // Second texture coordinates are stored per control point, colors per polygon.
+        geom.second_uvs = FbxContainer {
+            elements: vec![Vec2::new(0.0, 0.5), Vec2::new(1.0, 0.5), Vec2::new(1.0, 1.5), Vec2::new(0.0, 1.5)],
+            index: Vec::new(),
+            mapping: FbxMapping::ByVertex,
+            reference: FbxReference::Direct,
+        };
+        let red = Color::from_rgba(255, 0, 0, 255);
+        let green = Color::from_rgba(0, 255, 0, 128);
+        geom.colors = FbxContainer {
+            elements: vec![red, green],
+            index: vec![0, 0, 0, 1, 1, 1],
+            mapping: FbxMapping::ByPolygonVertex,
+            reference: FbxReference::IndexToDirect,
+        };
+        let (mesh, control_points) = convert_geometry(&geom, false);
+        let data = mesh.surfaces()[0].get_data();
+        let data = data.lock().unwrap();
+
+        assert_eq!(data.get_vertices().len(), 6);
+        for (vertex, control_point) in data.get_vertices().iter().zip(control_points[0].iter()) {
+            assert_eq!(vertex.second_tex_coord, geom.second_uvs.elements[*control_point]);
+            // First triangle faces up.
+            let expected_color = if vertex.normal.z > 0.0 { red } else { green };
+            assert_eq!(vertex.color, expected_color);
+        }
+
+        // Vertices without colors are white.
+        let (mesh, _) = convert_geometry(&make_geometry(), false);
+        let data = mesh.surfaces()[0].get_data();
+        assert!(data.lock().unwrap().get_vertices().iter().all(|vertex| vertex.color == Color::WHITE));
+    }
+
+    fn make_camera(aperture_mode: FbxApertureMode) -> FbxCamera {
+        FbxCamera {
+            aperture_mode,
+            field_of_view: 90.0,
+            field_of_view_y: 45.0,
+            aspect_width: 320.0,
+            aspect_height: 160.0,
+            focal_length: 12.0,
+            film_height: 24.0 / 25.4,
+            near_plane: 10.0,
+            far_plane: 4000.0,
+        }
+    }
+
+    #[test]
+    fn camera_vertical_fov() {
+        let expected = [
+            (FbxApertureMode::HorizontalAndVertical, 45.0f32.to_radians()),
+            // Horizontal field of view of 90 degrees with aspect ratio of 2.
+            (FbxApertureMode::Horizontal, 2.0 * 0.5f32.atan()),
+            (FbxApertureMode::Vertical, 90.0f32.to_radians()),
+            // Film height of 24 mm with focal length of 12 mm.
+            (FbxApertureMode::FocalLength, 90.0f32.to_radians()),
+        ];
+        for (aperture_mode, fov) in expected.iter() {
+            assert!((make_camera(*aperture_mode).vertical_fov() - fov).abs() < 1.0e-5);
+        }
+
+        // Zero aspect height does not produce NaN.
+        let mut camera = make_camera(FbxApertureMode::Horizontal);
+        camera.aspect_height = 0.0;
+        assert!((camera.vertical_fov() - 90.0f32.to_radians()).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn cameras_are_disabled() {
+        let text = format!("{}Objects: {{\n}}\nConnections: {{\n}}\n", HEADER);
+        let fbx = fbx_ascii::read_ascii(&mut Cursor::new(text.as_bytes()), text.len() as u64).unwrap();
+        let conversion = FbxConversion::new(&FbxGlobalSettings::default(), &FbxImportOptions::no_conversion(), 1.0);
+        let camera = fbx.convert_camera(&make_camera(FbxApertureMode::Vertical), &conversion);
+        assert!(!camera.is_enabled());
+        assert!((camera.fov() - 90.0f32.to_radians()).abs() < 1.0e-5);
+    }
+}
@@ -2,6 +2,7 @@ use std::path::PathBuf;
 use crate::{
     resource::fbx::{
         FbxNode,
+        FbxComponent,
         find_and_borrow_node,
     },
     core::{
@@ -14,12 +15,15 @@ use crate::{
 
 pub struct FbxTexture {
     filename: PathBuf,
+    /// Embedded media with content of texture, if any.
+    pub(in crate::resource::fbx) video: Handle<FbxComponent>,
 }
 
 impl FbxTexture {
     pub(in crate::resource::fbx) fn read(texture_node_hanle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<Self, String> {
         let mut texture = FbxTexture {
-            filename: PathBuf::new()
+            filename: PathBuf::new(),
+            video: Handle::NONE,
         };
         if let Ok(relative_file_name_node) = find_and_borrow_node(nodes, texture_node_hanle, "RelativeFilename") {
             // Since most of FBX files were made on Windows in 3ds MAX or Maya, it contains
@@ -36,4 +40,29 @@ impl FbxTexture {
     pub(in crate::resource::fbx) fn get_file_path(&self) -> &PathBuf {
         &self.filename
     }
-}
\ No newline at end of file
+}
+
+/// Media object, it stores content of a texture file when media is embedded in FBX.
+pub struct FbxVideo {
+    content: Vec<u8>,
+}
+
+impl FbxVideo {
+    pub(in crate::resource::fbx) fn read(video_node_handle: Handle<FbxNode>, nodes: &Pool<FbxNode>) -> Result<Self, String> {
+        // Only binary FBX stores content as raw data, base64 content of ASCII FBX is ignored.
+        let content = find_and_borrow_node(nodes, video_node_handle, "Content")
+            .ok()
+            .and_then(|content| content.get_attrib(0).ok())
+            .and_then(|content| content.as_raw())
+            .map(|content| content.to_vec())
+            .unwrap_or_default();
+        Ok(FbxVideo {
+            content
+        })
+    }
+
+    /// Returns content of file of embedded media, empty if media is not embedded.
+    pub(in crate::resource::fbx) fn content(&self) -> &[u8] {
+        &self.content
+    }
+}
@@ -0,0 +1,231 @@
+//! Reader of Khronos texture (KTX 1.1) files.
+//!
+//! Only little-endian 2D textures are supported. Block compressed formats are read as is
+//! with every mip level stored in file, uncompressed formats are read as R8, RGB8 or RGBA8.
+
+use byteorder::{ByteOrder, LittleEndian};
+use image::ImageError;
+use crate::resource::{
+    texture::{Texture, TextureKind},
+    block_compression,
+};
+
+const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+const HEADER_SIZE: usize = 64;
+const ENDIANNESS_LITTLE: u32 = 0x0403_0201;
+
+// Internal formats of OpenGL, they're duplicated here because loader does not depend on renderer.
+const COMPRESSED_RGB_S3TC_DXT1: u32 = 0x83F0;
+const COMPRESSED_RGBA_S3TC_DXT1: u32 = 0x83F1;
+const COMPRESSED_RGBA_S3TC_DXT3: u32 = 0x83F2;
+const COMPRESSED_RGBA_S3TC_DXT5: u32 = 0x83F3;
+const COMPRESSED_SRGB_S3TC_DXT1: u32 = 0x8C4C;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT1: u32 = 0x8C4D;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT3: u32 = 0x8C4E;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT5: u32 = 0x8C4F;
+const COMPRESSED_RED_RGTC1: u32 = 0x8DBB;
+const COMPRESSED_RG_RGTC2: u32 = 0x8DBD;
+const COMPRESSED_RGBA_BPTC_UNORM: u32 = 0x8E8C;
+const COMPRESSED_SRGB_ALPHA_BPTC_UNORM: u32 = 0x8E8D;
+const COMPRESSED_RGB_BPTC_SIGNED_FLOAT: u32 = 0x8E8E;
+const COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT: u32 = 0x8E8F;
+const R8: u32 = 0x8229;
+const RGB8: u32 = 0x8051;
+const RGBA8: u32 = 0x8058;
+const SRGB8: u32 = 0x8C41;
+const SRGB8_ALPHA8: u32 = 0x8C43;
+
+fn format_error<S: Into<String>>(message: S) -> ImageError {
+    ImageError::FormatError(message.into())
+}
+
+fn unsupported<S: Into<String>>(message: S) -> ImageError {
+    ImageError::UnsupportedError(message.into())
+}
+
+/// Returns kind of texture of given internal format and whether format stores colors in
+/// sRGB space.
+fn texture_kind(internal_format: u32) -> Result<(TextureKind, bool), ImageError> {
+    match internal_format {
+        COMPRESSED_RGB_S3TC_DXT1 | COMPRESSED_RGBA_S3TC_DXT1 => Ok((TextureKind::BC1, false)),
+        COMPRESSED_SRGB_S3TC_DXT1 | COMPRESSED_SRGB_ALPHA_S3TC_DXT1 => Ok((TextureKind::BC1, true)),
+        COMPRESSED_RGBA_S3TC_DXT3 => Ok((TextureKind::BC2, false)),
+        COMPRESSED_SRGB_ALPHA_S3TC_DXT3 => Ok((TextureKind::BC2, true)),
+        COMPRESSED_RGBA_S3TC_DXT5 => Ok((TextureKind::BC3, false)),
+        COMPRESSED_SRGB_ALPHA_S3TC_DXT5 => Ok((TextureKind::BC3, true)),
+        COMPRESSED_RED_RGTC1 => Ok((TextureKind::BC4, false)),
+        COMPRESSED_RG_RGTC2 => Ok((TextureKind::BC5, false)),
+        COMPRESSED_RGBA_BPTC_UNORM => Ok((TextureKind::BC7, false)),
+        COMPRESSED_SRGB_ALPHA_BPTC_UNORM => Ok((TextureKind::BC7, true)),
+        COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT => Ok((TextureKind::BC6H, false)),
+        COMPRESSED_RGB_BPTC_SIGNED_FLOAT => Ok((TextureKind::BC6HSigned, false)),
+        R8 => Ok((TextureKind::R8, false)),
+        RGB8 => Ok((TextureKind::RGB8, false)),
+        SRGB8 => Ok((TextureKind::RGB8, true)),
+        RGBA8 => Ok((TextureKind::RGBA8, false)),
+        SRGB8_ALPHA8 => Ok((TextureKind::RGBA8, true)),
+        _ => Err(unsupported(format!("Unsupported internal format {:#X} of KTX texture", internal_format))),
+    }
+}
+
+/// Reads KTX texture from memory.
+pub(in crate) fn read(data: &[u8]) -> Result<Texture, ImageError> {
+    if data.len() < HEADER_SIZE || data[0..12] != IDENTIFIER {
+        return Err(format_error("Not a KTX texture"));
+    }
+    if LittleEndian::read_u32(&data[12..]) != ENDIANNESS_LITTLE {
+        return Err(unsupported("Big-endian KTX textures are not supported"));
+    }
+
+    let internal_format = LittleEndian::read_u32(&data[28..]);
+    let width = LittleEndian::read_u32(&data[36..]);
+    let height = LittleEndian::read_u32(&data[40..]);
+    let depth = LittleEndian::read_u32(&data[44..]);
+    let array_elements = LittleEndian::read_u32(&data[48..]);
+    let faces = LittleEndian::read_u32(&data[52..]);
+    let stored_mip_count = LittleEndian::read_u32(&data[56..]);
+    let key_value_size = LittleEndian::read_u32(&data[60..]) as usize;
+
+    if depth > 0 || array_elements > 0 || faces != 1 {
+        return Err(unsupported("Only 2D KTX textures are supported"));
+    }
+    if width == 0 || height == 0 {
+        return Err(format_error("KTX texture has zero size"));
+    }
+
+    let (kind, srgb) = texture_kind(internal_format)?;
+
+    // Zero means that mips must be generated at load time.
+    let max_mip_count = 32 - width.max(height).leading_zeros();
+    let mip_count = stored_mip_count.max(1).min(max_mip_count);
+
+    let mut bytes = Vec::new();
+    let mut offset = HEADER_SIZE + key_value_size;
+    for level in 0..mip_count {
+        let image_size = data.get(offset..offset + 4)
+            .map(LittleEndian::read_u32)
+            .ok_or_else(|| format_error("KTX texture has not enough data for every mip level"))? as usize;
+        offset += 4;
+        let image = data.get(offset..offset + image_size)
+            .ok_or_else(|| format_error("KTX texture has not enough data for every mip level"))?;
+
+        let level_width = (width >> level).max(1) as usize;
+        let level_height = (height >> level).max(1) as usize;
+        if kind.is_compressed() {
+            let size = block_compression::level_size(kind, level_width as u32, level_height as u32);
+            if image_size < size {
+                return Err(format_error("KTX texture has invalid size of mip level"));
+            }
+            bytes.extend_from_slice(&image[..size]);
+        } else {
+            // Rows of uncompressed images are aligned to four bytes.
+            let row_size = level_width * kind.bytes_per_pixel();
+            let stride = (row_size + 3) & !3;
+            if image_size < stride * (level_height - 1) + row_size {
+                return Err(format_error("KTX texture has invalid size of mip level"));
+            }
+            for row in 0..level_height {
+                bytes.extend_from_slice(&image[row * stride..row * stride + row_size]);
+            }
+        }
+
+        // Mip levels are aligned to four bytes too.
+        offset += (image_size + 3) & !3;
+    }
+
+    let mut texture = Texture::from_mip_chain(width, height, kind, mip_count, bytes);
+    texture.options.srgb = srgb;
+    Ok(texture)
+}
+
+#[cfg(test)]
+mod test {
+    use byteorder::{ByteOrder, LittleEndian};
+    use crate::resource::{
+        ktx::{read, IDENTIFIER, ENDIANNESS_LITTLE, RGB8, RGBA8, SRGB8_ALPHA8, COMPRESSED_RGBA_S3TC_DXT5, COMPRESSED_SRGB_ALPHA_S3TC_DXT5},
+        texture::TextureKind,
+    };
+
+    fn make_ktx(internal_format: u32, width: u32, height: u32, mip_count: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0; 64];
+        data[0..12].copy_from_slice(&IDENTIFIER);
+        LittleEndian::write_u32(&mut data[12..], ENDIANNESS_LITTLE);
+        LittleEndian::write_u32(&mut data[28..], internal_format);
+        LittleEndian::write_u32(&mut data[36..], width);
+        LittleEndian::write_u32(&mut data[40..], height);
+        LittleEndian::write_u32(&mut data[52..], 1);
+        LittleEndian::write_u32(&mut data[56..], mip_count);
+        for level in levels {
+            let mut size = [0; 4];
+            LittleEndian::write_u32(&mut size, level.len() as u32);
+            data.extend_from_slice(&size);
+            data.extend_from_slice(level);
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn header() {
+        let data = make_ktx(RGBA8, 1, 2, 1, &[vec![1, 2, 3, 4, 5, 6, 7, 8]]);
+        let texture = read(&data).unwrap();
+        assert_eq!((texture.width, texture.height), (1, 2));
+        assert_eq!(texture.kind, TextureKind::RGBA8);
+        assert_eq!(texture.bytes, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let data = make_ktx(COMPRESSED_RGBA_S3TC_DXT5, 4, 4, 1, &[vec![9; 16]]);
+        assert_eq!(read(&data).unwrap().kind, TextureKind::BC3);
+
+        let mut data = make_ktx(RGBA8, 1, 1, 1, &[vec![0; 4]]);
+        data[0] = 0;
+        assert!(read(&data).is_err());
+
+        let mut data = make_ktx(RGBA8, 1, 1, 1, &[vec![0; 4]]);
+        LittleEndian::write_u32(&mut data[12..], 0x0102_0304);
+        assert!(read(&data).is_err());
+    }
+
+    #[test]
+    fn srgb_formats() {
+        let texture = read(&make_ktx(SRGB8_ALPHA8, 1, 1, 1, &[vec![0; 4]])).unwrap();
+        assert_eq!(texture.kind, TextureKind::RGBA8);
+        assert!(texture.options.srgb);
+
+        let texture = read(&make_ktx(COMPRESSED_SRGB_ALPHA_S3TC_DXT5, 4, 4, 1, &[vec![0; 16]])).unwrap();
+        assert_eq!(texture.kind, TextureKind::BC3);
+        assert!(texture.options.srgb);
+
+        assert!(!read(&make_ktx(RGBA8, 1, 1, 1, &[vec![0; 4]])).unwrap().options.srgb);
+    }
+
+    #[test]
+    fn row_alignment() {
+        // Rows of 1x2 RGB image are padded to four bytes.
+        let data = make_ktx(RGB8, 1, 2, 1, &[vec![1, 2, 3, 0, 4, 5, 6, 0]]);
+        assert_eq!(read(&data).unwrap().bytes, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn truncated_data() {
+        assert!(read(&make_ktx(RGBA8, 1, 1, 1, &[])[..40]).is_err());
+        assert!(read(&make_ktx(RGBA8, 1, 1, 1, &[])).is_err());
+        assert!(read(&make_ktx(RGBA8, 2, 2, 1, &[vec![0; 12]])).is_err());
+
+        // Second mip level is missing.
+        assert!(read(&make_ktx(RGBA8, 2, 2, 2, &[vec![0; 16]])).is_err());
+    }
+
+    #[test]
+    fn mip_count_clamping() {
+        let levels = [vec![1; 16], vec![2; 4]];
+        let texture = read(&make_ktx(RGBA8, 2, 2, 10, &levels)).unwrap();
+        assert_eq!(texture.mip_count, 2);
+        assert_eq!(texture.bytes.len(), 20);
+
+        // Zero means that there is one level.
+        let texture = read(&make_ktx(RGBA8, 2, 2, 0, &levels[..1])).unwrap();
+        assert_eq!(texture.mip_count, 1);
+    }
+}
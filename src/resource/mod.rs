@@ -1,3 +1,28 @@
 pub mod texture;
 pub mod fbx;
 pub mod model;
+pub mod vfs;
+pub mod archive;
+pub mod error;
+pub mod dds;
+pub mod ktx;
+pub mod block_compression;
+pub mod compiled_model;
+
+/// State of a resource. Resources that are loaded asynchronously are created in pending
+/// state and then become either loaded or failed.
+///
+/// Only textures have state, because only textures are shared before they're loaded. Models
+/// and sound buffers are put in resource manager once they're loaded, so every instance of
+/// them is always loaded. State of model that is requested asynchronously is tracked by
+/// [ModelRequest](crate::engine::resource_manager::ModelRequest) instead, and failures of
+/// every kind of resource are reported by
+/// [poll_event](crate::engine::resource_manager::ResourceManager::poll_event).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResourceState {
+    /// Resource is still loading.
+    Pending,
+    Ok,
+    /// Resource failed to load, contains reason of failure.
+    LoadError(String),
+}
@@ -2,16 +2,33 @@ use crate::{
     scene::{
         Scene,
         node::Node,
+        mesh::Mesh,
         base::AsBase
     },
-    animation::Animation,
-    resource::{fbx, fbx::error::FbxError},
+    animation::{
+        Animation,
+        channel::KeyFrameReductionSettings,
+    },
+    resource::{
+        fbx::{
+            self,
+            error::FbxWarning,
+            conversion::FbxImportOptions,
+        },
+        compiled_model,
+        error::ResourceError,
+        texture::TextureKind,
+    },
+    renderer::surface::{Surface, SurfaceSharedData},
     engine::resource_manager::ResourceManager,
     core::{
         pool::Handle,
         visitor::{Visit, VisitResult, Visitor},
     },
-    utils::log::Log
+    utils::{
+        log::Log,
+        weld::WeldOptions,
+    },
 };
 use std::{
     path::{Path, PathBuf},
@@ -19,9 +36,241 @@ use std::{
         Arc,
         Mutex,
         Weak
-    }
+    },
+    collections::HashSet,
+    ffi::OsStr,
 };
 
+/// Defines where tangents of imported meshes come from.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TangentPolicy {
+    /// Tangents are taken from file, they're calculated only if file has none.
+    FromFile,
+    /// Tangents are always calculated, tangents from file are ignored.
+    Calculate,
+    /// Tangents are never calculated, meshes without tangents in file won't have
+    /// correct normal mapping.
+    Never,
+}
+
+impl TangentPolicy {
+    pub fn new(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(TangentPolicy::FromFile),
+            1 => Ok(TangentPolicy::Calculate),
+            2 => Ok(TangentPolicy::Never),
+            _ => Err(format!("Invalid tangent policy {}!", id))
+        }
+    }
+
+    pub fn id(self) -> u32 {
+        match self {
+            TangentPolicy::FromFile => 0,
+            TangentPolicy::Calculate => 1,
+            TangentPolicy::Never => 2,
+        }
+    }
+}
+
+/// Options of model import, see [request_model_with_options](crate::engine::resource_manager::ResourceManager::request_model_with_options).
+/// Default options give same result as [request_model](crate::engine::resource_manager::ResourceManager::request_model).
+///
+/// Naming patterns of normal and specular maps are file names in which `{stem}` and `{ext}`
+/// are replaced with file stem and extension of diffuse texture, for example default pattern
+/// of normal maps `{stem}_normal.{ext}` gives `wall_normal.png` for `wall.png`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelImportOptions {
+    /// Directories in which textures of model are searched, in order of priority. Empty list
+    /// means [textures path](crate::engine::resource_manager::ResourceManager::textures_path)
+    /// of resource manager.
+    pub texture_search_paths: Vec<PathBuf>,
+    /// Pattern of file name of normal map, None disables normal maps.
+    pub normal_map_pattern: Option<String>,
+    /// Pattern of file name of specular map, None disables specular maps.
+    pub specular_map_pattern: Option<String>,
+    /// Kind of diffuse textures. Every diffuse texture is loaded as RGBA8 by default, it takes
+    /// more memory than needed for opaque textures, but works with transparent ones.
+    pub diffuse_texture_kind: TextureKind,
+    pub tangents: TangentPolicy,
+    /// Uniform scale which is applied to model in addition to conversion of units of file.
+    /// To use other size of unit than the one stored in file, disable `convert_units` of
+    /// [fbx_import_options](ModelImportOptions::fbx_import_options) and set scale instead.
+    pub scale: f32,
+    /// Conversion of axes and units of FBX file to engine's convention.
+    pub fbx_import_options: FbxImportOptions,
+    /// Tolerances of reduction of key frames of imported animations, None disables reduction.
+    pub key_frame_reduction: Option<KeyFrameReductionSettings>,
+    /// Whether static meshes should be merged into one mesh to reduce count of draw calls.
+    /// Mesh is static if it has no children, it is not skinned, has no blend shapes and
+    /// neither it nor its parents are animated.
+    pub merge_meshes: bool,
+    /// Whether only skeleton and animations should be imported. Every node is created as
+    /// base node, no geometry or textures are loaded. Useful for files that are used as
+    /// source of animations only, see [retarget_animations](Model::retarget_animations).
+    pub animation_only: bool,
+    /// Whether warnings of import should be treated as errors, so model with missing
+    /// textures, unknown light types, etc. fails to load. Useful for validation of assets.
+    pub strict: bool,
+    /// Tolerances of welding of vertices of meshes. Vertices of FBX are stored per polygon
+    /// corner, so they're welded to share vertices between polygons.
+    pub weld: WeldOptions,
+    /// Whether triangles and vertices of meshes should be reordered for faster rendering,
+    /// see [optimize](crate::renderer::surface::SurfaceSharedData::optimize). Disabled by
+    /// default, because order of vertices and triangles of imported meshes changes, which
+    /// breaks code that relies on indices of vertices of imported meshes.
+    pub optimize_meshes: bool,
+}
+
+impl Default for ModelImportOptions {
+    fn default() -> Self {
+        Self {
+            texture_search_paths: Vec::new(),
+            normal_map_pattern: Some("{stem}_normal.{ext}".to_owned()),
+            specular_map_pattern: None,
+            diffuse_texture_kind: TextureKind::RGBA8,
+            tangents: TangentPolicy::FromFile,
+            scale: 1.0,
+            fbx_import_options: Default::default(),
+            key_frame_reduction: None,
+            merge_meshes: false,
+            animation_only: false,
+            strict: false,
+            weld: Default::default(),
+            optimize_meshes: false,
+        }
+    }
+}
+
+impl ModelImportOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_texture_search_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.texture_search_paths.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn with_normal_map_pattern(mut self, pattern: Option<&str>) -> Self {
+        self.normal_map_pattern = pattern.map(|pattern| pattern.to_owned());
+        self
+    }
+
+    pub fn with_specular_map_pattern(mut self, pattern: Option<&str>) -> Self {
+        self.specular_map_pattern = pattern.map(|pattern| pattern.to_owned());
+        self
+    }
+
+    pub fn with_diffuse_texture_kind(mut self, kind: TextureKind) -> Self {
+        self.diffuse_texture_kind = kind;
+        self
+    }
+
+    pub fn with_tangents(mut self, tangents: TangentPolicy) -> Self {
+        self.tangents = tangents;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_fbx_import_options(mut self, fbx_import_options: FbxImportOptions) -> Self {
+        self.fbx_import_options = fbx_import_options;
+        self
+    }
+
+    pub fn with_key_frame_reduction(mut self, settings: Option<KeyFrameReductionSettings>) -> Self {
+        self.key_frame_reduction = settings;
+        self
+    }
+
+    pub fn with_merge_meshes(mut self, merge_meshes: bool) -> Self {
+        self.merge_meshes = merge_meshes;
+        self
+    }
+
+    pub fn with_animation_only(mut self, animation_only: bool) -> Self {
+        self.animation_only = animation_only;
+        self
+    }
+
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn with_weld_options(mut self, weld: WeldOptions) -> Self {
+        self.weld = weld;
+        self
+    }
+
+    pub fn with_optimize_meshes(mut self, optimize_meshes: bool) -> Self {
+        self.optimize_meshes = optimize_meshes;
+        self
+    }
+
+    fn search_paths(&self, resource_manager: &ResourceManager) -> Vec<PathBuf> {
+        if self.texture_search_paths.is_empty() {
+            vec![resource_manager.textures_path().to_path_buf()]
+        } else {
+            self.texture_search_paths.clone()
+        }
+    }
+
+    /// Returns path of texture file in first search path where it exists.
+    pub(in crate) fn find_texture(&self, resource_manager: &ResourceManager, file_name: &OsStr) -> Option<PathBuf> {
+        let vfs = resource_manager.vfs().read().unwrap();
+        self.search_paths(resource_manager)
+            .into_iter()
+            .map(|path| path.join(file_name))
+            .find(|path| vfs.exists(path))
+    }
+
+    /// Same as [find_texture](ModelImportOptions::find_texture), but falls back to first
+    /// search path if texture exists nowhere, so missing texture is reported by loader.
+    pub(in crate) fn texture_path(&self, resource_manager: &ResourceManager, file_name: &OsStr) -> PathBuf {
+        self.find_texture(resource_manager, file_name)
+            .unwrap_or_else(|| self.search_paths(resource_manager)[0].join(file_name))
+    }
+
+    /// Applies naming pattern to file stem and extension of a texture.
+    pub(in crate) fn map_file_name(pattern: &str, stem: &OsStr, extension: &OsStr) -> String {
+        pattern
+            .replace("{stem}", &stem.to_string_lossy())
+            .replace("{ext}", &extension.to_string_lossy())
+    }
+}
+
+impl Visit for ModelImportOptions {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.texture_search_paths.visit("TextureSearchPaths", visitor)?;
+        self.normal_map_pattern.visit("NormalMapPattern", visitor)?;
+        self.specular_map_pattern.visit("SpecularMapPattern", visitor)?;
+        let mut diffuse_texture_kind = self.diffuse_texture_kind.id();
+        diffuse_texture_kind.visit("DiffuseTextureKind", visitor)?;
+        let mut tangents = self.tangents.id();
+        tangents.visit("Tangents", visitor)?;
+        if visitor.is_reading() {
+            self.diffuse_texture_kind = TextureKind::new(diffuse_texture_kind)?;
+            self.tangents = TangentPolicy::new(tangents)?;
+        }
+        self.scale.visit("Scale", visitor)?;
+        self.fbx_import_options.visit("FbxImportOptions", visitor)?;
+        self.key_frame_reduction.visit("KeyFrameReduction", visitor)?;
+        self.merge_meshes.visit("MergeMeshes", visitor)?;
+        self.animation_only.visit("AnimationOnly", visitor)?;
+        self.strict.visit("Strict", visitor)?;
+        self.weld.visit("Weld", visitor)?;
+        self.optimize_meshes.visit("OptimizeMeshes", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
 /// Model is an isolated scene that is used to create copies of its data - this
 /// process is known as `instantiation`. Isolation in this context means that
 /// such scene cannot be modified, rendered, etc. It just a data source.
@@ -36,6 +285,10 @@ pub struct Model {
     // enable_shared_from_this trick from C++
     pub(in crate) self_weak_ref: Option<Weak<Mutex<Model>>>,
     pub(in crate) path: PathBuf,
+    /// Options model was imported with, they're used to reload model.
+    pub(in crate) options: ModelImportOptions,
+    /// Non-fatal problems of import.
+    warnings: Vec<FbxWarning>,
     scene: Scene,
 }
 
@@ -44,6 +297,8 @@ impl Default for Model {
         Self {
             self_weak_ref: None,
             path: PathBuf::new(),
+            options: Default::default(),
+            warnings: Vec::new(),
             scene: Scene::new(),
         }
     }
@@ -55,11 +310,110 @@ impl Visit for Model {
 
         self.self_weak_ref.visit("SelfWeakRef", visitor)?;
         self.path.visit("Path", visitor)?;
+        self.options.visit("Options", visitor)?;
 
         visitor.leave_region()
     }
 }
 
+/// Merges static meshes of scene into one mesh which is attached to given root, surfaces
+/// with same textures are merged into one surface. Does nothing if there are less than two
+/// static meshes.
+fn merge_static_meshes(scene: &mut Scene, root: Handle<Node>) {
+    // Nodes which move independently - animated ones and bones.
+    let mut dynamic = HashSet::new();
+    for animation in scene.animations.iter() {
+        for track in animation.get_tracks() {
+            dynamic.insert(track.get_node());
+        }
+        for track in animation.get_property_tracks() {
+            dynamic.insert(track.node());
+        }
+    }
+    for node in scene.graph.linear_iter() {
+        if let Node::Mesh(mesh) = node {
+            for surface in mesh.surfaces() {
+                dynamic.extend(surface.bones.iter().copied());
+            }
+        }
+    }
+
+    let is_static = |mut handle: Handle<Node>| {
+        while handle.is_some() {
+            if dynamic.contains(&handle) {
+                return false;
+            }
+            handle = scene.graph.get(handle).base().parent();
+        }
+        true
+    };
+
+    let meshes = scene.graph
+        .pair_iter()
+        .filter(|(handle, node)| match node {
+            Node::Mesh(mesh) => mesh.base().children().is_empty() &&
+                mesh.blend_shape_weights().is_empty() &&
+                mesh.surfaces().iter().all(|surface| surface.bones.is_empty() &&
+                    surface.get_data().lock().unwrap().blend_shapes().is_empty()) &&
+                is_static(*handle),
+            _ => false,
+        })
+        .map(|(handle, _)| handle)
+        .collect::<Vec<_>>();
+    if meshes.len() < 2 {
+        return;
+    }
+
+    let texture_key = |surface: &Surface| {
+        let ptr = |texture: Option<Arc<Mutex<_>>>| texture.map(|texture| &*texture as *const _ as usize);
+        (ptr(surface.get_diffuse_texture()), ptr(surface.get_normal_texture()), ptr(surface.get_specular_texture()))
+    };
+
+    let mut merged = Mesh::default();
+    let mut keys = Vec::new();
+    for handle in meshes.iter() {
+        if let Node::Mesh(mesh) = scene.graph.get(*handle) {
+            let transform = mesh.base().global_transform();
+            for surface in mesh.surfaces() {
+                let key = texture_key(surface);
+                let index = match keys.iter().position(|k| *k == key) {
+                    Some(index) => index,
+                    None => {
+                        let mut merged_surface = Surface::new(Arc::new(Mutex::new(SurfaceSharedData::new())));
+                        if let Some(texture) = surface.get_diffuse_texture() {
+                            merged_surface.set_diffuse_texture(texture);
+                        }
+                        if let Some(texture) = surface.get_normal_texture() {
+                            merged_surface.set_normal_texture(texture);
+                        }
+                        if let Some(texture) = surface.get_specular_texture() {
+                            merged_surface.set_specular_texture(texture);
+                        }
+                        merged.add_surface(merged_surface);
+                        keys.push(key);
+                        keys.len() - 1
+                    }
+                };
+                merged.surfaces()[index]
+                    .get_data()
+                    .lock()
+                    .unwrap()
+                    .append(&surface.get_data().lock().unwrap(), transform);
+            }
+        }
+    }
+
+    Log::writeln(format!("{} static meshes are merged into one mesh with {} surfaces", meshes.len(), keys.len()));
+
+    for handle in meshes {
+        scene.graph.remove_node(handle);
+    }
+    merged.base_mut().set_name("MergedMeshes");
+    let merged = scene.graph.add_node(Node::Mesh(merged));
+    scene.graph.link_nodes(merged, root);
+    scene.graph.update_transforms();
+}
+
 pub struct ModelInstance {
     pub root: Handle<Node>,
 
@@ -82,16 +436,79 @@ fn upgrade_self_weak_ref(self_weak_ref: &Option<Weak<Mutex<Model>>>) -> Arc<Mute
 }
 
 impl Model {
-    pub(in crate) fn load<P: AsRef<Path>>(path: P, resource_manager: &mut ResourceManager) -> Result<Model, FbxError> {
+    /// Loads model from compiled model if it is up-to-date and options are default, otherwise
+    /// imports it from source file. See [compiled_model](crate::resource::compiled_model) module docs.
+    pub(in crate) fn load<P: AsRef<Path>>(path: P, resource_manager: &mut ResourceManager, options: &ModelImportOptions) -> Result<Model, ResourceError> {
+        let cache_path = compiled_model::cache_path(path.as_ref());
+        let cache = {
+            let vfs = resource_manager.vfs().read().unwrap();
+            if *options == ModelImportOptions::default() && compiled_model::is_cache_up_to_date(&vfs, path.as_ref()) {
+                Some(vfs.read(&cache_path))
+            } else {
+                None
+            }
+        };
+        if let Some(data) = cache {
+            match data.and_then(|data| compiled_model::read(&data, resource_manager, options)) {
+                Ok(scene) => {
+                    Log::writeln(format!("Model {:?} is loaded from compiled model {:?}", path.as_ref(), cache_path));
+                    return Ok(Model {
+                        self_weak_ref: None,
+                        path: path.as_ref().to_path_buf(),
+                        options: options.clone(),
+                        warnings: Vec::new(),
+                        scene,
+                    });
+                }
+                Err(e) => Log::writeln(format!("Unable to read compiled model {:?}, source will be imported. Reason: {}", cache_path, e)),
+            }
+        }
+
+        Self::import(path, resource_manager, options)
+    }
+
+    /// Imports model from source file, compiled model is ignored.
+    pub(in crate) fn import<P: AsRef<Path>>(path: P, resource_manager: &mut ResourceManager, options: &ModelImportOptions) -> Result<Model, ResourceError> {
         let mut scene = Scene::new();
-        fbx::load_to_scene(&mut scene, resource_manager, path.as_ref())?;
+        let (root, warnings) = fbx::load_to_scene(&mut scene, resource_manager, path.as_ref(), options)?;
+        // Name animations by file name so they can be referenced from data files.
+        if let Some(stem) = path.as_ref().file_stem().and_then(|s| s.to_str()) {
+            for animation in scene.animations.iter_mut() {
+                if animation.name().is_empty() {
+                    animation.set_name(stem);
+                }
+            }
+        }
+        if let Some(settings) = options.key_frame_reduction {
+            for animation in scene.animations.iter_mut() {
+                let report = animation.reduce_key_frames(&settings);
+                Log::writeln(format!("Key frames of animation {} reduced from {} to {} - compression ratio {:.2}",
+                                     animation.name(), report.keys_before, report.keys_after, report.compression_ratio()));
+            }
+        }
+        if options.merge_meshes && !options.animation_only {
+            merge_static_meshes(&mut scene, root);
+        }
         Ok(Model {
             self_weak_ref: None,
             path: path.as_ref().to_path_buf(),
+            options: options.clone(),
+            warnings,
             scene,
         })
     }
 
+    /// Returns options model was imported with.
+    pub fn import_options(&self) -> &ModelImportOptions {
+        &self.options
+    }
+
+    /// Returns non-fatal problems found while model was imported from source file. Models
+    /// loaded from compiled model have no warnings, they were reported when model was compiled.
+    pub fn import_warnings(&self) -> &[FbxWarning] {
+        &self.warnings
+    }
+
     /// Tries to instantiate model from given resource. Does not retarget available
     /// animations from model to its instance. Can be helpful if you only need geometry.
     pub fn instantiate_geometry(&self, dest_scene: &mut Scene) -> Handle<Node> {
@@ -170,6 +587,16 @@ impl Model {
                 anim_copy.get_tracks_mut()[i].set_node(instance_node);
             }
 
+            // Same for property tracks.
+            for (i, ref_track) in ref_anim.get_property_tracks().iter().enumerate() {
+                let ref_node = self.scene.graph.get(ref_track.node());
+                let instance_node = dest_scene.graph.find_by_name(root, ref_node.base().name());
+                if instance_node.is_none() {
+                    Log::writeln(format!("Failed to retarget property track {:?} for node {}", self.path, ref_node.base().name()));
+                }
+                anim_copy.get_property_tracks_mut()[i].set_node(instance_node);
+            }
+
             animation_handles.push(dest_scene.animations.add(anim_copy));
         }
 
@@ -181,7 +608,147 @@ impl Model {
         &self.scene
     }
 
+    pub(in crate) fn get_scene_mut(&mut self) -> &mut Scene {
+        &mut self.scene
+    }
+
+    /// Returns approximate size of geometry of model in bytes, surface data shared between
+    /// meshes is counted once.
+    pub fn memory_usage(&self) -> usize {
+        let mut counted = HashSet::new();
+        let mut size = 0;
+        for node in self.scene.graph.linear_iter() {
+            if let Node::Mesh(mesh) = node {
+                for surface in mesh.surfaces() {
+                    let data = surface.get_data();
+                    if counted.insert(&*data as *const _ as usize) {
+                        size += data.lock().unwrap().memory_usage();
+                    }
+                }
+            }
+        }
+        size
+    }
+
     pub fn find_node_by_name(&self, name: &str) -> Handle<Node> {
         self.scene.graph.find_by_name_from_root(name)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use std::{
+        ffi::OsStr,
+        sync::{Arc, Mutex},
+    };
+    use crate::{
+        core::pool::Handle,
+        scene::{
+            Scene,
+            node::Node,
+            mesh::Mesh,
+            base::{AsBase, BaseBuilder},
+        },
+        animation::{Animation, Track},
+        renderer::surface::{Surface, SurfaceSharedData},
+        resource::{
+            model::{ModelImportOptions, merge_static_meshes},
+            texture::{Texture, TextureKind},
+        },
+    };
+
+    #[test]
+    fn map_file_name() {
+        let stem = OsStr::new("wall");
+        let extension = OsStr::new("png");
+        assert_eq!(ModelImportOptions::map_file_name("{stem}_normal.{ext}", stem, extension), "wall_normal.png");
+        assert_eq!(ModelImportOptions::map_file_name("specular/{stem}.dds", stem, extension), "specular/wall.dds");
+        // Every occurrence is replaced, unknown placeholders are kept.
+        assert_eq!(ModelImportOptions::map_file_name("{stem}_{stem}.{ext}.{size}", stem, extension), "wall_wall.png.{size}");
+        assert_eq!(ModelImportOptions::map_file_name("normal.tga", stem, extension), "normal.tga");
+    }
+
+    fn add_mesh(scene: &mut Scene, name: &str, parent: Handle<Node>, texture: &Arc<Mutex<Texture>>) -> Handle<Node> {
+        let mut surface = Surface::new(Arc::new(Mutex::new(SurfaceSharedData::make_cube())));
+        surface.set_diffuse_texture(texture.clone());
+        let mut mesh = Mesh::default();
+        *mesh.base_mut() = BaseBuilder::new().with_name(name).build();
+        mesh.add_surface(surface);
+        let handle = scene.graph.add_node(Node::Mesh(mesh));
+        scene.graph.link_nodes(handle, parent);
+        handle
+    }
+
+    #[test]
+    fn merge_eligibility() {
+        let mut scene = Scene::new();
+        let root = scene.graph.add_node(Node::Base(BaseBuilder::new().with_name("Root").build()));
+        let brick = Arc::new(Mutex::new(Texture::new(1, 1, TextureKind::RGBA8)));
+        let stone = Arc::new(Mutex::new(Texture::new(1, 1, TextureKind::RGBA8)));
+
+        // Static meshes, first two share texture.
+        add_mesh(&mut scene, "Wall", root, &brick);
+        add_mesh(&mut scene, "Tower", root, &brick);
+        add_mesh(&mut scene, "Floor", root, &stone);
+
+        // Skinned mesh.
+        let bone = scene.graph.add_node(Node::Base(BaseBuilder::new().with_name("Bone").build()));
+        scene.graph.link_nodes(bone, root);
+        let skinned = add_mesh(&mut scene, "Skinned", root, &brick);
+        if let Node::Mesh(mesh) = scene.graph.get_mut(skinned) {
+            mesh.surfaces_mut()[0].bones.push(bone);
+        }
+
+        // Mesh with animated parent.
+        let door = scene.graph.add_node(Node::Base(BaseBuilder::new().with_name("Door").build()));
+        scene.graph.link_nodes(door, root);
+        add_mesh(&mut scene, "Handle", door, &brick);
+        let mut track = Track::new();
+        track.set_node(door);
+        let mut animation = Animation::default();
+        animation.add_track(track);
+        scene.animations.add(animation);
+
+        // Mesh with children.
+        let lamp = add_mesh(&mut scene, "Lamp", root, &brick);
+        let light = scene.graph.add_node(Node::Base(BaseBuilder::new().with_name("Light").build()));
+        scene.graph.link_nodes(light, lamp);
+
+        // Mesh with blend shapes.
+        let face = add_mesh(&mut scene, "Face", root, &brick);
+        if let Node::Mesh(mesh) = scene.graph.get_mut(face) {
+            mesh.set_blend_shape_weight(0, 0.5);
+        }
+
+        scene.graph.update_transforms();
+        merge_static_meshes(&mut scene, root);
+
+        let graph = &scene.graph;
+        for name in ["Wall", "Tower", "Floor"].iter() {
+            assert!(graph.find_by_name_from_root(name).is_none());
+        }
+        for name in ["Skinned", "Handle", "Lamp", "Face"].iter() {
+            assert!(graph.find_by_name_from_root(name).is_some());
+        }
+        let merged = graph.find_by_name_from_root("MergedMeshes");
+        assert_eq!(graph.get(merged).base().parent(), root);
+        if let Node::Mesh(mesh) = graph.get(merged) {
+            // Surfaces with same textures are merged.
+            assert_eq!(mesh.surfaces().len(), 2);
+            let cube_vertex_count = SurfaceSharedData::make_cube().get_vertices().len();
+            let data = mesh.surfaces()[0].get_data();
+            assert_eq!(data.lock().unwrap().get_vertices().len(), 2 * cube_vertex_count);
+            assert!(Arc::ptr_eq(&mesh.surfaces()[0].get_diffuse_texture().unwrap(), &brick));
+            assert!(Arc::ptr_eq(&mesh.surfaces()[1].get_diffuse_texture().unwrap(), &stone));
+        } else {
+            panic!("Merged meshes must be a mesh");
+        }
+
+        // Single static mesh is left as is.
+        let mut scene = Scene::new();
+        let root = scene.graph.add_node(Node::Base(BaseBuilder::new().build()));
+        add_mesh(&mut scene, "Wall", root, &brick);
+        merge_static_meshes(&mut scene, root);
+        assert!(scene.graph.find_by_name_from_root("Wall").is_some());
+        assert!(scene.graph.find_by_name_from_root("MergedMeshes").is_none());
+    }
+}
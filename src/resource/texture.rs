@@ -1,12 +1,23 @@
-use std::path::*;
+use std::{
+    path::*,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 use crate::{
     core::visitor::{
         Visit,
         VisitResult,
         Visitor
-    }
+    },
+    resource::{
+        vfs::Vfs,
+        ResourceState,
+        dds,
+        ktx,
+        block_compression,
+    },
 };
-use image::GenericImageView;
+use image::{GenericImageView, ImageFormat};
+use serde::{Serialize, Deserialize};
 
 pub struct Texture {
     pub(in crate) path: PathBuf,
@@ -14,7 +25,26 @@ pub struct Texture {
     pub(in crate) height: u32,
     pub(in crate) bytes: Vec<u8>,
     pub(in crate) kind: TextureKind,
-    pub(in crate) loaded: bool
+    /// Amount of mip levels in `bytes`, levels are stored one after another starting from
+    /// the largest one. Textures with one level get their mips generated by renderer.
+    pub(in crate) mip_count: u32,
+    pub(in crate) state: ResourceState,
+    /// Increased every time when texture is reloaded, renderer uses it to find out
+    /// that texture must be uploaded to GPU again.
+    pub(in crate) revision: u32,
+    pub(in crate) options: TextureImportOptions,
+    /// True if options were given at request time, such options have priority over
+    /// sidecar file when texture is reloaded.
+    pub(in crate) options_overridden: bool,
+    /// Region that was changed since last upload to GPU, renderer uploads only this region.
+    pub(in crate) dirty_region: Option<TextureRegion>,
+    /// Renderer uses this id to tell apart textures that were allocated at same address.
+    pub(in crate) unique_id: usize,
+}
+
+fn next_unique_id() -> usize {
+    static NEXT_UNIQUE_ID: AtomicUsize = AtomicUsize::new(0);
+    NEXT_UNIQUE_ID.fetch_add(1, Ordering::Relaxed)
 }
 
 impl Default for Texture {
@@ -25,7 +55,13 @@ impl Default for Texture {
             height: 0,
             bytes: Vec::new(),
             kind: TextureKind::RGBA8,
-            loaded: false
+            mip_count: 1,
+            state: ResourceState::Pending,
+            revision: 0,
+            options: Default::default(),
+            options_overridden: false,
+            dirty_region: None,
+            unique_id: next_unique_id(),
         }
     }
 }
@@ -42,15 +78,339 @@ impl Visit for Texture {
 
         self.path.visit("Path", visitor)?;
 
+        // Textures saved by older versions have no options.
+        let options_result = self.options.visit("Options", visitor)
+            .and_then(|_| self.options_overridden.visit("OptionsOverridden", visitor));
+        if !visitor.is_reading() {
+            options_result?;
+        } else if options_result.is_err() {
+            self.options = Default::default();
+            self.options_overridden = false;
+        }
+
+        visitor.leave_region()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TextureMinificationFilter {
+    Nearest,
+    /// Nearest pixel from nearest mip level.
+    NearestMip,
+    Linear,
+    /// Trilinear filtering.
+    LinearMip,
+}
+
+impl TextureMinificationFilter {
+    pub fn new(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(TextureMinificationFilter::Nearest),
+            1 => Ok(TextureMinificationFilter::NearestMip),
+            2 => Ok(TextureMinificationFilter::Linear),
+            3 => Ok(TextureMinificationFilter::LinearMip),
+            _ => Err(format!("Invalid minification filter {}!", id))
+        }
+    }
+
+    pub fn id(self) -> u32 {
+        match self {
+            TextureMinificationFilter::Nearest => 0,
+            TextureMinificationFilter::NearestMip => 1,
+            TextureMinificationFilter::Linear => 2,
+            TextureMinificationFilter::LinearMip => 3,
+        }
+    }
+
+    pub fn uses_mips(self) -> bool {
+        match self {
+            TextureMinificationFilter::NearestMip | TextureMinificationFilter::LinearMip => true,
+            TextureMinificationFilter::Nearest | TextureMinificationFilter::Linear => false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TextureMagnificationFilter {
+    Nearest,
+    Linear,
+}
+
+impl TextureMagnificationFilter {
+    pub fn new(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(TextureMagnificationFilter::Nearest),
+            1 => Ok(TextureMagnificationFilter::Linear),
+            _ => Err(format!("Invalid magnification filter {}!", id))
+        }
+    }
+
+    pub fn id(self) -> u32 {
+        match self {
+            TextureMagnificationFilter::Nearest => 0,
+            TextureMagnificationFilter::Linear => 1,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TextureWrapMode {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+}
+
+impl TextureWrapMode {
+    pub fn new(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(TextureWrapMode::Repeat),
+            1 => Ok(TextureWrapMode::MirroredRepeat),
+            2 => Ok(TextureWrapMode::ClampToEdge),
+            3 => Ok(TextureWrapMode::ClampToBorder),
+            _ => Err(format!("Invalid wrap mode {}!", id))
+        }
+    }
+
+    pub fn id(self) -> u32 {
+        match self {
+            TextureWrapMode::Repeat => 0,
+            TextureWrapMode::MirroredRepeat => 1,
+            TextureWrapMode::ClampToEdge => 2,
+            TextureWrapMode::ClampToBorder => 3,
+        }
+    }
+}
+
+/// Rectangular region of texture in pixels.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TextureRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl TextureRegion {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns coordinate of right edge of region, None if it does not fit in u32.
+    pub fn right(&self) -> Option<u32> {
+        self.x.checked_add(self.width)
+    }
+
+    /// Returns coordinate of bottom edge of region, None if it does not fit in u32.
+    pub fn bottom(&self) -> Option<u32> {
+        self.y.checked_add(self.height)
+    }
+
+    /// Returns smallest region that contains both regions. Edges that do not fit in u32
+    /// are placed at u32::MAX.
+    pub fn union(self, other: Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().unwrap_or(u32::MAX).max(other.right().unwrap_or(u32::MAX));
+        let bottom = self.bottom().unwrap_or(u32::MAX).max(other.bottom().unwrap_or(u32::MAX));
+        Self::new(x, y, right - x, bottom - y)
+    }
+
+    /// Returns part of region that lies inside of texture of given size, None if region is
+    /// empty or entirely outside of texture.
+    pub fn clip(self, width: u32, height: u32) -> Option<Self> {
+        let right = self.right().unwrap_or(u32::MAX).min(width);
+        let bottom = self.bottom().unwrap_or(u32::MAX).min(height);
+        if self.x >= right || self.y >= bottom {
+            None
+        } else {
+            Some(Self::new(self.x, self.y, right - self.x, bottom - self.y))
+        }
+    }
+}
+
+/// Options that define how texture is sampled and uploaded to GPU. Options can be passed
+/// at request time or stored in sidecar file next to image, see
+/// [parse](TextureImportOptions::parse) for its format.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TextureImportOptions {
+    pub minification_filter: TextureMinificationFilter,
+    pub magnification_filter: TextureMagnificationFilter,
+    pub wrap_s: TextureWrapMode,
+    pub wrap_t: TextureWrapMode,
+    /// Level of anisotropic filtering, it is clamped to maximum supported by driver.
+    /// 1.0 disables anisotropic filtering.
+    pub anisotropy: f32,
+    /// Whether mip levels should be generated for textures that do not have them.
+    pub generate_mips: bool,
+    /// Whether texture stores colors in sRGB space, such textures are converted to linear
+    /// space when sampled.
+    pub srgb: bool,
+}
+
+impl Default for TextureImportOptions {
+    fn default() -> Self {
+        Self {
+            minification_filter: TextureMinificationFilter::LinearMip,
+            magnification_filter: TextureMagnificationFilter::Linear,
+            wrap_s: TextureWrapMode::Repeat,
+            wrap_t: TextureWrapMode::Repeat,
+            anisotropy: 16.0,
+            generate_mips: true,
+            srgb: false,
+        }
+    }
+}
+
+impl Visit for TextureImportOptions {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut minification_filter = self.minification_filter.id();
+        minification_filter.visit("MinificationFilter", visitor)?;
+        let mut magnification_filter = self.magnification_filter.id();
+        magnification_filter.visit("MagnificationFilter", visitor)?;
+        let mut wrap_s = self.wrap_s.id();
+        wrap_s.visit("WrapS", visitor)?;
+        let mut wrap_t = self.wrap_t.id();
+        wrap_t.visit("WrapT", visitor)?;
+        if visitor.is_reading() {
+            self.minification_filter = TextureMinificationFilter::new(minification_filter)?;
+            self.magnification_filter = TextureMagnificationFilter::new(magnification_filter)?;
+            self.wrap_s = TextureWrapMode::new(wrap_s)?;
+            self.wrap_t = TextureWrapMode::new(wrap_t)?;
+        }
+
+        self.anisotropy.visit("Anisotropy", visitor)?;
+        self.generate_mips.visit("GenerateMips", visitor)?;
+        self.srgb.visit("Srgb", visitor)?;
+
         visitor.leave_region()
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+impl TextureImportOptions {
+    pub fn with_minification_filter(mut self, filter: TextureMinificationFilter) -> Self {
+        self.minification_filter = filter;
+        self
+    }
+
+    pub fn with_magnification_filter(mut self, filter: TextureMagnificationFilter) -> Self {
+        self.magnification_filter = filter;
+        self
+    }
+
+    /// Sets same wrap mode for both coordinates.
+    pub fn with_wrap(mut self, wrap: TextureWrapMode) -> Self {
+        self.wrap_s = wrap;
+        self.wrap_t = wrap;
+        self
+    }
+
+    pub fn with_wrap_s(mut self, wrap: TextureWrapMode) -> Self {
+        self.wrap_s = wrap;
+        self
+    }
+
+    pub fn with_wrap_t(mut self, wrap: TextureWrapMode) -> Self {
+        self.wrap_t = wrap;
+        self
+    }
+
+    pub fn with_anisotropy(mut self, anisotropy: f32) -> Self {
+        self.anisotropy = anisotropy.max(1.0);
+        self
+    }
+
+    pub fn with_generate_mips(mut self, generate_mips: bool) -> Self {
+        self.generate_mips = generate_mips;
+        self
+    }
+
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Returns path of sidecar file with options for texture at given path, it is path of
+    /// texture with `.options` appended, `wall.png` has options in `wall.png.options`.
+    pub fn sidecar_path<P: AsRef<Path>>(texture_path: P) -> PathBuf {
+        let mut path = texture_path.as_ref().as_os_str().to_owned();
+        path.push(".options");
+        PathBuf::from(path)
+    }
+
+    /// Parses options from text of sidecar file, which is options written in RON format.
+    /// Fields that are not listed keep their default values.
+    ///
+    /// ```text
+    /// // Pixel art sprite
+    /// (
+    ///     minification_filter: Nearest,
+    ///     magnification_filter: Nearest,
+    ///     wrap_s: ClampToEdge,
+    ///     wrap_t: ClampToEdge,
+    ///     anisotropy: 1.0,
+    ///     generate_mips: false,
+    ///     srgb: true,
+    /// )
+    /// ```
+    ///
+    /// Minification filters are `Nearest`, `NearestMip`, `Linear`, `LinearMip`, magnification
+    /// filters are `Nearest` and `Linear`. Wrap modes are `Repeat`, `MirroredRepeat`,
+    /// `ClampToEdge`, `ClampToBorder`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let options: Self = ron::de::from_str(text).map_err(|e| e.to_string())?;
+        Ok(options.with_anisotropy(options.anisotropy))
+    }
+
+    /// Returns text of sidecar file with these options, see [parse](TextureImportOptions::parse).
+    pub fn to_text(&self) -> Result<String, String> {
+        ron::ser::to_string_pretty(self, Default::default()).map_err(|e| e.to_string())
+    }
+
+    /// Reads options from sidecar file of texture at given path. Returns None if there is
+    /// no sidecar file.
+    pub fn load_sidecar<P: AsRef<Path>>(vfs: &Vfs, texture_path: P) -> Option<Result<Self, String>> {
+        let path = Self::sidecar_path(texture_path);
+        if !vfs.exists(&path) {
+            return None;
+        }
+        Some(vfs.read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| String::from_utf8(data).map_err(|e| e.to_string()))
+            .and_then(|text| Self::parse(&text))
+            .map_err(|e| format!("Invalid options file {:?}: {}", path, e)))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum TextureKind {
     R8,
     RGB8,
     RGBA8,
+    /// Block compressed RGB with 1-bit alpha, also known as DXT1.
+    BC1,
+    /// Block compressed RGBA with explicit 4-bit alpha, also known as DXT3.
+    BC2,
+    /// Block compressed RGBA with interpolated alpha, also known as DXT5.
+    BC3,
+    /// Block compressed single channel.
+    BC4,
+    /// Block compressed two channels, mostly used for normal maps.
+    BC5,
+    /// Block compressed unsigned HDR RGB.
+    BC6H,
+    /// Block compressed signed HDR RGB.
+    BC6HSigned,
+    /// High quality block compressed RGBA.
+    BC7,
 }
 
 impl TextureKind {
@@ -59,6 +419,14 @@ impl TextureKind {
             0 => Ok(TextureKind::R8),
             1 => Ok(TextureKind::RGB8),
             2 => Ok(TextureKind::RGBA8),
+            3 => Ok(TextureKind::BC1),
+            4 => Ok(TextureKind::BC2),
+            5 => Ok(TextureKind::BC3),
+            6 => Ok(TextureKind::BC4),
+            7 => Ok(TextureKind::BC5),
+            8 => Ok(TextureKind::BC6H),
+            9 => Ok(TextureKind::BC6HSigned),
+            10 => Ok(TextureKind::BC7),
             _ => Err(format!("Invalid texture kind {}!", id))
         }
     }
@@ -68,21 +436,162 @@ impl TextureKind {
             TextureKind::R8 => 0,
             TextureKind::RGB8 => 1,
             TextureKind::RGBA8 => 2,
+            TextureKind::BC1 => 3,
+            TextureKind::BC2 => 4,
+            TextureKind::BC3 => 5,
+            TextureKind::BC4 => 6,
+            TextureKind::BC5 => 7,
+            TextureKind::BC6H => 8,
+            TextureKind::BC6HSigned => 9,
+            TextureKind::BC7 => 10,
+        }
+    }
+
+    #[inline]
+    pub fn is_compressed(self) -> bool {
+        self.block_size().is_some()
+    }
+
+    /// Returns size in bytes of 4x4 block of compressed texture, None for uncompressed kinds.
+    pub fn block_size(self) -> Option<usize> {
+        match self {
+            TextureKind::R8 | TextureKind::RGB8 | TextureKind::RGBA8 => None,
+            TextureKind::BC1 | TextureKind::BC4 => Some(8),
+            TextureKind::BC2 | TextureKind::BC3 | TextureKind::BC5 |
+            TextureKind::BC6H | TextureKind::BC6HSigned | TextureKind::BC7 => Some(16),
+        }
+    }
+
+    /// Returns size in bytes of a pixel of uncompressed texture, zero for compressed kinds.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            TextureKind::R8 => 1,
+            TextureKind::RGB8 => 3,
+            TextureKind::RGBA8 => 4,
+            _ => 0,
         }
     }
 }
 
+/// Content of texture file along with options of texture. Reading of files is the only part
+/// of texture loading that needs VFS, so VFS does not have to be locked while texture is
+/// decoded.
+pub(in crate) struct TextureFile {
+    data: Vec<u8>,
+    options: Option<TextureImportOptions>,
+    options_overridden: bool,
+}
+
+impl TextureFile {
+    /// Reads texture file at given virtual path. If `options` is None, options are read from
+    /// sidecar file if there is one.
+    pub(in crate) fn read(vfs: &Vfs, path: &Path, options: Option<TextureImportOptions>) -> Result<Self, image::ImageError> {
+        let data = vfs.read(path)?;
+        let options_overridden = options.is_some();
+        let options = match options {
+            Some(options) => Some(options),
+            None => match TextureImportOptions::load_sidecar(vfs, path) {
+                Some(options) => Some(options.map_err(image::ImageError::FormatError)?),
+                None => None,
+            },
+        };
+        Ok(Self {
+            data,
+            options,
+            options_overridden,
+        })
+    }
+
+    pub(in crate) fn decode(self, path: &Path, kind: TextureKind) -> Result<Texture, image::ImageError> {
+        let mut texture = Texture::load_from_memory(&self.data, path, kind)?;
+        if let Some(options) = self.options {
+            // Format of DDS and KTX files tells whether colors are in sRGB space, it is
+            // kept even if options do not mention it.
+            texture.options = TextureImportOptions {
+                srgb: options.srgb || texture.options.srgb,
+                ..options
+            };
+        }
+        texture.options_overridden = self.options_overridden;
+        Ok(texture)
+    }
+}
+
 impl Texture {
-    pub(in crate) fn load_from_file<P: AsRef<Path>>(path: P, kind: TextureKind) -> Result<Self, image::ImageError> {
-        let dyn_img = image::open(path.as_ref())?;
+    /// Creates texture of given size filled with zeros. Such texture can be filled later
+    /// using [set_region](Texture::set_region) or [pixels_mut](Texture::pixels_mut).
+    pub fn new(width: u32, height: u32, kind: TextureKind) -> Self {
+        let bytes = vec![0; block_compression::level_size(kind, width, height)];
+        Self::from_bytes(width, height, kind, bytes)
+    }
+
+    /// Creates texture from given pixels, rows of pixels must be stored one after another
+    /// starting from top row. Compressed kinds expect rows of blocks.
+    pub fn from_pixels(width: u32, height: u32, kind: TextureKind, bytes: Vec<u8>) -> Result<Self, String> {
+        let expected = block_compression::level_size(kind, width, height);
+        if bytes.len() != expected {
+            return Err(format!("Texture {}x{} of kind {:?} must have {} bytes, got {}", width, height, kind, expected, bytes.len()));
+        }
+        Ok(Self::from_bytes(width, height, kind, bytes))
+    }
+
+    /// Loads texture from file at given virtual path. DDS and KTX textures are loaded as is
+    /// with every mip level they have, so requested kind is ignored for them. If `options`
+    /// is None, options are read from sidecar file or default options are used.
+    pub(in crate) fn load<P: AsRef<Path>>(vfs: &Vfs,
+                                          path: P,
+                                          kind: TextureKind,
+                                          options: Option<TextureImportOptions>,
+    ) -> Result<Self, image::ImageError> {
+        TextureFile::read(vfs, path.as_ref(), options)?.decode(path.as_ref(), kind)
+    }
+
+    /// Decodes texture from file content, for example from media embedded in a model. Path
+    /// is used to detect format by extension and becomes path of texture.
+    pub(in crate) fn load_from_memory(data: &[u8], path: &Path, kind: TextureKind) -> Result<Self, image::ImageError> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_default();
+        let container = match extension.as_str() {
+            "dds" => Some(dds::read(data)?),
+            "ktx" => Some(ktx::read(data)?),
+            _ => None,
+        };
+        if let Some(mut texture) = container {
+            texture.path = path.to_path_buf();
+            return Ok(texture);
+        }
+
+        // Some formats (TGA for example) have no signature, so format is taken from extension
+        // first, just like when image is opened from file.
+        let format = match extension.as_str() {
+                "png" => Some(ImageFormat::PNG),
+                "jpg" | "jpeg" => Some(ImageFormat::JPEG),
+                "gif" => Some(ImageFormat::GIF),
+                "webp" => Some(ImageFormat::WEBP),
+                "tif" | "tiff" => Some(ImageFormat::TIFF),
+                "tga" => Some(ImageFormat::TGA),
+                "bmp" => Some(ImageFormat::BMP),
+                "ico" => Some(ImageFormat::ICO),
+                "hdr" => Some(ImageFormat::HDR),
+                "pbm" | "pam" | "ppm" | "pgm" => Some(ImageFormat::PNM),
+                _ => None,
+            };
+        let dyn_img = match format {
+            Some(format) => image::load_from_memory_with_format(data, format)?,
+            None => image::load_from_memory(data)?,
+        };
 
         let width = dyn_img.width();
         let height = dyn_img.height();
 
-        let bytes = match kind {
-            TextureKind::R8 => dyn_img.to_luma().into_raw(),
-            TextureKind::RGB8 => dyn_img.to_rgb().into_raw(),
-            TextureKind::RGBA8 => dyn_img.to_rgba().into_raw(),
+        let (kind, bytes) = match kind {
+            TextureKind::R8 => (kind, dyn_img.to_luma().into_raw()),
+            TextureKind::RGB8 => (kind, dyn_img.to_rgb().into_raw()),
+            // Ordinary images are never compressed at load time.
+            _ => (TextureKind::RGBA8, dyn_img.to_rgba().into_raw()),
         };
 
         Ok(Texture {
@@ -90,24 +599,218 @@ impl Texture {
             width,
             height,
             bytes,
-            path: path.as_ref().to_path_buf(),
-            loaded: true,
+            mip_count: 1,
+            path: path.to_path_buf(),
+            state: ResourceState::Ok,
+            revision: 0,
+            options: Default::default(),
+            options_overridden: false,
+            dirty_region: None,
+            unique_id: next_unique_id(),
         })
     }
 
     pub(in crate) fn from_bytes(width: u32, height: u32, kind: TextureKind, bytes: Vec<u8>) -> Self {
+        Self::from_mip_chain(width, height, kind, 1, bytes)
+    }
+
+    /// Creates texture from given mip levels stored one after another.
+    pub(in crate) fn from_mip_chain(width: u32, height: u32, kind: TextureKind, mip_count: u32, bytes: Vec<u8>) -> Self {
         Self {
             path: Default::default(),
             width,
             height,
             bytes,
             kind,
-            loaded: true
+            mip_count: mip_count.max(1),
+            state: ResourceState::Ok,
+            revision: 0,
+            options: Default::default(),
+            options_overridden: false,
+            dirty_region: None,
+            unique_id: next_unique_id(),
         }
     }
 
     pub fn is_loaded(&self) -> bool {
-        self.loaded
+        self.state == ResourceState::Ok
+    }
+
+    /// Returns current state of texture, texture requested asynchronously stays in pending
+    /// state until it is loaded or failed to load.
+    pub fn state(&self) -> &ResourceState {
+        &self.state
+    }
+
+    #[inline]
+    pub fn kind(&self) -> TextureKind {
+        self.kind
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Returns pixels of every mip level of texture.
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns pixels for modification, whole texture is marked as changed.
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        self.mark_dirty(TextureRegion::new(0, 0, self.width, self.height));
+        &mut self.bytes
+    }
+
+    /// Marks region as changed, so renderer will upload it again on next frame. Region is
+    /// clipped by bounds of texture, region that is entirely outside of texture is ignored.
+    /// Compressed textures and textures with mip chain are always uploaded entirely.
+    pub fn mark_dirty(&mut self, region: TextureRegion) {
+        let region = match region.clip(self.width, self.height) {
+            Some(region) => region,
+            None => return,
+        };
+        if self.kind.is_compressed() || self.mip_count > 1 {
+            self.revision = self.revision.wrapping_add(1);
+        } else {
+            self.dirty_region = Some(match self.dirty_region {
+                Some(dirty_region) => dirty_region.union(region),
+                None => region,
+            });
+        }
+    }
+
+    /// Returns region that was changed since last upload to GPU.
+    #[inline]
+    pub fn dirty_region(&self) -> Option<TextureRegion> {
+        self.dirty_region
+    }
+
+    /// Replaces pixels of given region, `pixels` must contain rows of region one after another.
+    /// Only uncompressed textures without mip chain can be changed by regions.
+    pub fn set_region(&mut self, region: TextureRegion, pixels: &[u8]) -> Result<(), String> {
+        if self.kind.is_compressed() || self.mip_count > 1 {
+            return Err("Only uncompressed textures without mip chain can be changed by regions".to_owned());
+        }
+        if region.right().map_or(true, |right| right > self.width) || region.bottom().map_or(true, |bottom| bottom > self.height) {
+            return Err(format!("Region {:?} is out of bounds of {}x{} texture", region, self.width, self.height));
+        }
+        let bytes_per_pixel = self.kind.bytes_per_pixel();
+        let region_row_size = region.width as usize * bytes_per_pixel;
+        if pixels.len() != region_row_size * region.height as usize {
+            return Err(format!("Region {:?} must have {} bytes, got {}", region, region_row_size * region.height as usize, pixels.len()));
+        }
+
+        let row_size = self.width as usize * bytes_per_pixel;
+        for (row, source) in pixels.chunks_exact(region_row_size.max(1)).enumerate() {
+            let begin = (region.y as usize + row) * row_size + region.x as usize * bytes_per_pixel;
+            self.bytes[begin..begin + region_row_size].copy_from_slice(source);
+        }
+        self.mark_dirty(region);
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn mip_count(&self) -> u32 {
+        self.mip_count
+    }
+
+    #[inline]
+    pub fn options(&self) -> &TextureImportOptions {
+        &self.options
+    }
+
+    /// Sets new options, renderer will apply them on next frame. Options set this way are
+    /// kept when texture is reloaded.
+    pub fn set_options(&mut self, options: TextureImportOptions) {
+        if self.options != options {
+            self.options = options;
+            self.options_overridden = true;
+            self.revision = self.revision.wrapping_add(1);
+        }
     }
 }
 
+#[cfg(test)]
+mod test {
+    use crate::resource::texture::{
+        Texture,
+        TextureKind,
+        TextureRegion,
+        TextureImportOptions,
+        TextureMinificationFilter,
+        TextureWrapMode,
+    };
+
+    #[test]
+    fn parse_import_options() {
+        let text = "// Sprite\n(\n    minification_filter: Nearest,\n    wrap_s: ClampToEdge,\n    anisotropy: 0.0,\n    srgb: true,\n)\n";
+        let options = TextureImportOptions::parse(text).unwrap();
+        assert_eq!(options.minification_filter, TextureMinificationFilter::Nearest);
+        assert_eq!(options.wrap_s, TextureWrapMode::ClampToEdge);
+        assert_eq!(options.wrap_t, TextureWrapMode::Repeat);
+        assert_eq!(options.anisotropy, 1.0);
+        assert!(options.srgb);
+        assert!(options.generate_mips);
+
+        let options = TextureImportOptions::default()
+            .with_wrap(TextureWrapMode::MirroredRepeat)
+            .with_generate_mips(false);
+        assert_eq!(TextureImportOptions::parse(&options.to_text().unwrap()).unwrap(), options);
+
+        assert!(TextureImportOptions::parse("(wrap_s: Sideways)").is_err());
+        assert!(TextureImportOptions::parse("(unknown: 1)").is_err());
+        assert!(TextureImportOptions::parse("srgb").is_err());
+    }
+
+    #[test]
+    fn region_union() {
+        let a = TextureRegion::new(1, 2, 3, 4);
+        let b = TextureRegion::new(3, 0, 5, 1);
+        assert_eq!(a.union(b), TextureRegion::new(1, 0, 7, 6));
+
+        let huge = TextureRegion::new(u32::MAX - 1, 0, 10, 1);
+        assert_eq!(huge.right(), None);
+        assert_eq!(a.union(huge), TextureRegion::new(1, 0, u32::MAX - 1, 6));
+    }
+
+    #[test]
+    fn set_region() {
+        let mut texture = Texture::new(3, 2, TextureKind::R8);
+        texture.set_region(TextureRegion::new(1, 0, 2, 2), &[1, 2, 3, 4]).unwrap();
+        assert_eq!(texture.bytes, vec![0, 1, 2, 0, 3, 4]);
+        assert_eq!(texture.dirty_region(), Some(TextureRegion::new(1, 0, 2, 2)));
+
+        assert!(texture.set_region(TextureRegion::new(2, 0, 2, 1), &[0, 0]).is_err());
+        assert!(texture.set_region(TextureRegion::new(u32::MAX, 0, 2, 1), &[0, 0]).is_err());
+        assert!(texture.set_region(TextureRegion::new(0, u32::MAX, 1, 2), &[0, 0]).is_err());
+        assert!(texture.set_region(TextureRegion::new(0, 0, 1, 1), &[0, 0]).is_err());
+        assert_eq!(texture.bytes, vec![0, 1, 2, 0, 3, 4]);
+    }
+
+    #[test]
+    fn mark_dirty_clips_region() {
+        let mut texture = Texture::new(4, 4, TextureKind::RGBA8);
+        texture.mark_dirty(TextureRegion::new(10, 10, 1, 1));
+        assert_eq!(texture.dirty_region(), None);
+
+        texture.mark_dirty(TextureRegion::new(2, 3, u32::MAX, u32::MAX));
+        assert_eq!(texture.dirty_region(), Some(TextureRegion::new(2, 3, 2, 1)));
+
+        texture.mark_dirty(TextureRegion::new(0, 0, 1, 1));
+        assert_eq!(texture.dirty_region(), Some(TextureRegion::new(0, 0, 4, 4)));
+    }
+}
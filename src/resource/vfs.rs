@@ -0,0 +1,232 @@
+//! Virtual file system.
+//!
+//! Every loader of resource manager reads files through VFS, so resources can be stored either
+//! as loose files in directories or packed in archives. VFS consists of mount points, each mount
+//! point maps some virtual directory to a source of files - real directory or archive. Virtual
+//! paths look exactly like ordinary relative paths, so `data/textures/wall.png` can be read from
+//! `data/textures` directory on disk or from `data.pak` archive mounted to `data` directory.
+//!
+//! Default VFS has only one mount point - current working directory mounted to root, so every
+//! path is read from disk as is.
+
+use std::{
+    io,
+    fs,
+    path::{Path, PathBuf, Component},
+    time::SystemTime,
+};
+use crate::resource::archive::Archive;
+
+/// Source of files of a mount point. Every path passed to methods of source is relative
+/// to mount point.
+pub trait MountSource: Send + Sync {
+    /// Reads whole file into memory.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Returns modification time of file, it is used by hot reloading.
+    fn modified(&self, path: &Path) -> Option<SystemTime>;
+
+    /// Returns path of file on disk if file is stored as loose file. Some loaders (like
+    /// streaming sound buffers) prefer to read files by themselves.
+    fn real_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    /// Writes whole file, creating or replacing it. Sources are read-only by default.
+    fn write(&self, _path: &Path, _data: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "mount source is read-only"))
+    }
+}
+
+/// Directory on disk.
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_owned()
+        }
+    }
+
+    #[inline]
+    pub fn root(&self) -> &Path {
+        self.root.as_path()
+    }
+}
+
+impl MountSource for DirectorySource {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(self.root.join(path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.root.join(path).is_file()
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        fs::metadata(self.root.join(path)).and_then(|metadata| metadata.modified()).ok()
+    }
+
+    fn real_path(&self, path: &Path) -> Option<PathBuf> {
+        let path = self.root.join(path);
+        if path.is_file() {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let path = self.root.join(path);
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(path, data)
+    }
+}
+
+struct MountPoint {
+    point: PathBuf,
+    source: Box<dyn MountSource>,
+}
+
+pub struct Vfs {
+    mount_points: Vec<MountPoint>,
+}
+
+impl Default for Vfs {
+    fn default() -> Self {
+        let mut vfs = Self::new();
+        // Empty root keeps paths of files as is, so default VFS reads exactly what
+        // would be read by std::fs.
+        vfs.mount_directory("", "");
+        vfs
+    }
+}
+
+/// Removes `.` components and resolves `..` components where possible, so
+/// `data/./textures/../models/a.fbx` becomes `data/models/a.fbx`.
+pub fn normalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.as_ref().components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                let can_pop = match result.components().last() {
+                    Some(Component::Normal(_)) => true,
+                    _ => false,
+                };
+                if can_pop {
+                    result.pop();
+                } else {
+                    result.push(component.as_os_str());
+                }
+            }
+            _ => result.push(component.as_os_str()),
+        }
+    }
+    result
+}
+
+impl Vfs {
+    /// Creates VFS without mount points, nothing can be read from it until something is mounted.
+    pub fn new() -> Self {
+        Self {
+            mount_points: Vec::new()
+        }
+    }
+
+    /// Mounts given source to given virtual directory, empty path is a root. Mount points
+    /// mounted later have priority over previous ones, so archive with patch can be mounted
+    /// over main archive.
+    pub fn mount<P: AsRef<Path>>(&mut self, point: P, source: Box<dyn MountSource>) {
+        self.mount_points.push(MountPoint {
+            point: normalize_path(point),
+            source,
+        })
+    }
+
+    pub fn mount_directory<P: AsRef<Path>, D: AsRef<Path>>(&mut self, point: P, directory: D) {
+        self.mount(point, Box::new(DirectorySource::new(directory)))
+    }
+
+    /// Opens archive at given path on disk and mounts it to given virtual directory.
+    pub fn mount_archive<P: AsRef<Path>, A: AsRef<Path>>(&mut self, point: P, archive: A) -> io::Result<()> {
+        let archive = Archive::open(archive)?;
+        self.mount(point, Box::new(archive));
+        Ok(())
+    }
+
+    /// Removes every mount point at given virtual directory. Returns true if something
+    /// was unmounted.
+    pub fn unmount<P: AsRef<Path>>(&mut self, point: P) -> bool {
+        let point = normalize_path(point);
+        let count = self.mount_points.len();
+        self.mount_points.retain(|mount_point| mount_point.point != point);
+        count != self.mount_points.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.mount_points.clear();
+    }
+
+    /// Returns iterator over sources that can contain given path along with path relative to
+    /// each source, in order of priority.
+    fn sources<'a>(&'a self, path: &Path) -> impl Iterator<Item=(&'a dyn MountSource, PathBuf)> + 'a {
+        let path = normalize_path(path);
+        self.mount_points
+            .iter()
+            .rev()
+            .filter_map(move |mount_point| {
+                path.strip_prefix(&mount_point.point)
+                    .ok()
+                    .map(|relative| (&*mount_point.source, relative.to_path_buf()))
+            })
+    }
+
+    /// Reads whole file from first mount point that has it.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<u8>> {
+        for (source, relative) in self.sources(path.as_ref()) {
+            if source.exists(&relative) {
+                return source.read(&relative);
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::NotFound, format!("{:?} not found in any mount point", path.as_ref())))
+    }
+
+    pub fn exists<P: AsRef<Path>>(&self, path: P) -> bool {
+        self.sources(path.as_ref()).any(|(source, relative)| source.exists(&relative))
+    }
+
+    pub fn modified<P: AsRef<Path>>(&self, path: P) -> Option<SystemTime> {
+        self.sources(path.as_ref())
+            .find(|(source, relative)| source.exists(relative))
+            .and_then(|(source, relative)| source.modified(&relative))
+    }
+
+    /// Writes whole file to first mount point which accepts given path and is writable,
+    /// archives are read-only so in most cases file is written to a mounted directory.
+    pub fn write<P: AsRef<Path>>(&self, path: P, data: &[u8]) -> io::Result<()> {
+        for (source, relative) in self.sources(path.as_ref()) {
+            match source.write(&relative, data) {
+                Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => continue,
+                result => return result,
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, format!("{:?} can't be written to any mount point", path.as_ref())))
+    }
+
+    /// Returns path on disk if file is a loose file, None if file does not exist or stored
+    /// in archive.
+    pub fn real_path<P: AsRef<Path>>(&self, path: P) -> Option<PathBuf> {
+        self.sources(path.as_ref())
+            .find(|(source, relative)| source.exists(relative))
+            .and_then(|(source, relative)| source.real_path(&relative))
+    }
+}
@@ -1,10 +1,14 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 use crate::{
     utils::log::Log,
     scene::{
         node::Node,
         base::AsBase,
     },
+    resource::model::Model,
     core::{
         pool::{
             Handle,
@@ -279,6 +283,18 @@ impl Graph {
 
         self.update_transforms();
 
+        self.sync_with_resources(|_| true);
+
+        Log::writeln("Graph resolved successfully!".to_owned());
+    }
+
+    /// Takes new surfaces and bind poses from given model for every node instantiated from it.
+    /// Must be called when model was reloaded, otherwise instances will keep old data.
+    pub(in crate) fn resync_model_instances(&mut self, model: &Arc<Mutex<Model>>) {
+        self.sync_with_resources(|resource| Arc::ptr_eq(resource, model));
+    }
+
+    fn sync_with_resources<F>(&mut self, filter: F) where F: Fn(&Arc<Mutex<Model>>) -> bool {
         // Resolve original handles. Original handle is a handle to a node in resource from which
         // a node was instantiated from. We can resolve it only by names of nodes, but this is not
         // reliable way of doing this, because some editors allow nodes to have same names for
@@ -286,7 +302,7 @@ impl Graph {
         // names.
         for node in self.pool.iter_mut() {
             let base = node.base_mut();
-            if let Some(model) = base.resource() {
+            if let Some(model) = base.resource().filter(|model| filter(model)) {
                 let model = model.lock().unwrap();
                 for (handle, resource_node) in model.get_scene().graph.pair_iter() {
                     if resource_node.base().name() == base.name() {
@@ -317,7 +333,7 @@ impl Graph {
             if let Node::Mesh(mesh) = node {
                 let root_handle = graph.find_model_root(node_handle);
                 let node_name = String::from(mesh.base().name());
-                if let Some(model) = mesh.base().resource() {
+                if let Some(model) = mesh.base().resource().filter(|model| filter(model)) {
                     let model = model.lock().unwrap();
                     let resource_node_handle = model.find_node_by_name(node_name.as_str());
                     if let Node::Mesh(resource_mesh) = model.get_scene().graph.get(resource_node_handle) {
@@ -337,8 +353,6 @@ impl Graph {
                 }
             }
         }
-
-        Log::writeln("Graph resolved successfully!".to_owned());
     }
 
     pub fn update_transforms(&mut self) {
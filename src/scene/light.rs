@@ -229,6 +229,11 @@ impl Light {
         self.color
     }
 
+    #[inline]
+    pub fn is_cast_shadows(&self) -> bool {
+        self.cast_shadows
+    }
+
     #[inline]
     pub fn get_kind(&self) -> &LightKind {
         &self.kind
@@ -18,6 +18,8 @@ pub struct Mesh {
     surfaces: Vec<Surface>,
     bounding_box: Cell<AxisAlignedBoundingBox>,
     dirty: Cell<bool>,
+    /// Weights of blend shapes, each surface of mesh has same set of blend shapes.
+    blend_shape_weights: Vec<f32>,
 }
 
 impl Default for Mesh {
@@ -27,6 +29,7 @@ impl Default for Mesh {
             surfaces: Default::default(),
             bounding_box: Default::default(),
             dirty: Cell::new(true),
+            blend_shape_weights: Default::default(),
         }
     }
 }
@@ -46,6 +49,7 @@ impl Visit for Mesh {
         visitor.enter_region(name)?;
 
         self.base.visit("Common", visitor)?;
+        self.blend_shape_weights.visit("BlendShapeWeights", visitor)?;
 
         // No need to serialize surfaces, correct ones will be assigned on resolve stage.
         visitor.leave_region()
@@ -73,6 +77,29 @@ impl Mesh {
         self.surfaces.push(surface);
     }
 
+    /// Returns weights of blend shapes of surfaces. See `BlendShape` docs.
+    #[inline]
+    pub fn blend_shape_weights(&self) -> &[f32] {
+        &self.blend_shape_weights
+    }
+
+    /// Sets weight of blend shape with given index, usually weight is in 0..1 range.
+    pub fn set_blend_shape_weight(&mut self, index: usize, weight: f32) {
+        if index >= self.blend_shape_weights.len() {
+            self.blend_shape_weights.resize(index + 1, 0.0);
+        }
+        self.blend_shape_weights[index] = weight;
+    }
+
+    /// Searches index of blend shape with given name.
+    pub fn find_blend_shape(&self, name: &str) -> Option<usize> {
+        let data = self.surfaces.first()?.get_data();
+        let data = data.lock().unwrap();
+        data.blend_shapes()
+            .iter()
+            .position(|blend_shape| blend_shape.name == name)
+    }
+
     /// Performs lazy bounding box evaluation.
     /// Bounding box presented in *local coordinates*
     pub fn bounding_box(&self) -> AxisAlignedBoundingBox {
@@ -0,0 +1,286 @@
+//! Reordering of triangles and vertices of indexed triangle meshes for faster rendering.
+//!
+//! Triangles are ordered to reuse results of vertex shader from post-transform vertex
+//! cache of GPU and to reduce overdraw, vertices are ordered in order of their use to
+//! reduce cache misses on vertex fetch. Triangle ordering is based on "Fast Triangle
+//! Reordering for Vertex Locality and Reduced Overdraw" by P. Sander, D. Nehab and
+//! J. Barczak (also known as "Tipsify"), it works in linear time.
+
+use crate::core::math::vec3::Vec3;
+
+/// Returns first live vertex starting from cursor, or None if every triangle was emitted.
+fn next_live_vertex(live: &[u32], cursor: &mut usize) -> Option<usize> {
+    while *cursor < live.len() {
+        if live[*cursor] > 0 {
+            return Some(*cursor);
+        }
+        *cursor += 1;
+    }
+    None
+}
+
+/// Reorders triangles for post-transform vertex cache of given size. Returns indices of
+/// first triangles of clusters - runs of triangles that start with empty cache, order
+/// of clusters can be changed almost without loss of cache efficiency.
+pub fn optimize_vertex_cache(indices: &mut [u32], vertex_count: usize, cache_size: usize) -> Vec<usize> {
+    let triangle_count = indices.len() / 3;
+
+    // Count of not yet emitted triangles that use each vertex.
+    let mut live = vec![0u32; vertex_count];
+    for index in indices[..triangle_count * 3].iter() {
+        live[*index as usize] += 1;
+    }
+
+    // Triangles adjacent to each vertex, triangles of vertex i are stored in
+    // adjacency[offsets[i]..offsets[i + 1]].
+    let mut offsets = vec![0; vertex_count + 1];
+    for i in 0..vertex_count {
+        offsets[i + 1] = offsets[i] + live[i] as usize;
+    }
+    let mut adjacency = vec![0u32; triangle_count * 3];
+    let mut fill = offsets.clone();
+    for (triangle, vertices) in indices.chunks_exact(3).enumerate() {
+        for index in vertices {
+            let index = *index as usize;
+            adjacency[fill[index]] = triangle as u32;
+            fill[index] += 1;
+        }
+    }
+
+    // Vertex is in cache if it was added to cache less than cache_size misses ago.
+    let mut cache_time = vec![0; vertex_count];
+    let mut time = cache_size + 1;
+    let mut emitted = vec![false; triangle_count];
+    let mut dead_end_stack = Vec::new();
+    let mut candidates = Vec::new();
+    let mut output = Vec::with_capacity(triangle_count * 3);
+    let mut clusters = Vec::new();
+    let mut cursor = 0;
+
+    let mut fanning = next_live_vertex(&live, &mut cursor);
+    if fanning.is_some() {
+        clusters.push(0);
+    }
+    while let Some(fanning_vertex) = fanning {
+        // Emit every triangle around fanning vertex.
+        candidates.clear();
+        for triangle in adjacency[offsets[fanning_vertex]..offsets[fanning_vertex + 1]].iter() {
+            let triangle = *triangle as usize;
+            if emitted[triangle] {
+                continue;
+            }
+            for index in indices[triangle * 3..triangle * 3 + 3].iter() {
+                let vertex = *index as usize;
+                output.push(*index);
+                dead_end_stack.push(vertex);
+                candidates.push(vertex);
+                live[vertex] -= 1;
+                if time - cache_time[vertex] > cache_size {
+                    cache_time[vertex] = time;
+                    time += 1;
+                }
+            }
+            emitted[triangle] = true;
+        }
+
+        // Next fanning vertex is the one that will still be in cache after its triangles
+        // are emitted, the older it is, the better.
+        let mut best = None;
+        let mut best_priority = 0;
+        for vertex in candidates.iter() {
+            let vertex = *vertex;
+            if live[vertex] > 0 {
+                let age = time - cache_time[vertex];
+                let priority = if age + 2 * live[vertex] as usize <= cache_size { age } else { 0 };
+                if best.is_none() || priority > best_priority {
+                    best = Some(vertex);
+                    best_priority = priority;
+                }
+            }
+        }
+
+        fanning = match best {
+            Some(vertex) => Some(vertex),
+            None => {
+                // Dead end, continue from most recently used live vertex or from any live vertex.
+                let mut next = None;
+                while let Some(vertex) = dead_end_stack.pop() {
+                    if live[vertex] > 0 {
+                        next = Some(vertex);
+                        break;
+                    }
+                }
+                if next.is_none() {
+                    next = next_live_vertex(&live, &mut cursor);
+                }
+                if let Some(vertex) = next {
+                    if time - cache_time[vertex] > cache_size {
+                        clusters.push(output.len() / 3);
+                    }
+                }
+                next
+            }
+        };
+    }
+
+    indices[..output.len()].copy_from_slice(&output);
+
+    clusters
+}
+
+/// Reorders clusters of triangles so clusters that face outwards of mesh are drawn first,
+/// they most likely occlude other clusters. Clusters are defined by indices of their first
+/// triangles, see [optimize_vertex_cache](fn.optimize_vertex_cache.html).
+pub fn optimize_overdraw<F: Fn(usize) -> Vec3>(indices: &mut [u32], position: F, clusters: &[usize]) {
+    let triangle_count = indices.len() / 3;
+    if clusters.len() < 2 {
+        return;
+    }
+
+    // Area-weighted centroid and normal of each cluster.
+    let mut mesh_centroid = Vec3::ZERO;
+    let mut mesh_area = 0.0;
+    let mut cluster_data = Vec::with_capacity(clusters.len());
+    for (i, first) in clusters.iter().enumerate() {
+        let last = clusters.get(i + 1).copied().unwrap_or(triangle_count);
+        let mut centroid = Vec3::ZERO;
+        let mut normal = Vec3::ZERO;
+        let mut area = 0.0;
+        for triangle in indices[*first * 3..last * 3].chunks_exact(3) {
+            let a = position(triangle[0] as usize);
+            let b = position(triangle[1] as usize);
+            let c = position(triangle[2] as usize);
+            let triangle_normal = (b - a).cross(&(c - a));
+            let triangle_area = triangle_normal.len() * 0.5;
+            centroid += (a + b + c).scale(triangle_area / 3.0);
+            normal += triangle_normal;
+            area += triangle_area;
+        }
+        mesh_centroid += centroid;
+        mesh_area += area;
+        cluster_data.push((*first, last, centroid, normal, area));
+    }
+    if mesh_area > 0.0 {
+        mesh_centroid = mesh_centroid.scale(1.0 / mesh_area);
+    }
+
+    let mut order = cluster_data
+        .iter()
+        .map(|(first, last, centroid, normal, area)| {
+            let centroid = if *area > 0.0 { centroid.scale(1.0 / area) } else { *centroid };
+            let outwardness = normal
+                .normalized()
+                .map_or(0.0, |normal| (centroid - mesh_centroid).dot(&normal));
+            (*first, *last, outwardness)
+        })
+        .collect::<Vec<_>>();
+    // Stable sort keeps original order of equal clusters, so flat meshes stay intact.
+    order.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut output = Vec::with_capacity(triangle_count * 3);
+    for (first, last, _) in order {
+        output.extend_from_slice(&indices[first * 3..last * 3]);
+    }
+    indices[..output.len()].copy_from_slice(&output);
+}
+
+/// Remaps indices so vertices are numbered in order of their first use, unused vertices go
+/// last. Returns new order of vertices: i-th vertex of new order is `order[i]`-th vertex of
+/// old order.
+pub fn optimize_vertex_fetch(indices: &mut [u32], vertex_count: usize) -> Vec<u32> {
+    let mut remap = vec![u32::max_value(); vertex_count];
+    let mut order = Vec::with_capacity(vertex_count);
+    for index in indices.iter_mut() {
+        let new_index = &mut remap[*index as usize];
+        if *new_index == u32::max_value() {
+            *new_index = order.len() as u32;
+            order.push(*index);
+        }
+        *index = *new_index;
+    }
+    for (i, new_index) in remap.iter().enumerate() {
+        if *new_index == u32::max_value() {
+            order.push(i as u32);
+        }
+    }
+    order
+}
+
+/// Calculates average cache miss ratio - average count of vertex shader invocations per
+/// triangle for FIFO post-transform cache of given size. 3.0 is the worst possible value,
+/// regular grids can get close to 0.5.
+pub fn average_cache_miss_ratio(indices: &[u32], vertex_count: usize, cache_size: usize) -> f32 {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return 0.0;
+    }
+    // Number of miss at which vertex was added to cache.
+    let mut cache_time = vec![None; vertex_count];
+    let mut misses: usize = 0;
+    for index in indices[..triangle_count * 3].iter() {
+        let time = &mut cache_time[*index as usize];
+        let in_cache = match *time {
+            Some(time) => misses - time < cache_size,
+            None => false,
+        };
+        if !in_cache {
+            *time = Some(misses);
+            misses += 1;
+        }
+    }
+    misses as f32 / triangle_count as f32
+}
+
+#[cfg(test)]
+mod test {
+    use crate::utils::mesh_optimizer::{
+        optimize_vertex_cache,
+        optimize_vertex_fetch,
+        average_cache_miss_ratio,
+    };
+
+    /// Grid of quads with vertices numbered row by row and triangles in random order.
+    fn shuffled_grid(size: u32) -> Vec<u32> {
+        let mut triangles = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                let i = y * (size + 1) + x;
+                triangles.push([i, i + 1, i + size + 1]);
+                triangles.push([i + 1, i + size + 2, i + size + 1]);
+            }
+        }
+        // Deterministic shuffle.
+        let count = triangles.len();
+        for i in 0..count {
+            triangles.swap(i, (i * 7919 + 13) % count);
+        }
+        triangles.iter().flat_map(|t| t.iter().copied()).collect()
+    }
+
+    #[test]
+    fn vertex_cache_optimization() {
+        let vertex_count = 33 * 33;
+        let mut indices = shuffled_grid(32);
+        let source = indices.clone();
+        let before = average_cache_miss_ratio(&indices, vertex_count, 16);
+        optimize_vertex_cache(&mut indices, vertex_count, 16);
+        let after = average_cache_miss_ratio(&indices, vertex_count, 16);
+        assert!(after < before);
+        assert!(after < 1.0);
+
+        // Every triangle must be preserved.
+        let mut a = source.chunks(3).map(|t| t.to_vec()).collect::<Vec<_>>();
+        let mut b = indices.chunks(3).map(|t| t.to_vec()).collect::<Vec<_>>();
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn vertex_fetch_optimization() {
+        let mut indices = vec![3, 1, 2, 2, 1, 0];
+        let order = optimize_vertex_fetch(&mut indices, 5);
+        assert_eq!(indices, vec![0, 1, 2, 2, 1, 3]);
+        assert_eq!(order, vec![3, 1, 2, 0, 4]);
+    }
+}
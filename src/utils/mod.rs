@@ -1,6 +1,8 @@
 pub mod astar;
 pub mod log;
+pub mod mesh_optimizer;
 pub mod navmesh;
+pub mod weld;
 
 use crate::{
     scene::{mesh::Mesh, base::AsBase},
@@ -11,7 +13,10 @@ use crate::{
         math::vec2::Vec2,
         math::TriangleDefinition
     },
-    utils::navmesh::Navmesh,
+    utils::{
+        navmesh::Navmesh,
+        weld::{VertexWelder, WeldOptions, WeldVertex},
+    },
 };
 use std::{
     any::Any,
@@ -57,6 +62,7 @@ pub fn mesh_to_static_geometry(mesh: &Mesh) -> StaticGeometry {
 pub struct SimpleMesh<T> {
     pub vertices: Vec<T>,
     pub indices: Vec<u32>,
+    welder: VertexWelder,
 }
 
 impl<T> Default for SimpleMesh<T> {
@@ -64,6 +70,7 @@ impl<T> Default for SimpleMesh<T> {
         Self {
             vertices: Default::default(),
             indices: Default::default(),
+            welder: Default::default(),
         }
     }
 }
@@ -71,6 +78,12 @@ impl<T> Default for SimpleMesh<T> {
 impl<T: PartialEq> SimpleMesh<T> {
     /// Inserts vertex or its index. Performs optimizing insertion with checking if such
     /// vertex already exists. Returns true if inserted vertex was unique.
+    ///
+    /// # Notes
+    ///
+    /// Vertices are compared exactly and the search is linear, so it is slow for big meshes.
+    /// Use [insert_welded_vertex](SimpleMesh::insert_welded_vertex) for vertices that
+    /// implement [WeldVertex](crate::utils::weld::WeldVertex).
     pub fn insert_vertex(&mut self, vertex: T) -> bool {
         // Reverse search is much faster because it is most likely that we'll find identic
         // vertex at the end of the array.
@@ -88,9 +101,44 @@ impl<T: PartialEq> SimpleMesh<T> {
     }
 }
 
+impl<T: WeldVertex> SimpleMesh<T> {
+    /// Creates empty mesh which welds inserted vertices using given tolerances, see
+    /// [insert_welded_vertex](SimpleMesh::insert_welded_vertex).
+    pub fn with_weld_options(options: WeldOptions) -> Self {
+        Self {
+            vertices: Default::default(),
+            indices: Default::default(),
+            welder: VertexWelder::new(options),
+        }
+    }
+
+    /// Inserts vertex or its index. Vertex is welded with existing vertex which is close
+    /// enough using tolerances of the mesh, lookup is done using spatial hash. Returns true
+    /// if inserted vertex was unique.
+    ///
+    /// # Notes
+    ///
+    /// If vertices were modified directly, [reset_welder](SimpleMesh::reset_welder) must be
+    /// called before insertion.
+    pub fn insert_welded_vertex(&mut self, vertex: T) -> bool {
+        self.welder.insert(&mut self.vertices, &mut self.indices, vertex)
+    }
+
+    /// Drops lookup table of welding, it is rebuilt on next insertion.
+    pub fn reset_welder(&mut self) {
+        self.welder.clear();
+    }
+}
+
 pub fn mesh_to_navmesh(mesh: &Mesh) -> Navmesh {
+    mesh_to_navmesh_with_weld_options(mesh, WeldOptions::default())
+}
+
+/// Same as [mesh_to_navmesh](fn.mesh_to_navmesh.html), but vertices of mesh are welded with
+/// given tolerance of position, it allows to connect surfaces which edges don't match exactly.
+pub fn mesh_to_navmesh_with_weld_options(mesh: &Mesh, options: WeldOptions) -> Navmesh {
     // Join surfaces into one simple mesh.
-    let mut simple_mesh = SimpleMesh::default();
+    let mut simple_mesh = SimpleMesh::with_weld_options(options);
     let global_transform = mesh.base().global_transform();
     for surface in mesh.surfaces() {
         let shared_data = surface.get_data();
@@ -102,9 +150,9 @@ pub fn mesh_to_navmesh(mesh: &Mesh) -> Navmesh {
         let last = indices.len() - indices.len() % 3;
         let mut i: usize = 0;
         while i < last {
-            simple_mesh.insert_vertex(global_transform.transform_vector(vertices[indices[i] as usize].position));
-            simple_mesh.insert_vertex(global_transform.transform_vector(vertices[indices[i + 1] as usize].position));
-            simple_mesh.insert_vertex(global_transform.transform_vector(vertices[indices[i + 2] as usize].position));
+            simple_mesh.insert_welded_vertex(global_transform.transform_vector(vertices[indices[i] as usize].position));
+            simple_mesh.insert_welded_vertex(global_transform.transform_vector(vertices[indices[i + 1] as usize].position));
+            simple_mesh.insert_welded_vertex(global_transform.transform_vector(vertices[indices[i + 2] as usize].position));
             i += 3;
         }
     }
@@ -112,6 +160,8 @@ pub fn mesh_to_navmesh(mesh: &Mesh) -> Navmesh {
     // Then build navmesh.
     let triangles = simple_mesh.indices
         .chunks(3)
+        // Welding can collapse small triangles.
+        .filter(|v| v[0] != v[1] && v[1] != v[2] && v[2] != v[0])
         .map(|v| TriangleDefinition { indices: [v[0], v[1], v[2]] })
         .collect::<Vec<_>>();
     Navmesh::new(&triangles, &simple_mesh.vertices)
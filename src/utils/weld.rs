@@ -0,0 +1,301 @@
+//! Vertex welding - merging of vertices that are equal or close enough to each other.
+//!
+//! Welder keeps spatial hash of positions of vertices, so search of vertex to weld with
+//! takes constant time instead of linear search over every vertex of a mesh.
+
+use crate::{
+    core::{
+        math::vec3::Vec3,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    renderer::surface::Vertex,
+};
+use std::collections::HashMap;
+
+/// Tolerances of welding. Vertices are welded if difference of each component of their
+/// attributes is within respective epsilon. Zero epsilon means exact match.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WeldOptions {
+    pub position_epsilon: f32,
+    pub normal_epsilon: f32,
+    /// Tolerance of both sets of texture coordinates.
+    pub tex_coord_epsilon: f32,
+}
+
+impl Default for WeldOptions {
+    fn default() -> Self {
+        Self {
+            position_epsilon: 0.0,
+            normal_epsilon: 0.0,
+            tex_coord_epsilon: 0.0,
+        }
+    }
+}
+
+impl WeldOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_position_epsilon(mut self, epsilon: f32) -> Self {
+        self.position_epsilon = epsilon.max(0.0);
+        self
+    }
+
+    pub fn with_normal_epsilon(mut self, epsilon: f32) -> Self {
+        self.normal_epsilon = epsilon.max(0.0);
+        self
+    }
+
+    pub fn with_tex_coord_epsilon(mut self, epsilon: f32) -> Self {
+        self.tex_coord_epsilon = epsilon.max(0.0);
+        self
+    }
+}
+
+impl Visit for WeldOptions {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.position_epsilon.visit("PositionEpsilon", visitor)?;
+        self.normal_epsilon.visit("NormalEpsilon", visitor)?;
+        self.tex_coord_epsilon.visit("TexCoordEpsilon", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Vertex that can be welded.
+pub trait WeldVertex {
+    /// Position of vertex, it is used as key of spatial hash.
+    fn weld_position(&self) -> Vec3;
+
+    /// Returns true if vertex can be welded with other using given tolerances.
+    fn can_weld(&self, other: &Self, options: &WeldOptions) -> bool;
+}
+
+#[inline]
+fn is_close(a: f32, b: f32, epsilon: f32) -> bool {
+    a == b || (a - b).abs() <= epsilon
+}
+
+#[inline]
+fn is_close_vec3(a: &Vec3, b: &Vec3, epsilon: f32) -> bool {
+    is_close(a.x, b.x, epsilon) && is_close(a.y, b.y, epsilon) && is_close(a.z, b.z, epsilon)
+}
+
+impl WeldVertex for Vec3 {
+    fn weld_position(&self) -> Vec3 {
+        *self
+    }
+
+    fn can_weld(&self, other: &Self, options: &WeldOptions) -> bool {
+        is_close_vec3(self, other, options.position_epsilon)
+    }
+}
+
+impl WeldVertex for Vertex {
+    fn weld_position(&self) -> Vec3 {
+        self.position
+    }
+
+    fn can_weld(&self, other: &Self, options: &WeldOptions) -> bool {
+        is_close_vec3(&self.position, &other.position, options.position_epsilon) &&
+            is_close_vec3(&self.normal, &other.normal, options.normal_epsilon) &&
+            is_close(self.tex_coord.x, other.tex_coord.x, options.tex_coord_epsilon) &&
+            is_close(self.tex_coord.y, other.tex_coord.y, options.tex_coord_epsilon) &&
+            is_close(self.second_tex_coord.x, other.second_tex_coord.x, options.tex_coord_epsilon) &&
+            is_close(self.second_tex_coord.y, other.second_tex_coord.y, options.tex_coord_epsilon) &&
+            self.color == other.color
+    }
+}
+
+/// Marks end of list of vertices of a cell.
+const NO_VERTEX: u32 = u32::max_value();
+
+/// Spatial hash of vertices of a mesh which is used to find vertex to weld with.
+///
+/// # Notes
+///
+/// Welder doesn't own vertices, it indexes vertices that were added to array since last
+/// search. If existing vertices were modified, welder must be [cleared](VertexWelder::clear).
+#[derive(Clone, Debug, Default)]
+pub struct VertexWelder {
+    options: WeldOptions,
+    /// Last indexed vertex of each cell.
+    cells: HashMap<[i64; 3], u32>,
+    /// Previous indexed vertex of same cell for each indexed vertex.
+    next: Vec<u32>,
+}
+
+impl VertexWelder {
+    pub fn new(options: WeldOptions) -> Self {
+        Self {
+            options,
+            cells: Default::default(),
+            next: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn options(&self) -> WeldOptions {
+        self.options
+    }
+
+    pub fn set_options(&mut self, options: WeldOptions) {
+        self.options = options;
+        self.clear();
+    }
+
+    /// Drops spatial hash and frees its memory, hash will be rebuilt on next search.
+    pub fn clear(&mut self) {
+        self.cells = Default::default();
+        self.next = Default::default();
+    }
+
+    fn cell(&self, position: Vec3) -> [i64; 3] {
+        let epsilon = self.options.position_epsilon;
+        let key = |x: f32| {
+            if epsilon > 0.0 {
+                // Coordinates that are within epsilon lie either in same or in adjacent cells.
+                (x / epsilon + 0.5).floor() as i64
+            } else if x == 0.0 {
+                // -0.0 is equal to 0.0, but has other bits.
+                0
+            } else {
+                i64::from(x.to_bits())
+            }
+        };
+        [key(position.x), key(position.y), key(position.z)]
+    }
+
+    fn index<T: WeldVertex>(&mut self, vertices: &[T]) {
+        if self.next.len() > vertices.len() {
+            // Vertices were removed, so indexed vertices are not valid anymore.
+            self.clear();
+        }
+        for i in self.next.len()..vertices.len() {
+            let cell = self.cell(vertices[i].weld_position());
+            let last = self.cells.entry(cell).or_insert(NO_VERTEX);
+            self.next.push(*last);
+            *last = i as u32;
+        }
+    }
+
+    fn find_in_cell<T, F>(&self, cell: &[i64; 3], vertices: &[T], vertex: &T, filter: &F) -> Option<u32>
+        where T: WeldVertex, F: Fn(u32) -> bool {
+        let mut current = *self.cells.get(cell)?;
+        while current != NO_VERTEX {
+            if filter(current) && vertices[current as usize].can_weld(vertex, &self.options) {
+                return Some(current);
+            }
+            current = self.next[current as usize];
+        }
+        None
+    }
+
+    /// Returns index of a vertex in given array which given vertex can be welded with.
+    /// If there are several such vertices, last added one is preferred.
+    pub fn find<T: WeldVertex>(&mut self, vertices: &[T], vertex: &T) -> Option<u32> {
+        self.find_filtered(vertices, vertex, |_| true)
+    }
+
+    /// Same as [find](VertexWelder::find), but considers only vertices whose indices pass
+    /// given filter.
+    pub fn find_filtered<T, F>(&mut self, vertices: &[T], vertex: &T, filter: F) -> Option<u32>
+        where T: WeldVertex, F: Fn(u32) -> bool {
+        self.index(vertices);
+        let cell = self.cell(vertex.weld_position());
+        if self.options.position_epsilon > 0.0 {
+            // Check own cell first, it most likely contains vertex to weld with.
+            if let Some(index) = self.find_in_cell(&cell, vertices, vertex, &filter) {
+                return Some(index);
+            }
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        if dx == 0 && dy == 0 && dz == 0 {
+                            continue;
+                        }
+                        let adjacent = [cell[0].wrapping_add(dx), cell[1].wrapping_add(dy), cell[2].wrapping_add(dz)];
+                        if let Some(index) = self.find_in_cell(&adjacent, vertices, vertex, &filter) {
+                            return Some(index);
+                        }
+                    }
+                }
+            }
+            None
+        } else {
+            self.find_in_cell(&cell, vertices, vertex, &filter)
+        }
+    }
+
+    /// Welds vertex with existing one or adds it to given array, index of vertex is pushed
+    /// to indices. Returns true if vertex was unique.
+    pub fn insert<T: WeldVertex>(&mut self, vertices: &mut Vec<T>, indices: &mut Vec<u32>, vertex: T) -> bool {
+        self.insert_filtered(vertices, indices, vertex, |_| true)
+    }
+
+    /// Same as [insert](VertexWelder::insert), but welds only with vertices whose indices
+    /// pass given filter.
+    pub fn insert_filtered<T, F>(&mut self, vertices: &mut Vec<T>, indices: &mut Vec<u32>, vertex: T, filter: F) -> bool
+        where T: WeldVertex, F: Fn(u32) -> bool {
+        match self.find_filtered(vertices, &vertex, filter) {
+            Some(existing_index) => {
+                indices.push(existing_index);
+                false
+            }
+            None => {
+                indices.push(vertices.len() as u32);
+                vertices.push(vertex);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::math::vec3::Vec3,
+        utils::weld::{VertexWelder, WeldOptions},
+    };
+
+    #[test]
+    fn exact_welding() {
+        let mut welder = VertexWelder::default();
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        assert!(welder.insert(&mut vertices, &mut indices, Vec3::new(1.0, 0.0, 0.0)));
+        assert!(welder.insert(&mut vertices, &mut indices, Vec3::new(0.0, 1.0, 0.0)));
+        assert!(!welder.insert(&mut vertices, &mut indices, Vec3::new(1.0, 0.0, 0.0)));
+        assert!(!welder.insert(&mut vertices, &mut indices, Vec3::new(0.0, 1.0, -0.0)));
+        assert!(welder.insert(&mut vertices, &mut indices, Vec3::new(1.0, 0.0001, 0.0)));
+        assert_eq!(vertices.len(), 3);
+        assert_eq!(indices, vec![0, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn welding_with_epsilon() {
+        let mut welder = VertexWelder::new(WeldOptions::new().with_position_epsilon(0.01));
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        assert!(welder.insert(&mut vertices, &mut indices, Vec3::new(0.0049, 0.0, 0.0)));
+        // Lies in adjacent cell.
+        assert!(!welder.insert(&mut vertices, &mut indices, Vec3::new(0.0051, 0.0, 0.0)));
+        assert!(!welder.insert(&mut vertices, &mut indices, Vec3::new(0.012, 0.0, 0.0)));
+        assert!(welder.insert(&mut vertices, &mut indices, Vec3::new(0.016, 0.0, 0.0)));
+        assert_eq!(indices, vec![0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn filtered_welding() {
+        let mut welder = VertexWelder::default();
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        assert!(welder.insert(&mut vertices, &mut indices, Vec3::new(1.0, 0.0, 0.0)));
+        assert!(welder.insert_filtered(&mut vertices, &mut indices, Vec3::new(1.0, 0.0, 0.0), |i| i != 0));
+        assert!(!welder.insert_filtered(&mut vertices, &mut indices, Vec3::new(1.0, 0.0, 0.0), |i| i == 0));
+        assert_eq!(indices, vec![0, 1, 0]);
+    }
+}